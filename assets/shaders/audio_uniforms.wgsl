@@ -0,0 +1,19 @@
+// The audio and timing inputs shared by every shader visualizer, see
+// `audio_uniforms.rs`. Bring them in with
+//   #import "shaders/audio_uniforms.wgsl"::audio
+
+struct AudioUniforms {
+    // x=elapsed seconds, y=time delta, z=camera zoom
+    time: vec4<f32>,
+    // xy=physical resolution
+    resolution: vec4<f32>,
+    // x=bass, y=mid, z=treble, w=flux
+    levels: vec4<f32>,
+    // x=volume, y=peak, z=momentary, w=short-term loudness, 0..1
+    loudness: vec4<f32>,
+    // LFO bank outputs, 0..1
+    lfo: vec4<f32>,
+};
+
+@group(2) @binding(5)
+var<storage, read> audio: AudioUniforms;
@@ -0,0 +1,41 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct ColorGrade {
+    // In radians.
+    hue_shift: f32,
+    saturation: f32,
+    contrast: f32,
+    gamma: f32,
+};
+
+@group(0) @binding(0)
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var screen_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> grade: ColorGrade;
+
+// Rec. 709 luma weights.
+const LUMA = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+// Rotates the color around the gray axis, which keeps its luma.
+fn rotate_hue(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let axis = vec3<f32>(0.57735);
+    let c = cos(angle);
+    let s = sin(angle);
+    return color * c + cross(axis, color) * s + axis * dot(axis, color) * (1.0 - c);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let source = textureSample(screen_texture, screen_sampler, in.uv);
+    var color = rotate_hue(source.rgb, grade.hue_shift);
+
+    let luma = dot(color, LUMA);
+    color = mix(vec3<f32>(luma), color, grade.saturation);
+    // Contrast pivots around mid gray.
+    color = (color - 0.5) * grade.contrast + 0.5;
+    color = pow(max(color, vec3<f32>(0.0)), vec3<f32>(1.0 / grade.gamma));
+
+    return vec4<f32>(color, source.a);
+}
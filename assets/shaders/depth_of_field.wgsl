@@ -0,0 +1,69 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_render::view::View
+
+struct DepthOfField {
+    // In world units.
+    focus_distance: f32,
+    // Circle of confusion of a point at infinity, as a fraction of the view
+    // height.
+    coc_scale: f32,
+    // In pixels.
+    max_blur: f32,
+};
+
+@group(0) @binding(0)
+var screen_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var screen_sampler: sampler;
+#ifdef MULTISAMPLED
+@group(0) @binding(2)
+var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(2)
+var depth_texture: texture_depth_2d;
+#endif
+@group(0) @binding(3)
+var<uniform> lens: DepthOfField;
+@group(0) @binding(4)
+var<uniform> view: View;
+
+const SAMPLES: u32 = 32u;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+// Blur radius in pixels of what the depth prepass has at this pixel.
+fn blur_radius(position: vec2<f32>, size: vec2<f32>) -> f32 {
+    // The last argument is the sample index or the mip level, 0 either way.
+    let depth = textureLoad(depth_texture, vec2<i32>(position), 0);
+    let uv = position / size;
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let view_position = view.inverse_projection * ndc;
+    let distance = max(-view_position.z / view_position.w, 1e-4);
+    let coc = lens.coc_scale * abs(distance - lens.focus_distance) / distance;
+    return min(0.5 * coc * size.y, lens.max_blur);
+}
+
+// Gathers along a golden angle spiral as wide as the blur of this pixel.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(screen_texture));
+    let center = textureSampleLevel(screen_texture, screen_sampler, in.uv, 0.0);
+    let radius = blur_radius(in.position.xy, size);
+    if radius < 0.5 {
+        return center;
+    }
+
+    var sum = center.rgb;
+    var weight = 1.0;
+    for (var i = 1u; i < SAMPLES; i++) {
+        let angle = f32(i) * GOLDEN_ANGLE;
+        let offset = vec2<f32>(cos(angle), sin(angle)) * sqrt(f32(i) / f32(SAMPLES)) * radius;
+        let position = clamp(in.position.xy + offset, vec2<f32>(0.5), size - 0.5);
+        // Only what is blurred that far spreads here, so sharp things in
+        // focus don't bleed into the blur behind them.
+        if blur_radius(position, size) >= length(offset) {
+            sum += textureSampleLevel(screen_texture, screen_sampler, position / size, 0.0).rgb;
+            weight += 1.0;
+        }
+    }
+    return vec4<f32>(sum / weight, center.a);
+}
@@ -1,20 +1,28 @@
+#import "shaders/audio_uniforms.wgsl"::audio
+
 struct DiscMaterial {
     color: vec4<f32>,
-    time: f32,
     radius: f32,
     line_thickness: f32,
     iterations: f32,
     speed: f32,
     center_radius_factor: f32,
-    resolution: vec2<f32>,
-    bass: f32,
-    flux: f32,
-    zoom: f32,
-    padding: f32,
+    // 0 unless each ring follows its own band.
+    band_count: f32,
 };
 
 @group(2) @binding(0)
 var<uniform> material: DiscMaterial;
+// Spectrum in row 0, waveform (centered on 0.5) in row 1, 512 texels wide.
+@group(2) @binding(1)
+var audio_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var audio_sampler: sampler;
+// Every band, and the samples of the analysis window in -1..1.
+@group(2) @binding(3)
+var<storage, read> bins: array<f32>;
+@group(2) @binding(4)
+var<storage, read> waveform: array<f32>;
 
 const PI : f32 = 3.1415926535;
 
@@ -45,37 +53,48 @@ fn fragment(
 ) -> @location(0) vec4<f32> {
     // 1. Normalization to coordinates -1.0 to 1.0
     // frag_coord is in physical pixels (0..Width, 0..Height)
-    // material.resolution is also in physical pixels thanks to the Rust fix.
+    // audio.resolution is also in physical pixels thanks to the Rust fix.
     // So (frag_coord / res) * 2 - 1 perfectly centers (0,0) in the middle of the screen.
-    var p = (frag_coord.xy / material.resolution) * 2.0 - 1.0;
+    let resolution = audio.resolution.xy;
+    var p = (frag_coord.xy / resolution) * 2.0 - 1.0;
 
     // Aspect ratio correction
-    p.x = p.x * (material.resolution.x / material.resolution.y);
+    p.x = p.x * (resolution.x / resolution.y);
 
     // Y-axis correction (optional but recommended: Y upwards)
     p.y = -p.y;
 
     // Zoom application (Camera scale)
     // If zoom = 2.0 (zoomed out), p becomes 2x larger, so the circle (fixed radius) appears smaller.
-    p = p * material.zoom;
+    p = p * audio.time.z;
 
-    let reactive_radius = material.radius + (material.bass * 0.1);
-    let reactive_thickness = material.line_thickness + (material.flux * 0.05);
+    let reactive_radius = material.radius + (audio.levels.x * 0.1);
+    let reactive_thickness = material.line_thickness + (audio.levels.w * 0.05);
 
     var final_frag: f32 = 0.0;
 
     // Loop to create multiple echoes/rings
     for (var i = 0.0; i < material.iterations; i = i + 1.0) {
         let divi = i / material.iterations * material.center_radius_factor;
-        let current_radius = reactive_radius - divi;
+        var current_radius = reactive_radius - divi;
+        var current_thickness = reactive_thickness;
+
+        // Band mode: the rings split the spectrum from the outer one (bass)
+        // inwards, each swelling with its own band instead of the global bass.
+        if (material.band_count > 0.0) {
+            let band = min(u32(i / material.iterations * material.band_count), u32(material.band_count) - 1u);
+            let amplitude = bins[min(band, arrayLength(&bins) - 1u)];
+            current_radius = material.radius + amplitude * 0.1 - divi;
+            current_thickness = material.line_thickness + amplitude * 0.05;
+        }
 
         // Calculation of arc length based on time
-        let sine_wave = (sin(material.time * material.speed - divi * 5.0) * -0.5 + 0.5);
+        let sine_wave = (sin(audio.time.x * material.speed - divi * 5.0) * -0.5 + 0.5);
         let full_circle = 2.0 * PI;
         let overcompensation = 0.1;
         let end_angle = sine_wave * (full_circle + overcompensation);
 
-        final_frag += ring(p, current_radius, reactive_thickness, end_angle);
+        final_frag += ring(p, current_radius, current_thickness, end_angle);
     }
 
     let final_color = material.color.rgb * clamp(final_frag, 0.0, 1.0);
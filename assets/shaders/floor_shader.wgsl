@@ -0,0 +1,43 @@
+#import bevy_pbr::forward_io::VertexOutput
+#import bevy_pbr::mesh_view_bindings::view
+
+struct FloorMaterial {
+    tint: vec4<f32>,
+    roughness: f32,
+};
+
+@group(2) @binding(0)
+var<uniform> material: FloorMaterial;
+@group(2) @binding(1)
+var reflection_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var reflection_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The reflection was rendered from the mirrored camera, so the pixel
+    // under this fragment on screen is the reflected one, flipped left to right.
+    var uv = (in.position.xy - view.viewport.xy) / view.viewport.zw;
+    uv.x = 1.0 - uv.x;
+
+    // Rougher floors blur the reflection over a wider area.
+    let radius = material.roughness * 0.02;
+    var color = vec3<f32>(0.0);
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * radius;
+            color += textureSample(reflection_texture, reflection_sampler, uv + offset).rgb;
+        }
+    }
+    color = color / 9.0;
+
+    // Reflections get stronger at grazing angles, and rough floors reflect less.
+    let view_dir = normalize(view.world_position - in.world_position.xyz);
+    let fresnel = 0.4 + 0.6 * pow(1.0 - abs(view_dir.y), 3.0);
+    let strength = fresnel * (1.0 - 0.5 * material.roughness);
+
+    // Fade out toward the edges of the floor instead of ending on a hard line.
+    let edge = 1.0 - smoothstep(0.6, 1.0, length(in.uv * 2.0 - 1.0));
+
+    return vec4<f32>(color * material.tint.rgb, material.tint.a * strength * edge);
+}
@@ -0,0 +1,76 @@
+// The Shadertoy audio texture computed on the GPU, see `gpu_fft.rs`: a
+// 1024-point FFT of the latest samples in row 0, the samples themselves in
+// row 1. One workgroup runs the whole radix-2 transform in shared memory.
+
+const N: u32 = 1024u;
+const LOG_N: u32 = 10u;
+const THREADS: u32 = 256u;
+const WIDTH: u32 = 512u;
+const TAU: f32 = 6.283185307179586;
+
+struct Params {
+    // Multiplies the spectrum, as the bass sensitivity does on the CPU path.
+    gain: f32,
+};
+
+@group(0) @binding(0)
+var<storage, read> samples: array<f32>;
+@group(0) @binding(1)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var output: texture_storage_2d<rgba16float, write>;
+
+var<workgroup> re: array<f32, N>;
+var<workgroup> im: array<f32, N>;
+
+@compute @workgroup_size(256)
+fn fft(@builtin(local_invocation_index) thread: u32) {
+    let count = arrayLength(&samples);
+    let start = count - min(count, N);
+
+    // Hann-windowed, in bit-reversed order for the in-place butterflies.
+    for (var i = thread; i < N; i += THREADS) {
+        var sample = 0.0;
+        if start + i < count {
+            sample = samples[start + i];
+        }
+        let hann = 0.5 - 0.5 * cos(TAU * f32(i) / f32(N - 1u));
+        let j = reverseBits(i) >> (32u - LOG_N);
+        re[j] = sample * hann;
+        im[j] = 0.0;
+    }
+    workgroupBarrier();
+
+    for (var stage = 1u; stage <= LOG_N; stage++) {
+        let half = 1u << (stage - 1u);
+        for (var butterfly = thread; butterfly < N / 2u; butterfly += THREADS) {
+            let k = butterfly % half;
+            let i = (butterfly / half) * half * 2u + k;
+            let j = i + half;
+            let angle = -TAU * f32(k) / f32(half * 2u);
+            let w = vec2<f32>(cos(angle), sin(angle));
+            let t_re = w.x * re[j] - w.y * im[j];
+            let t_im = w.x * im[j] + w.y * re[j];
+            re[j] = re[i] - t_re;
+            im[j] = im[i] - t_im;
+            re[i] = re[i] + t_re;
+            im[i] = im[i] + t_im;
+        }
+        workgroupBarrier();
+    }
+
+    for (var x = thread; x < WIDTH; x += THREADS) {
+        // A full-scale sine reads 1: the Hann window sums to N / 2.
+        let amplitude = length(vec2<f32>(re[x], im[x])) * 4.0 / f32(N) * params.gain;
+        let db = 20.0 * log2(max(amplitude, 1e-6)) * 0.30103;
+        let level = clamp((db + 100.0) / 70.0, 0.0, 1.0);
+        textureStore(output, vec2<i32>(i32(x), 0), vec4<f32>(level, level, level, 1.0));
+
+        var sample = 0.0;
+        if count > 0u {
+            sample = samples[x * count / WIDTH];
+        }
+        let wave = clamp(sample * 0.5 + 0.5, 0.0, 1.0);
+        textureStore(output, vec2<i32>(i32(x), 1), vec4<f32>(wave, wave, wave, 1.0));
+    }
+}
@@ -1,16 +1,29 @@
 #import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import "shaders/audio_uniforms.wgsl"::audio
 
 // --- 1. CONFIGURATION & UNIFORMS ---
 
 struct IcoMaterial {
     color: vec4<f32>,            // Tint global
-    resolution_mouse: vec4<f32>, // xy = physical resolution, zw = mouse
-    time_params: vec4<f32>,      // x = time, y = speed, z = CAMERA ZOOM
+    view_params: vec4<f32>,      // xy = view orbit offset (-1..1), z = speed
     audio_params: vec4<f32>,     // x = Bass, y = Mid, z = Treble, w = Flux
+    secondary_color: vec4<f32>,  // Ambient light tint
+    background_color: vec4<f32>,
+    glow_params: vec4<f32>,      // x = rim glow intensity
 };
 
 @group(2) @binding(0)
 var<uniform> material: IcoMaterial;
+// Spectrum in row 0, waveform (centered on 0.5) in row 1, 512 texels wide.
+@group(2) @binding(1)
+var audio_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var audio_sampler: sampler;
+// Every band, and the samples of the analysis window in -1..1.
+@group(2) @binding(3)
+var<storage, read> bins: array<f32>;
+@group(2) @binding(4)
+var<storage, read> waveform: array<f32>;
 
 const PI: f32 = 3.14159265359;
 const MAX_TRACE_DISTANCE: f32 = 40.0;
@@ -335,12 +348,12 @@ fn doLighting(col_in: vec3<f32>, pos: vec3<f32>, nor: vec3<f32>, refl: vec3<f32>
 
     var lin = vec3<f32>(0.0);
     lin += 1.20 * dif * vec3<f32>(0.95, 0.80, 0.60) * material.color.rgb;
-    lin += 0.80 * amb * vec3<f32>(0.50, 0.70, 0.80) * occ;
+    lin += 0.80 * amb * material.secondary_color.rgb * occ;
     lin += 0.30 * bac * vec3<f32>(0.25, 0.25, 0.25) * occ;
 
     // React to Flux: Add extra brightness to the fresnel rim light
     let flux_flash = material.audio_params.w * 0.5;
-    lin += (0.20 + flux_flash) * fre * vec3<f32>(1.00, 1.00, 1.00) * occ;
+    lin += (material.glow_params.x + flux_flash) * fre * vec3<f32>(1.00, 1.00, 1.00) * occ;
 
     col = col * lin;
 
@@ -368,7 +381,7 @@ fn calcIntersection(ro: vec3<f32>, rd: vec3<f32>, basis: IcoBasis, t: f32) -> ve
 }
 
 fn render_scene(res: vec2<f32>, ro: vec3<f32>, rd: vec3<f32>, basis: IcoBasis, t: f32) -> vec3<f32> {
-    var color = vec3<f32>(0.04, 0.045, 0.05);
+    var color = material.background_color.rgb;
 
     if (res.y > -0.5) {
         let pos = ro + rd * res.x;
@@ -394,11 +407,11 @@ fn calcLookAtMatrix(ro: vec3<f32>, ta: vec3<f32>, roll: f32) -> mat3x3<f32> {
 fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
     let basis = initIcosahedron();
 
-    var t_val = material.time_params.x * material.time_params.y;
+    var t_val = audio.time.x * material.view_params.z;
     t_val = (t_val / 4.0) - floor(t_val / 4.0);
 
-    let resolution = material.resolution_mouse.xy;
-    let camera_zoom_scale = material.time_params.z;
+    let resolution = audio.resolution.xy;
+    let camera_zoom_scale = audio.time.z;
 
     if (resolution.x == 0.0) { return vec4<f32>(0.0); }
 
@@ -411,11 +424,19 @@ fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
     // Distance of camera (ro). Scaled by camera zoom.
     var zoom = 4.0 * camera_zoom_scale;
 
-    let ro = zoom * orient;
+    let ro_base = zoom * orient;
     let ta = vec3<f32>(0.0);
 
-    let camMat = calcLookAtMatrix(ro, ta, 0.0);
-    let rd = normalize(camMat * vec3<f32>(p_corrected.xy, 2.0));
+    let camMat = calcLookAtMatrix(ro_base, ta, 0.0);
+    var rd = normalize(camMat * vec3<f32>(p_corrected.xy, 2.0));
+
+    // The orbit offset swings the whole camera around the ico, up to 45
+    // degrees each way (the camera looks down the Y axis).
+    let orbit = material.view_params.xy;
+    let swing = rotationMatrix(vec3<f32>(0.0, 0.0, 1.0), -orbit.x * PI * 0.25)
+        * rotationMatrix(vec3<f32>(1.0, 0.0, 0.0), orbit.y * PI * 0.25);
+    let ro = swing * ro_base;
+    rd = swing * rd;
 
     let res = calcIntersection(ro, rd, basis, t_val);
     var color = render_scene(res, ro, rd, basis, t_val);
@@ -0,0 +1,89 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import "shaders/audio_uniforms.wgsl"::audio
+
+// --- Shadertoy compatibility header ---
+// Provides Shadertoy's inputs under their usual names, so a port only has to
+// translate the GLSL of its `mainImage` (below the header) to WGSL:
+//   iResolution, iTime, iTimeDelta, iFrame, iMouse
+//   iChannel0: 512x2 audio texture, spectrum in row 0, waveform in row 1.
+//   Read it with `texture0(uv)`, or `textureSampleLevel` with `iChannel0_sampler`.
+//   With "Spectrum From GPU FFT" the spectrum is Shadertoy's own: linear FFT
+//   bins up to about 11 kHz, in dB, instead of the visualizer's bands.
+// Beyond Shadertoy's, `material.loudness` holds the EBU R128 loudness: x and y
+// the momentary and short-term loudness in 0..1, z and w the same in LUFS, and
+// the `bins` and `waveform` arrays every band and sample of the latest
+// analysis frame, unscaled (`arrayLength(&bins)` gives the band count). The
+// `audio` inputs shared by every visualizer (see `audio_uniforms.wgsl`) hold
+// the bass, mid, treble, flux, peak and LFO outputs.
+// `fragCoord` starts at the bottom-left corner, as on Shadertoy.
+
+struct ShadertoyMaterial {
+    resolution: vec4<f32>,
+    mouse: vec4<f32>,
+    // x=time, y=time delta, z=frame
+    time_params: vec4<f32>,
+    tint: vec4<f32>,
+    loudness: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> material: ShadertoyMaterial;
+@group(2) @binding(1)
+var iChannel0: texture_2d<f32>;
+@group(2) @binding(2)
+var iChannel0_sampler: sampler;
+@group(2) @binding(3)
+var<storage, read> bins: array<f32>;
+@group(2) @binding(4)
+var<storage, read> waveform: array<f32>;
+
+var<private> iResolution: vec3<f32>;
+var<private> iTime: f32;
+var<private> iTimeDelta: f32;
+var<private> iFrame: i32;
+var<private> iMouse: vec4<f32>;
+
+// GLSL's `texture(iChannel0, uv)`; unlike `textureSample`, it may be called
+// from loops and branches.
+fn texture0(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(iChannel0, iChannel0_sampler, uv, 0.0);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    iResolution = material.resolution.xyz;
+    iTime = material.time_params.x;
+    iTimeDelta = material.time_params.y;
+    iFrame = i32(material.time_params.z);
+    iMouse = material.mouse;
+
+    let frag_coord = vec2<f32>(in.position.x, iResolution.y - in.position.y);
+    let color = mainImage(frag_coord);
+    return vec4<f32>(color.rgb * material.tint.rgb, 1.0);
+}
+
+// --- Ported shader ---
+// Spectrum bars with the waveform drawn over them, fading through the
+// colors over time. Replace this function with your own port.
+fn mainImage(fragCoord: vec2<f32>) -> vec4<f32> {
+    let uv = fragCoord / iResolution.xy;
+
+    // 64 bars, each sampling the spectrum at its center.
+    let bars = 64.0;
+    let bar = floor(uv.x * bars);
+    let fft = texture0(vec2<f32>((bar + 0.5) / bars, 0.25)).x;
+    let in_bar = step(uv.y, fft) * step(0.1, fract(uv.x * bars));
+    let hue = vec3<f32>(0.0, 0.33, 0.67) + bar / bars + iTime * 0.1;
+    let bar_color = 0.5 + 0.5 * cos(6.28318 * hue);
+
+    let wave = texture0(vec2<f32>(uv.x, 0.75)).x;
+    let line = 1.0 - smoothstep(0.0, 0.01, abs(uv.y - wave));
+
+    var color = bar_color * in_bar * (0.4 + 0.6 * uv.y) + vec3<f32>(line);
+
+    // Brighten around the cursor while the button is held.
+    if (iMouse.z > 0.0) {
+        color += 0.1 / (1.0 + length(fragCoord - iMouse.xy) * 0.05);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,384 @@
+// src/analysis.rs
+
+use crate::config::SpectrumWeighting;
+use crate::fft::Fft;
+use crate::loudness::{a_weighting, KWeightingCurve, LoudnessMeter};
+use crate::resample::ANALYSIS_SAMPLE_RATE;
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+use std::sync::mpsc::{Receiver, Sender};
+
+pub const FFT_SIZE: usize = 4096;
+// A second, longer FFT resolves the bands entirely below this frequency. It
+// is at most this long, see `Quality::long_fft_size`.
+pub const LONG_FFT_SIZE: usize = 16384;
+const LONG_FFT_CUTOFF: f32 = 200.0;
+
+// Sent to the analysis thread on every analysis tick.
+pub struct AnalysisRequest {
+    pub window: Vec<f32>,
+    // Samples consumed from the buffer since the last request. They lead up
+    // to `window`, and together they feed the long FFT.
+    pub consumed: Vec<f32>,
+    pub sample_rate: u32,
+    pub num_bands: usize,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    // Overrides the bands spread over the frequency range, see
+    // `VizSettings::band_edges`.
+    pub band_edges: Option<Vec<f32>>,
+    pub weighting: SpectrumWeighting,
+    // Frequencies of the tone detectors, in Hz.
+    pub tones: Vec<f32>,
+    // Size of the long FFT, 0 (or no more than `FFT_SIZE`) for none.
+    pub long_fft_size: usize,
+}
+
+// Raw (unsmoothed) features of one analysis window.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnalysisFrame {
+    pub volume: f32,
+    pub flux: f32,
+    pub bins: Vec<f32>,
+    // Summed magnitude above 4 kHz.
+    pub treble_energy: f32,
+    // EBU R128 loudness of the stream up to the window, see `loudness.rs`.
+    pub momentary_lufs: f32,
+    pub short_term_lufs: f32,
+    // Share of consecutive samples that change sign, 0..1: high for noise
+    // and cymbals, low for bass and sustained tones.
+    pub zero_crossing_rate: f32,
+    // Share of the spectrum's energy in its percussive part, 0..1.
+    pub percussiveness: f32,
+    // Amplitude of the sine at each of the request's tone frequencies, 1 at
+    // full scale.
+    pub tones: Vec<f32>,
+    pub spectrum: Vec<(f32, f32)>,
+    pub band_limits: Vec<f32>,
+    pub waveform: Vec<f32>,
+}
+
+// Runs the FFTs and feature extraction away from the Bevy schedule. The
+// thread stops once the request sender is dropped.
+pub fn spawn_analysis_thread(requests: Receiver<AnalysisRequest>, frames: Sender<AnalysisFrame>) {
+    std::thread::Builder::new()
+        .name("audio-analysis".into())
+        .spawn(move || {
+            let mut worker = AnalysisWorker::new();
+            while let Ok(mut request) = requests.recv() {
+                worker.push_history(&request.consumed);
+                // If we fell behind, keep the history continuous but only analyse the newest window.
+                for newer in requests.try_iter() {
+                    worker.push_history(&newer.consumed);
+                    request = newer;
+                }
+                if frames.send(worker.analyse(request)).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("Failed to spawn the audio analysis thread");
+}
+
+// Turns requests into frames. Also driven directly for offline analysis.
+pub(crate) struct AnalysisWorker {
+    short: Fft,
+    planner: RealFftPlanner<f32>,
+    // Planned on first use, and again when the requested size changes.
+    long: Option<Fft>,
+    long_history: VecDeque<f32>,
+    previous_spectrum: Vec<(f32, f32)>,
+    loudness: LoudnessMeter,
+    k_weighting: KWeightingCurve,
+    percussive_split: PercussiveSplit,
+}
+
+impl AnalysisWorker {
+    pub(crate) fn new() -> Self {
+        let mut planner = RealFftPlanner::new();
+        Self {
+            short: Fft::new(&mut planner, FFT_SIZE),
+            planner,
+            long: None,
+            long_history: VecDeque::with_capacity(LONG_FFT_SIZE - FFT_SIZE),
+            previous_spectrum: Vec::new(),
+            loudness: LoudnessMeter::new(ANALYSIS_SAMPLE_RATE),
+            k_weighting: KWeightingCurve::new(ANALYSIS_SAMPLE_RATE),
+            percussive_split: PercussiveSplit::default(),
+        }
+    }
+
+    // Takes the stream in order, without gaps; the loudness is measured over it.
+    pub(crate) fn push_history(&mut self, samples: &[f32]) {
+        self.loudness.push(samples);
+        self.long_history.extend(samples);
+        let excess = self
+            .long_history
+            .len()
+            .saturating_sub(LONG_FFT_SIZE - FFT_SIZE);
+        self.long_history.drain(..excess);
+    }
+
+    pub(crate) fn analyse(&mut self, request: AnalysisRequest) -> AnalysisFrame {
+        let sample_rate = request.sample_rate;
+        self.short.process(request.window.iter().copied());
+
+        let squared_sum = request.window.iter().map(|s| s * s).sum::<f32>();
+        let volume = (squared_sum / request.window.len().max(1) as f32).sqrt();
+        let crossings = request
+            .window
+            .windows(2)
+            .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+            .count();
+        let zero_crossing_rate =
+            crossings as f32 / request.window.len().saturating_sub(1).max(1) as f32;
+
+        let spectrum: Vec<(f32, f32)> = self.short.spectrum(sample_rate, 20.0, 20000.0).collect();
+        let flux = if !self.previous_spectrum.is_empty()
+            && self.previous_spectrum.len() == spectrum.len()
+        {
+            spectrum
+                .iter()
+                .zip(&self.previous_spectrum)
+                .map(|((_, cur_mag), (_, prev_mag))| (cur_mag - prev_mag).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        } else {
+            0.0
+        };
+        self.previous_spectrum.clone_from(&spectrum);
+        let tones = request
+            .tones
+            .iter()
+            .map(|freq| goertzel_amplitude(&request.window, *freq, sample_rate))
+            .collect();
+        let magnitudes: Vec<f32> = spectrum.iter().map(|(_, mag)| *mag).collect();
+        let percussiveness = self.percussive_split.percussiveness(&magnitudes);
+
+        // Bands are spread over the user's frequency range, or between their
+        // own edges, kept inside what the FFT can see.
+        let nyquist = sample_rate as f32 / 2.0;
+        let edges = match &request.band_edges {
+            Some(edges) if edges.len() > 1 => {
+                edges.iter().map(|edge| edge.clamp(20.0, nyquist)).collect()
+            }
+            _ => {
+                let min_freq = request.min_frequency.clamp(20.0, nyquist / 2.0);
+                let max_freq = request.max_frequency.clamp(min_freq * 2.0, nyquist);
+                log_band_edges(request.num_bands.max(1), min_freq, max_freq)
+            }
+        };
+        let min_freq = edges[0];
+        let max_freq = edges[edges.len() - 1];
+        let band_limits = edges[1..].to_vec();
+        let num_bands = band_limits.len();
+
+        // Only the bands are weighted; the spectrum is kept as it is for display.
+        let k_weighting = &self.k_weighting;
+        let weight = |freq: f32| match request.weighting {
+            SpectrumWeighting::Off => 1.0,
+            SpectrumWeighting::A => a_weighting(freq),
+            SpectrumWeighting::K => k_weighting.gain(freq),
+        };
+
+        let mut bins = vec![0.0; num_bands];
+        let mut current_band = 0;
+        let mut treble_energy = 0.0;
+
+        for (freq, val) in &spectrum {
+            if *freq > 4000.0 {
+                treble_energy += val;
+            }
+
+            if *freq < min_freq || *freq > max_freq {
+                continue;
+            }
+            while current_band < num_bands - 1 && *freq > band_limits[current_band] {
+                current_band += 1;
+            }
+            bins[current_band] += val * weight(*freq);
+        }
+
+        // Low bands only span a few bins of the short FFT; recompute those
+        // that sit entirely below the cutoff from the long one.
+        let long_size = request.long_fft_size.min(LONG_FFT_SIZE);
+        let history = long_size.saturating_sub(FFT_SIZE);
+        if history > 0
+            && self.long_history.len() >= history
+            && request.window.len() == FFT_SIZE
+            && band_limits[0] <= LONG_FFT_CUTOFF
+        {
+            if self.long.as_ref().map(Fft::size) != Some(long_size) {
+                self.long = None;
+            }
+            let planner = &mut self.planner;
+            let long = self
+                .long
+                .get_or_insert_with(|| Fft::new(planner, long_size));
+            let start = self.long_history.len() - history;
+            long.process(
+                self.long_history
+                    .range(start..)
+                    .chain(&request.window)
+                    .copied(),
+            );
+            // Tones come out sqrt(N) louder with this scaling; match the short FFT.
+            let scale = (FFT_SIZE as f32 / long_size as f32).sqrt();
+            let mut lower = min_freq;
+            for (band, upper) in band_limits.iter().enumerate() {
+                if *upper > LONG_FFT_CUTOFF {
+                    break;
+                }
+                bins[band] = long
+                    .spectrum(sample_rate, lower, *upper)
+                    .filter(|(freq, _)| *freq > lower)
+                    .map(|(freq, val)| val * weight(freq))
+                    .sum::<f32>()
+                    * scale;
+                lower = *upper;
+            }
+        }
+
+        AnalysisFrame {
+            volume,
+            flux,
+            bins,
+            treble_energy,
+            momentary_lufs: self.loudness.momentary(),
+            short_term_lufs: self.loudness.short_term(),
+            zero_crossing_rate,
+            percussiveness,
+            tones,
+            spectrum,
+            band_limits,
+            waveform: request.window,
+        }
+    }
+}
+
+// Amplitude of the sine at `freq` in `window`, by the Goertzel algorithm:
+// a single DFT bin, placed exactly on the frequency.
+fn goertzel_amplitude(window: &[f32], freq: f32, sample_rate: u32) -> f32 {
+    let len = window.len();
+    if len < 2 {
+        return 0.0;
+    }
+    let coeff = 2.0 * (TAU * freq as f64 / sample_rate as f64).cos();
+    let mut s1 = 0.0;
+    let mut s2 = 0.0;
+    let mut window_sum = 0.0;
+    for (i, sample) in window.iter().enumerate() {
+        // Hann window, so neighbouring tones don't leak in.
+        let hann = 0.5 - 0.5 * (TAU * i as f64 / (len - 1) as f64).cos();
+        window_sum += hann;
+        let s = *sample as f64 * hann + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+    (2.0 * power.max(0.0).sqrt() / window_sum) as f32
+}
+
+// The edges of `num_bands` bands spread evenly on a log scale from
+// `min_freq` to `max_freq`, lowest first.
+pub fn log_band_edges(num_bands: usize, min_freq: f32, max_freq: f32) -> Vec<f32> {
+    (0..=num_bands)
+        .map(|i| min_freq * (max_freq / min_freq).powf(i as f32 / num_bands as f32))
+        .collect()
+}
+
+// Splits the spectrum into a harmonic and a percussive part by median
+// filtering the spectrogram: sustained tones are smooth over time, hits are
+// smooth across frequency. Only past frames are used for the time median.
+#[derive(Default)]
+struct PercussiveSplit {
+    // Magnitudes of the latest frames, oldest first.
+    frames: VecDeque<Vec<f32>>,
+}
+
+impl PercussiveSplit {
+    // Length of the time median, about 280 ms at the analysis rate.
+    const TIME_FRAMES: usize = 17;
+    // Length of the frequency median, about 180 Hz of the short FFT.
+    const FREQUENCY_BINS: usize = 17;
+    // Below this energy, the frame is silence and counts as harmonic.
+    const FLOOR: f32 = 1e-6;
+
+    fn percussiveness(&mut self, magnitudes: &[f32]) -> f32 {
+        if self
+            .frames
+            .back()
+            .is_some_and(|frame| frame.len() != magnitudes.len())
+        {
+            self.frames.clear();
+        }
+        if self.frames.len() == Self::TIME_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(magnitudes.to_vec());
+
+        let half = Self::FREQUENCY_BINS / 2;
+        let mut window = Vec::with_capacity(Self::TIME_FRAMES.max(Self::FREQUENCY_BINS));
+        let mut harmonic = 0.0;
+        let mut percussive = 0.0;
+        for (i, magnitude) in magnitudes.iter().enumerate() {
+            window.clear();
+            window.extend(self.frames.iter().map(|frame| frame[i]));
+            let h = median(&mut window).powi(2);
+            window.clear();
+            window.extend_from_slice(
+                &magnitudes[i.saturating_sub(half)..(i + half + 1).min(magnitudes.len())],
+            );
+            let p = median(&mut window).powi(2);
+            // Soft masks share the bin's energy between the two parts.
+            if h + p > 0.0 {
+                let energy = magnitude * magnitude;
+                harmonic += energy * h / (h + p);
+                percussive += energy * p / (h + p);
+            }
+        }
+        if harmonic + percussive < Self::FLOOR {
+            return 0.0;
+        }
+        percussive / (harmonic + percussive)
+    }
+}
+
+// The middle value, reordering `values`. Must not be empty.
+fn median(values: &mut [f32]) -> f32 {
+    let middle = values.len() / 2;
+    *values
+        .select_nth_unstable_by(middle, |a, b| a.total_cmp(b))
+        .1
+}
+
+// Flags frames whose spectral flux jumps well above its recent average.
+#[derive(Default)]
+pub struct OnsetDetector {
+    average: f32,
+    cooldown: u32,
+}
+
+impl OnsetDetector {
+    // How far above the running average the flux has to jump.
+    const RATIO: f32 = 1.5;
+    // Below this, flux is noise even in silence.
+    const FLOOR: f32 = 1e-3;
+    // Frames ignored after an onset, about 100 ms at the analysis rate.
+    const COOLDOWN_FRAMES: u32 = 6;
+    // Running average weight of each new frame, about half a second.
+    const AVERAGE_WEIGHT: f32 = 1.0 / 30.0;
+
+    pub fn detect(&mut self, flux: f32) -> bool {
+        let onset = self.cooldown == 0 && flux > Self::FLOOR && flux > self.average * Self::RATIO;
+        self.cooldown = if onset {
+            Self::COOLDOWN_FRAMES
+        } else {
+            self.cooldown.saturating_sub(1)
+        };
+        self.average += (flux - self.average) * Self::AVERAGE_WEIGHT;
+        onset
+    }
+}
@@ -1,61 +1,97 @@
 // src/audio.rs
 
-use crate::{config::VisualsConfig, AppState, VisualizationEnabled};
-use bevy::prelude::*;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use rodio::{source::Source, Decoder, Sink};
-use spectrum_analyzer::{
-    samples_fft_to_spectrum, scaling::divide_by_N_sqrt, windows::hann_window, FrequencyLimit,
+use crate::{
+    analysis::{spawn_analysis_thread, AnalysisFrame, AnalysisRequest, FFT_SIZE},
+    backend::{Backend, Capture, CaptureError},
+    config::{
+        CrossoverSettings, Envelope, GeneralSettings, SilenceGateSettings, SmoothingSettings,
+        VisualizerId, VisualsConfig,
+    },
+    eq::{sync_equalizer, AnalysisEq, EqControl, EqSource},
+    idle::IdleMode,
+    interpolate::{interpolate_analysis, restore_analysis, AnalysisInterpolator},
+    loudness::{loudness_level, SILENCE_LUFS},
+    monitor::{manage_monitor, MonitorTap},
+    offline::OfflineAnalysis,
+    recording::SessionRecorder,
+    resample::{Resampler, ANALYSIS_SAMPLE_RATE},
+    stretch::{StretchControl, TimeStretch},
+    toast::{Toast, ToastSender, Toasts},
+    AppState, VisualizationEnabled,
 };
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rodio::{source::Source, Sink};
 use std::collections::VecDeque;
-use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-// --- Symphonia Helper ---
-
-fn get_duration_with_symphonia(path: &Path) -> Result<Duration, Box<dyn std::error::Error>> {
-    let src = std::fs::File::open(path)?;
-    let mss = symphonia::core::io::MediaSourceStream::new(Box::new(src), Default::default());
-
-    let hint = symphonia::core::probe::Hint::new();
-    let meta_opts: symphonia::core::meta::MetadataOptions = Default::default();
-    let fmt_opts: symphonia::core::formats::FormatOptions = Default::default();
-
-    let probed = symphonia::default::get_probe().format(&hint, mss, &fmt_opts, &meta_opts)?;
-    let format = probed.format;
-
-    let track = format
-        .tracks()
-        .iter()
-        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
-        .ok_or("No supported audio track found")?;
-
-    let time_base = track.codec_params.time_base.ok_or("Missing time base")?;
-    let n_frames = track.codec_params.n_frames.ok_or("Missing frame count")?;
-
-    let total_time = time_base.calc_time(n_frames);
+// The file name shown to the user in notifications.
+pub fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
 
-    Ok(Duration::from_secs(total_time.seconds) + Duration::from_secs_f64(total_time.frac))
+// Averages interleaved frames of `channels` samples down to mono. The
+// analysis works on mono samples at `ANALYSIS_SAMPLE_RATE`.
+pub(crate) fn downmix(samples: &[f32], channels: u16) -> impl Iterator<Item = f32> + '_ {
+    let channels = channels.max(1) as usize;
+    samples
+        .chunks_exact(channels)
+        .map(move |frame| frame.iter().sum::<f32>() / channels as f32)
 }
 
 // --- Bevy Plugin and Components ---
 
+// Passes the samples on to the sink and their mono downmix, resampled to
+// `ANALYSIS_SAMPLE_RATE`, to the analysis.
 struct AudioDataTee<S> {
     source: S,
     sender: Sender<f32>,
+    frame_sum: f32,
+    frame_pos: u16,
+    resampler: Resampler,
+}
+
+impl<S: Source<Item = f32>> AudioDataTee<S> {
+    fn new(source: S, sender: Sender<f32>) -> Self {
+        Self {
+            source,
+            sender,
+            frame_sum: 0.0,
+            frame_pos: 0,
+            resampler: Resampler::new(ANALYSIS_SAMPLE_RATE),
+        }
+    }
 }
 
 impl<S> Iterator for AudioDataTee<S>
 where
-    S: Iterator<Item = f32>,
+    S: Source<Item = f32>,
 {
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
         let sample = self.source.next()?;
-        self.sender.send(sample).ok();
+        let channels = self.source.channels().max(1);
+        self.frame_sum += sample;
+        self.frame_pos += 1;
+        if self.frame_pos >= channels {
+            let sender = &self.sender;
+            self.resampler.push(
+                self.frame_sum / channels as f32,
+                self.source.sample_rate(),
+                |s| {
+                    sender.send(s).ok();
+                },
+            );
+            self.frame_sum = 0.0;
+            self.frame_pos = 0;
+        }
         Some(sample)
     }
 }
@@ -80,52 +116,99 @@ where
 
 pub struct AudioPlugin;
 
-#[derive(Resource)]
-pub struct AnalysisTimer(pub Timer);
+// Analysis frames per second, independent of the frame rate.
+pub const ANALYSIS_RATE_HZ: f64 = 60.0;
+// Capacity of the sample buffers; the oldest samples are dropped past this,
+// e.g. while rendering is stopped. About 6 s at 44.1 kHz.
+const MAX_BUFFERED_SAMPLES: usize = 1 << 18;
 
 #[derive(Resource, Clone)]
 pub struct AnalysisAudioSender(pub Sender<f32>);
 pub struct AnalysisAudioReceiver(pub Receiver<f32>);
 
+// Channels to and from the analysis thread (see `analysis.rs`).
+#[derive(Resource)]
+pub struct AnalysisRequestSender(pub Sender<AnalysisRequest>);
+pub struct AnalysisFrameReceiver(pub Receiver<AnalysisFrame>);
+
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
         let (mic_tx, mic_rx) = std::sync::mpsc::channel::<Vec<f32>>();
         let (analysis_tx, analysis_rx) = std::sync::mpsc::channel::<f32>();
-
-        app.insert_resource(AnalysisTimer(Timer::new(
-            Duration::from_secs_f32(1.0 / 60.0),
-            TimerMode::Repeating,
-        )))
-        .insert_resource(MicAudioSender(mic_tx))
-        .insert_non_send_resource(MicAudioReceiver(mic_rx))
-        .insert_resource(AnalysisAudioSender(analysis_tx))
-        .insert_non_send_resource(AnalysisAudioReceiver(analysis_rx))
-        .init_resource::<AudioSamples>()
-        .init_resource::<AudioAnalysis>()
-        .init_resource::<SelectedMic>()
-        .init_resource::<MicAudioBuffer>()
-        .add_systems(
-            Update,
-            (
-                read_mic_data_system,
-                read_analysis_data_system,
-                manage_audio_playback,
-                apply_playback_changes.after(manage_audio_playback),
-                update_playback_position.after(apply_playback_changes),
-                audio_analysis_system
-                    .after(read_mic_data_system)
-                    .after(read_analysis_data_system)
-                    .after(manage_audio_playback)
-                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0),
-            )
-                .run_if(
-                    in_state(AppState::Visualization2D)
-                        .or_else(in_state(AppState::Visualization3D))
-                        .or_else(in_state(AppState::VisualizationOrb))
-                        .or_else(in_state(AppState::VisualizationDisc))
-                        .or_else(in_state(AppState::VisualizationIco)),
+        let (request_tx, request_rx) = std::sync::mpsc::channel::<AnalysisRequest>();
+        let (frame_tx, frame_rx) = std::sync::mpsc::channel::<AnalysisFrame>();
+        spawn_analysis_thread(request_rx, frame_tx);
+
+        app.insert_resource(Time::<Fixed>::from_hz(ANALYSIS_RATE_HZ))
+            .insert_resource(MicAudioSender(mic_tx))
+            .insert_non_send_resource(MicAudioReceiver(mic_rx))
+            .insert_resource(AnalysisAudioSender(analysis_tx))
+            .insert_non_send_resource(AnalysisAudioReceiver(analysis_rx))
+            .insert_resource(AnalysisRequestSender(request_tx))
+            .insert_non_send_resource(AnalysisFrameReceiver(frame_rx))
+            .init_resource::<AudioSamples>()
+            .init_resource::<AudioAnalysis>()
+            .init_resource::<SilenceGate>()
+            .init_resource::<PeakFollower>()
+            .init_resource::<AnalysisInterpolator>()
+            // Before replay ticks in FixedUpdate and live frames in Update.
+            .add_systems(PreUpdate, restore_analysis)
+            .init_resource::<SelectedMic>()
+            .init_resource::<StretchControl>()
+            .init_resource::<EqControl>()
+            .init_resource::<AnalysisEq>()
+            .init_resource::<MonitorTap>()
+            .init_resource::<MicLatency>()
+            .init_resource::<MicAudioBuffer>()
+            // The channels are drained in every state, so samples sent while
+            // in the menus don't pile up in them.
+            .add_systems(
+                Update,
+                (
+                    sync_equalizer,
+                    manage_monitor,
+                    pause_in_menus,
+                    (read_mic_data_system, read_analysis_data_system).after(sync_equalizer),
                 ),
-        );
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_mic_buffer_size.before(manage_audio_playback),
+                    manage_audio_playback,
+                    apply_playback_changes.after(manage_audio_playback),
+                    manage_mic_mix.after(manage_audio_playback),
+                    update_playback_position.after(apply_playback_changes),
+                    apply_analysis_frames,
+                    follow_peak
+                        .after(read_analysis_data_system)
+                        .after(read_mic_data_system),
+                    interpolate_analysis.after(apply_analysis_frames),
+                )
+                    .run_if(
+                        in_state(AppState::Visualization2D)
+                            .or_else(in_state(AppState::Visualization3D))
+                            .or_else(in_state(AppState::VisualizationOrb))
+                            .or_else(in_state(AppState::VisualizationDisc))
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
+                    ),
+            )
+            // Analysis runs at a fixed rate: a slow frame runs several ticks,
+            // each taking its own window, instead of skipping frames.
+            .add_systems(
+                FixedUpdate,
+                audio_analysis_system
+                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0)
+                    .run_if(
+                        in_state(AppState::Visualization2D)
+                            .or_else(in_state(AppState::Visualization3D))
+                            .or_else(in_state(AppState::VisualizationOrb))
+                            .or_else(in_state(AppState::VisualizationDisc))
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
+                    ),
+            );
     }
 }
 
@@ -163,6 +246,8 @@ impl PlaybackInfo {
 pub enum AudioSource {
     File(PathBuf),
     Microphone,
+    // Analysis frames recorded earlier, see `recording.rs`.
+    Replay(PathBuf),
     #[default]
     None,
 }
@@ -170,37 +255,193 @@ pub enum AudioSource {
 #[derive(Resource, Default)]
 pub struct SelectedAudioSource(pub AudioSource);
 
+// The input device picked in the selection screen, by host and device name
+// (see `AudioBackend::hosts`); `None` stands for the defaults.
 #[derive(Resource, Default)]
-pub struct SelectedMic(pub Option<String>);
+pub struct SelectedMic {
+    pub host: Option<String>,
+    pub device: Option<String>,
+}
+
+impl SelectedMic {
+    // The selected host, or the default one if it is unavailable.
+    pub fn host(&self, backend: &Backend) -> String {
+        self.host
+            .clone()
+            .filter(|host| backend.0.hosts().contains(host))
+            .unwrap_or_else(|| backend.0.default_host())
+    }
+}
 
 #[derive(Resource, Clone)]
 pub struct MicAudioSender(pub Sender<Vec<f32>>);
 pub struct MicAudioReceiver(pub Receiver<Vec<f32>>);
 
 #[allow(dead_code)]
-pub struct MicStream(pub Option<cpal::Stream>);
+pub struct MicStream(pub Option<Capture>);
 
-#[derive(Resource, Default)]
+// Both sample buffers are rings of `MAX_BUFFERED_SAMPLES`, filled through
+// `push_samples`. Their storage is allocated once.
+#[derive(Resource)]
 pub struct MicAudioBuffer(pub VecDeque<f32>);
 
-#[derive(Resource, Default, Clone)]
+impl Default for MicAudioBuffer {
+    fn default() -> Self {
+        Self(VecDeque::with_capacity(MAX_BUFFERED_SAMPLES))
+    }
+}
+
+#[derive(Resource, Clone)]
 pub struct AudioSamples(pub VecDeque<f32>);
 
+impl Default for AudioSamples {
+    fn default() -> Self {
+        Self(VecDeque::with_capacity(MAX_BUFFERED_SAMPLES))
+    }
+}
+
 #[derive(Resource)]
 pub struct AudioInfo {
     pub sample_rate: u32,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct AudioAnalysis {
     pub frequency_bins: Vec<f32>,
     pub bass: f32,
+    // The bass split at the sub/punch crossover: the rumble of an 808 below,
+    // the thump of a kick above.
+    pub sub_bass: f32,
+    pub punch: f32,
     pub mid: f32,
     pub treble: f32,
     pub treble_average: f32,
     pub volume: f32,
     pub flux: f32,
+    // Peak envelope of the raw signal, updated every frame rather than on
+    // analysis ticks. See `PeakFollower`.
+    pub peak: f32,
+    // Counts the analysis frames applied. The peak, the interpolation and
+    // the idle animation rewrite this resource between ticks, so
+    // `is_changed` doesn't tell a new tick; see `TickWatcher`.
+    pub tick: u64,
+    // How fast volume, bass, mid and treble rise, per second, and 0 while
+    // they fall: hits rather than sustained levels.
+    pub volume_rise: f32,
+    pub bass_rise: f32,
+    pub mid_rise: f32,
+    pub treble_rise: f32,
+    // EBU R128 momentary (400 ms) and short-term (3 s) loudness, in LUFS.
+    pub momentary_lufs: f32,
+    pub short_term_lufs: f32,
+    // The momentary loudness in 0..1, as a modulation source.
+    pub loudness: f32,
+    // Unsmoothed, see `AnalysisFrame`. A high percussiveness means drums,
+    // a low one pads and sustained notes.
+    pub zero_crossing_rate: f32,
+    pub percussiveness: f32,
+    // Level of each tone detector in 0..1, in the order of the settings: -60
+    // dB and below is 0, a full-scale sine 1. See `AudioAnalysis::tone`.
+    pub tones: Vec<f32>,
     pub previous_spectrum: Vec<(f32, f32)>,
+    // The raw samples of the last analysis window, for waveform displays.
+    pub waveform: Vec<f32>,
+    // Upper frequency limit of each band in `frequency_bins`.
+    pub band_limits: Vec<f32>,
+    // The last few seconds of energy values, oldest first.
+    pub history: EnergyHistory,
+    // Decaying peak of each band, with `normalize_bands`.
+    band_peaks: Vec<f32>,
+}
+
+impl AudioAnalysis {
+    // The level of the tone detector called `name`, 0 if there is none.
+    pub fn tone(&self, general: &GeneralSettings, name: &str) -> f32 {
+        general
+            .tone_detectors
+            .iter()
+            .position(|detector| detector.name == name)
+            .and_then(|index| self.tones.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+// Tells the consumers that run once per analysis tick, such as the onset
+// detectors, whether a new one landed since they last looked.
+#[derive(Default)]
+pub struct TickWatcher(u64);
+
+impl TickWatcher {
+    pub fn ticked(&mut self, analysis: &AudioAnalysis) -> bool {
+        let ticked = analysis.tick != self.0;
+        self.0 = analysis.tick;
+        ticked
+    }
+}
+
+impl Default for AudioAnalysis {
+    fn default() -> Self {
+        Self {
+            frequency_bins: Vec::new(),
+            bass: 0.0,
+            sub_bass: 0.0,
+            punch: 0.0,
+            mid: 0.0,
+            treble: 0.0,
+            treble_average: 0.0,
+            volume: 0.0,
+            flux: 0.0,
+            peak: 0.0,
+            tick: 0,
+            volume_rise: 0.0,
+            bass_rise: 0.0,
+            mid_rise: 0.0,
+            treble_rise: 0.0,
+            momentary_lufs: SILENCE_LUFS,
+            short_term_lufs: SILENCE_LUFS,
+            loudness: 0.0,
+            zero_crossing_rate: 0.0,
+            percussiveness: 0.0,
+            tones: Vec::new(),
+            previous_spectrum: Vec::new(),
+            waveform: Vec::new(),
+            band_limits: Vec::new(),
+            history: EnergyHistory::default(),
+            band_peaks: Vec::new(),
+        }
+    }
+}
+
+// How much energy history `AudioAnalysis` keeps.
+const ENERGY_HISTORY_SECS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnergySample {
+    pub volume: f32,
+    pub bass: f32,
+    pub flux: f32,
+}
+
+// Ring buffer with one sample per analysis frame.
+#[derive(Default)]
+pub struct EnergyHistory {
+    samples: VecDeque<EnergySample>,
+}
+
+impl EnergyHistory {
+    pub const CAPACITY: usize = (ENERGY_HISTORY_SECS * ANALYSIS_RATE_HZ) as usize;
+
+    fn push(&mut self, sample: EnergySample) {
+        if self.samples.len() == Self::CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &EnergySample> {
+        self.samples.iter()
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -209,11 +450,14 @@ pub fn manage_audio_playback(
     selected_source: Res<SelectedAudioSource>,
     sink: NonSend<Sink>,
     mut mic_stream: NonSendMut<MicStream>,
-    mic_sender: Res<MicAudioSender>,
+    mic_opener: MicOpener,
     analysis_sender: Res<AnalysisAudioSender>,
-    selected_mic: Res<SelectedMic>,
     mut audio_samples: ResMut<AudioSamples>,
     mut playback_info: ResMut<PlaybackInfo>,
+    stretch: Res<StretchControl>,
+    eq_control: Res<EqControl>,
+    backend: Res<Backend>,
+    mut toasts: ResMut<Toasts>,
 ) {
     if !selected_source.is_changed() {
         return;
@@ -228,26 +472,31 @@ pub fn manage_audio_playback(
         AudioSource::File(path) => {
             info!("Audio source changed. Attempting to load file: {:?}", path);
 
-            let duration = match get_duration_with_symphonia(path) {
+            let decoded = match backend.0.decode_file(path) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    error!("❌ Failed to load audio file {:?}: {}", path, e);
+                    toasts.error(format!("Could not load {}: {}", display_name(path), e));
+                    return;
+                }
+            };
+
+            let duration = match backend.0.file_duration(path) {
                 Ok(d) => {
-                    info!("✅ Successfully read duration with Symphonia: {:?}", d);
+                    info!("✅ Successfully read duration: {:?}", d);
                     d
                 }
                 Err(e) => {
                     error!(
-                        "❌ Failed to get duration with Symphonia: {}. The progress bar will be incorrect.",
+                        "❌ Failed to get duration: {}. The progress bar will be incorrect.",
                         e
                     );
                     Duration::ZERO
                 }
             };
 
-            let file_bytes = std::fs::read(path).expect("Failed to read music file for playback");
-            let cursor = Cursor::new(file_bytes);
-            let source = Decoder::new(cursor).unwrap();
-
             commands.insert_resource(AudioInfo {
-                sample_rate: source.sample_rate(),
+                sample_rate: decoded.sample_rate,
             });
 
             playback_info.duration = duration;
@@ -255,52 +504,29 @@ pub fn manage_audio_playback(
             playback_info.last_update = Some(Instant::now());
             playback_info.position_at_last_update = Duration::ZERO;
 
-            let tee_source = AudioDataTee {
-                source: source.convert_samples(),
-                sender: analysis_sender.0.clone(),
-            };
+            // The analysis taps the stretched audio, as it is heard.
+            let equalized = EqSource::new(decoded.source, eq_control.clone());
+            let stretched = TimeStretch::new(equalized, stretch.clone());
+            let tee_source = AudioDataTee::new(stretched, analysis_sender.0.clone());
 
             sink.append(tee_source);
+            toasts.success(format!("Now playing {}", display_name(path)));
         }
         AudioSource::Microphone => {
             info!("Starting microphone capture");
-            let host = cpal::default_host();
-            let device = selected_mic
-                .0
-                .as_ref()
-                .and_then(|name| {
-                    host.input_devices()
-                        .ok()?
-                        .find(|d| d.name().unwrap_or_default() == *name)
-                })
-                .unwrap_or_else(|| {
-                    host.default_input_device()
-                        .expect("No default audio input device found")
-                });
-            let config = device
-                .default_input_config()
-                .expect("Failed to get default input config");
-            info!(
-                "Initializing microphone: {} with config {:?}",
-                device.name().unwrap(),
-                config
-            );
-            commands.insert_resource(AudioInfo {
-                sample_rate: config.sample_rate().0,
-            });
-            let tx = mic_sender.0.clone();
-            let stream = device
-                .build_input_stream(
-                    &config.into(),
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        tx.send(data.to_vec()).ok();
-                    },
-                    |err| error!("An error occurred on the audio stream: {}", err),
-                    None,
-                )
-                .expect("Failed to build input stream");
-            stream.play().expect("Failed to play audio stream");
-            *mic_stream = MicStream(Some(stream));
+            match mic_opener.open() {
+                Ok(capture) => {
+                    commands.insert_resource(AudioInfo {
+                        sample_rate: capture.sample_rate,
+                    });
+                    toasts.success(format!("Listening to {}", capture.device_name));
+                    *mic_stream = MicStream(Some(capture));
+                }
+                Err(message) => toasts.error(message),
+            }
+        }
+        AudioSource::Replay(path) => {
+            info!("Audio source changed. Replaying analysis from {:?}", path);
         }
         AudioSource::None => {
             info!("Stopping all audio");
@@ -308,16 +534,135 @@ pub fn manage_audio_playback(
     }
 }
 
+// Everything needed to open the microphone.
+#[derive(SystemParam)]
+pub struct MicOpener<'w> {
+    config: Res<'w, VisualsConfig>,
+    selected_mic: Res<'w, SelectedMic>,
+    backend: Res<'w, Backend>,
+    mic_sender: Res<'w, MicAudioSender>,
+    toast_sender: Res<'w, ToastSender>,
+    monitor_tap: Res<'w, MonitorTap>,
+    latency: Res<'w, MicLatency>,
+}
+
+impl MicOpener<'_> {
+    // Opens the selected input device, or the default one, streaming its
+    // samples to the analysis, and to the monitor while it is on. Returns the
+    // capture stream, or the message to show when it can't be opened.
+    fn open(&self) -> Result<Capture, String> {
+        let tx = self.mic_sender.0.clone();
+        let mut resampler = Resampler::new(ANALYSIS_SAMPLE_RATE);
+        let toast_tx = self.toast_sender.0.clone();
+        let monitor_tap = self.monitor_tap.clone();
+        let latency = self.latency.clone();
+        latency.set(None);
+        self.backend.0.open_capture(
+            &self.selected_mic.host(&self.backend),
+            self.selected_mic.device.as_deref(),
+            self.config.general.mic_buffer_frames,
+            Box::new(move |chunk| {
+                latency.set(Some(chunk.latency));
+                let mono: Vec<f32> = downmix(chunk.samples, chunk.channels).collect();
+                let samples = resampler.process(&mono, chunk.sample_rate);
+                monitor_tap.send(&samples);
+                tx.send(samples).ok();
+            }),
+            Box::new(move |err| {
+                let message = match err {
+                    CaptureError::Disconnected => "Microphone disconnected".to_string(),
+                    CaptureError::Other(msg) => format!("Microphone error: {}", msg),
+                };
+                toast_tx.send(Toast::error(message)).ok();
+            }),
+        )
+    }
+}
+
+// Latency of the microphone stream, last measured by its callback, shared
+// with the audio thread as the bits of the seconds.
+#[derive(Resource, Clone, Default)]
+pub struct MicLatency(Arc<AtomicU32>);
+
+impl MicLatency {
+    pub fn get(&self) -> Option<Duration> {
+        let secs = f32::from_bits(self.0.load(Ordering::Relaxed));
+        (secs > 0.0).then(|| Duration::from_secs_f32(secs))
+    }
+
+    fn set(&self, latency: Option<Duration>) {
+        let secs = latency.map_or(0.0, |latency| latency.as_secs_f32());
+        self.0.store(secs.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// Reopens the microphone when its buffer size changes.
+fn apply_mic_buffer_size(
+    config: Res<VisualsConfig>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+    mut mic_stream: NonSendMut<MicStream>,
+    mut buffer_frames: Local<Option<u32>>,
+) {
+    let frames = config.general.mic_buffer_frames;
+    let previous = buffer_frames.replace(frames);
+    if previous.is_none() || previous == Some(frames) {
+        return;
+    }
+    match selected_source.0 {
+        AudioSource::Microphone => selected_source.set_changed(),
+        // Mixing opens it again on the config change.
+        AudioSource::File(_) => *mic_stream = MicStream(None),
+        _ => {}
+    }
+}
+
+// Opens the microphone next to a playing file while mixing is on, and
+// closes it when mixing is turned off.
+fn manage_mic_mix(
+    config: Res<VisualsConfig>,
+    selected_source: Res<SelectedAudioSource>,
+    mic_opener: MicOpener,
+    mut mic_stream: NonSendMut<MicStream>,
+    mut mic_buffer: ResMut<MicAudioBuffer>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !config.is_changed() && !selected_source.is_changed() {
+        return;
+    }
+    if !matches!(selected_source.0, AudioSource::File(_)) {
+        return;
+    }
+    let wanted = config.general.mix.mic_with_file;
+    match (wanted, mic_stream.0.is_some()) {
+        (true, false) => match mic_opener.open() {
+            Ok(capture) => {
+                mic_buffer.0.clear();
+                toasts.success(format!("Mixing in {}", capture.device_name));
+                *mic_stream = MicStream(Some(capture));
+            }
+            Err(message) => toasts.error(message),
+        },
+        (false, true) => *mic_stream = MicStream(None),
+        _ => {}
+    }
+}
+
 #[allow(clippy::collapsible_if)]
 fn apply_playback_changes(
     mut playback_info: ResMut<PlaybackInfo>,
     sink: NonSend<Sink>,
+    stretch: Res<StretchControl>,
+    eq_control: Res<EqControl>,
+    config: Res<VisualsConfig>,
     selected_source: Res<SelectedAudioSource>,
     analysis_sender: Res<AnalysisAudioSender>,
+    backend: Res<Backend>,
+    mut toasts: ResMut<Toasts>,
 ) {
-    if !playback_info.is_changed() {
+    if !playback_info.is_changed() && !config.is_changed() {
         return;
     }
+    let rate = playback_rate(&sink, &stretch);
 
     match playback_info.status {
         PlaybackStatus::Playing => {
@@ -329,28 +674,30 @@ fn apply_playback_changes(
         }
         PlaybackStatus::Paused => {
             if !sink.is_paused() {
-                sink.pause();
-                if let Some(last_update) = playback_info.last_update.take() {
-                    let elapsed = last_update.elapsed().as_secs_f32() * sink.speed();
-                    playback_info.position =
-                        playback_info.position_at_last_update + Duration::from_secs_f32(elapsed);
-                }
-                playback_info.last_update = None;
+                pause_sink(&mut playback_info, &sink, rate);
             }
         }
     }
 
-    if sink.speed() != playback_info.speed {
+    // With pitch preservation the speed goes to the time-stretch and the
+    // sink plays at 1x; otherwise the sink resamples as before.
+    let (sink_speed, stretch_speed) = if config.general.preserve_pitch {
+        (1.0, playback_info.speed)
+    } else {
+        (playback_info.speed, 1.0)
+    };
+    if sink.speed() != sink_speed || stretch.speed() != stretch_speed {
         if !sink.is_paused() {
             if let Some(last_update) = playback_info.last_update.take() {
-                let elapsed = last_update.elapsed().as_secs_f32() * sink.speed();
+                let elapsed = last_update.elapsed().as_secs_f32() * rate;
                 playback_info.position =
                     playback_info.position_at_last_update + Duration::from_secs_f32(elapsed);
             }
             playback_info.last_update = Some(Instant::now());
             playback_info.position_at_last_update = playback_info.position;
         }
-        sink.set_speed(playback_info.speed);
+        sink.set_speed(sink_speed);
+        stretch.set_speed(stretch_speed);
     }
 
     if let Some(seek_pos_secs) = playback_info.seek_to.take() {
@@ -358,16 +705,20 @@ fn apply_playback_changes(
             info!("Seeking to {} seconds", seek_pos_secs);
             let seek_duration = Duration::from_secs_f32(seek_pos_secs);
 
-            let file_bytes = std::fs::read(path).expect("Failed to read music file for seeking");
-            let cursor = Cursor::new(file_bytes);
-            let source = Decoder::new(cursor).unwrap();
+            let decoded = match backend.0.decode_file(path) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    error!("❌ Failed to reopen {:?} for seeking: {}", path, e);
+                    toasts.error(format!("Could not seek in {}: {}", display_name(path), e));
+                    return;
+                }
+            };
 
-            let new_source = source.skip_duration(seek_duration).convert_samples();
+            let new_source = decoded.source.skip_duration(seek_duration);
+            let equalized = EqSource::new(new_source, eq_control.clone());
+            let stretched = TimeStretch::new(equalized, stretch.clone());
 
-            let tee_source = AudioDataTee {
-                source: new_source,
-                sender: analysis_sender.0.clone(),
-            };
+            let tee_source = AudioDataTee::new(stretched, analysis_sender.0.clone());
 
             sink.stop();
             sink.clear();
@@ -387,10 +738,15 @@ fn apply_playback_changes(
     }
 }
 
-fn update_playback_position(mut playback_info: ResMut<PlaybackInfo>, sink: NonSend<Sink>) {
+fn update_playback_position(
+    mut playback_info: ResMut<PlaybackInfo>,
+    sink: NonSend<Sink>,
+    stretch: Res<StretchControl>,
+) {
     if playback_info.status == PlaybackStatus::Playing {
         if let Some(last_update) = playback_info.last_update {
-            let elapsed_since_update = last_update.elapsed().as_secs_f32() * sink.speed();
+            let elapsed_since_update =
+                last_update.elapsed().as_secs_f32() * playback_rate(&sink, &stretch);
             let new_pos = playback_info.position_at_last_update
                 + Duration::from_secs_f32(elapsed_since_update);
 
@@ -407,158 +763,502 @@ fn update_playback_position(mut playback_info: ResMut<PlaybackInfo>, sink: NonSe
     }
 }
 
+// Pauses the sink, keeping the position it stopped at.
+fn pause_sink(playback_info: &mut PlaybackInfo, sink: &Sink, rate: f32) {
+    sink.pause();
+    if let Some(last_update) = playback_info.last_update.take() {
+        let elapsed = last_update.elapsed().as_secs_f32() * rate;
+        playback_info.position =
+            playback_info.position_at_last_update + Duration::from_secs_f32(elapsed);
+    }
+}
+
+// With `pause_in_menu`, a file playing when the menus open is paused, and
+// resumed once a visualization is back. Otherwise it keeps playing.
+fn pause_in_menus(
+    app_state: Res<State<AppState>>,
+    config: Res<VisualsConfig>,
+    sink: NonSend<Sink>,
+    stretch: Res<StretchControl>,
+    mut playback_info: ResMut<PlaybackInfo>,
+    mut paused_for_menu: Local<bool>,
+) {
+    if !app_state.is_changed() {
+        return;
+    }
+    let in_menu = VisualizerId::from_state(app_state.get()).is_none();
+    if in_menu {
+        if config.general.pause_in_menu && playback_info.status == PlaybackStatus::Playing {
+            pause_sink(&mut playback_info, &sink, playback_rate(&sink, &stretch));
+            playback_info.status = PlaybackStatus::Paused;
+            *paused_for_menu = true;
+        }
+    } else if std::mem::take(&mut *paused_for_menu) {
+        // Played again by `apply_playback_changes`.
+        playback_info.status = PlaybackStatus::Playing;
+    }
+}
+
+// How many seconds of the file play per second.
+fn playback_rate(sink: &Sink, stretch: &StretchControl) -> f32 {
+    sink.speed() * stretch.speed()
+}
+
 pub fn read_analysis_data_system(
     receiver: Option<NonSend<AnalysisAudioReceiver>>,
     mut buffer: ResMut<AudioSamples>,
+    mut eq: ResMut<AnalysisEq>,
+    mut follower: ResMut<PeakFollower>,
 ) {
     if let Some(receiver) = receiver {
-        buffer.0.extend(receiver.0.try_iter());
+        let mut new_data: Vec<f32> = receiver.0.try_iter().collect();
+        eq.process(&mut new_data);
+        push_samples(&mut follower.file, new_data.iter().copied());
+        push_samples(&mut buffer.0, new_data);
     }
 }
 
 pub fn read_mic_data_system(
     receiver: Option<NonSend<MicAudioReceiver>>,
     mut buffer: ResMut<MicAudioBuffer>,
+    mut eq: ResMut<AnalysisEq>,
+    mut follower: ResMut<PeakFollower>,
 ) {
     if let Some(receiver) = receiver {
-        for new_data in receiver.0.try_iter() {
-            buffer.0.extend(new_data);
+        for mut new_data in receiver.0.try_iter() {
+            eq.process(&mut new_data);
+            push_samples(&mut follower.mic, new_data.iter().copied());
+            push_samples(&mut buffer.0, new_data);
         }
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn audio_analysis_system(
+// A peak envelope follower on the raw signal, run sample by sample as they
+// arrive instead of on analysis ticks, for elements that should react within
+// a frame. File samples wait out the playback latency first, so the level
+// follows what is heard. The microphone isn't mixed in over a file.
+#[derive(Resource, Default)]
+pub struct PeakFollower {
+    file: VecDeque<f32>,
+    mic: VecDeque<f32>,
+    level: f32,
+}
+
+fn follow_peak(
     time: Res<Time>,
-    mut analysis_timer: ResMut<AnalysisTimer>,
+    audio_info: Option<Res<AudioInfo>>,
+    audio_source: Res<SelectedAudioSource>,
+    config: Res<VisualsConfig>,
+    gate: Res<SilenceGate>,
+    mut follower: ResMut<PeakFollower>,
     mut audio_analysis: ResMut<AudioAnalysis>,
+) {
+    let follower = &mut *follower;
+    let mix = &config.general.mix;
+    let (samples, delay, gain) = match &audio_source.0 {
+        AudioSource::File(_) => {
+            follower.mic.clear();
+            let latency = audio_info.map_or(0.0, |info| playback_latency(&config.general, &info));
+            let delay = (latency * ANALYSIS_SAMPLE_RATE as f32) as usize;
+            (&mut follower.file, delay, mix.file_gain)
+        }
+        AudioSource::Microphone => {
+            follower.file.clear();
+            (&mut follower.mic, 0, mix.mic_gain)
+        }
+        AudioSource::Replay(_) | AudioSource::None => {
+            follower.file.clear();
+            follower.mic.clear();
+            follower.level = 0.0;
+            if audio_analysis.peak != 0.0 {
+                audio_analysis.peak = 0.0;
+            }
+            return;
+        }
+    };
+
+    let envelope = &config.general.smoothing.peak;
+    let coefficient =
+        |seconds: f32| (-1.0 / (seconds.max(1e-5) * ANALYSIS_SAMPLE_RATE as f32)).exp();
+    let attack = coefficient(envelope.attack);
+    let release = coefficient(envelope.release);
+    let ready = samples.len().saturating_sub(delay);
+    if ready == 0 {
+        // Paused, or starved: let the level fall on its own.
+        follower.level = smooth(envelope, follower.level, 0.0, time.delta_seconds());
+    }
+    for sample in samples.drain(..ready) {
+        let input = (sample * gain).abs();
+        let coefficient = if input > follower.level {
+            attack
+        } else {
+            release
+        };
+        follower.level = input + (follower.level - input) * coefficient;
+    }
+
+    let gated = gate.is_closed(&config.general.silence_gate);
+    let peak = if gated { 0.0 } else { follower.level };
+    // Only on changes, so silence doesn't mark the analysis changed.
+    if audio_analysis.peak != peak {
+        audio_analysis.peak = peak;
+    }
+}
+
+// Appends to a sample ring, dropping the oldest samples once it holds
+// `MAX_BUFFERED_SAMPLES`, so it never grows past its first allocation.
+fn push_samples(buffer: &mut VecDeque<f32>, samples: impl IntoIterator<Item = f32>) {
+    for sample in samples {
+        if buffer.len() >= MAX_BUFFERED_SAMPLES {
+            buffer.pop_front();
+        }
+        buffer.push_back(sample);
+    }
+}
+
+// Band peaks never fall below this, so silence and noise aren't scaled up to
+// full height. A full-scale sine peaks its band around 16.
+const BAND_PEAK_FLOOR: f32 = 0.05;
+
+// Divides each band by its peak, which jumps to any louder value and halves
+// every `normalize_release` seconds otherwise. Bands then peak at 1.
+fn normalize_bands(
+    audio_analysis: &mut AudioAnalysis,
+    smoothing: &SmoothingSettings,
+    bins: &mut [f32],
+    dt: f32,
+) {
+    let peaks = &mut audio_analysis.band_peaks;
+    if peaks.len() != bins.len() {
+        peaks.clear();
+        peaks.resize(bins.len(), BAND_PEAK_FLOOR);
+    }
+    let decay = 0.5f32.powf(dt / smoothing.normalize_release.max(0.01));
+    for (peak, value) in peaks.iter_mut().zip(bins) {
+        *peak = (*peak * decay).max(*value).max(BAND_PEAK_FLOOR);
+        *value /= *peak;
+    }
+}
+
+// One-pole follower moving `current` toward `target` over a step of `dt` seconds.
+fn smooth(envelope: &Envelope, current: f32, target: f32, dt: f32) -> f32 {
+    let time_constant = if target > current {
+        envelope.attack
+    } else {
+        envelope.release
+    };
+    if time_constant <= 0.0 {
+        return target;
+    }
+    target + (current - target) * (-dt / time_constant).exp()
+}
+
+// How far behind the newest pulled sample the visuals should be, in seconds.
+fn playback_latency(general: &GeneralSettings, audio_info: &AudioInfo) -> f32 {
+    let auto = if general.auto_latency {
+        // The backend doesn't report the device buffer it picked; assume it holds
+        // about half an analysis window, which also centres the window on
+        // what is being heard.
+        FFT_SIZE as f32 / 2.0 / audio_info.sample_rate as f32
+    } else {
+        0.0
+    };
+    (auto + general.latency_offset_ms / 1000.0).max(0.0)
+}
+
+// Cuts the next analysis window out of the active buffer and hands it to the
+// analysis thread.
+#[allow(clippy::too_many_arguments)]
+pub fn audio_analysis_system(
+    fixed_time: Res<Time<Fixed>>,
+    virtual_time: Res<Time<Virtual>>,
     audio_info: Option<Res<AudioInfo>>,
     audio_source: Res<SelectedAudioSource>,
     mut audio_samples: ResMut<AudioSamples>,
     mut mic_buffer: ResMut<MicAudioBuffer>,
     config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    request_sender: Res<AnalysisRequestSender>,
+    offline: Res<OfflineAnalysis>,
 ) {
-    analysis_timer.0.tick(time.delta());
-    if !analysis_timer.0.just_finished() {
-        return;
-    }
-
     let Some(audio_info) = audio_info else { return };
-    let fft_size = 4096;
 
-    let analysis_buffer: Option<Vec<f32>> = match &audio_source.0 {
+    // Ticks catching up after a slow frame are behind the newest samples too.
+    let behind = virtual_time
+        .elapsed()
+        .saturating_sub(fixed_time.elapsed())
+        .as_secs_f32();
+    let mix = &config.general.mix;
+    // File samples reach the buffer when the sink pulls them, ahead of the
+    // speakers, so the window is taken that far back from the newest sample.
+    // A microphone mixed in is only as late as the tick.
+    let (window, consumed) = match &audio_source.0 {
+        // A file analysed ahead is played from its track instead.
+        AudioSource::File(path) if offline.track_for(path).is_some() => return,
         AudioSource::File(_) => {
-            if audio_samples.0.len() < fft_size {
-                None
-            } else {
-                let buffer_len = audio_samples.0.len();
-                let analysis_vec = audio_samples.0.iter().copied().take(fft_size).collect();
-                let drain_amount = buffer_len.saturating_sub(fft_size / 2);
-                audio_samples.0.drain(..drain_amount);
-                Some(analysis_vec)
+            let latency = playback_latency(&config.general, &audio_info);
+            let Some((mut window, mut consumed)) =
+                take_window(&mut audio_samples.0, latency + behind)
+            else {
+                return;
+            };
+            scale(&mut window, mix.file_gain);
+            scale(&mut consumed, mix.file_gain);
+            if mix.mic_with_file {
+                if let Some((mic_window, mic_consumed)) = take_window(&mut mic_buffer.0, behind) {
+                    mix_into(&mut window, &mic_window, mix.mic_gain);
+                    mix_into(&mut consumed, &mic_consumed, mix.mic_gain);
+                }
             }
+            (window, consumed)
         }
         AudioSource::Microphone => {
-            if mic_buffer.0.len() < fft_size {
-                None
-            } else {
-                let buffer_len = mic_buffer.0.len();
-                let analysis_vec = mic_buffer.0.iter().copied().take(fft_size).collect();
-                let drain_amount = buffer_len.saturating_sub(fft_size / 2);
-                mic_buffer.0.drain(..drain_amount);
-                Some(analysis_vec)
-            }
+            let Some((mut window, mut consumed)) = take_window(&mut mic_buffer.0, behind) else {
+                return;
+            };
+            scale(&mut window, mix.mic_gain);
+            scale(&mut consumed, mix.mic_gain);
+            (window, consumed)
         }
-        AudioSource::None => None,
+        AudioSource::Replay(_) | AudioSource::None => return,
     };
 
-    let Some(samples_slice) = analysis_buffer else {
+    // The band count follows the settings of the visualizer currently on screen.
+    let Some(viz_id) = VisualizerId::from_state(app_state.get()) else {
         return;
     };
 
-    let hann_window = hann_window(&samples_slice);
+    // The thread only goes away with the app.
+    let _ = request_sender.0.send(AnalysisRequest {
+        window,
+        consumed,
+        sample_rate: ANALYSIS_SAMPLE_RATE,
+        num_bands: config.num_bands(viz_id),
+        min_frequency: config.general.min_frequency,
+        max_frequency: config.general.max_frequency,
+        band_edges: config.band_edges(viz_id),
+        weighting: config.general.weighting,
+        tones: config
+            .general
+            .tone_detectors
+            .iter()
+            .map(|detector| detector.frequency)
+            .collect(),
+        long_fft_size: config.general.quality.long_fft_size(),
+    });
+}
 
-    let spectrum = samples_fft_to_spectrum(
-        &hann_window,
-        audio_info.sample_rate,
-        FrequencyLimit::Range(20.0, 20000.0),
-        Some(&divide_by_N_sqrt),
-    )
-    .expect("Failed to compute spectrum");
+// Cuts an analysis window ending `delay` seconds before the newest sample
+// out of a buffer, with the samples before it, which are done with: later
+// ticks look further ahead.
+fn take_window(buffer: &mut VecDeque<f32>, delay: f32) -> Option<(Vec<f32>, Vec<f32>)> {
+    // The buffers hold mono samples at the analysis rate, see `downmix`.
+    let delay = (delay * ANALYSIS_SAMPLE_RATE as f32) as usize;
+    if buffer.len() < FFT_SIZE + delay {
+        return None;
+    }
+    let window_start = buffer.len() - delay - FFT_SIZE;
+    let window = buffer
+        .range(window_start..window_start + FFT_SIZE)
+        .copied()
+        .collect();
+    let consumed = buffer.drain(..window_start).collect();
+    Some((window, consumed))
+}
 
-    let squared_sum = samples_slice.iter().map(|s| s * s).sum::<f32>();
-    audio_analysis.volume = (squared_sum / samples_slice.len() as f32).sqrt();
+fn scale(samples: &mut [f32], gain: f32) {
+    if gain != 1.0 {
+        samples.iter_mut().for_each(|sample| *sample *= gain);
+    }
+}
 
-    let spectrum_data: Vec<(f32, f32)> = spectrum
-        .data()
-        .iter()
-        .map(|(f, v)| (f.val(), v.val()))
-        .collect();
+// Adds `other` times `gain` to `samples`, lining up their ends: the two
+// sources run at the same rate but not in step, so only their newest
+// samples are known to match.
+fn mix_into(samples: &mut [f32], other: &[f32], gain: f32) {
+    for (sample, other) in samples.iter_mut().rev().zip(other.iter().rev()) {
+        *sample += other * gain;
+    }
+}
 
-    if !audio_analysis.previous_spectrum.is_empty()
-        && audio_analysis.previous_spectrum.len() == spectrum_data.len()
-    {
-        let sum_of_squared_diffs = spectrum_data
-            .iter()
-            .zip(&audio_analysis.previous_spectrum)
-            .map(|((_, cur_mag), (_, prev_mag))| (cur_mag - prev_mag).powi(2))
-            .sum::<f32>();
-        audio_analysis.flux = sum_of_squared_diffs.sqrt();
-    } else {
-        audio_analysis.flux = 0.0;
+// Smooths the frames coming back from the analysis thread into `AudioAnalysis`.
+pub fn apply_analysis_frames(
+    receiver: Option<NonSend<AnalysisFrameReceiver>>,
+    fixed_time: Res<Time<Fixed>>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+    mut recorder: ResMut<SessionRecorder>,
+    mut gate: ResMut<SilenceGate>,
+    config: Res<VisualsConfig>,
+) {
+    let Some(receiver) = receiver else { return };
+    let dt = fixed_time.timestep().as_secs_f32();
+
+    for mut frame in receiver.0.try_iter() {
+        gate.apply(&config.general.silence_gate, &mut frame, dt);
+        recorder.capture(&frame);
+        apply_analysis_frame(
+            &mut audio_analysis,
+            &config.general.smoothing,
+            &config.general.crossovers,
+            frame,
+            dt,
+        );
     }
+}
 
-    let num_bands = config.num_bands;
-    let mut new_bins = vec![0.0; num_bands];
-    let min_freq = 20.0f32;
-    let max_freq = 20000.0f32;
-    let band_limits: Vec<f32> = (0..num_bands)
-        .map(|i| min_freq * (max_freq / min_freq).powf((i as f32 + 1.0) / num_bands as f32))
-        .collect();
+// Silences the frames of an input that stays under the gate threshold, so
+// the noise floor of a live microphone doesn't flicker the visuals. Frames
+// are recorded as gated.
+#[derive(Resource, Default)]
+pub struct SilenceGate {
+    // Seconds the input has been under the threshold.
+    below_for: f32,
+}
 
-    let mut current_band = 0;
-    let mut treble_val = 0.0;
+impl SilenceGate {
+    // Once closed this long, the smoothed outputs have faded out and the
+    // visualizers can stop updating.
+    const PAUSE_AFTER_SECS: f32 = 1.0;
 
-    for (freq, val) in spectrum.data() {
-        if current_band < num_bands - 1 && freq.val() > band_limits[current_band] {
-            current_band += 1;
+    fn apply(&mut self, settings: &SilenceGateSettings, frame: &mut AnalysisFrame, dt: f32) {
+        let threshold = 10f32.powf(settings.threshold_db / 20.0);
+        if !settings.enabled || frame.volume >= threshold {
+            self.below_for = 0.0;
+            return;
         }
-        new_bins[current_band] += val.val();
-
-        if freq.val() > 4000.0 {
-            treble_val += val.val();
+        self.below_for += dt;
+        if self.below_for < settings.hold {
+            return;
         }
+        // Zero targets, so the outputs decay through the usual smoothing.
+        frame.volume = 0.0;
+        frame.flux = 0.0;
+        frame.treble_energy = 0.0;
+        frame.momentary_lufs = SILENCE_LUFS;
+        frame.short_term_lufs = SILENCE_LUFS;
+        frame.zero_crossing_rate = 0.0;
+        frame.percussiveness = 0.0;
+        frame.tones.fill(0.0);
+        frame.bins.fill(0.0);
+        frame
+            .spectrum
+            .iter_mut()
+            .for_each(|(_, magnitude)| *magnitude = 0.0);
+        frame.waveform.fill(0.0);
+    }
+
+    fn is_closed(&self, settings: &SilenceGateSettings) -> bool {
+        settings.enabled && self.below_for >= settings.hold
     }
 
-    let smoothing = 0.5;
+    fn is_settled(&self, settings: &SilenceGateSettings) -> bool {
+        settings.enabled && self.below_for >= settings.hold + Self::PAUSE_AFTER_SECS
+    }
+}
+
+// Run condition of the costlier visualizer updates: false once the gate has
+// been closed long enough for the scene to come to rest, if pausing is on.
+// The idle animation keeps them running.
+pub fn visuals_awake(
+    config: Res<VisualsConfig>,
+    gate: Res<SilenceGate>,
+    idle: Res<IdleMode>,
+) -> bool {
+    let settings = &config.general.silence_gate;
+    !(settings.pause_visuals && gate.is_settled(settings)) || idle.active
+}
+
+// Folds one raw frame into `AudioAnalysis`, `dt` seconds after the previous one.
+pub fn apply_analysis_frame(
+    audio_analysis: &mut AudioAnalysis,
+    smoothing: &SmoothingSettings,
+    crossovers: &CrossoverSettings,
+    mut frame: AnalysisFrame,
+    dt: f32,
+) {
+    audio_analysis.tick += 1;
+    let num_bands = frame.bins.len();
+    if smoothing.normalize_bands {
+        normalize_bands(audio_analysis, smoothing, &mut frame.bins, dt);
+    }
+
+    let previous = [
+        audio_analysis.volume,
+        audio_analysis.bass,
+        audio_analysis.mid,
+        audio_analysis.treble,
+    ];
+
+    // Each output rises with its attack time and falls with its release time.
+    audio_analysis.volume = smooth(&smoothing.volume, audio_analysis.volume, frame.volume, dt);
+    audio_analysis.flux = frame.flux;
+    // Already averaged over their windows.
+    audio_analysis.momentary_lufs = frame.momentary_lufs;
+    audio_analysis.short_term_lufs = frame.short_term_lufs;
+    audio_analysis.loudness = loudness_level(frame.momentary_lufs);
+    audio_analysis.zero_crossing_rate = frame.zero_crossing_rate;
+    audio_analysis.percussiveness = frame.percussiveness;
+
     if audio_analysis.frequency_bins.len() != num_bands {
         audio_analysis.frequency_bins.resize(num_bands, 0.0);
     }
-
-    for (i, bin_val) in new_bins.iter().take(num_bands).enumerate() {
-        audio_analysis.frequency_bins[i] =
-            audio_analysis.frequency_bins[i] * smoothing + bin_val * (1.0 - smoothing);
+    for (i, bin_val) in frame.bins.iter().enumerate() {
+        audio_analysis.frequency_bins[i] = smooth(
+            &smoothing.bins,
+            audio_analysis.frequency_bins[i],
+            *bin_val,
+            dt,
+        );
     }
 
-    audio_analysis.treble_average =
-        audio_analysis.treble_average * smoothing + treble_val * (1.0 - smoothing);
+    audio_analysis.tones.resize(frame.tones.len(), 0.0);
+    for (level, amplitude) in audio_analysis.tones.iter_mut().zip(&frame.tones) {
+        let target = (1.0 + amplitude.max(1e-6).log10() / 3.0).max(0.0);
+        *level = smooth(&smoothing.volume, *level, target, dt);
+    }
 
-    audio_analysis.bass = audio_analysis
-        .frequency_bins
-        .iter()
-        .take(num_bands / 4)
-        .sum();
-    audio_analysis.mid = audio_analysis
-        .frequency_bins
+    audio_analysis.treble_average = smooth(
+        &smoothing.treble,
+        audio_analysis.treble_average,
+        frame.treble_energy,
+        dt,
+    );
+
+    // Sub-bass, punch, mid and treble.
+    let mut ranges = [0.0f32; 4];
+    for (value, upper) in frame.bins.iter().zip(&frame.band_limits) {
+        let range = [
+            crossovers.sub_punch,
+            crossovers.bass_mid,
+            crossovers.mid_treble,
+        ]
         .iter()
-        .skip(num_bands / 4)
-        .take(num_bands / 2)
-        .sum();
-    audio_analysis.treble = audio_analysis
-        .frequency_bins
-        .iter()
-        .skip(3 * num_bands / 4)
-        .sum();
+        .filter(|&&crossover| *upper > crossover)
+        .count();
+        ranges[range] += value;
+    }
+    let [sub_bass, punch, mid, treble] = ranges;
+    let bass = sub_bass + punch;
+    audio_analysis.bass = smooth(&smoothing.bass, audio_analysis.bass, bass, dt);
+    audio_analysis.sub_bass = smooth(&smoothing.bass, audio_analysis.sub_bass, sub_bass, dt);
+    audio_analysis.punch = smooth(&smoothing.bass, audio_analysis.punch, punch, dt);
+    audio_analysis.mid = smooth(&smoothing.mid, audio_analysis.mid, mid, dt);
+    audio_analysis.treble = smooth(&smoothing.treble, audio_analysis.treble, treble, dt);
+
+    let rise = |previous: f32, current: f32| ((current - previous) / dt.max(1e-6)).max(0.0);
+    audio_analysis.volume_rise = rise(previous[0], audio_analysis.volume);
+    audio_analysis.bass_rise = rise(previous[1], audio_analysis.bass);
+    audio_analysis.mid_rise = rise(previous[2], audio_analysis.mid);
+    audio_analysis.treble_rise = rise(previous[3], audio_analysis.treble);
+
+    let sample = EnergySample {
+        volume: audio_analysis.volume,
+        bass: audio_analysis.bass,
+        flux: audio_analysis.flux,
+    };
+    audio_analysis.history.push(sample);
 
-    audio_analysis.previous_spectrum = spectrum_data;
+    audio_analysis.previous_spectrum = frame.spectrum;
+    audio_analysis.band_limits = frame.band_limits;
+    audio_analysis.waveform = frame.waveform;
 }
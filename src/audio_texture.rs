@@ -0,0 +1,94 @@
+// src/audio_texture.rs
+
+use crate::audio::{apply_analysis_frames, AudioAnalysis};
+use crate::audio_uniforms::AudioUniformsPlugin;
+use crate::config::VisualsConfig;
+use crate::gpu_fft::GpuFftPlugin;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+// As wide as Shadertoy's audio input, so its shaders read it unchanged.
+pub const AUDIO_TEXTURE_WIDTH: u32 = 512;
+
+pub struct AudioTexturePlugin;
+
+impl Plugin for AudioTexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((AudioUniformsPlugin, GpuFftPlugin))
+            .add_systems(Startup, setup_audio_texture)
+            .add_systems(Update, update_audio_texture.after(apply_analysis_frames));
+    }
+}
+
+// The latest analysis frame as a 512x2 texture for the shader visualizers:
+// the spectrum in row 0 and the waveform in row 1, both stretched over its
+// width. Like Shadertoy's, the waveform is centered on 0.5. A material only
+// picks up the new data when it is itself modified, which the visualizers do
+// every frame.
+#[derive(Resource)]
+pub struct AudioTexture(pub Handle<Image>);
+
+fn setup_audio_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // Silence: an empty spectrum and a flat waveform.
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: AUDIO_TEXTURE_WIDTH,
+            height: 2,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0],
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::default(),
+    );
+    image.data[AUDIO_TEXTURE_WIDTH as usize..].fill(128);
+    commands.insert_resource(AudioTexture(images.add(image)));
+}
+
+// The bands or waveform for the `bins` and `waveform` storage arrays of the
+// shader materials, which unlike the texture hold every value at full
+// precision. A storage binding can't be empty, so silence is a single zero.
+pub fn storage_array(values: &[f32]) -> Vec<f32> {
+    if values.is_empty() {
+        vec![0.0]
+    } else {
+        values.to_vec()
+    }
+}
+
+// Rewritten on each new analysis frame. The spectrum is scaled by the bass
+// sensitivity.
+fn update_audio_texture(
+    config: Res<VisualsConfig>,
+    audio_analysis: Res<AudioAnalysis>,
+    audio_texture: Option<Res<AudioTexture>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !audio_analysis.is_changed() {
+        return;
+    }
+    let Some(image) = audio_texture.and_then(|texture| images.get_mut(&texture.0)) else {
+        return;
+    };
+    let width = AUDIO_TEXTURE_WIDTH as usize;
+    let gain = config.general.bass_sensitivity;
+    let spectrum = &audio_analysis.frequency_bins;
+    let waveform = &audio_analysis.waveform;
+
+    let (spectrum_row, waveform_row) = image.data.split_at_mut(width);
+    for (x, texel) in spectrum_row.iter_mut().enumerate() {
+        let amplitude = spectrum
+            .get(x * spectrum.len() / width)
+            .copied()
+            .unwrap_or(0.0);
+        *texel = ((amplitude * gain).clamp(0.0, 1.0) * 255.0) as u8;
+    }
+    for (x, texel) in waveform_row.iter_mut().enumerate() {
+        let sample = waveform
+            .get(x * waveform.len() / width)
+            .copied()
+            .unwrap_or(0.0);
+        *texel = ((sample * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0) as u8;
+    }
+}
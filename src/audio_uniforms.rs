@@ -0,0 +1,113 @@
+// src/audio_uniforms.rs
+
+use crate::audio::AudioAnalysis;
+use crate::camera::MainCamera2D;
+use crate::interpolate::interpolate_analysis;
+use crate::lfo::LfoBank;
+use crate::loudness::loudness_level;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_resource::{encase, Buffer, BufferDescriptor, BufferUsages, ShaderType};
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::window::PrimaryWindow;
+
+// What every shader visualizer reads, filled once per frame into a single
+// GPU buffer that all the materials bind. A new shader visualizer only has
+// to bind `AudioUniformsBuffer` at binding 5 and import `audio_uniforms.wgsl`,
+// and its own update system only handles its own settings.
+pub struct AudioUniformsPlugin;
+
+impl Plugin for AudioUniformsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractResourcePlugin::<AudioUniforms>::default(),
+            ExtractResourcePlugin::<AudioUniformsBuffer>::default(),
+        ))
+        .init_resource::<AudioUniforms>()
+        .add_systems(Startup, setup_audio_uniforms_buffer)
+        .add_systems(Update, update_audio_uniforms.after(interpolate_analysis));
+
+        app.sub_app_mut(RenderApp)
+            .add_systems(Render, write_audio_uniforms.in_set(RenderSet::Prepare));
+    }
+}
+
+#[derive(Resource, Clone, Default, ExtractResource, ShaderType)]
+pub struct AudioUniforms {
+    pub time: Vec4,       // x=elapsed seconds, y=time delta, z=camera zoom, w=unused
+    pub resolution: Vec4, // x=width, y=height in physical pixels, z,w=unused
+    pub levels: Vec4,     // x=bass, y=mid, z=treble, w=flux
+    pub loudness: Vec4,   // x=volume, y=peak, z=momentary, w=short-term loudness, 0..1
+    pub lfo: Vec4,        // LFO bank outputs, 0..1
+}
+
+// Bound as a read-only storage buffer, as `AsBindGroup` can take a buffer
+// as is for those, but not for uniforms. Created once and only rewritten, so
+// the materials' bind groups stay valid.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct AudioUniformsBuffer(pub Buffer);
+
+fn setup_audio_uniforms_buffer(mut commands: Commands, render_device: Res<RenderDevice>) {
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("audio_uniforms_buffer"),
+        size: AudioUniforms::min_size().get(),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    commands.insert_resource(AudioUniformsBuffer(buffer));
+}
+
+fn update_audio_uniforms(
+    time: Res<Time>,
+    audio_analysis: Res<AudioAnalysis>,
+    lfo_bank: Res<LfoBank>,
+    mut uniforms: ResMut<AudioUniforms>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<&OrthographicProjection, With<MainCamera2D>>,
+) {
+    let zoom_level = q_camera
+        .get_single()
+        .map_or(1.0, |projection| projection.scale);
+    uniforms.time = Vec4::new(
+        time.elapsed_seconds(),
+        time.delta_seconds(),
+        zoom_level,
+        0.0,
+    );
+    if let Ok(window) = q_window.get_single() {
+        uniforms.resolution = Vec4::new(
+            window.resolution.physical_width() as f32,
+            window.resolution.physical_height() as f32,
+            0.0,
+            0.0,
+        );
+    }
+    uniforms.levels = Vec4::new(
+        audio_analysis.bass,
+        audio_analysis.mid,
+        audio_analysis.treble,
+        audio_analysis.flux,
+    );
+    uniforms.loudness = Vec4::new(
+        audio_analysis.volume,
+        audio_analysis.peak,
+        audio_analysis.loudness,
+        loudness_level(audio_analysis.short_term_lufs),
+    );
+    uniforms.lfo = lfo_bank.as_vec4();
+}
+
+fn write_audio_uniforms(
+    uniforms: Option<Res<AudioUniforms>>,
+    buffer: Option<Res<AudioUniformsBuffer>>,
+    render_queue: Res<RenderQueue>,
+) {
+    let (Some(uniforms), Some(buffer)) = (uniforms, buffer) else {
+        return;
+    };
+    let mut bytes = encase::StorageBuffer::new(Vec::new());
+    if bytes.write(&*uniforms).is_ok() {
+        render_queue.write_buffer(&buffer.0, 0, bytes.as_ref());
+    }
+}
@@ -0,0 +1,351 @@
+// src/backend.rs
+
+use bevy::log::{error, info};
+use bevy::prelude::Resource;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+use std::any::Any;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+// What the app needs from the platform's audio: listing input devices,
+// capturing from one, playing and decoding files. Everything else works on
+// the samples these hand over, so another backend (JACK, the browser, a
+// silent one for tests) only has to implement this trait. Playback stays a
+// rodio output, as the whole playback chain is made of rodio sources.
+pub trait AudioBackend: Send + Sync {
+    // The APIs capture can go through by name, e.g. ASIO or JACK next to the
+    // platform default, and the one used when none is picked.
+    fn hosts(&self) -> Vec<String>;
+    fn default_host(&self) -> String;
+    // The input devices of `host`, by name.
+    fn input_devices(&self, host: &str) -> Vec<String>;
+    // The device capture would open: `device` if `host` has it, else the
+    // default one.
+    fn resolve_input(&self, host: &str, device: Option<&str>) -> Option<InputDevice>;
+    // Starts capturing, calling `on_data` from the audio thread with each
+    // buffer, until the returned stream is dropped. `buffer_frames` is a
+    // hint, 0 leaving it to the backend. Errors are the message to show.
+    fn open_capture(
+        &self,
+        host: &str,
+        device: Option<&str>,
+        buffer_frames: u32,
+        on_data: CaptureCallback,
+        on_error: CaptureErrorCallback,
+    ) -> Result<Capture, String>;
+    fn open_playback(&self) -> Result<Playback, String>;
+    fn decode_file(&self, path: &Path) -> Result<DecodedFile, Box<dyn Error>>;
+    // Read from the container, without decoding.
+    fn file_duration(&self, path: &Path) -> Result<Duration, Box<dyn Error>>;
+}
+
+// The backend in use, shared with the threads that decode files.
+#[derive(Resource, Clone)]
+pub struct Backend(pub Arc<dyn AudioBackend>);
+
+pub struct InputDevice {
+    pub name: String,
+    // Unknown when the device won't report its default configuration.
+    pub sample_rate: Option<u32>,
+}
+
+// One buffer of captured audio, interleaved.
+pub struct CaptureChunk<'a> {
+    pub samples: &'a [f32],
+    pub channels: u16,
+    pub sample_rate: u32,
+    // From capture to the callback, as measured by the backend or else the
+    // length of the buffer.
+    pub latency: Duration,
+}
+
+pub enum CaptureError {
+    Disconnected,
+    Other(String),
+}
+
+pub type CaptureCallback = Box<dyn FnMut(CaptureChunk) + Send>;
+pub type CaptureErrorCallback = Box<dyn FnMut(CaptureError) + Send>;
+
+// An open capture stream; capture stops when it is dropped.
+pub struct Capture {
+    pub device_name: String,
+    pub sample_rate: u32,
+    _stream: Box<dyn Any>,
+}
+
+// The audio output. The handle plays sources until this is dropped.
+pub struct Playback {
+    pub handle: OutputStreamHandle,
+    _stream: Box<dyn Any>,
+}
+
+pub struct DecodedFile {
+    pub source: Box<dyn Source<Item = f32> + Send>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+// cpal for capture, rodio for playback and decoding, symphonia for durations.
+pub struct CpalBackend;
+
+impl CpalBackend {
+    fn host(name: &str) -> cpal::Host {
+        cpal::available_hosts()
+            .into_iter()
+            .find(|id| id.name() == name)
+            .and_then(|id| cpal::host_from_id(id).ok())
+            .unwrap_or_else(cpal::default_host)
+    }
+
+    fn device(host: &cpal::Host, name: Option<&str>) -> Option<cpal::Device> {
+        name.and_then(|name| {
+            host.input_devices()
+                .ok()?
+                .find(|d| d.name().unwrap_or_default() == name)
+        })
+        .or_else(|| host.default_input_device())
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn hosts(&self) -> Vec<String> {
+        cpal::available_hosts()
+            .into_iter()
+            .map(|id| id.name().to_string())
+            .collect()
+    }
+
+    fn default_host(&self) -> String {
+        cpal::default_host().id().name().to_string()
+    }
+
+    fn input_devices(&self, host: &str) -> Vec<String> {
+        Self::host(host)
+            .input_devices()
+            .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+            .unwrap_or_default()
+    }
+
+    fn resolve_input(&self, host: &str, device: Option<&str>) -> Option<InputDevice> {
+        let device = Self::device(&Self::host(host), device)?;
+        Some(InputDevice {
+            name: device.name().unwrap_or_default(),
+            sample_rate: device
+                .default_input_config()
+                .ok()
+                .map(|config| config.sample_rate().0),
+        })
+    }
+
+    fn open_capture(
+        &self,
+        host: &str,
+        device: Option<&str>,
+        buffer_frames: u32,
+        mut on_data: CaptureCallback,
+        mut on_error: CaptureErrorCallback,
+    ) -> Result<Capture, String> {
+        let Some(device) = Self::device(&Self::host(host), device) else {
+            error!("No audio input device found");
+            return Err("No microphone found".to_string());
+        };
+        let supported = match device.default_input_config() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to get default input config: {}", e);
+                return Err(format!("Microphone unavailable: {}", e));
+            }
+        };
+        let device_name = device.name().unwrap_or_default();
+        info!(
+            "Initializing microphone: {} with config {:?}",
+            device_name, supported
+        );
+        let channels = supported.channels();
+        let sample_rate = supported.sample_rate().0;
+        let mut config: cpal::StreamConfig = supported.config();
+        config.buffer_size = buffer_size(buffer_frames, supported.buffer_size());
+
+        let stream = match device.build_input_stream(
+            &config,
+            move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                let timestamp = info.timestamp();
+                let buffered = Duration::from_secs_f32(
+                    data.len() as f32 / channels.max(1) as f32 / sample_rate as f32,
+                );
+                on_data(CaptureChunk {
+                    samples: data,
+                    channels,
+                    sample_rate,
+                    latency: timestamp
+                        .callback
+                        .duration_since(&timestamp.capture)
+                        .unwrap_or(buffered),
+                });
+            },
+            move |err| {
+                error!("An error occurred on the audio stream: {}", err);
+                on_error(match err {
+                    cpal::StreamError::DeviceNotAvailable => CaptureError::Disconnected,
+                    other => CaptureError::Other(other.to_string()),
+                });
+            },
+            None,
+        ) {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Failed to build input stream: {}", e);
+                return Err(format!("Could not open {}: {}", device_name, e));
+            }
+        };
+        if let Err(e) = stream.play() {
+            error!("Failed to play audio stream: {}", e);
+            return Err(format!("Could not start {}: {}", device_name, e));
+        }
+        Ok(Capture {
+            device_name,
+            sample_rate,
+            _stream: Box::new(stream),
+        })
+    }
+
+    fn open_playback(&self) -> Result<Playback, String> {
+        let (stream, handle) = OutputStream::try_default().map_err(|e| e.to_string())?;
+        Ok(Playback {
+            handle,
+            _stream: Box::new(stream),
+        })
+    }
+
+    fn decode_file(&self, path: &Path) -> Result<DecodedFile, Box<dyn Error>> {
+        let file_bytes = std::fs::read(path)?;
+        let decoder = Decoder::new(Cursor::new(file_bytes))?;
+        Ok(DecodedFile {
+            sample_rate: decoder.sample_rate(),
+            channels: decoder.channels(),
+            source: Box::new(decoder.convert_samples()),
+        })
+    }
+
+    fn file_duration(&self, path: &Path) -> Result<Duration, Box<dyn Error>> {
+        get_duration_with_symphonia(path)
+    }
+}
+
+// No devices, no output, and every file decodes to the same samples held in
+// memory, so what works on decoded audio can be tested without a sound card
+// or audio files (see the tests of `offline.rs`).
+#[cfg(test)]
+pub struct NullBackend {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+#[cfg(test)]
+impl NullBackend {
+    pub fn new(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            samples,
+            sample_rate,
+            channels,
+        }
+    }
+}
+
+#[cfg(test)]
+impl AudioBackend for NullBackend {
+    fn hosts(&self) -> Vec<String> {
+        vec![self.default_host()]
+    }
+
+    fn default_host(&self) -> String {
+        "Null".to_string()
+    }
+
+    fn input_devices(&self, _host: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn resolve_input(&self, _host: &str, _device: Option<&str>) -> Option<InputDevice> {
+        None
+    }
+
+    fn open_capture(
+        &self,
+        _host: &str,
+        _device: Option<&str>,
+        _buffer_frames: u32,
+        _on_data: CaptureCallback,
+        _on_error: CaptureErrorCallback,
+    ) -> Result<Capture, String> {
+        Err("No microphone found".to_string())
+    }
+
+    fn open_playback(&self) -> Result<Playback, String> {
+        Err("No audio output".to_string())
+    }
+
+    fn decode_file(&self, _path: &Path) -> Result<DecodedFile, Box<dyn Error>> {
+        Ok(DecodedFile {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            source: Box::new(rodio::buffer::SamplesBuffer::new(
+                self.channels,
+                self.sample_rate,
+                self.samples.clone(),
+            )),
+        })
+    }
+
+    fn file_duration(&self, _path: &Path) -> Result<Duration, Box<dyn Error>> {
+        let frames = self.samples.len() / self.channels.max(1) as usize;
+        Ok(Duration::from_secs_f64(
+            frames as f64 / self.sample_rate.max(1) as f64,
+        ))
+    }
+}
+
+// --- Symphonia Helper ---
+
+fn get_duration_with_symphonia(path: &Path) -> Result<Duration, Box<dyn Error>> {
+    let src = std::fs::File::open(path)?;
+    let mss = symphonia::core::io::MediaSourceStream::new(Box::new(src), Default::default());
+
+    let hint = symphonia::core::probe::Hint::new();
+    let meta_opts: symphonia::core::meta::MetadataOptions = Default::default();
+    let fmt_opts: symphonia::core::formats::FormatOptions = Default::default();
+
+    let probed = symphonia::default::get_probe().format(&hint, mss, &fmt_opts, &meta_opts)?;
+    let format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+        .ok_or("No supported audio track found")?;
+
+    let time_base = track.codec_params.time_base.ok_or("Missing time base")?;
+    let n_frames = track.codec_params.n_frames.ok_or("Missing frame count")?;
+
+    let total_time = time_base.calc_time(n_frames);
+
+    Ok(Duration::from_secs(total_time.seconds) + Duration::from_secs_f64(total_time.frac))
+}
+
+// The requested buffer size, in frames, within what the device supports; 0
+// leaves it to the backend.
+fn buffer_size(frames: u32, supported: &cpal::SupportedBufferSize) -> cpal::BufferSize {
+    match (frames, supported) {
+        (0, _) => cpal::BufferSize::Default,
+        (frames, cpal::SupportedBufferSize::Range { min, max }) => {
+            cpal::BufferSize::Fixed(frames.clamp(*min, *max))
+        }
+        (frames, cpal::SupportedBufferSize::Unknown) => cpal::BufferSize::Fixed(frames),
+    }
+}
@@ -0,0 +1,99 @@
+// src/bench.rs
+
+use crate::audio::SelectedAudioSource;
+use crate::config::VisualizerId;
+use crate::recording::source_for;
+use crate::{ActiveVisualization, AppState};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+// Frames ignored at the start while assets load and pipelines compile.
+const WARMUP_FRAMES: usize = 60;
+
+// Options of the `--bench` mode, see `cli.rs`.
+pub struct BenchOptions {
+    pub viz: VisualizerId,
+    pub frames: usize,
+    pub file: PathBuf,
+}
+
+pub struct BenchPlugin(pub BenchOptions);
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        let options = &self.0;
+        info!(
+            "Benchmarking {:?} for {} frames with {}",
+            options.viz,
+            options.frames,
+            options.file.display()
+        );
+
+        app.insert_resource(SelectedAudioSource(source_for(options.file.clone())))
+            .insert_resource(ActiveVisualization(options.viz.state()))
+            .insert_resource(BenchRun {
+                target_state: options.viz.state(),
+                frames: options.frames,
+                warmup_left: WARMUP_FRAMES,
+                frame_times: Vec::with_capacity(options.frames),
+            })
+            .add_systems(Startup, start_bench)
+            .add_systems(Last, record_frame_time);
+    }
+}
+
+#[derive(Resource)]
+struct BenchRun {
+    target_state: AppState,
+    frames: usize,
+    warmup_left: usize,
+    frame_times: Vec<f32>,
+}
+
+fn start_bench(run: Res<BenchRun>, mut next_app_state: ResMut<NextState<AppState>>) {
+    next_app_state.set(run.target_state.clone());
+}
+
+fn record_frame_time(
+    time: Res<Time<Real>>,
+    app_state: Res<State<AppState>>,
+    mut run: ResMut<BenchRun>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if *app_state.get() != run.target_state {
+        return;
+    }
+    if run.warmup_left > 0 {
+        run.warmup_left -= 1;
+        return;
+    }
+
+    run.frame_times.push(time.delta_seconds() * 1000.0);
+    if run.frame_times.len() >= run.frames {
+        print_stats(&mut run.frame_times);
+        exit.send(AppExit);
+    }
+}
+
+fn print_stats(frame_times: &mut [f32]) {
+    if frame_times.is_empty() {
+        return;
+    }
+    frame_times.sort_by(f32::total_cmp);
+
+    let count = frame_times.len();
+    let mean = frame_times.iter().sum::<f32>() / count as f32;
+    let percentile = |p: f32| frame_times[((count - 1) as f32 * p).round() as usize];
+
+    println!("Frames:  {}", count);
+    println!("Mean:    {:.2} ms ({:.1} FPS)", mean, 1000.0 / mean);
+    println!("Median:  {:.2} ms", percentile(0.5));
+    println!("P95:     {:.2} ms", percentile(0.95));
+    println!("P99:     {:.2} ms", percentile(0.99));
+    println!(
+        "Min/Max: {:.2} / {:.2} ms",
+        frame_times[0],
+        frame_times[count - 1]
+    );
+}
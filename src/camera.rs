@@ -1,10 +1,26 @@
 // src/camera.rs
 
-use crate::{config::VisualsConfig, AppState};
+use crate::{
+    audio::AudioAnalysis,
+    config::{CameraBookmark, TonemappingMode, VisualizerId, VisualsConfig},
+    floor::FLOOR_LAYER,
+    governor::ActiveQuality,
+    keys::{KeyAction, KeyBindings},
+    mini_player::MiniPlayer,
+    toast::Toasts,
+    AppState,
+};
 use bevy::{
-    core_pipeline::bloom::BloomSettings,
+    core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping},
     input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
+    render::{
+        camera::{Exposure, ScalingMode},
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
     window::PrimaryWindow,
 };
 use bevy_egui::{EguiContexts, EguiSet};
@@ -24,6 +40,14 @@ pub struct PanOrbitController {
     pub enabled: bool,
 }
 
+// Moves the orbit camera to a recalled bookmark.
+#[derive(Component)]
+struct CameraTween {
+    from: CameraBookmark,
+    to: CameraBookmark,
+    timer: Timer,
+}
+
 impl Default for PanOrbitController {
     fn default() -> Self {
         PanOrbitController {
@@ -44,7 +68,17 @@ impl Plugin for CameraPlugin {
             .add_systems(OnExit(AppState::VisualizationOrb), despawn_3d_camera)
             .add_systems(
                 Update,
-                (pan_orbit_camera, update_bloom_settings)
+                (
+                    // In the mini player, dragging moves the window instead.
+                    pan_orbit_camera.run_if(|mini: Res<MiniPlayer>| !mini.active),
+                    gamepad_orbit_camera,
+                    update_bloom_settings,
+                    update_fog_settings,
+                    update_projection,
+                    update_exposure,
+                    camera_bookmarks,
+                    tween_camera.after(camera_bookmarks),
+                )
                     .run_if(
                         in_state(AppState::Visualization3D)
                             .or_else(in_state(AppState::VisualizationOrb)),
@@ -55,16 +89,19 @@ impl Plugin for CameraPlugin {
             .add_systems(OnEnter(AppState::Visualization2D), setup_2d_camera)
             .add_systems(OnEnter(AppState::VisualizationDisc), setup_2d_camera)
             .add_systems(OnEnter(AppState::VisualizationIco), setup_2d_camera)
+            .add_systems(OnEnter(AppState::VisualizationShadertoy), setup_2d_camera)
             .add_systems(OnExit(AppState::Visualization2D), despawn_2d_camera)
             .add_systems(OnExit(AppState::VisualizationDisc), despawn_2d_camera)
             .add_systems(OnExit(AppState::VisualizationIco), despawn_2d_camera)
+            .add_systems(OnExit(AppState::VisualizationShadertoy), despawn_2d_camera)
             .add_systems(
                 Update,
                 control_2d_camera
                     .run_if(
                         in_state(AppState::Visualization2D)
                             .or_else(in_state(AppState::VisualizationDisc))
-                            .or_else(in_state(AppState::VisualizationIco)),
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
                     )
                     .after(EguiSet::InitContexts),
             );
@@ -85,6 +122,8 @@ fn setup_3d_camera(mut commands: Commands) {
         },
         BloomSettings::default(),
         PanOrbitController::default(),
+        // The floor is kept off the default layer, which its reflection renders.
+        RenderLayers::from_layers(&[0, FLOOR_LAYER]),
         MainCamera3D,
     ));
 
@@ -112,6 +151,28 @@ fn despawn_3d_camera(
     }
 }
 
+// An image cameras can render into and materials can sample.
+pub fn render_target_image(label: &'static str, size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some(label),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    // Allocates the pixel data.
+    image.resize(size);
+    image
+}
+
 fn setup_2d_camera(mut commands: Commands) {
     commands.spawn((Camera2dBundle::default(), MainCamera2D));
 }
@@ -124,21 +185,30 @@ fn despawn_2d_camera(mut commands: Commands, camera_query: Query<Entity, With<Ma
 
 fn update_bloom_settings(
     config: Res<VisualsConfig>,
+    quality: Res<ActiveQuality>,
+    app_state: Res<State<AppState>>,
     mut camera_query: Query<(Entity, Option<&mut BloomSettings>), With<MainCamera3D>>,
     mut commands: Commands,
 ) {
+    // Each 3D visualizer carries its own bloom configuration.
+    let Some(bloom) =
+        VisualizerId::from_state(app_state.get()).and_then(|id| config.settings(id).bloom())
+    else {
+        return;
+    };
+
     if let Ok((camera_entity, bloom_settings)) = camera_query.get_single_mut() {
-        if config.bloom_enabled {
+        if bloom.enabled && quality.0.bloom() {
             match bloom_settings {
                 Some(mut settings) => {
-                    settings.intensity = config.bloom_intensity;
-                    settings.prefilter_settings.threshold = config.bloom_threshold;
+                    settings.intensity = bloom.intensity;
+                    settings.prefilter_settings.threshold = bloom.threshold;
                 }
                 None => {
                     commands.entity(camera_entity).insert(BloomSettings {
-                        intensity: config.bloom_intensity,
+                        intensity: bloom.intensity,
                         prefilter_settings: bevy::core_pipeline::bloom::BloomPrefilterSettings {
-                            threshold: config.bloom_threshold,
+                            threshold: bloom.threshold,
                             ..default()
                         },
                         ..default()
@@ -151,7 +221,237 @@ fn update_bloom_settings(
     }
 }
 
+// Linear fog from the visualizer's settings, pulled closer by the bass when
+// its audio density is set.
+fn update_fog_settings(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    audio_analysis: Res<AudioAnalysis>,
+    mut camera_query: Query<(Entity, Option<&mut FogSettings>), With<MainCamera3D>>,
+    mut commands: Commands,
+) {
+    let Some(fog) =
+        VisualizerId::from_state(app_state.get()).and_then(|id| config.settings(id).fog())
+    else {
+        return;
+    };
+    let Ok((camera_entity, fog_settings)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    if !fog.enabled {
+        if fog_settings.is_some() {
+            commands.entity(camera_entity).remove::<FogSettings>();
+        }
+        return;
+    }
+    let density = 1.0 + audio_analysis.bass.max(0.0) * fog.audio_density;
+    let start = fog.start / density;
+    let settings = FogSettings {
+        color: fog.color,
+        falloff: FogFalloff::Linear {
+            start,
+            end: (fog.end / density).max(start + 0.1),
+        },
+        ..default()
+    };
+    match fog_settings {
+        Some(mut fog_settings) => *fog_settings = settings,
+        None => {
+            commands.entity(camera_entity).insert(settings);
+        }
+    }
+}
+
+// Perspective or orthographic projection from the visualizer's settings. The
+// orthographic view height matches what the perspective view shows at the
+// orbit focus, so zooming keeps working and toggling doesn't jump.
+fn update_projection(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    audio_analysis: Res<AudioAnalysis>,
+    mut camera_query: Query<(&PanOrbitController, &mut Projection), With<MainCamera3D>>,
+) {
+    let Some(settings) =
+        VisualizerId::from_state(app_state.get()).and_then(|id| config.settings(id).camera())
+    else {
+        return;
+    };
+    let Ok((pan_orbit, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let fov_degrees = settings.fov + audio_analysis.bass.clamp(0.0, 1.0) * settings.bass_fov;
+    let fov = fov_degrees.clamp(1.0, 170.0).to_radians();
+    let height = 2.0 * pan_orbit.radius * (fov / 2.0).tan();
+    // Only mark it changed on an actual change, as that rebuilds the frustum.
+    match projection.bypass_change_detection() {
+        Projection::Perspective(perspective) if !settings.orthographic => {
+            if perspective.fov == fov {
+                return;
+            }
+            perspective.fov = fov;
+        }
+        Projection::Orthographic(orthographic) if settings.orthographic => {
+            if matches!(orthographic.scaling_mode, ScalingMode::FixedVertical(h) if h == height) {
+                return;
+            }
+            orthographic.scaling_mode = ScalingMode::FixedVertical(height);
+        }
+        // Switching between the two.
+        current => {
+            *current = if settings.orthographic {
+                Projection::Orthographic(OrthographicProjection {
+                    // The camera orbits close to the scene; keep what is behind it.
+                    near: -100.0,
+                    scaling_mode: ScalingMode::FixedVertical(height),
+                    ..default()
+                })
+            } else {
+                Projection::Perspective(PerspectiveProjection { fov, ..default() })
+            };
+        }
+    }
+    projection.set_changed();
+}
+
+fn update_exposure(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut camera_query: Query<(&mut Exposure, &mut Tonemapping), With<MainCamera3D>>,
+) {
+    let Some(settings) =
+        VisualizerId::from_state(app_state.get()).and_then(|id| config.settings(id).camera())
+    else {
+        return;
+    };
+    let Ok((mut exposure, mut tonemapping)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    // Each stop up halves the exposure value.
+    let ev100 = Exposure::default().ev100 - settings.exposure;
+    if exposure.ev100 != ev100 {
+        exposure.ev100 = ev100;
+    }
+    let wanted = match settings.tonemapping {
+        TonemappingMode::None => Tonemapping::None,
+        TonemappingMode::Reinhard => Tonemapping::Reinhard,
+        TonemappingMode::AcesFitted => Tonemapping::AcesFitted,
+        TonemappingMode::AgX => Tonemapping::AgX,
+        TonemappingMode::TonyMcMapface => Tonemapping::TonyMcMapface,
+        TonemappingMode::BlenderFilmic => Tonemapping::BlenderFilmic,
+    };
+    if *tonemapping != wanted {
+        *tonemapping = wanted;
+    }
+}
+
+// Recall camera bookmark N with Shift and the Nth key, save it with Ctrl.
+// The keys alone switch visualizers.
+fn camera_bookmarks(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    app_state: Res<State<AppState>>,
+    mut config: ResMut<VisualsConfig>,
+    mut toasts: ResMut<Toasts>,
+    mut query: Query<(Entity, &mut PanOrbitController, &mut Transform), With<MainCamera3D>>,
+) {
+    let Some(slot) = bindings.just_pressed_index(KeyAction::CameraView, &keyboard) else {
+        return;
+    };
+    let Some(id) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let Ok((entity, mut pan_orbit, mut transform)) = query.get_single_mut() else {
+        return;
+    };
+
+    if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        let (_, settings) = config.general_and_settings_mut(id);
+        if let Some(camera) = settings.camera_mut() {
+            camera.bookmarks[slot] = Some(capture_bookmark(&pan_orbit, &transform));
+            toasts.info(format!("Saved camera view {}", slot + 1));
+        }
+        return;
+    }
+    if !keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+
+    let Some(camera) = config.settings(id).camera() else {
+        return;
+    };
+    let Some(bookmark) = camera.bookmarks[slot] else {
+        toasts.info(format!("No camera view saved on {}", slot + 1));
+        return;
+    };
+    if camera.bookmark_tween <= 0.0 {
+        apply_bookmark(&bookmark, &mut pan_orbit, &mut transform);
+        commands.entity(entity).remove::<CameraTween>();
+        return;
+    }
+    commands.entity(entity).insert(CameraTween {
+        from: capture_bookmark(&pan_orbit, &transform),
+        to: bookmark,
+        timer: Timer::from_seconds(camera.bookmark_tween, TimerMode::Once),
+    });
+}
+
+// Eases between the two poses; real time, so it still runs while frozen.
+fn tween_camera(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut query: Query<(
+        Entity,
+        &mut CameraTween,
+        &mut PanOrbitController,
+        &mut Transform,
+    )>,
+) {
+    for (entity, mut tween, mut pan_orbit, mut transform) in &mut query {
+        let t = tween.timer.tick(time.delta()).fraction();
+        let t = t * t * (3.0 - 2.0 * t);
+        let (from, to) = (tween.from, tween.to);
+        let pose = CameraBookmark {
+            focus: Vec3::from(from.focus)
+                .lerp(Vec3::from(to.focus), t)
+                .to_array(),
+            rotation: Quat::from_array(from.rotation)
+                .slerp(Quat::from_array(to.rotation), t)
+                .to_array(),
+            radius: from.radius + (to.radius - from.radius) * t,
+        };
+        apply_bookmark(&pose, &mut pan_orbit, &mut transform);
+        if tween.timer.finished() {
+            commands.entity(entity).remove::<CameraTween>();
+        }
+    }
+}
+
+pub fn capture_bookmark(pan_orbit: &PanOrbitController, transform: &Transform) -> CameraBookmark {
+    CameraBookmark {
+        focus: pan_orbit.focus.to_array(),
+        rotation: transform.rotation.to_array(),
+        radius: pan_orbit.radius,
+    }
+}
+
+pub fn apply_bookmark(
+    bookmark: &CameraBookmark,
+    pan_orbit: &mut PanOrbitController,
+    transform: &mut Transform,
+) {
+    pan_orbit.focus = Vec3::from(bookmark.focus);
+    pan_orbit.radius = bookmark.radius;
+    transform.rotation = Quat::from_array(bookmark.rotation).normalize();
+    transform.translation =
+        pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+}
+
 fn control_2d_camera(
+    primary_window: Query<&Window, With<PrimaryWindow>>,
     mut ev_scroll: EventReader<MouseWheel>,
     mut camera_query: Query<&mut OrthographicProjection, With<MainCamera2D>>,
     mut contexts: EguiContexts,
@@ -163,6 +463,14 @@ fn control_2d_camera(
             return;
         }
     }
+    // The mouse is over another window, e.g. the settings one.
+    if primary_window
+        .get_single()
+        .map_or(true, |window| window.cursor_position().is_none())
+    {
+        ev_scroll.clear();
+        return;
+    }
 
     if let Ok(mut projection) = camera_query.get_single_mut() {
         for ev in ev_scroll.read() {
@@ -193,6 +501,12 @@ fn pan_orbit_camera(
     let Ok(window) = primary_window.get_single() else {
         return;
     };
+    // The mouse is over another window, e.g. the settings one.
+    if window.cursor_position().is_none() {
+        ev_motion.clear();
+        ev_scroll.clear();
+        return;
+    }
 
     if let Ok((mut pan_orbit, mut transform)) = query.get_single_mut() {
         if !pan_orbit.enabled {
@@ -228,3 +542,46 @@ fn pan_orbit_camera(
             pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
     }
 }
+
+// Left stick orbits around the focus, right stick Y zooms.
+fn gamepad_orbit_camera(
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    time: Res<Time>,
+    mut query: Query<(&mut PanOrbitController, &mut Transform), With<MainCamera3D>>,
+) {
+    let mut orbit = Vec2::ZERO;
+    let mut zoom = 0.0;
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type: GamepadAxisType| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        orbit += Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        zoom += axis(GamepadAxisType::RightStickY);
+    }
+
+    if orbit == Vec2::ZERO && zoom == 0.0 {
+        return;
+    }
+
+    if let Ok((mut pan_orbit, mut transform)) = query.get_single_mut() {
+        if !pan_orbit.enabled {
+            return;
+        }
+
+        let dt = time.delta_seconds();
+        let yaw = Quat::from_rotation_y(-orbit.x * std::f32::consts::PI * dt);
+        let pitch = Quat::from_rotation_x(orbit.y * std::f32::consts::FRAC_PI_2 * dt);
+        transform.rotation = yaw * transform.rotation * pitch;
+
+        pan_orbit.radius = (pan_orbit.radius - zoom * pan_orbit.radius * dt).max(5.0);
+
+        let rot_matrix = Mat3::from_quat(transform.rotation);
+        transform.translation =
+            pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+    }
+}
@@ -0,0 +1,75 @@
+// src/cli.rs
+
+use crate::bench::BenchOptions;
+use crate::config::VisualizerId;
+use crate::wallpaper::WallpaperOptions;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: Rust_visualizer [--bench [--frames <count>] | --wallpaper] \
+                     [--viz <bars_2d|cubes_3d|orb|disc|ico|shadertoy>] [--file <audio file or recording>]";
+
+// Startup modes picked on the command line. Without any, the app opens on
+// the main menu.
+#[derive(Default)]
+pub struct LaunchOptions {
+    pub bench: Option<BenchOptions>,
+    pub wallpaper: Option<WallpaperOptions>,
+}
+
+impl LaunchOptions {
+    // Exits with the usage on bad arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter();
+        let mut bench = false;
+        let mut wallpaper = false;
+        let mut viz = None;
+        let mut frames = None;
+        let mut file = None;
+
+        while let Some(arg) = args.next() {
+            let parsed = match arg.as_str() {
+                "--bench" => {
+                    bench = true;
+                    Some(())
+                }
+                "--wallpaper" => {
+                    wallpaper = true;
+                    Some(())
+                }
+                "--viz" => args
+                    .next()
+                    .and_then(|key| VisualizerId::from_key(&key))
+                    .map(|id| viz = Some(id)),
+                "--frames" => args
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .map(|count| frames = Some(count)),
+                "--file" => args.next().map(|path| file = Some(PathBuf::from(path))),
+                _ => None,
+            };
+            if parsed.is_none() {
+                usage_error(&format!("Invalid argument '{}'", arg));
+            }
+        }
+        if bench && wallpaper {
+            usage_error("--bench and --wallpaper can't be combined");
+        }
+
+        let mut options = LaunchOptions::default();
+        if bench {
+            options.bench = Some(BenchOptions {
+                viz: viz.unwrap_or(VisualizerId::Cubes3D),
+                frames: frames.unwrap_or(1000),
+                file: file.unwrap_or_else(|| PathBuf::from("assets/audio/ShortClip.wav")),
+            });
+        } else if wallpaper {
+            options.wallpaper = Some(WallpaperOptions { viz, file });
+        }
+        options
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
@@ -1,92 +1,2237 @@
 // src/config.rs
 
+use crate::{
+    eq::EQ_BAND_COUNT, i18n::Language, palette::Palette, toast::Toasts, ActiveVisualization,
+    AppState,
+};
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::reflect::Struct;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 
-// A resource that holds all the configurable parameters for the visualizations.
-// This allows users to tweak the visuals in real-time through the UI.
-#[derive(Resource, Clone)]
-pub struct VisualsConfig {
-    // --- General Settings ---
+// How often the in-memory config is compared against what is on disk.
+const SAVE_INTERVAL_SECS: f32 = 1.0;
+// File name (without extension) of the settings shared by all visualizers.
+const GENERAL_SECTION: &str = "general";
+const PRESETS_DIR: &str = "presets";
+const TIMELINES_DIR: &str = "timelines";
+const THUMBNAILS_DIR: &str = "thumbnails";
+const SESSION_FILE: &str = "session.ron";
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let dir = config_dir();
+        let mut store = ConfigStore::new(dir.clone());
+        let config = store.load();
+
+        // Watch the config directory so files edited by hand or generated
+        // by external tools are applied while the visualizer runs.
+        match ConfigWatcher::new(&dir) {
+            Ok(watcher) => {
+                app.insert_non_send_resource(watcher);
+            }
+            Err(e) => warn!("Config hot reloading disabled: {}", e),
+        }
+
+        // Bench and wallpaper runs override the visualizer to start with.
+        let start_visualizer = ActiveVisualization(config.general.start_visualizer.state());
+
+        app.insert_resource(config)
+            .insert_resource(store)
+            .insert_resource(start_visualizer)
+            .add_systems(
+                Update,
+                (
+                    reload_changed_config_files,
+                    save_config_system.after(reload_changed_config_files),
+                ),
+            )
+            .add_systems(Last, flush_config_on_exit);
+    }
+}
+
+// Identifies each visualizer. Used as the key of the per-visualizer settings map
+// and as the file name of its persisted settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VisualizerId {
+    Bars2D,
+    Cubes3D,
+    Orb,
+    Disc,
+    Ico,
+    Shadertoy,
+}
+
+impl VisualizerId {
+    pub const ALL: [VisualizerId; 6] = [
+        VisualizerId::Bars2D,
+        VisualizerId::Cubes3D,
+        VisualizerId::Orb,
+        VisualizerId::Disc,
+        VisualizerId::Ico,
+        VisualizerId::Shadertoy,
+    ];
+
+    // Stable name used for the settings file on disk.
+    pub fn key(self) -> &'static str {
+        match self {
+            VisualizerId::Bars2D => "bars_2d",
+            VisualizerId::Cubes3D => "cubes_3d",
+            VisualizerId::Orb => "orb",
+            VisualizerId::Disc => "disc",
+            VisualizerId::Ico => "ico",
+            VisualizerId::Shadertoy => "shadertoy",
+        }
+    }
+
+    pub fn state(self) -> AppState {
+        match self {
+            VisualizerId::Bars2D => AppState::Visualization2D,
+            VisualizerId::Cubes3D => AppState::Visualization3D,
+            VisualizerId::Orb => AppState::VisualizationOrb,
+            VisualizerId::Disc => AppState::VisualizationDisc,
+            VisualizerId::Ico => AppState::VisualizationIco,
+            VisualizerId::Shadertoy => AppState::VisualizationShadertoy,
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        VisualizerId::ALL.into_iter().find(|id| id.key() == key)
+    }
+
+    // The parameters shown in the settings panel, with their reflected fields.
+    pub fn ui_fields(self) -> &'static [FieldUi] {
+        match self {
+            VisualizerId::Bars2D => Bars2DSettings::UI_FIELDS,
+            VisualizerId::Cubes3D => Cubes3DSettings::UI_FIELDS,
+            VisualizerId::Orb => OrbSettings::UI_FIELDS,
+            VisualizerId::Disc => DiscSettings::UI_FIELDS,
+            VisualizerId::Ico => IcoSettings::UI_FIELDS,
+            VisualizerId::Shadertoy => ShadertoySettings::UI_FIELDS,
+        }
+    }
+
+    pub fn from_state(state: &AppState) -> Option<Self> {
+        VisualizerId::ALL
+            .into_iter()
+            .find(|id| id.state() == *state)
+    }
+}
+
+// --- General Settings (shared by every visualizer) ---
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneralSettings {
     pub bass_sensitivity: f32,
+    // Frequency range the bands are spread over, in Hz.
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    // Perceptual curve applied to the spectrum before it is split into bands.
+    pub weighting: SpectrumWeighting,
+    pub crossovers: CrossoverSettings,
+    pub smoothing: SmoothingSettings,
+    pub interpolation: AnalysisInterpolation,
+    // Delay applied to the visuals of a playing file; the offset is added on
+    // top of the automatic estimate and may be negative.
+    pub auto_latency: bool,
+    pub latency_offset_ms: f32,
+    // Analyse a playing file whole in the background, and drive the visuals
+    // from that analysis at the playback position once it is done, see
+    // `offline.rs`.
+    pub analyse_ahead: bool,
+    // Move seeks to the nearest beat of the file's beat grid, once analysed
+    // ahead.
+    pub snap_to_beats: bool,
+    pub scene_switch: SceneSwitchSettings,
+    // Keep the pitch of a file when its speed is changed, by time-stretching.
+    pub preserve_pitch: bool,
+    // Pause a playing file while the menus are open, instead of letting it
+    // play on.
+    pub pause_in_menu: bool,
+    pub mix: MixSettings,
+    // Buffer size of the microphone stream in frames; 0 leaves it to the
+    // backend. Smaller is lower latency, larger more robust.
+    pub mic_buffer_frames: u32,
+    pub monitor: MonitorSettings,
+    pub eq: EqSettings,
+    // Folder the library lists the audio files of, see `library.rs`.
+    pub library_folder: Option<PathBuf>,
+    // The order the playlist plays in.
+    pub repeat: RepeatMode,
+    pub shuffle: bool,
+    pub lfos: [LfoSettings; LFO_COUNT],
+    pub macros: [MacroSettings; MACRO_COUNT],
+    pub tone_detectors: Vec<ToneDetector>,
+    pub details_panel_enabled: bool,
+    pub settings_panel: PanelPlacement,
+    // The visualizer "Start Visualization" opens at launch.
+    pub start_visualizer: VisualizerId,
+    pub visualizers: VisualizerList,
+    pub theme: ThemeSettings,
+    pub language: Language,
+    // Hide the cursor and panels after this many idle seconds in fullscreen.
+    pub auto_hide_enabled: bool,
+    pub auto_hide_delay: f32,
+    pub now_playing: NowPlayingSettings,
+    pub strobe: StrobeSettings,
+    pub palette: PaletteSettings,
+    pub grading: ColorGradingSettings,
+    pub idle: IdleSettings,
+    pub silence_gate: SilenceGateSettings,
+    pub remote: RemoteSettings,
+    pub hue: HueSettings,
+    pub discord: DiscordSettings,
+    pub output: OutputSettings,
+    pub background: BackgroundSettings,
+    pub quality: Quality,
+    pub adaptive_quality: AdaptiveQualitySettings,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            bass_sensitivity: 1.0,
+            min_frequency: 20.0,
+            max_frequency: 20000.0,
+            weighting: SpectrumWeighting::Off,
+            crossovers: CrossoverSettings::default(),
+            smoothing: SmoothingSettings::default(),
+            interpolation: AnalysisInterpolation::Off,
+            auto_latency: true,
+            latency_offset_ms: 0.0,
+            analyse_ahead: false,
+            snap_to_beats: false,
+            scene_switch: SceneSwitchSettings::default(),
+            preserve_pitch: false,
+            pause_in_menu: false,
+            mix: MixSettings::default(),
+            mic_buffer_frames: 0,
+            monitor: MonitorSettings::default(),
+            eq: EqSettings::default(),
+            library_folder: None,
+            repeat: RepeatMode::Off,
+            shuffle: false,
+            lfos: [
+                LfoSettings::default(),
+                LfoSettings {
+                    shape: LfoShape::Saw,
+                    rate: LfoRate::Beats(4.0),
+                    ..default()
+                },
+                LfoSettings {
+                    shape: LfoShape::Square,
+                    rate: LfoRate::Beats(0.5),
+                    ..default()
+                },
+                LfoSettings {
+                    rate: LfoRate::Hz(0.1),
+                    ..default()
+                },
+            ],
+            macros: default(),
+            tone_detectors: Vec::new(),
+            details_panel_enabled: false,
+            settings_panel: PanelPlacement::Docked,
+            start_visualizer: VisualizerId::Cubes3D,
+            visualizers: VisualizerList::default(),
+            theme: ThemeSettings::default(),
+            language: Language::default(),
+            auto_hide_enabled: true,
+            auto_hide_delay: 3.0,
+            now_playing: NowPlayingSettings::default(),
+            strobe: StrobeSettings::default(),
+            palette: PaletteSettings::default(),
+            grading: ColorGradingSettings::default(),
+            idle: IdleSettings::default(),
+            silence_gate: SilenceGateSettings::default(),
+            remote: RemoteSettings::default(),
+            hue: HueSettings::default(),
+            discord: DiscordSettings::default(),
+            output: OutputSettings::default(),
+            background: BackgroundSettings::default(),
+            quality: Quality::High,
+            adaptive_quality: AdaptiveQualitySettings::default(),
+        }
+    }
+}
+
+// Weights each frequency by how loud it sounds, so sub-bass doesn't dominate
+// the bands. Both curves are normalized to 1 at 1 kHz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpectrumWeighting {
+    Off,
+    // IEC 61672 A-weighting, the hearing curve at low levels.
+    A,
+    // The K-weighting of the loudness measurement (ITU-R BS.1770).
+    K,
+}
+
+// How the analysis outputs move between analysis ticks, for displays
+// refreshing faster than the analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnalysisInterpolation {
+    // They step on every tick.
+    Off,
+    // Toward the latest tick, smooth but a tick late.
+    Interpolate,
+    // Past the latest tick along its slope, on time but overshooting turns.
+    Extrapolate,
+}
+
+// What the playlist plays once a track ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatMode {
+    // The next track, stopping after the last one.
+    Off,
+    // The next track, starting over after the last one.
+    All,
+    // The same track again.
+    One,
+}
+
+// Where the derived ranges meet, in Hz: sub-bass below `sub_punch`, punch
+// from there to `bass_mid` (the two together are the bass), mid up to
+// `mid_treble` and treble above. A band counts toward the range its upper
+// edge falls in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrossoverSettings {
+    pub sub_punch: f32,
+    pub bass_mid: f32,
+    pub mid_treble: f32,
+}
+
+impl Default for CrossoverSettings {
+    fn default() -> Self {
+        Self {
+            sub_punch: 60.0,
+            bass_mid: 150.0,
+            mid_treble: 4000.0,
+        }
+    }
+}
+
+// --- Analysis Smoothing ---
+// Attack/release time constants, in seconds, for one analysis output.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Envelope {
+    pub attack: f32,
+    pub release: f32,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            attack: 0.01,
+            release: 0.15,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmoothingSettings {
+    pub bins: Envelope,
+    pub bass: Envelope,
+    pub mid: Envelope,
+    pub treble: Envelope,
+    pub volume: Envelope,
+    // Of the peak follower on the raw signal, see `PeakFollower`.
+    pub peak: Envelope,
+    // Divides each band by its own slowly decaying peak, so every band gets
+    // comparable activity however the mix is balanced. `normalize_release`
+    // is the time, in seconds, a peak takes to fall by half.
+    pub normalize_bands: bool,
+    pub normalize_release: f32,
+}
+
+impl Default for SmoothingSettings {
+    fn default() -> Self {
+        Self {
+            bins: Envelope::default(),
+            // Let kicks ring a little longer than the rest.
+            bass: Envelope {
+                attack: 0.01,
+                release: 0.25,
+            },
+            mid: Envelope::default(),
+            treble: Envelope {
+                attack: 0.005,
+                release: 0.1,
+            },
+            volume: Envelope::default(),
+            peak: Envelope {
+                attack: 0.001,
+                release: 0.1,
+            },
+            normalize_bands: false,
+            normalize_release: 5.0,
+        }
+    }
+}
+
+// --- Source Mix ---
+// Gains of the file and microphone in the analysis. With `mic_with_file`, the
+// microphone is mixed in while a file plays.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixSettings {
+    pub mic_with_file: bool,
+    pub file_gain: f32,
+    pub mic_gain: f32,
+}
+
+impl Default for MixSettings {
+    fn default() -> Self {
+        Self {
+            mic_with_file: false,
+            file_gain: 1.0,
+            mic_gain: 1.0,
+        }
+    }
+}
+
+// --- Microphone Monitoring ---
+// Plays the microphone back through the speakers at `gain`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorSettings {
+    pub enabled: bool,
+    pub gain: f32,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gain: 1.0,
+        }
+    }
+}
+
+// --- Equalizer ---
+// Gains of the graphic EQ bands in dB (see `eq.rs`). It filters the analysis
+// input, and the file being played as well with `playback`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqSettings {
+    pub enabled: bool,
+    pub gains: [f32; EQ_BAND_COUNT],
+    pub playback: bool,
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gains: [0.0; EQ_BAND_COUNT],
+            playback: false,
+        }
+    }
+}
+
+// --- LFOs ---
+// Fits in one vec4 uniform on the shader side.
+pub const LFO_COUNT: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LfoShape {
+    Sine,
+    Saw,
+    Square,
+}
+
+impl LfoShape {
+    pub const ALL: [LfoShape; 3] = [LfoShape::Sine, LfoShape::Saw, LfoShape::Square];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LfoRate {
+    // Cycles per second.
+    Hz(f32),
+    // Beats per cycle, following the tempo.
+    Beats(f32),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LfoSettings {
+    pub enabled: bool,
+    pub shape: LfoShape,
+    pub rate: LfoRate,
+}
+
+impl Default for LfoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shape: LfoShape::Sine,
+            rate: LfoRate::Beats(1.0),
+        }
+    }
+}
+
+// --- Macro Knobs ---
+pub const MACRO_COUNT: usize = 4;
+
+// One parameter driven by a macro: the knob's 0..1 maps onto `min..max`.
+// `field` names a field of the visualizer's settings, as in `UI_FIELDS`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MacroTarget {
+    pub visualizer: VisualizerId,
+    pub field: String,
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroSettings {
+    pub value: f32,
+    pub targets: Vec<MacroTarget>,
+}
+
+// --- Tone Detectors ---
+pub const MAX_TONE_DETECTORS: usize = 8;
+
+// A single frequency watched on its own, e.g. mains hum at 50 Hz or an A at
+// 440 Hz. Its level is a modulation source looked up by `name`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ToneDetector {
+    pub name: String,
+    pub frequency: f32,
+}
+
+// --- "Now Playing" Overlay ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayCorner {
+    pub const ALL: [OverlayCorner; 4] = [
+        OverlayCorner::TopLeft,
+        OverlayCorner::TopRight,
+        OverlayCorner::BottomLeft,
+        OverlayCorner::BottomRight,
+    ];
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NowPlayingSettings {
+    pub enabled: bool,
+    pub corner: OverlayCorner,
+    pub opacity: f32,
+}
+
+impl Default for NowPlayingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            // Toasts use the bottom-right corner.
+            corner: OverlayCorner::BottomLeft,
+            opacity: 0.7,
+        }
+    }
+}
+
+// --- Strobe ---
+// A full-screen flash over the visualizer on each beat.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrobeSettings {
+    pub enabled: bool,
+    pub color: Color,
+    // Opacity of the flash at its peak.
+    pub intensity: f32,
+    // Seconds a flash takes to fade out.
+    pub decay: f32,
+    // Flashes per second at most. Capped at 3, the photosensitive seizure
+    // threshold of the WCAG guidelines.
+    pub max_rate: f32,
+}
+
+impl StrobeSettings {
+    pub const MAX_SAFE_RATE: f32 = 3.0;
+}
+
+impl Default for StrobeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: Color::WHITE,
+            intensity: 0.5,
+            decay: 0.2,
+            max_rate: 2.0,
+        }
+    }
+}
+
+// --- Visualizer List ---
+// The order the selector, the number keys and the switchers go through the
+// visualizers in, and those left out of them.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisualizerList {
+    pub order: Vec<VisualizerId>,
+    pub hidden: Vec<VisualizerId>,
+}
+
+impl Default for VisualizerList {
+    fn default() -> Self {
+        Self {
+            order: VisualizerId::ALL.to_vec(),
+            hidden: Vec::new(),
+        }
+    }
+}
+
+impl VisualizerList {
+    // Every visualizer, in order; those missing from an older config last.
+    pub fn ordered(&self) -> Vec<VisualizerId> {
+        let mut ordered: Vec<VisualizerId> = Vec::with_capacity(VisualizerId::ALL.len());
+        for id in self.order.iter().copied().chain(VisualizerId::ALL) {
+            if !ordered.contains(&id) {
+                ordered.push(id);
+            }
+        }
+        ordered
+    }
+
+    // The visualizers that aren't hidden, in order. Never empty: with all of
+    // them hidden, they are all shown.
+    pub fn shown(&self) -> Vec<VisualizerId> {
+        let ordered = self.ordered();
+        let shown: Vec<VisualizerId> = ordered
+            .iter()
+            .copied()
+            .filter(|id| !self.hidden.contains(id))
+            .collect();
+        if shown.is_empty() {
+            ordered
+        } else {
+            shown
+        }
+    }
+
+    pub fn is_shown(&self, id: VisualizerId) -> bool {
+        self.shown().contains(&id)
+    }
+
+    // The shown visualizer `step` places after `current`, wrapping around;
+    // from a hidden or no visualizer, the first one.
+    pub fn cycle(&self, current: Option<VisualizerId>, step: isize) -> VisualizerId {
+        let shown = self.shown();
+        let Some(index) = current.and_then(|id| shown.iter().position(|&other| other == id)) else {
+            return shown[0];
+        };
+        shown[(index as isize + step).rem_euclid(shown.len() as isize) as usize]
+    }
+
+    // Moves a visualizer one place up (`-1`) or down (`1`) the order.
+    pub fn move_by(&mut self, id: VisualizerId, step: isize) {
+        let mut ordered = self.ordered();
+        let Some(index) = ordered.iter().position(|&other| other == id) else {
+            return;
+        };
+        let target = index as isize + step;
+        if (0..ordered.len() as isize).contains(&target) {
+            ordered.swap(index, target as usize);
+        }
+        self.order = ordered;
+    }
+
+    pub fn set_hidden(&mut self, id: VisualizerId, hidden: bool) {
+        self.hidden.retain(|&other| other != id);
+        if hidden {
+            self.hidden.push(id);
+        }
+    }
+}
+
+// --- Scene Switching ---
+// What playback entering a section of a file analysed ahead switches to
+// (see `scenes.rs`): a preset, or else a visualizer, or else nothing.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneTarget {
+    pub visualizer: Option<VisualizerId>,
+    // Name of a preset in the `presets` folder, see `load_preset`.
+    pub preset: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneSwitchSettings {
+    pub enabled: bool,
+    // For sections quieter and louder than the file on average.
+    pub quiet: SceneTarget,
+    pub loud: SceneTarget,
+}
+
+impl Default for SceneSwitchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quiet: SceneTarget {
+                visualizer: Some(VisualizerId::Orb),
+                preset: String::new(),
+            },
+            loud: SceneTarget {
+                visualizer: Some(VisualizerId::Cubes3D),
+                preset: String::new(),
+            },
+        }
+    }
+}
+
+// --- Palettes ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteCycle {
+    Off,
+    // Every `cycle_bars` bars of the tapped tempo.
+    Bars,
+    // When the loudness shifts for long enough to be a new section.
+    Sections,
+}
+
+impl PaletteCycle {
+    pub const ALL: [PaletteCycle; 3] = [
+        PaletteCycle::Off,
+        PaletteCycle::Bars,
+        PaletteCycle::Sections,
+    ];
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaletteSettings {
+    // Index in `palette::PALETTES` of the last applied palette.
+    pub current: usize,
+    pub cycle: PaletteCycle,
+    pub cycle_bars: u32,
+}
+
+impl Default for PaletteSettings {
+    fn default() -> Self {
+        Self {
+            current: 0,
+            cycle: PaletteCycle::Off,
+            cycle_bars: 8,
+        }
+    }
+}
+
+// --- Color Grading ---
+// Applied over the whole frame of every visualizer.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorGradingSettings {
+    // In degrees.
+    pub hue_shift: f32,
+    pub saturation: f32,
+    pub contrast: f32,
+    pub gamma: f32,
+    // Keeps turning the hue, in degrees per second.
+    pub auto_hue: bool,
+    pub auto_hue_speed: f32,
+}
+
+impl Default for ColorGradingSettings {
+    fn default() -> Self {
+        Self {
+            hue_shift: 0.0,
+            saturation: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            auto_hue: false,
+            auto_hue_speed: 10.0,
+        }
+    }
+}
+
+// --- Idle Animation ---
+// Stands in for the audio after `delay` seconds of silence.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleSettings {
+    pub enabled: bool,
+    pub delay: f32,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay: 5.0,
+        }
+    }
+}
+
+// --- Silence Gate ---
+// Silences the analysis once the input stays under `threshold_db` for `hold`
+// seconds, and can then pause the visualizer updates.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SilenceGateSettings {
+    pub enabled: bool,
+    pub threshold_db: f32,
+    pub hold: f32,
+    pub pause_visuals: bool,
+}
+
+impl Default for SilenceGateSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -50.0,
+            hold: 0.5,
+            pause_visuals: false,
+        }
+    }
+}
+
+// --- Remote Control ---
+// WebSocket and HTTP servers for remote control, off unless the user turns them on.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Listen on every network interface instead of this computer only.
+    pub allow_network: bool,
+    // Origins of the web pages allowed to connect with `allow_network`, e.g.
+    // "http://192.168.1.20:8080" for a control page served to a phone.
+    pub allowed_origins: Vec<String>,
+    pub http_enabled: bool,
+    pub http_port: u16,
+    // Listen on every network interface instead of this computer only.
+    pub http_allow_network: bool,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9001,
+            allow_network: false,
+            allowed_origins: Vec::new(),
+            http_enabled: false,
+            http_port: 9002,
+            http_allow_network: false,
+        }
+    }
+}
+
+// --- Philips Hue ---
+// The bridge and entertainment area that follow the visuals. `username` and
+// `client_key` are handed out by the bridge when paired, the second keying
+// the encrypted stream.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HueSettings {
+    pub enabled: bool,
+    pub bridge_ip: String,
+    pub username: String,
+    pub client_key: String,
+    // The id of the entertainment area streamed to.
+    pub area: String,
+    // Brightness between beats and on a beat, in 0..1.
+    pub min_brightness: f32,
+    pub max_brightness: f32,
+}
+
+impl Default for HueSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bridge_ip: String::new(),
+            username: String::new(),
+            client_key: String::new(),
+            area: String::new(),
+            min_brightness: 0.2,
+            max_brightness: 1.0,
+        }
+    }
+}
+
+// --- Discord ---
+// Rich Presence shows the track and visualizer on the user's Discord profile.
+// Discord names the presence after the application whose ID is given here,
+// created in its developer portal.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordSettings {
+    pub enabled: bool,
+    pub application_id: String,
+}
+
+// --- Output Window ---
+// A second, visuals-only window for a projector or another screen. `monitor`
+// is the index of the screen it opens on; the visuals are rendered at
+// `render_scale` times its resolution (below 1 to upscale, above to supersample).
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    pub enabled: bool,
+    pub monitor: usize,
+    pub render_scale: f32,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            monitor: 1,
+            render_scale: 1.0,
+        }
+    }
+}
+
+// --- Settings Panel ---
+// Where the settings of the current visualizer are shown: docked beside the
+// visuals, floating over them, or in a window of their own that can be moved
+// to another screen (see `settings_window.rs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanelPlacement {
+    Docked,
+    Floating,
+    Window,
+}
+
+impl PanelPlacement {
+    pub const ALL: [PanelPlacement; 3] = [
+        PanelPlacement::Docked,
+        PanelPlacement::Floating,
+        PanelPlacement::Window,
+    ];
+}
+
+// --- Background Throttling ---
+// What the app does while its window is unfocused or minimized, to save
+// battery. Audio playback keeps running in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackgroundMode {
+    FullSpeed,
+    // Capped at `BackgroundSettings::fps`.
+    Throttled,
+    // Only redraws when the window gets an event.
+    Suspended,
+}
+
+impl BackgroundMode {
+    pub const ALL: [BackgroundMode; 3] = [
+        BackgroundMode::FullSpeed,
+        BackgroundMode::Throttled,
+        BackgroundMode::Suspended,
+    ];
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundSettings {
+    pub mode: BackgroundMode,
+    pub fps: f32,
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        Self {
+            mode: BackgroundMode::FullSpeed,
+            fps: 10.0,
+        }
+    }
+}
+
+// --- Quality ---
+// Scales the costly parts of the visuals together, so slower machines run
+// smoothly with a single setting. Ordered from lowest to highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    pub fn lower(self) -> Self {
+        match self {
+            Quality::High => Quality::Medium,
+            _ => Quality::Low,
+        }
+    }
+
+    pub fn higher(self) -> Self {
+        match self {
+            Quality::Low => Quality::Medium,
+            _ => Quality::High,
+        }
+    }
+
+    // Subdivisions of the orb's icosphere; each level has four times the
+    // triangles to deform.
+    pub fn orb_subdivisions(self) -> usize {
+        match self {
+            Quality::Low => 3,
+            Quality::Medium => 4,
+            Quality::High => 5,
+        }
+    }
+
+    // Cubes per column of the 3D grid, from the configured column size.
+    pub fn cube_column_size(self, column_size: usize) -> usize {
+        let scale = match self {
+            Quality::Low => 0.5,
+            Quality::Medium => 0.75,
+            Quality::High => 1.0,
+        };
+        ((column_size as f32 * scale).ceil() as usize).max(1)
+    }
+
+    // Bevy's bloom has no cheaper mode, so it is dropped entirely.
+    pub fn bloom(self) -> bool {
+        self != Quality::Low
+    }
+
+    // Size of the long FFT refining the low bands, 0 for none.
+    pub fn long_fft_size(self) -> usize {
+        match self {
+            Quality::Low => 0,
+            Quality::Medium => 8192,
+            Quality::High => 16384,
+        }
+    }
+
+    // Frame rate cap while the window is focused, none at High.
+    pub fn max_fps(self) -> Option<f32> {
+        match self {
+            Quality::Low => Some(30.0),
+            Quality::Medium => Some(60.0),
+            Quality::High => None,
+        }
+    }
+}
+
+// Lowers the quality of the visuals below the chosen one while the frame
+// rate can't hold the target, see `governor.rs`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveQualitySettings {
+    pub enabled: bool,
+    pub target_fps: f32,
+}
+
+impl Default for AdaptiveQualitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_fps: 50.0,
+        }
+    }
+}
+
+// --- UI Theme (egui style) ---
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub dark_mode: bool,
+    pub accent_color: Color,
+    pub panel_opacity: f32,
+    pub font_scale: f32,
+    // Scales the whole UI on top of the screen's own scale factor, for
+    // panels too small on a 4K screen or too large on a projector.
+    pub ui_scale: f32,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            accent_color: Color::rgb(0.0, 0.36, 0.5),
+            panel_opacity: 1.0,
+            font_scale: 1.0,
+            ui_scale: 1.0,
+        }
+    }
+}
+
+// --- Bloom Settings (used by the 3D scenes) ---
+#[derive(Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct BloomConfig {
+    pub enabled: bool,
+    pub intensity: f32,
+    pub threshold: f32,
+    pub color: Color,
+}
+
+impl Default for BloomConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 0.3,
+            threshold: 0.8,
+            color: Color::rgb(1.0, 0.2, 0.0),
+        }
+    }
+}
+
+// --- Floor Reflection (used by the 3D scenes) ---
+// A mirror-like floor under the scene. `roughness` blurs the reflection and
+// `tint` colors it; the tint's alpha sets how strongly it shows.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct FloorConfig {
+    pub enabled: bool,
+    pub roughness: f32,
+    pub tint: Color,
+}
+
+impl Default for FloorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            roughness: 0.3,
+            tint: Color::rgba(0.6, 0.7, 0.9, 0.6),
+        }
+    }
+}
+
+// --- Fog (used by the 3D scenes) ---
+// Linear distance fog between `start` and `end`. With `audio_density` above
+// zero, the bass pulls both distances closer, thickening the fog on hits.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct FogConfig {
+    pub enabled: bool,
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+    pub audio_density: f32,
+}
+
+impl Default for FogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // The default background, so distant shapes dissolve into it.
+            color: Color::rgb(0.4, 0.4, 0.4),
+            start: 15.0,
+            end: 45.0,
+            audio_density: 0.0,
+        }
+    }
+}
+
+// --- Camera (used by the 3D scenes) ---
+// Field of view in degrees. `bass_fov` widens it by up to that many degrees on
+// bass hits. The orthographic view frames the same area as the perspective
+// one at the orbit focus. `exposure` is in stops over Bevy's default.
+// Depth of field keeps what is `focus_distance` away from the camera sharp
+// (the default is the orbit radius) and blurs the rest more at lower f-numbers.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct CameraConfig {
+    pub fov: f32,
+    pub orthographic: bool,
+    pub bass_fov: f32,
+    pub exposure: f32,
+    pub tonemapping: TonemappingMode,
+    pub depth_of_field: bool,
+    pub focus_distance: f32,
+    pub aperture_f_stops: f32,
+    // Views saved with Ctrl+1..9 and recalled with 1..9, moving there over
+    // `bookmark_tween` seconds (instantly at 0).
+    pub bookmarks: [Option<CameraBookmark>; BOOKMARK_COUNT],
+    pub bookmark_tween: f32,
+}
+
+pub const BOOKMARK_COUNT: usize = 9;
+
+// An orbit camera pose: what it looks at, from which direction and how far.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct CameraBookmark {
+    pub focus: [f32; 3],
+    pub rotation: [f32; 4],
+    pub radius: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            // Bevy's default perspective.
+            fov: 45.0,
+            orthographic: false,
+            bass_fov: 0.0,
+            exposure: 0.0,
+            tonemapping: TonemappingMode::TonyMcMapface,
+            depth_of_field: false,
+            focus_distance: 15.0,
+            aperture_f_stops: 5.6,
+            bookmarks: Default::default(),
+            bookmark_tween: 1.0,
+        }
+    }
+}
+
+// The tonemapping curves of Bevy's HDR pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum TonemappingMode {
+    None,
+    Reinhard,
+    AcesFitted,
+    AgX,
+    TonyMcMapface,
+    BlenderFilmic,
+}
+
+impl TonemappingMode {
+    pub const ALL: [TonemappingMode; 6] = [
+        TonemappingMode::None,
+        TonemappingMode::Reinhard,
+        TonemappingMode::AcesFitted,
+        TonemappingMode::AgX,
+        TonemappingMode::TonyMcMapface,
+        TonemappingMode::BlenderFilmic,
+    ];
+}
+
+// --- Settings Panel Metadata ---
+// The settings panel reads and writes visualizer parameters through `Reflect`
+// and lays them out from these entries, so a new parameter only needs a line
+// in its struct's `UI_FIELDS`. Fields without an entry aren't shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsGroup {
+    Modulation,
+    Colors,
+    Geometry,
+    Motion,
+}
+
+impl SettingsGroup {
+    pub const ALL: [SettingsGroup; 4] = [
+        SettingsGroup::Modulation,
+        SettingsGroup::Colors,
+        SettingsGroup::Geometry,
+        SettingsGroup::Motion,
+    ];
+}
+
+pub struct FieldUi {
+    pub field: &'static str,
+    pub label: &'static str,
+    pub group: SettingsGroup,
+    // Slider bounds of numeric fields.
+    pub range: Option<(f64, f64)>,
+}
+
+impl FieldUi {
+    pub const fn slider(
+        field: &'static str,
+        label: &'static str,
+        group: SettingsGroup,
+        min: f64,
+        max: f64,
+    ) -> Self {
+        Self {
+            field,
+            label,
+            group,
+            range: Some((min, max)),
+        }
+    }
+
+    // Checkboxes and color pickers need no range.
+    pub const fn plain(field: &'static str, label: &'static str, group: SettingsGroup) -> Self {
+        Self {
+            field,
+            label,
+            group,
+            range: None,
+        }
+    }
+}
+
+// --- 2D Visualizer ---
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct Bars2DSettings {
     pub num_bands: usize,
-    pub details_panel_enabled: bool,
+    pub band_edges: Vec<f32>,
+    pub inactive_color: Color,
+    pub active_color: Color,
+}
 
-    // --- Bloom Settings ---
-    pub bloom_enabled: bool,
-    pub bloom_intensity: f32,
-    pub bloom_threshold: f32,
-    pub bloom_color: Color,
+impl Default for Bars2DSettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 16,
+            band_edges: Vec::new(),
+            inactive_color: Color::rgb(0.2, 0.2, 0.8),
+            active_color: Color::rgb(1.0, 0.3, 0.9),
+        }
+    }
+}
 
-    // --- 2D Visualizer ---
-    pub viz2d_inactive_color: Color,
-    pub viz2d_active_color: Color,
+impl Bars2DSettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::plain("inactive_color", "Inactive Color", SettingsGroup::Colors),
+        FieldUi::plain("active_color", "Active Color", SettingsGroup::Colors),
+        FieldUi::slider(
+            "num_bands",
+            "Frequency Bands (Rebuilds Grid)",
+            SettingsGroup::Geometry,
+            4.0,
+            64.0,
+        ),
+    ];
+}
 
-    // --- 3D Visualizer ---
+// --- 3D Visualizer ---
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct Cubes3DSettings {
+    pub num_bands: usize,
+    pub band_edges: Vec<f32>,
     pub spread_enabled: bool,
-    pub viz3d_base_color: Color,
-    pub viz3d_column_size: usize,
+    pub base_color: Color,
+    pub column_size: usize,
+    // Columns flash toward `flash_color` when their band has an onset, fading
+    // out over `flash_decay` seconds.
+    pub onset_flash: bool,
+    pub flash_color: Color,
+    pub flash_decay: f32,
+    pub bloom: BloomConfig,
+    pub floor: FloorConfig,
+    pub fog: FogConfig,
+    pub camera: CameraConfig,
+}
 
-    // --- Orb Visualizer ---
-    pub orb_base_color: Color,
-    pub orb_peak_color: Color,
-    pub orb_noise_speed: f32,
-    pub orb_noise_frequency: f32,
-    pub orb_treble_influence: f32,
+impl Default for Cubes3DSettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 16,
+            band_edges: Vec::new(),
+            spread_enabled: true,
+            base_color: Color::rgb(0.8, 0.7, 0.6),
+            column_size: 8,
+            onset_flash: true,
+            flash_color: Color::rgb(0.2, 0.9, 1.0),
+            flash_decay: 0.3,
+            bloom: BloomConfig::default(),
+            floor: FloorConfig::default(),
+            fog: FogConfig::default(),
+            camera: CameraConfig::default(),
+        }
+    }
+}
 
-    // --- Disc Visualizer Settings ---
-    pub disc_color: Color,
-    pub disc_radius: f32,
-    pub disc_line_thickness: f32,
-    pub disc_iterations: i32,
-    pub disc_speed: f32,
-    pub disc_center_radius_factor: f32,
+impl Cubes3DSettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::plain("spread_enabled", "Spread Effect", SettingsGroup::Modulation),
+        FieldUi::plain("onset_flash", "Onset Flash", SettingsGroup::Modulation),
+        FieldUi::slider(
+            "flash_decay",
+            "Flash Decay (s)",
+            SettingsGroup::Modulation,
+            0.05,
+            1.0,
+        ),
+        FieldUi::plain("base_color", "Cube Base Color", SettingsGroup::Colors),
+        FieldUi::plain("flash_color", "Flash Color", SettingsGroup::Colors),
+        FieldUi::slider(
+            "column_size",
+            "Column Size",
+            SettingsGroup::Geometry,
+            1.0,
+            16.0,
+        ),
+        FieldUi::slider(
+            "num_bands",
+            "Frequency Bands (Rebuilds Grid)",
+            SettingsGroup::Geometry,
+            4.0,
+            32.0,
+        ),
+    ];
+}
 
-    // --- Ico Visualizer Settings ---
-    pub ico_speed: f32,
-    pub ico_color: Color,
+// --- Orb Visualizer ---
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct OrbSettings {
+    pub num_bands: usize,
+    pub band_edges: Vec<f32>,
+    pub base_color: Color,
+    // The glow color is picked along this low-mid-high palette by the
+    // spectral centroid, or by the loudest band with `dominant_band_hue`.
+    pub palette_low: Color,
+    pub palette_mid: Color,
+    pub palette_high: Color,
+    pub dominant_band_hue: bool,
+    pub noise_speed: f32,
+    pub noise_frequency: f32,
+    pub treble_influence: f32,
+    // Rings sent across the surface on beats; none with a zero amplitude.
+    pub ripple_amplitude: f32,
+    pub ripple_speed: f32,
+    pub bloom: BloomConfig,
+    pub floor: FloorConfig,
+    pub fog: FogConfig,
+    pub camera: CameraConfig,
 }
 
-impl Default for VisualsConfig {
+impl Default for OrbSettings {
     fn default() -> Self {
         Self {
-            // --- General ---
-            bass_sensitivity: 1.0,
             num_bands: 16,
-            details_panel_enabled: false,
+            band_edges: Vec::new(),
+            base_color: Color::rgb(0.1, 0.1, 0.7),
+            palette_low: Color::rgb(1.0, 0.1, 0.3),
+            palette_mid: Color::rgb(1.0, 0.0, 1.0),
+            palette_high: Color::rgb(0.1, 0.8, 1.0),
+            dominant_band_hue: false,
+            noise_speed: 1.0,
+            noise_frequency: 2.0,
+            treble_influence: 0.3,
+            ripple_amplitude: 0.3,
+            ripple_speed: 2.0,
+            bloom: BloomConfig::default(),
+            floor: FloorConfig::default(),
+            fog: FogConfig::default(),
+            camera: CameraConfig::default(),
+        }
+    }
+}
 
-            // --- Bloom ---
-            bloom_enabled: true,
-            bloom_intensity: 0.3,
-            bloom_threshold: 0.8,
-            bloom_color: Color::rgb(1.0, 0.2, 0.0),
+impl OrbSettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::slider(
+            "treble_influence",
+            "Treble Influence",
+            SettingsGroup::Modulation,
+            0.0,
+            1.0,
+        ),
+        FieldUi::plain(
+            "dominant_band_hue",
+            "Color From Loudest Band",
+            SettingsGroup::Modulation,
+        ),
+        FieldUi::slider(
+            "ripple_amplitude",
+            "Beat Ripple Amplitude",
+            SettingsGroup::Modulation,
+            0.0,
+            1.0,
+        ),
+        FieldUi::plain("base_color", "Base Color", SettingsGroup::Colors),
+        FieldUi::plain("palette_low", "Bass Color", SettingsGroup::Colors),
+        FieldUi::plain("palette_mid", "Mid Color", SettingsGroup::Colors),
+        FieldUi::plain("palette_high", "Treble Color", SettingsGroup::Colors),
+        FieldUi::slider(
+            "noise_speed",
+            "Noise Speed",
+            SettingsGroup::Motion,
+            0.1,
+            5.0,
+        ),
+        FieldUi::slider(
+            "noise_frequency",
+            "Noise Frequency",
+            SettingsGroup::Motion,
+            0.5,
+            10.0,
+        ),
+        FieldUi::slider(
+            "ripple_speed",
+            "Beat Ripple Speed",
+            SettingsGroup::Motion,
+            0.5,
+            6.0,
+        ),
+    ];
+}
 
-            // --- 2D ---
-            viz2d_inactive_color: Color::rgb(0.2, 0.2, 0.8),
-            viz2d_active_color: Color::rgb(1.0, 0.3, 0.9),
+// --- Disc Visualizer ---
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct DiscSettings {
+    pub num_bands: usize,
+    pub band_edges: Vec<f32>,
+    pub color: Color,
+    pub radius: f32,
+    pub line_thickness: f32,
+    pub iterations: i32,
+    pub speed: f32,
+    pub center_radius_factor: f32,
+    // Each ring reacts to its own frequency band instead of the overall bass.
+    pub band_rings: bool,
+}
 
-            // --- 3D ---
-            spread_enabled: true,
-            viz3d_base_color: Color::rgb(0.8, 0.7, 0.6),
-            viz3d_column_size: 8,
-
-            // --- Orb ---
-            orb_base_color: Color::rgb(0.1, 0.1, 0.7),
-            orb_peak_color: Color::rgb(1.0, 0.0, 1.0),
-            orb_noise_speed: 1.0,
-            orb_noise_frequency: 2.0,
-            orb_treble_influence: 0.3,
-
-            // --- Disc Visualizer Defaults ---
-            disc_color: Color::rgb(1.0, 0.8, 0.2),
-            disc_radius: 0.8,
-            disc_line_thickness: 0.07,
-            disc_iterations: 35,
-            disc_speed: 1.0,
-            disc_center_radius_factor: 1.0,
-
-            // --- Ico Visualizer Defaults ---
-            ico_speed: 0.5,
-            ico_color: Color::rgb(0.5, 0.8, 0.9),
+impl Default for DiscSettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 16,
+            band_edges: Vec::new(),
+            color: Color::rgb(1.0, 0.8, 0.2),
+            radius: 0.8,
+            line_thickness: 0.07,
+            iterations: 35,
+            speed: 1.0,
+            center_radius_factor: 1.0,
+            band_rings: false,
+        }
+    }
+}
+
+impl DiscSettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::plain("band_rings", "One Ring Per Band", SettingsGroup::Modulation),
+        FieldUi::plain("color", "Disc Color", SettingsGroup::Colors),
+        FieldUi::slider("radius", "Radius", SettingsGroup::Geometry, 0.1, 2.0),
+        FieldUi::slider(
+            "line_thickness",
+            "Line Thickness",
+            SettingsGroup::Geometry,
+            0.01,
+            0.5,
+        ),
+        FieldUi::slider(
+            "iterations",
+            "Iterations (Echoes)",
+            SettingsGroup::Geometry,
+            1.0,
+            50.0,
+        ),
+        FieldUi::slider(
+            "center_radius_factor",
+            "Center Factor",
+            SettingsGroup::Geometry,
+            -1.0,
+            2.0,
+        ),
+        FieldUi::slider("speed", "Rotation Speed", SettingsGroup::Motion, -5.0, 5.0),
+    ];
+}
+
+// --- Ico Visualizer ---
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct IcoSettings {
+    pub num_bands: usize,
+    pub band_edges: Vec<f32>,
+    pub speed: f32,
+    pub color: Color,
+    // Tint of the ambient light, which colors the shaded side.
+    pub secondary_color: Color,
+    pub background_color: Color,
+    // Strength of the rim light, on top of what the flux adds.
+    pub glow_intensity: f32,
+    // How far the cursor swings the view around the ico (0 ignores it).
+    // `auto_orbit` swings it slowly on its own instead, for unattended setups.
+    pub mouse_sensitivity: f32,
+    pub auto_orbit: bool,
+}
+
+impl Default for IcoSettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 16,
+            band_edges: Vec::new(),
+            speed: 0.5,
+            color: Color::rgb(0.5, 0.8, 0.9),
+            secondary_color: Color::rgb_linear(0.5, 0.7, 0.8),
+            background_color: Color::rgb_linear(0.04, 0.045, 0.05),
+            glow_intensity: 0.2,
+            mouse_sensitivity: 1.0,
+            auto_orbit: false,
+        }
+    }
+}
+
+impl IcoSettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::slider(
+            "glow_intensity",
+            "Glow Intensity",
+            SettingsGroup::Modulation,
+            0.0,
+            2.0,
+        ),
+        FieldUi::plain("color", "Metallic Color", SettingsGroup::Colors),
+        FieldUi::plain("secondary_color", "Ambient Color", SettingsGroup::Colors),
+        FieldUi::plain(
+            "background_color",
+            "Background Color",
+            SettingsGroup::Colors,
+        ),
+        FieldUi::slider("speed", "Rotation Speed", SettingsGroup::Motion, -3.0, 3.0),
+        FieldUi::slider(
+            "mouse_sensitivity",
+            "Mouse Sensitivity",
+            SettingsGroup::Motion,
+            0.0,
+            2.0,
+        ),
+        FieldUi::plain(
+            "auto_orbit",
+            "Auto Orbit (Ignore Mouse)",
+            SettingsGroup::Motion,
+        ),
+    ];
+}
+
+// --- Shadertoy Visualizer ---
+// Runs `assets/shaders/shadertoy.wgsl`, a port of a Shadertoy shader. Its
+// audio texture is built from `num_bands` bands.
+#[derive(Clone, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct ShadertoySettings {
+    pub num_bands: usize,
+    pub band_edges: Vec<f32>,
+    // Multiplies the shader output.
+    pub tint: Color,
+    // How fast `iTime` runs.
+    pub time_scale: f32,
+    // Computes iChannel0 with an FFT on the GPU, see `gpu_fft.rs`.
+    pub gpu_fft: bool,
+}
+
+impl Default for ShadertoySettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 64,
+            band_edges: Vec::new(),
+            tint: Color::WHITE,
+            time_scale: 1.0,
+            gpu_fft: false,
+        }
+    }
+}
+
+impl ShadertoySettings {
+    pub const UI_FIELDS: &'static [FieldUi] = &[
+        FieldUi::slider(
+            "num_bands",
+            "Spectrum Resolution",
+            SettingsGroup::Geometry,
+            8.0,
+            128.0,
+        ),
+        FieldUi::plain("tint", "Tint", SettingsGroup::Colors),
+        FieldUi::slider("time_scale", "Time Scale", SettingsGroup::Motion, 0.0, 4.0),
+        FieldUi::plain("gpu_fft", "Spectrum From GPU FFT", SettingsGroup::Geometry),
+    ];
+}
+
+// The settings of a single visualizer, as stored in the `VisualsConfig` map.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum VizSettings {
+    Bars2D(Bars2DSettings),
+    Cubes3D(Cubes3DSettings),
+    Orb(OrbSettings),
+    Disc(DiscSettings),
+    Ico(IcoSettings),
+    Shadertoy(ShadertoySettings),
+}
+
+impl VizSettings {
+    pub fn default_for(id: VisualizerId) -> Self {
+        match id {
+            VisualizerId::Bars2D => VizSettings::Bars2D(default()),
+            VisualizerId::Cubes3D => VizSettings::Cubes3D(default()),
+            VisualizerId::Orb => VizSettings::Orb(default()),
+            VisualizerId::Disc => VizSettings::Disc(default()),
+            VisualizerId::Ico => VizSettings::Ico(default()),
+            VisualizerId::Shadertoy => VizSettings::Shadertoy(default()),
+        }
+    }
+
+    pub fn id(&self) -> VisualizerId {
+        match self {
+            VizSettings::Bars2D(_) => VisualizerId::Bars2D,
+            VizSettings::Cubes3D(_) => VisualizerId::Cubes3D,
+            VizSettings::Orb(_) => VisualizerId::Orb,
+            VizSettings::Disc(_) => VisualizerId::Disc,
+            VizSettings::Ico(_) => VisualizerId::Ico,
+            VizSettings::Shadertoy(_) => VisualizerId::Shadertoy,
+        }
+    }
+
+    pub fn num_bands(&self) -> usize {
+        match self {
+            VizSettings::Bars2D(s) => s.num_bands,
+            VizSettings::Cubes3D(s) => s.num_bands,
+            VizSettings::Orb(s) => s.num_bands,
+            VizSettings::Disc(s) => s.num_bands,
+            VizSettings::Ico(s) => s.num_bands,
+            VizSettings::Shadertoy(s) => s.num_bands,
+        }
+    }
+
+    // The edges of the bands in Hz, lowest first, when set by hand instead of
+    // the log spacing of the frequency range. They are ignored once the band
+    // count no longer matches.
+    pub fn band_edges(&self) -> Option<&[f32]> {
+        let edges = match self {
+            VizSettings::Bars2D(s) => &s.band_edges,
+            VizSettings::Cubes3D(s) => &s.band_edges,
+            VizSettings::Orb(s) => &s.band_edges,
+            VizSettings::Disc(s) => &s.band_edges,
+            VizSettings::Ico(s) => &s.band_edges,
+            VizSettings::Shadertoy(s) => &s.band_edges,
+        };
+        (edges.len() == self.num_bands() + 1).then_some(edges.as_slice())
+    }
+
+    pub fn band_edges_mut(&mut self) -> &mut Vec<f32> {
+        match self {
+            VizSettings::Bars2D(s) => &mut s.band_edges,
+            VizSettings::Cubes3D(s) => &mut s.band_edges,
+            VizSettings::Orb(s) => &mut s.band_edges,
+            VizSettings::Disc(s) => &mut s.band_edges,
+            VizSettings::Ico(s) => &mut s.band_edges,
+            VizSettings::Shadertoy(s) => &mut s.band_edges,
+        }
+    }
+
+    // The color that best represents the visualizer, e.g. for smart lights.
+    pub fn dominant_color(&self) -> Color {
+        match self {
+            VizSettings::Bars2D(s) => s.active_color,
+            VizSettings::Cubes3D(s) => s.base_color,
+            VizSettings::Orb(s) => s.palette_mid,
+            VizSettings::Disc(s) => s.color,
+            VizSettings::Ico(s) => s.color,
+            VizSettings::Shadertoy(s) => s.tint,
+        }
+    }
+
+    // Recolors the visualizer with a palette, from its darkest color for
+    // backgrounds and resting states to its brightest for peaks. Shadertoy
+    // ports keep their own colors.
+    pub fn apply_palette(&mut self, palette: &Palette) {
+        let [dark, low, mid, high] = palette.colors;
+        match self {
+            VizSettings::Bars2D(s) => {
+                s.inactive_color = low;
+                s.active_color = high;
+            }
+            VizSettings::Cubes3D(s) => {
+                s.base_color = mid;
+                s.flash_color = high;
+            }
+            VizSettings::Orb(s) => {
+                s.base_color = low;
+                s.palette_low = low;
+                s.palette_mid = mid;
+                s.palette_high = high;
+            }
+            VizSettings::Disc(s) => s.color = high,
+            VizSettings::Ico(s) => {
+                s.color = mid;
+                s.secondary_color = high;
+                s.background_color = dark;
+            }
+            VizSettings::Shadertoy(_) => {}
+        }
+    }
+
+    pub fn bloom(&self) -> Option<&BloomConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&s.bloom),
+            VizSettings::Orb(s) => Some(&s.bloom),
+            _ => None,
+        }
+    }
+
+    pub fn bloom_mut(&mut self) -> Option<&mut BloomConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&mut s.bloom),
+            VizSettings::Orb(s) => Some(&mut s.bloom),
+            _ => None,
+        }
+    }
+
+    pub fn floor(&self) -> Option<&FloorConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&s.floor),
+            VizSettings::Orb(s) => Some(&s.floor),
+            _ => None,
+        }
+    }
+
+    pub fn floor_mut(&mut self) -> Option<&mut FloorConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&mut s.floor),
+            VizSettings::Orb(s) => Some(&mut s.floor),
+            _ => None,
+        }
+    }
+
+    pub fn fog(&self) -> Option<&FogConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&s.fog),
+            VizSettings::Orb(s) => Some(&s.fog),
+            _ => None,
+        }
+    }
+
+    pub fn fog_mut(&mut self) -> Option<&mut FogConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&mut s.fog),
+            VizSettings::Orb(s) => Some(&mut s.fog),
+            _ => None,
         }
     }
+
+    pub fn camera(&self) -> Option<&CameraConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&s.camera),
+            VizSettings::Orb(s) => Some(&s.camera),
+            _ => None,
+        }
+    }
+
+    pub fn camera_mut(&mut self) -> Option<&mut CameraConfig> {
+        match self {
+            VizSettings::Cubes3D(s) => Some(&mut s.camera),
+            VizSettings::Orb(s) => Some(&mut s.camera),
+            _ => None,
+        }
+    }
+
+    // Reads a numeric field by name.
+    pub fn number(&self, field: &str) -> Option<f32> {
+        let field = self.as_struct().field(field)?;
+        if let Some(value) = field.downcast_ref::<f32>() {
+            Some(*value)
+        } else if let Some(value) = field.downcast_ref::<usize>() {
+            Some(*value as f32)
+        } else {
+            field.downcast_ref::<i32>().map(|value| *value as f32)
+        }
+    }
+
+    // Sets a numeric field by name, rounding for integer fields. Used by
+    // macros and automation; returns false if there is no such field.
+    pub fn set_number(&mut self, field: &str, value: f32) -> bool {
+        let Some(field) = self.as_struct_mut().field_mut(field) else {
+            return false;
+        };
+        if let Some(field) = field.downcast_mut::<f32>() {
+            *field = value;
+        } else if let Some(field) = field.downcast_mut::<usize>() {
+            *field = value.round().max(0.0) as usize;
+        } else if let Some(field) = field.downcast_mut::<i32>() {
+            *field = value.round() as i32;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn as_struct(&self) -> &dyn Struct {
+        match self {
+            VizSettings::Bars2D(s) => s,
+            VizSettings::Cubes3D(s) => s,
+            VizSettings::Orb(s) => s,
+            VizSettings::Disc(s) => s,
+            VizSettings::Ico(s) => s,
+            VizSettings::Shadertoy(s) => s,
+        }
+    }
+
+    pub fn as_struct_mut(&mut self) -> &mut dyn Struct {
+        match self {
+            VizSettings::Bars2D(s) => s,
+            VizSettings::Cubes3D(s) => s,
+            VizSettings::Orb(s) => s,
+            VizSettings::Disc(s) => s,
+            VizSettings::Ico(s) => s,
+            VizSettings::Shadertoy(s) => s,
+        }
+    }
+}
+
+// Links a settings struct to its `VizSettings` variant, so systems can ask the
+// config for their own typed settings with `config.viz::<OrbSettings>()`.
+pub trait VizSettingsEntry: Default + Into<VizSettings> {
+    const ID: VisualizerId;
+    fn from_entry(entry: &VizSettings) -> Option<&Self>;
+    fn from_entry_mut(entry: &mut VizSettings) -> Option<&mut Self>;
+}
+
+macro_rules! viz_settings_entry {
+    ($settings:ty, $variant:ident) => {
+        impl From<$settings> for VizSettings {
+            fn from(settings: $settings) -> Self {
+                VizSettings::$variant(settings)
+            }
+        }
+
+        impl VizSettingsEntry for $settings {
+            const ID: VisualizerId = VisualizerId::$variant;
+
+            fn from_entry(entry: &VizSettings) -> Option<&Self> {
+                match entry {
+                    VizSettings::$variant(settings) => Some(settings),
+                    _ => None,
+                }
+            }
+
+            fn from_entry_mut(entry: &mut VizSettings) -> Option<&mut Self> {
+                match entry {
+                    VizSettings::$variant(settings) => Some(settings),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+viz_settings_entry!(Bars2DSettings, Bars2D);
+viz_settings_entry!(Cubes3DSettings, Cubes3D);
+viz_settings_entry!(OrbSettings, Orb);
+viz_settings_entry!(DiscSettings, Disc);
+viz_settings_entry!(IcoSettings, Ico);
+viz_settings_entry!(ShadertoySettings, Shadertoy);
+
+// A resource that holds all the configurable parameters for the visualizations.
+// Each visualizer owns its own settings, so tweaking one never affects another.
+// This allows users to tweak the visuals in real-time through the UI.
+#[derive(Resource, Clone)]
+pub struct VisualsConfig {
+    pub general: GeneralSettings,
+    visualizers: HashMap<VisualizerId, VizSettings>,
+}
+
+impl Default for VisualsConfig {
+    fn default() -> Self {
+        Self {
+            general: GeneralSettings::default(),
+            visualizers: VisualizerId::ALL
+                .into_iter()
+                .map(|id| (id, VizSettings::default_for(id)))
+                .collect(),
+        }
+    }
+}
+
+impl VisualsConfig {
+    pub fn settings(&self, id: VisualizerId) -> &VizSettings {
+        self.visualizers
+            .get(&id)
+            .expect("Every visualizer has settings from startup")
+    }
+
+    pub fn viz<T: VizSettingsEntry>(&self) -> &T {
+        T::from_entry(self.settings(T::ID)).expect("Settings are keyed by their own id")
+    }
+
+    pub fn viz_mut<T: VizSettingsEntry>(&mut self) -> &mut T {
+        T::from_entry_mut(self.general_and_settings_mut(T::ID).1)
+            .expect("Settings are keyed by their own id")
+    }
+
+    // Borrows the shared settings alongside one visualizer's settings,
+    // for UI code that edits both at once.
+    pub fn general_and_settings_mut(
+        &mut self,
+        id: VisualizerId,
+    ) -> (&mut GeneralSettings, &mut VizSettings) {
+        let entry = self
+            .visualizers
+            .entry(id)
+            .or_insert_with(|| VizSettings::default_for(id));
+        (&mut self.general, entry)
+    }
+
+    // Replaces the settings of one visualizer, ignoring entries stored under the wrong id.
+    pub fn set_settings(&mut self, id: VisualizerId, settings: VizSettings) {
+        if settings.id() == id {
+            self.visualizers.insert(id, settings);
+        }
+    }
+
+    // Number of frequency bands the analysis should produce for the given visualizer.
+    pub fn num_bands(&self, id: VisualizerId) -> usize {
+        self.settings(id).num_bands()
+    }
+
+    // Band edges set by hand for the given visualizer, if any.
+    pub fn band_edges(&self, id: VisualizerId) -> Option<Vec<f32>> {
+        self.settings(id).band_edges().map(<[f32]>::to_vec)
+    }
+
+    // Serializes every persisted section to (file name, contents) pairs.
+    fn sections(&self) -> Vec<(String, String)> {
+        let mut sections = Vec::new();
+        if let Some(contents) = to_ron(&self.general) {
+            sections.push((GENERAL_SECTION.to_string(), contents));
+        }
+        for id in VisualizerId::ALL {
+            if let Some(contents) = to_ron(self.settings(id)) {
+                sections.push((id.key().to_string(), contents));
+            }
+        }
+        sections
+    }
+}
+
+// --- Persistence ---
+
+fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rust_visualizer")
+}
+
+fn to_ron<T: Serialize>(value: &T) -> Option<String> {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            error!("Failed to serialize config: {}", e);
+            None
+        }
+    }
+}
+
+fn parse_ron<T: DeserializeOwned>(name: &str, contents: &str) -> Option<T> {
+    match ron::from_str(contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Ignoring invalid config file '{}': {}", name, e);
+            None
+        }
+    }
+}
+
+// Named visualizer settings kept in the `presets` folder of the config
+// directory, e.g. a copy of `orb.ron` saved as `presets/calm.ron`.
+pub fn load_preset(name: &str) -> Result<VizSettings, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("invalid preset name '{}'", name));
+    }
+    let path = config_dir().join(PRESETS_DIR).join(format!("{}.ron", name));
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("preset '{}': {}", name, e))?;
+    ron::from_str(&contents).map_err(|e| format!("preset '{}': {}", name, e))
+}
+
+// Where the automation timeline of an audio file is kept, in the `timelines`
+// folder of the config directory. Named after the file and a hash of its full
+// path, so files of the same name in different folders keep their own.
+pub fn timeline_path(track: &Path) -> PathBuf {
+    let name = track
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    config_dir()
+        .join(TIMELINES_DIR)
+        .join(format!("{}-{:016x}.ron", name, path_hash(track)))
+}
+
+// FNV-1a, which unlike the standard library's hashers is guaranteed to give
+// the same value across runs and Rust versions.
+fn path_hash(path: &Path) -> u64 {
+    path.to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+// Where the preview of a visualizer shown in the selector is cached, in the
+// `thumbnails` folder of the config directory.
+pub fn thumbnail_path(id: VisualizerId) -> PathBuf {
+    config_dir()
+        .join(THUMBNAILS_DIR)
+        .join(format!("{}.png", id.key()))
+}
+
+// Where the last session is kept for "Resume Last Session".
+pub fn session_path() -> PathBuf {
+    config_dir().join(SESSION_FILE)
+}
+
+// Keeps track of where the config lives and what was last written,
+// so each section is only rewritten when it actually changed.
+#[derive(Resource)]
+pub struct ConfigStore {
+    dir: PathBuf,
+    save_timer: Timer,
+    last_saved: HashMap<String, String>,
+}
+
+impl ConfigStore {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            save_timer: Timer::from_seconds(SAVE_INTERVAL_SECS, TimerMode::Repeating),
+            last_saved: HashMap::new(),
+        }
+    }
+
+    fn section_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.ron", name))
+    }
+
+    fn load(&mut self) -> VisualsConfig {
+        let mut config = VisualsConfig::default();
+
+        self.apply_section(GENERAL_SECTION, &mut config);
+        for id in VisualizerId::ALL {
+            self.apply_section(id.key(), &mut config);
+        }
+
+        info!("Loaded visualizer config from {:?}", self.dir);
+        config
+    }
+
+    // Reads one section file from disk into the config.
+    // Returns false if the file is missing, invalid, or identical to what we last saw.
+    fn apply_section(&mut self, name: &str, config: &mut VisualsConfig) -> bool {
+        let Ok(contents) = std::fs::read_to_string(self.section_path(name)) else {
+            return false;
+        };
+        if self.last_saved.get(name) == Some(&contents) {
+            return false;
+        }
+
+        if name == GENERAL_SECTION {
+            let Some(general) = parse_ron(name, &contents) else {
+                return false;
+            };
+            config.general = general;
+        } else if let Some(id) = VisualizerId::from_key(name) {
+            let Some(settings) = parse_ron::<VizSettings>(name, &contents) else {
+                return false;
+            };
+            if settings.id() != id {
+                warn!("Settings file for {:?} contains another visualizer", id);
+                return false;
+            }
+            config.set_settings(id, settings);
+        } else {
+            return false;
+        }
+
+        // Remember our own serialization of the section, so the periodic save
+        // doesn't rewrite a hand-edited file that only differs in formatting.
+        let normalized = config
+            .sections()
+            .into_iter()
+            .find(|(section, _)| section == name)
+            .map_or(contents, |(_, normalized)| normalized);
+        self.last_saved.insert(name.to_string(), normalized);
+        true
+    }
+
+    fn save(&mut self, config: &VisualsConfig) {
+        for (name, contents) in config.sections() {
+            if self.last_saved.get(&name) == Some(&contents) {
+                continue;
+            }
+            if let Err(e) = std::fs::create_dir_all(&self.dir) {
+                error!("Failed to create config directory {:?}: {}", self.dir, e);
+                return;
+            }
+            let path = self.section_path(&name);
+            match std::fs::write(&path, &contents) {
+                Ok(()) => {
+                    self.last_saved.insert(name, contents);
+                }
+                Err(e) => error!("Failed to write config file {:?}: {}", path, e),
+            }
+        }
+    }
+}
+
+// Watches the config directory and forwards the paths of modified files.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+}
+
+impl ConfigWatcher {
+    fn new(dir: &Path) -> notify::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        let (tx, rx) = std::sync::mpsc::channel::<PathBuf>();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                if event.kind.is_modify() || event.kind.is_create() {
+                    for path in event.paths {
+                        tx.send(path).ok();
+                    }
+                }
+            }
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver: rx,
+        })
+    }
+}
+
+fn reload_changed_config_files(
+    watcher: Option<NonSend<ConfigWatcher>>,
+    mut config: ResMut<VisualsConfig>,
+    mut store: ResMut<ConfigStore>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let Some(watcher) = watcher else { return };
+
+    // A single save usually produces several events, so collect unique sections first.
+    let mut sections: Vec<String> = Vec::new();
+    for path in watcher.receiver.try_iter() {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("ron") {
+            continue;
+        }
+        if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+            if !sections.iter().any(|s| s == name) {
+                sections.push(name.to_string());
+            }
+        }
+    }
+
+    for name in sections {
+        if store.apply_section(&name, config.bypass_change_detection()) {
+            info!("Reloaded config section '{}' from disk", name);
+            toasts.info(format!("Reloaded {} settings", name));
+            config.set_changed();
+        }
+    }
+}
+
+fn save_config_system(time: Res<Time>, config: Res<VisualsConfig>, mut store: ResMut<ConfigStore>) {
+    store.save_timer.tick(time.delta());
+    if store.save_timer.just_finished() {
+        store.save(&config);
+    }
+}
+
+fn flush_config_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    config: Res<VisualsConfig>,
+    mut store: ResMut<ConfigStore>,
+) {
+    if exit_events.read().next().is_some() {
+        store.save(&config);
+    }
 }
@@ -0,0 +1,365 @@
+// src/discord.rs
+
+use crate::audio::{
+    display_name, AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource, SelectedMic,
+};
+use crate::config::{DiscordSettings, VisualizerId, VisualsConfig};
+use crate::i18n::Language;
+use crate::toast::Toasts;
+use crate::ui::viz_label;
+use crate::AppState;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Discord accepts about five presence updates every 20 seconds.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(4);
+// How often to look for Discord again while it isn't running.
+const RETRY_INTERVAL: Duration = Duration::from_secs(15);
+// Seeking by less than this doesn't send a new elapsed time.
+const ELAPSED_TOLERANCE_SECS: u64 = 2;
+
+// Opcodes of Discord's local IPC frames.
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+const OP_CLOSE: u32 = 2;
+
+pub struct DiscordPlugin;
+
+impl Plugin for DiscordPlugin {
+    fn build(&self, app: &mut App) {
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<DiscordJob>();
+        spawn_discord_thread(job_rx);
+
+        app.insert_resource(DiscordPresence { jobs: job_tx })
+            .add_systems(Update, update_discord_presence);
+    }
+}
+
+#[derive(Resource)]
+pub struct DiscordPresence {
+    jobs: Sender<DiscordJob>,
+}
+
+// What the profile shows: "Listening to <track>", "<visualizer> visualizer"
+// and, while playing, the time elapsed as a Unix timestamp of the start.
+#[derive(Clone)]
+struct Activity {
+    details: String,
+    state: String,
+    start: Option<u64>,
+}
+
+impl Activity {
+    fn same_as(&self, other: &Activity) -> bool {
+        self.details == other.details
+            && self.state == other.state
+            && match (self.start, other.start) {
+                (Some(a), Some(b)) => a.abs_diff(b) <= ELAPSED_TOLERANCE_SECS,
+                (a, b) => a == b,
+            }
+    }
+}
+
+// `None` clears the presence and lets go of Discord.
+struct DiscordJob {
+    application_id: String,
+    activity: Option<Activity>,
+}
+
+// Shows the presence while a visualization runs, and clears it in the menus
+// or once disabled. Only changes are sent, including a new application ID.
+// Turning it on without an application ID says so once instead of doing
+// nothing.
+#[allow(clippy::too_many_arguments)]
+fn update_discord_presence(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    selected_source: Res<SelectedAudioSource>,
+    selected_mic: Res<SelectedMic>,
+    playback_info: Res<PlaybackInfo>,
+    presence: Res<DiscordPresence>,
+    mut sent: Local<(String, Option<Activity>)>,
+    mut mic_since: Local<Option<u64>>,
+    mut toasts: ResMut<Toasts>,
+    mut warned: Local<bool>,
+) {
+    let discord = &config.general.discord;
+    let application_id = discord.application_id.trim();
+    let missing_id = discord.enabled && application_id.is_empty();
+    if missing_id && !*warned {
+        warn!("Discord Rich Presence is on but has no application ID");
+        toasts.error("Discord Rich Presence needs an application ID (see the Discord settings)");
+    }
+    *warned = missing_id;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let visualizer = VisualizerId::from_state(app_state.get());
+    let track = match &selected_source.0 {
+        AudioSource::File(path) | AudioSource::Replay(path) => {
+            let start = (playback_info.status == PlaybackStatus::Playing)
+                .then(|| now.saturating_sub(playback_info.position.as_secs()));
+            Some((display_name(path), start))
+        }
+        AudioSource::Microphone => Some((
+            selected_mic
+                .device
+                .clone()
+                .unwrap_or_else(|| "the microphone".to_string()),
+            Some(*mic_since.get_or_insert(now)),
+        )),
+        AudioSource::None => None,
+    };
+    if !matches!(selected_source.0, AudioSource::Microphone) {
+        *mic_since = None;
+    }
+
+    let activity = match (visualizer, track) {
+        (Some(visualizer), Some((name, start)))
+            if discord.enabled && !application_id.is_empty() =>
+        {
+            Some(Activity {
+                details: format!("Listening to {}", name),
+                state: format!("{} visualizer", viz_label(visualizer)),
+                start,
+            })
+        }
+        _ => None,
+    };
+    let unchanged = match (&activity, &sent.1) {
+        (Some(activity), Some(sent_activity)) => {
+            activity.same_as(sent_activity) && sent.0 == application_id
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return;
+    }
+
+    presence
+        .jobs
+        .send(DiscordJob {
+            application_id: application_id.to_string(),
+            activity: activity.clone(),
+        })
+        .ok();
+    *sent = (application_id.to_string(), activity);
+}
+
+// The "Discord" settings section.
+pub fn discord_section(ui: &mut egui::Ui, lang: Language, discord: &mut DiscordSettings) {
+    let t = |text: &'static str| lang.tr(text);
+
+    ui.checkbox(&mut discord.enabled, t("Show What's Playing on Discord"));
+    ui.horizontal(|ui| {
+        ui.label(t("Application ID"));
+        ui.text_edit_singleline(&mut discord.application_id);
+    });
+    if discord.enabled && discord.application_id.trim().is_empty() {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!(
+                "⚠ {}",
+                t("Create an application in the Discord Developer Portal and paste its ID.")
+            ),
+        );
+    }
+}
+
+// --- Discord Thread ---
+
+fn spawn_discord_thread(jobs: Receiver<DiscordJob>) {
+    std::thread::Builder::new()
+        .name("discord".into())
+        .spawn(move || {
+            let mut client: Option<IpcClient> = None;
+            // A presence Discord wasn't there to take yet.
+            let mut pending: Option<DiscordJob> = None;
+            let mut failing = false;
+            loop {
+                let job = match pending.take() {
+                    Some(job) => match jobs.recv_timeout(RETRY_INTERVAL) {
+                        Ok(newer) => newer,
+                        Err(RecvTimeoutError::Timeout) => job,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    },
+                    None => match jobs.recv() {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    },
+                };
+                // Only the newest presence matters if updates pile up.
+                let job = jobs.try_iter().last().unwrap_or(job);
+
+                match run_job(&mut client, &job) {
+                    Ok(()) => {
+                        failing = false;
+                        std::thread::sleep(UPDATE_INTERVAL);
+                    }
+                    Err(e) => {
+                        // Retried quietly until Discord shows up.
+                        if !failing {
+                            warn!("Discord Rich Presence: {}", e);
+                        }
+                        failing = true;
+                        client = None;
+                        pending = Some(job);
+                    }
+                }
+            }
+        })
+        .expect("Failed to spawn the Discord thread");
+}
+
+fn run_job(
+    client: &mut Option<IpcClient>,
+    job: &DiscordJob,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(activity) = &job.activity else {
+        if let Some(mut connected) = client.take() {
+            connected.set_activity(None)?;
+        }
+        return Ok(());
+    };
+    if client
+        .as_ref()
+        .is_some_and(|connected| connected.application_id != job.application_id)
+    {
+        *client = None;
+    }
+    if client.is_none() {
+        *client = Some(IpcClient::connect(&job.application_id)?);
+        info!("Connected to Discord");
+    }
+    if let Some(connected) = client {
+        connected.set_activity(Some(activity))?;
+    }
+    Ok(())
+}
+
+trait IpcStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> IpcStream for T {}
+
+// A connection to the Discord client running on this machine. Each frame is
+// an opcode and a length, both little-endian u32, then that much JSON.
+struct IpcClient {
+    stream: Box<dyn IpcStream>,
+    application_id: String,
+    nonce: u64,
+}
+
+impl IpcClient {
+    fn connect(application_id: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut client = Self {
+            stream: open_ipc_stream().ok_or("Discord is not running")?,
+            application_id: application_id.to_string(),
+            nonce: 0,
+        };
+        client.send(
+            OP_HANDSHAKE,
+            &json!({ "v": 1, "client_id": application_id }),
+        )?;
+        // The READY event, or a close frame for an unknown application ID.
+        client.receive()?;
+        Ok(client)
+    }
+
+    fn set_activity(
+        &mut self,
+        activity: Option<&Activity>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let activity = activity.map(|activity| {
+            let mut value = json!({ "details": activity.details, "state": activity.state });
+            if let Some(start) = activity.start {
+                value["timestamps"] = json!({ "start": start });
+            }
+            value
+        });
+        self.nonce += 1;
+        self.send(
+            OP_FRAME,
+            &json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": std::process::id(), "activity": activity },
+                "nonce": self.nonce.to_string(),
+            }),
+        )?;
+        let reply = self.receive()?;
+        if reply["evt"] == "ERROR" {
+            let message = reply["data"]["message"]
+                .as_str()
+                .unwrap_or("unexpected reply from Discord");
+            return Err(message.into());
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, opcode: u32, payload: &Value) -> std::io::Result<()> {
+        let body = payload.to_string();
+        let mut frame = Vec::with_capacity(8 + body.len());
+        frame.extend_from_slice(&opcode.to_le_bytes());
+        frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        frame.extend_from_slice(body.as_bytes());
+        self.stream.write_all(&frame)?;
+        self.stream.flush()
+    }
+
+    fn receive(&mut self) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut header = [0u8; 8];
+        self.stream.read_exact(&mut header)?;
+        let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut body = vec![0u8; length as usize];
+        self.stream.read_exact(&mut body)?;
+        let reply: Value = serde_json::from_slice(&body)?;
+        if opcode == OP_CLOSE {
+            let message = reply["message"]
+                .as_str()
+                .unwrap_or("Discord closed the connection");
+            return Err(message.to_string().into());
+        }
+        Ok(reply)
+    }
+}
+
+// Discord listens on the first free of ten sockets, in the runtime
+// directory, or in the sandbox of its Flatpak or Snap package.
+#[cfg(unix)]
+fn open_ipc_stream() -> Option<Box<dyn IpcStream>> {
+    let runtime_dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(std::env::var_os)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    ["", "app/com.discordapp.Discord", "snap.discord"]
+        .iter()
+        .flat_map(|sandbox| (0..10).map(move |i| (sandbox, i)))
+        .find_map(|(sandbox, i)| {
+            let path = runtime_dir.join(sandbox).join(format!("discord-ipc-{}", i));
+            std::os::unix::net::UnixStream::connect(path).ok()
+        })
+        .map(|stream| Box::new(stream) as Box<dyn IpcStream>)
+}
+
+// On Windows the sockets are named pipes.
+#[cfg(windows)]
+fn open_ipc_stream() -> Option<Box<dyn IpcStream>> {
+    (0..10)
+        .find_map(|i| {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(PathBuf::from(format!(r"\\.\pipe\discord-ipc-{}", i)))
+                .ok()
+        })
+        .map(|pipe| Box::new(pipe) as Box<dyn IpcStream>)
+}
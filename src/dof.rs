@@ -0,0 +1,277 @@
+// src/dof.rs
+
+use crate::camera::MainCamera3D;
+use crate::config::{VisualizerId, VisualsConfig};
+use crate::AppState;
+use bevy::core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy::core_pipeline::prepass::{DepthPrepass, ViewPrepassTextures};
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::binding_types::{
+    sampler, texture_2d, texture_depth_2d, texture_depth_2d_multisampled, uniform_buffer,
+};
+use bevy::render::render_resource::{
+    BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BindGroupLayoutEntryBuilder,
+    CachedRenderPipelineId, ColorTargetState, ColorWrites, FragmentState, MultisampleState,
+    Operations, PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
+    ShaderType, TextureSampleType,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::view::{ViewTarget, ViewUniform, ViewUniformOffset, ViewUniforms};
+use bevy::render::RenderApp;
+
+// Super 35, like Bevy's physical camera defaults.
+const SENSOR_HEIGHT: f32 = 0.01866;
+// The scenes are table-sized: a world unit is a centimeter to the lens, or
+// the blur would only show a few meters from the camera.
+const WORLD_UNIT: f32 = 0.01;
+// Largest blur radius in pixels, which bounds the cost of the gather.
+const MAX_BLUR: f32 = 24.0;
+
+pub struct DofPlugin;
+
+impl Plugin for DofPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<DepthOfField>::default(),
+            UniformComponentPlugin::<DepthOfField>::default(),
+        ))
+        .add_systems(Update, update_depth_of_field);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        // Before bloom and tonemapping, so the blur mixes linear HDR colors.
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<DofNode>>(Core3d, DofLabel)
+            .add_render_graph_edges(Core3d, (Node3d::EndMainPass, DofLabel, Node3d::Bloom));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<DofPipeline>();
+    }
+}
+
+// The lens of a camera, as the uniform of `depth_of_field.wgsl`. Cameras
+// without one skip the pass.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct DepthOfField {
+    // In world units.
+    focus_distance: f32,
+    // Circle of confusion of a point at infinity, as a fraction of the view
+    // height. Nearer points scale it by |distance - focus| / distance.
+    coc_scale: f32,
+    // In pixels.
+    max_blur: f32,
+}
+
+impl DepthOfField {
+    // The thin lens model, with the focal length that gives the field of view
+    // on the sensor.
+    fn new(fov_degrees: f32, focus_distance: f32, f_stops: f32) -> Self {
+        let focal_length = 0.5 * SENSOR_HEIGHT / (0.5 * fov_degrees.to_radians()).tan();
+        let focus = (focus_distance * WORLD_UNIT).max(focal_length * 1.01);
+        let coc = focal_length * focal_length / (f_stops.max(0.5) * (focus - focal_length));
+        Self {
+            focus_distance,
+            coc_scale: coc / SENSOR_HEIGHT,
+            max_blur: MAX_BLUR,
+        }
+    }
+}
+
+// Gives the 3D camera the lens of the current visualizer, along with the
+// depth prepass the blur reads. Turning the effect off removes both.
+fn update_depth_of_field(
+    mut commands: Commands,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut q_camera: Query<(Entity, Option<&mut DepthOfField>), With<MainCamera3D>>,
+) {
+    let lens = VisualizerId::from_state(app_state.get())
+        .and_then(|id| config.settings(id).camera())
+        .filter(|camera| camera.depth_of_field)
+        .map(|camera| {
+            DepthOfField::new(camera.fov, camera.focus_distance, camera.aperture_f_stops)
+        });
+
+    for (entity, current) in &mut q_camera {
+        match (lens, current) {
+            (Some(lens), Some(mut current)) => *current = lens,
+            (Some(lens), None) => {
+                commands.entity(entity).insert((lens, DepthPrepass));
+            }
+            (None, Some(_)) => {
+                commands
+                    .entity(entity)
+                    .remove::<(DepthOfField, DepthPrepass)>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct DofLabel;
+
+#[derive(Default)]
+struct DofNode;
+
+impl ViewNode for DofNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewPrepassTextures,
+        &'static ViewUniformOffset,
+        &'static DynamicUniformIndex<DepthOfField>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, prepass_textures, view_offset, lens_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if !view_target.is_hdr() {
+            return Ok(());
+        }
+        let Some(depth) = prepass_textures.depth_view() else {
+            return Ok(());
+        };
+        let dof_pipeline = world.resource::<DofPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (layout, pipeline_id) = if world.resource::<Msaa>().samples() > 1 {
+            (
+                &dof_pipeline.multisampled_layout,
+                dof_pipeline.multisampled_pipeline,
+            )
+        } else {
+            (&dof_pipeline.layout, dof_pipeline.pipeline)
+        };
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+        let lenses = world.resource::<ComponentUniforms<DepthOfField>>();
+        let Some(lens_binding) = lenses.uniforms().binding() else {
+            return Ok(());
+        };
+        let Some(view_binding) = world.resource::<ViewUniforms>().uniforms.binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "depth_of_field_bind_group",
+            layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &dof_pipeline.sampler,
+                depth,
+                lens_binding.clone(),
+                view_binding.clone(),
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("depth_of_field_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[lens_index.index(), view_offset.offset]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+// The 3D camera renders in HDR; the variants only differ in how the depth
+// prepass is sampled, which follows the MSAA setting.
+#[derive(Resource)]
+struct DofPipeline {
+    layout: BindGroupLayout,
+    multisampled_layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline: CachedRenderPipelineId,
+    multisampled_pipeline: CachedRenderPipelineId,
+}
+
+impl FromWorld for DofPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let create_layout = |label: &'static str, depth: BindGroupLayoutEntryBuilder| {
+            render_device.create_bind_group_layout(
+                label,
+                &BindGroupLayoutEntries::sequential(
+                    ShaderStages::FRAGMENT,
+                    (
+                        texture_2d(TextureSampleType::Float { filterable: true }),
+                        sampler(SamplerBindingType::Filtering),
+                        depth,
+                        uniform_buffer::<DepthOfField>(true),
+                        uniform_buffer::<ViewUniform>(true),
+                    ),
+                ),
+            )
+        };
+        let layout = create_layout("depth_of_field_bind_group_layout", texture_depth_2d());
+        let multisampled_layout = create_layout(
+            "depth_of_field_multisampled_bind_group_layout",
+            texture_depth_2d_multisampled(),
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/depth_of_field.wgsl");
+
+        let mut queue = |layout: &BindGroupLayout, shader_defs: Vec<_>| {
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("depth_of_field_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs,
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: ViewTarget::TEXTURE_FORMAT_HDR,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+        };
+        let pipeline = queue(&layout, vec![]);
+        let multisampled_pipeline = queue(&multisampled_layout, vec!["MULTISAMPLED".into()]);
+
+        Self {
+            layout,
+            multisampled_layout,
+            sampler,
+            pipeline,
+            multisampled_pipeline,
+        }
+    }
+}
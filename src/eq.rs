@@ -0,0 +1,245 @@
+// src/eq.rs
+
+use crate::audio::{AudioSource, SelectedAudioSource};
+use crate::config::{EqSettings, VisualsConfig};
+use crate::resample::ANALYSIS_SAMPLE_RATE;
+use bevy::prelude::*;
+use rodio::Source;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const EQ_BAND_COUNT: usize = 10;
+// Octave-spaced centers of the graphic EQ, in Hz.
+pub const EQ_FREQUENCIES: [f32; EQ_BAND_COUNT] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+// About one octave wide.
+const EQ_Q: f32 = 1.41;
+// The playback EQ picks up new gains every this many samples.
+const EQ_POLL_SAMPLES: usize = 1024;
+
+// The EQ gains as seen by the audio thread, which only applies them to the
+// file being played when `playback` is set.
+#[derive(Resource, Clone, Default)]
+pub struct EqControl {
+    gains: Arc<[AtomicU32; EQ_BAND_COUNT]>,
+    playback: Arc<AtomicBool>,
+}
+
+impl EqControl {
+    fn gains(&self) -> [f32; EQ_BAND_COUNT] {
+        std::array::from_fn(|i| f32::from_bits(self.gains[i].load(Ordering::Relaxed)))
+    }
+}
+
+// The EQ of the analysis input, used unless the playback EQ already
+// filters what the analysis receives.
+#[derive(Resource)]
+pub struct AnalysisEq {
+    pub active: bool,
+    equalizer: Equalizer,
+}
+
+impl Default for AnalysisEq {
+    fn default() -> Self {
+        Self {
+            active: false,
+            equalizer: Equalizer::new(ANALYSIS_SAMPLE_RATE, 1),
+        }
+    }
+}
+
+impl AnalysisEq {
+    // Filters mono samples at `ANALYSIS_SAMPLE_RATE` in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if !self.active {
+            return;
+        }
+        for sample in samples {
+            *sample = self.equalizer.process(*sample, 0);
+        }
+    }
+}
+
+// Hands the settings over to both EQs whenever they, or the source, change.
+pub fn sync_equalizer(
+    config: Res<VisualsConfig>,
+    selected_source: Res<SelectedAudioSource>,
+    control: Res<EqControl>,
+    mut analysis_eq: ResMut<AnalysisEq>,
+) {
+    if !config.is_changed() && !selected_source.is_changed() {
+        return;
+    }
+    let settings = &config.general.eq;
+    let gains = if settings.enabled {
+        settings.gains
+    } else {
+        [0.0; EQ_BAND_COUNT]
+    };
+    for (atomic, gain) in control.gains.iter().zip(gains) {
+        atomic.store(gain.to_bits(), Ordering::Relaxed);
+    }
+    let on_playback = settings.playback && matches!(selected_source.0, AudioSource::File(_));
+    control.playback.store(on_playback, Ordering::Relaxed);
+
+    analysis_eq.active = settings.enabled && !on_playback && !is_flat(settings);
+    analysis_eq.equalizer.set_gains(&gains);
+}
+
+fn is_flat(settings: &EqSettings) -> bool {
+    settings.gains.iter().all(|gain| *gain == 0.0)
+}
+
+// RBJ peaking filter in transposed direct form II.
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    fn set_peaking(&mut self, frequency: f32, gain_db: f32, sample_rate: u32) {
+        let nyquist = sample_rate as f32 / 2.0;
+        if gain_db == 0.0 || frequency >= nyquist {
+            (self.b0, self.b1, self.b2, self.a1, self.a2) = (1.0, 0.0, 0.0, 0.0, 0.0);
+            return;
+        }
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = std::f32::consts::TAU * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * EQ_Q);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha / a;
+        self.b0 = (1.0 + alpha * a) / a0;
+        self.b1 = -2.0 * cos / a0;
+        self.b2 = (1.0 - alpha * a) / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha / a) / a0;
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+// The ten peaking filters in series, for each channel.
+struct Equalizer {
+    sample_rate: u32,
+    gains: [f32; EQ_BAND_COUNT],
+    // `EQ_BAND_COUNT` filters per channel.
+    filters: Vec<Biquad>,
+}
+
+impl Equalizer {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            sample_rate,
+            gains: [0.0; EQ_BAND_COUNT],
+            filters: vec![Biquad::default(); channels.max(1) * EQ_BAND_COUNT],
+        }
+    }
+
+    // Keeps the filter states, so the change doesn't click.
+    fn set_gains(&mut self, gains: &[f32; EQ_BAND_COUNT]) {
+        self.gains = *gains;
+        for channel in self.filters.chunks_mut(EQ_BAND_COUNT) {
+            for ((filter, frequency), gain) in channel.iter_mut().zip(EQ_FREQUENCIES).zip(gains) {
+                filter.set_peaking(frequency, *gain, self.sample_rate);
+            }
+        }
+    }
+
+    fn process(&mut self, sample: f32, channel: usize) -> f32 {
+        let start = channel * EQ_BAND_COUNT;
+        self.filters[start..start + EQ_BAND_COUNT]
+            .iter_mut()
+            .zip(&self.gains)
+            .filter(|(_, gain)| **gain != 0.0)
+            .fold(sample, |sample, (filter, _)| filter.process(sample))
+    }
+}
+
+// Equalizes the file as it is played, while the control says so.
+pub struct EqSource<S> {
+    source: S,
+    control: EqControl,
+    equalizer: Equalizer,
+    channels: usize,
+    channel: usize,
+    active: bool,
+    until_poll: usize,
+}
+
+impl<S> EqSource<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(source: S, control: EqControl) -> Self {
+        let channels = source.channels().max(1) as usize;
+        let equalizer = Equalizer::new(source.sample_rate(), channels);
+        Self {
+            source,
+            control,
+            equalizer,
+            channels,
+            channel: 0,
+            active: false,
+            until_poll: 0,
+        }
+    }
+}
+
+impl<S> Iterator for EqSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        if self.until_poll == 0 {
+            self.until_poll = EQ_POLL_SAMPLES;
+            self.active = self.control.playback.load(Ordering::Relaxed);
+            let gains = self.control.gains();
+            if gains != self.equalizer.gains {
+                self.equalizer.set_gains(&gains);
+            }
+        }
+        self.until_poll -= 1;
+
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.channels;
+        Some(if self.active {
+            self.equalizer.process(sample, channel)
+        } else {
+            sample
+        })
+    }
+}
+
+impl<S> Source for EqSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
@@ -0,0 +1,169 @@
+// src/export.rs
+
+use crate::audio::{apply_analysis_frame, display_name, AudioAnalysis, ANALYSIS_RATE_HZ};
+use crate::backend::Backend;
+use crate::config::{CrossoverSettings, SmoothingSettings};
+use crate::offline::{self, TrackSettings};
+use crate::toast::Toast;
+use bevy::log::{error, info};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+// What the export analyses with, taken from the current settings.
+pub struct ExportSettings {
+    pub track: TrackSettings,
+    pub crossovers: CrossoverSettings,
+    pub smoothing: SmoothingSettings,
+}
+
+#[derive(Serialize)]
+struct ExportedAnalysis {
+    source: String,
+    rate_hz: f64,
+    band_limits: Vec<f32>,
+    frames: Vec<ExportedFrame>,
+}
+
+// One analysis tick, smoothed the way the visualizers see it.
+#[derive(Serialize)]
+struct ExportedFrame {
+    // Seconds from the start of the file to the centre of the window.
+    time: f32,
+    volume: f32,
+    bass: f32,
+    sub_bass: f32,
+    punch: f32,
+    mid: f32,
+    treble: f32,
+    flux: f32,
+    beat: bool,
+    bins: Vec<f32>,
+}
+
+// Analyses the whole of `source` in the background and writes it to
+// `destination`, as JSON for a `.json` path and CSV otherwise.
+pub fn spawn_export(
+    source: PathBuf,
+    destination: PathBuf,
+    settings: ExportSettings,
+    backend: Backend,
+    toasts: Sender<Toast>,
+) {
+    std::thread::Builder::new()
+        .name("analysis-export".into())
+        .spawn(move || {
+            let toast = match export(&backend, &source, &destination, &settings) {
+                Ok(frames) => {
+                    info!("Exported {} frames to {:?}", frames, destination);
+                    Toast::success(format!(
+                        "Exported analysis to {}",
+                        display_name(&destination)
+                    ))
+                }
+                Err(e) => {
+                    error!("❌ Failed to export analysis to {:?}: {}", destination, e);
+                    Toast::error(format!("Export failed: {}", e))
+                }
+            };
+            toasts.send(toast).ok();
+        })
+        .expect("Failed to spawn the analysis export thread");
+}
+
+fn export(
+    backend: &Backend,
+    source: &Path,
+    destination: &Path,
+    settings: &ExportSettings,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let exported = analyse_file(backend, source, settings)?;
+    let mut writer = BufWriter::new(File::create(destination)?);
+
+    let is_json = destination
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        serde_json::to_writer(&mut writer, &exported)?;
+    } else {
+        write_csv(&mut writer, &exported)?;
+    }
+    writer.flush()?;
+    Ok(exported.frames.len())
+}
+
+// Smooths the raw frames of the file's track the way the visualizers would.
+fn analyse_file(
+    backend: &Backend,
+    path: &Path,
+    settings: &ExportSettings,
+) -> Result<ExportedAnalysis, Box<dyn std::error::Error>> {
+    let track = offline::analyse_file(backend, path, &settings.track)?;
+    let dt = (1.0 / ANALYSIS_RATE_HZ) as f32;
+    let mut analysis = AudioAnalysis::default();
+    let mut frames = Vec::with_capacity(track.frames().len());
+
+    for track_frame in track.frames() {
+        apply_analysis_frame(
+            &mut analysis,
+            &settings.smoothing,
+            &settings.crossovers,
+            track_frame.frame.clone(),
+            dt,
+        );
+
+        frames.push(ExportedFrame {
+            time: track_frame.time,
+            volume: analysis.volume,
+            bass: analysis.bass,
+            sub_bass: analysis.sub_bass,
+            punch: analysis.punch,
+            mid: analysis.mid,
+            treble: analysis.treble,
+            flux: analysis.flux,
+            beat: track_frame.beat,
+            bins: analysis.frequency_bins.clone(),
+        });
+    }
+
+    Ok(ExportedAnalysis {
+        source: display_name(path),
+        rate_hz: ANALYSIS_RATE_HZ,
+        band_limits: analysis.band_limits,
+        frames,
+    })
+}
+
+// One row per frame; the bin columns are named after their upper frequency.
+fn write_csv(writer: &mut impl Write, exported: &ExportedAnalysis) -> std::io::Result<()> {
+    let mut line = String::from("time,volume,bass,sub_bass,punch,mid,treble,flux,beat");
+    for limit in &exported.band_limits {
+        let _ = write!(line, ",bin_{:.0}hz", limit);
+    }
+    writeln!(writer, "{}", line)?;
+
+    for frame in &exported.frames {
+        line.clear();
+        let _ = write!(
+            line,
+            "{:.4},{},{},{},{},{},{},{},{}",
+            frame.time,
+            frame.volume,
+            frame.bass,
+            frame.sub_bass,
+            frame.punch,
+            frame.mid,
+            frame.treble,
+            frame.flux,
+            u8::from(frame.beat)
+        );
+        for bin in &frame.bins {
+            let _ = write!(line, ",{}", bin);
+        }
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// src/fft.rs
+
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+// A Hann-windowed real FFT of fixed size. The plan, window and all buffers
+// are allocated once and reused on every call to `process`.
+pub struct Fft {
+    plan: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    magnitudes: Vec<f32>,
+}
+
+impl Fft {
+    pub fn new(planner: &mut RealFftPlanner<f32>, size: usize) -> Self {
+        let plan = planner.plan_fft_forward(size);
+        let window = (0..size)
+            .map(|i| {
+                0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / (size - 1) as f32).cos())
+            })
+            .collect();
+
+        Self {
+            input: plan.make_input_vec(),
+            output: plan.make_output_vec(),
+            scratch: plan.make_scratch_vec(),
+            magnitudes: vec![0.0; size / 2 + 1],
+            window,
+            plan,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.window.len()
+    }
+
+    // Windows the samples (zero-padded if fewer than `size`) and computes the
+    // magnitude of each bin, scaled by 1/sqrt(N).
+    pub fn process(&mut self, samples: impl IntoIterator<Item = f32>) -> &[f32] {
+        self.input.fill(0.0);
+        for ((input, sample), window) in self.input.iter_mut().zip(samples).zip(&self.window) {
+            *input = sample * window;
+        }
+
+        // Buffer lengths come from the plan itself, so this cannot fail.
+        self.plan
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)
+            .expect("FFT buffers match their plan");
+
+        let scale = 1.0 / (self.size() as f32).sqrt();
+        for (magnitude, bin) in self.magnitudes.iter_mut().zip(&self.output) {
+            *magnitude = bin.norm() * scale;
+        }
+        &self.magnitudes
+    }
+
+    pub fn bin_frequency(&self, bin: usize, sample_rate: u32) -> f32 {
+        bin as f32 * sample_rate as f32 / self.size() as f32
+    }
+
+    // (frequency, magnitude) of the last `process` call, within `min..=max` Hz.
+    pub fn spectrum(
+        &self,
+        sample_rate: u32,
+        min: f32,
+        max: f32,
+    ) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.magnitudes
+            .iter()
+            .enumerate()
+            .map(move |(bin, magnitude)| (self.bin_frequency(bin, sample_rate), *magnitude))
+            .filter(move |(freq, _)| *freq >= min && *freq <= max)
+    }
+}
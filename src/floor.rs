@@ -0,0 +1,216 @@
+// src/floor.rs
+
+use crate::camera::{render_target_image, MainCamera3D};
+use crate::config::{FloorConfig, VisualizerId, VisualsConfig};
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{AsBindGroup, Extent3d, ShaderRef};
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+// Only the visualizer cameras see this layer, so the reflection camera never
+// draws the floor into the texture the floor samples.
+pub const FLOOR_LAYER: u8 = 30;
+// Just below the cube grid and the orb at rest.
+const FLOOR_HEIGHT: f32 = -4.0;
+const FLOOR_SIZE: f32 = 80.0;
+// The reflection is rendered at this fraction of the window size; the
+// roughness blur hides the lower resolution.
+const REFLECTION_SCALE: f32 = 0.5;
+
+pub struct FloorPlugin;
+
+impl Plugin for FloorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<FloorMaterial>::default())
+            .add_systems(
+                Update,
+                (manage_floor, update_reflection, update_floor_material)
+                    .chain()
+                    .run_if(
+                        in_state(AppState::Visualization3D)
+                            .or_else(in_state(AppState::VisualizationOrb)),
+                    ),
+            )
+            .add_systems(OnExit(AppState::Visualization3D), despawn_floor)
+            .add_systems(OnExit(AppState::VisualizationOrb), despawn_floor);
+    }
+}
+
+// A planar reflection: the scene is rendered from the main camera mirrored
+// under the floor, and the floor shows that image at its own screen position.
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+struct FloorMaterial {
+    #[uniform(0)]
+    tint: Vec4,
+    #[uniform(0)]
+    roughness: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    reflection: Handle<Image>,
+}
+
+impl Material for FloorMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/floor_shader.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+// The floor mesh and its reflection camera.
+#[derive(Component)]
+struct FloorVisual;
+
+#[derive(Component)]
+struct ReflectionCamera;
+
+fn floor_config<'a>(
+    config: &'a VisualsConfig,
+    app_state: &State<AppState>,
+) -> Option<&'a FloorConfig> {
+    VisualizerId::from_state(app_state.get()).and_then(|id| config.settings(id).floor())
+}
+
+// Spawns or removes the floor as it is toggled in the current visualizer.
+fn manage_floor(
+    mut commands: Commands,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<FloorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    q_floor: Query<Entity, With<FloorVisual>>,
+) {
+    let Some(floor) = floor_config(&config, &app_state).filter(|floor| floor.enabled) else {
+        if !q_floor.is_empty() {
+            despawn_floor(commands, q_floor);
+        }
+        return;
+    };
+    if !q_floor.is_empty() {
+        return;
+    }
+
+    // Sized by `update_reflection` from the window.
+    let image = images.add(render_target_image(
+        "floor_reflection",
+        Extent3d {
+            width: 1,
+            height: 1,
+            ..default()
+        },
+    ));
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                // Rendered before the main camera samples it.
+                order: -1,
+                target: RenderTarget::Image(image.clone()),
+                ..default()
+            },
+            ..default()
+        },
+        ReflectionCamera,
+        FloorVisual,
+    ));
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(Plane3d::default().mesh().size(FLOOR_SIZE, FLOOR_SIZE)),
+            material: materials.add(FloorMaterial {
+                tint: Vec4::from(floor.tint.as_linear_rgba_f32()),
+                roughness: floor.roughness,
+                reflection: image,
+            }),
+            transform: Transform::from_xyz(0.0, FLOOR_HEIGHT, 0.0),
+            ..default()
+        },
+        RenderLayers::layer(FLOOR_LAYER),
+        FloorVisual,
+    ));
+}
+
+// Keeps the reflection camera mirroring the main camera, and its image sized
+// to the window.
+fn update_reflection(
+    mut images: ResMut<Assets<Image>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_main: Query<(&Transform, Ref<Projection>), (With<MainCamera3D>, Without<ReflectionCamera>)>,
+    mut q_reflection: Query<(&Camera, &mut Transform, &mut Projection), With<ReflectionCamera>>,
+) {
+    let (Ok(window), Ok((main_transform, main_projection))) =
+        (q_windows.get_single(), q_main.get_single())
+    else {
+        return;
+    };
+    let Ok((camera, mut transform, mut projection)) = q_reflection.get_single_mut() else {
+        return;
+    };
+
+    *transform = mirrored(main_transform);
+    if main_projection.is_changed() || projection.is_added() {
+        *projection = main_projection.clone();
+    }
+
+    let RenderTarget::Image(handle) = &camera.target else {
+        return;
+    };
+    let size = Extent3d {
+        width: ((window.physical_width() as f32 * REFLECTION_SCALE).round() as u32).max(1),
+        height: ((window.physical_height() as f32 * REFLECTION_SCALE).round() as u32).max(1),
+        ..default()
+    };
+    // Only touch the asset when the size changes, as that re-uploads it.
+    if images
+        .get(handle)
+        .is_some_and(|image| image.texture_descriptor.size != size)
+    {
+        if let Some(image) = images.get_mut(handle) {
+            image.resize(size);
+        }
+    }
+}
+
+// The main camera's pose mirrored through the floor plane. A mirror image
+// can't be expressed as a rotation, so this view comes out flipped left to
+// right, which the floor shader undoes. Whatever dips below the floor (tall
+// columns, a swelling orb) still shows in the reflection.
+fn mirrored(transform: &Transform) -> Transform {
+    let flip = Vec3::new(1.0, -1.0, 1.0);
+    let mut position = transform.translation * flip;
+    position.y += 2.0 * FLOOR_HEIGHT;
+    let forward = transform.rotation * Vec3::NEG_Z * flip;
+    let up = transform.rotation * Vec3::Y * flip;
+    Transform::from_translation(position).looking_to(forward, up)
+}
+
+fn update_floor_material(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut materials: ResMut<Assets<FloorMaterial>>,
+    q_floor: Query<&Handle<FloorMaterial>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    let Some(floor) = floor_config(&config, &app_state) else {
+        return;
+    };
+    for handle in &q_floor {
+        if let Some(material) = materials.get_mut(handle) {
+            material.tint = Vec4::from(floor.tint.as_linear_rgba_f32());
+            material.roughness = floor.roughness;
+        }
+    }
+}
+
+// The image and material are freed with the last handles to them.
+fn despawn_floor(mut commands: Commands, q_floor: Query<Entity, With<FloorVisual>>) {
+    for entity in &q_floor {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,116 @@
+// src/gamepad.rs
+
+use crate::audio::{AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource};
+use crate::config::{VisualizerId, VisualsConfig};
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+
+// Sensitivity change per second with a trigger fully pressed.
+const SENSITIVITY_RATE: f32 = 2.0;
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        // Camera sticks live in `camera.rs` next to the mouse controls.
+        app.add_systems(
+            Update,
+            (
+                gamepad_switch_visualizer,
+                gamepad_sensitivity,
+                gamepad_play_pause,
+            )
+                .run_if(
+                    in_state(AppState::Visualization3D)
+                        .or_else(in_state(AppState::Visualization2D))
+                        .or_else(in_state(AppState::VisualizationOrb))
+                        .or_else(in_state(AppState::VisualizationDisc))
+                        .or_else(in_state(AppState::VisualizationIco))
+                        .or_else(in_state(AppState::VisualizationShadertoy)),
+                ),
+        );
+    }
+}
+
+fn just_pressed(
+    gamepads: &Gamepads,
+    buttons: &ButtonInput<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+// D-pad left/right cycles through the shown visualizers.
+fn gamepad_switch_visualizer(
+    config: Res<VisualsConfig>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+) {
+    let step = if just_pressed(&gamepads, &buttons, GamepadButtonType::DPadRight) {
+        1
+    } else if just_pressed(&gamepads, &buttons, GamepadButtonType::DPadLeft) {
+        -1
+    } else {
+        return;
+    };
+
+    let Some(current) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let next = config
+        .general
+        .visualizers
+        .cycle(Some(current), step)
+        .state();
+
+    next_app_state.set(next.clone());
+    active_viz.0 = next;
+}
+
+// Right trigger raises the sensitivity, left trigger lowers it.
+fn gamepad_sensitivity(
+    gamepads: Res<Gamepads>,
+    triggers: Res<Axis<GamepadButton>>,
+    time: Res<Time>,
+    mut config: ResMut<VisualsConfig>,
+) {
+    let mut delta = 0.0;
+    for gamepad in gamepads.iter() {
+        let value = |button_type: GamepadButtonType| {
+            triggers
+                .get(GamepadButton::new(gamepad, button_type))
+                .unwrap_or(0.0)
+        };
+        delta += value(GamepadButtonType::RightTrigger2) - value(GamepadButtonType::LeftTrigger2);
+    }
+
+    if delta != 0.0 {
+        let general = &mut config.general;
+        general.bass_sensitivity = (general.bass_sensitivity
+            + delta * SENSITIVITY_RATE * time.delta_seconds())
+        .clamp(0.1, 10.0);
+    }
+}
+
+// Start toggles play/pause when a file is loaded.
+fn gamepad_play_pause(
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    selected_source: Res<SelectedAudioSource>,
+    mut playback_info: ResMut<PlaybackInfo>,
+) {
+    if !just_pressed(&gamepads, &buttons, GamepadButtonType::Start) {
+        return;
+    }
+    if let AudioSource::File(_) = selected_source.0 {
+        playback_info.status = match playback_info.status {
+            PlaybackStatus::Playing => PlaybackStatus::Paused,
+            PlaybackStatus::Paused => PlaybackStatus::Playing,
+        };
+    }
+}
@@ -0,0 +1,145 @@
+// src/governor.rs
+
+use crate::config::{Quality, VisualizerId, VisualsConfig};
+use crate::toast::Toasts;
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
+
+// Seconds the frame rate has to stay under the target before the quality is
+// lowered, and over it (with headroom) before it is raised back. Raising
+// waits longer, so a level that only just holds the target isn't left and
+// retaken in a loop.
+const LOWER_AFTER_SECS: f32 = 2.0;
+const RAISE_AFTER_SECS: f32 = 10.0;
+const RAISE_HEADROOM: f32 = 1.15;
+// Time constant of the averaged frame rate.
+const AVERAGE_SECS: f32 = 0.5;
+// Part of the frame rate cap or refresh rate the frame rate can be counted on
+// to reach: capped frame rates average a little under it.
+const CEILING_MARGIN: f32 = 0.95;
+
+// Watches the frame time and lowers the quality of the visuals when the app
+// can't hold `AdaptiveQualitySettings::target_fps`, up to the chosen quality.
+// Only the visuals follow it: the frame rate cap and the analysis keep the
+// chosen quality, the first since it would hold the frame rate down itself.
+pub struct GovernorPlugin;
+
+impl Plugin for GovernorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QualityGovernor>()
+            .init_resource::<ActiveQuality>()
+            .add_systems(PreUpdate, govern_quality);
+    }
+}
+
+// The quality the visuals are built at: the chosen one, or lower while the
+// governor holds it down.
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveQuality(pub Quality);
+
+impl Default for ActiveQuality {
+    fn default() -> Self {
+        Self(Quality::High)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct QualityGovernor {
+    // Averaged frame rate while measuring, 0 before.
+    pub fps: f32,
+    // How far the governor lets the quality go, none to leave the chosen one.
+    pub limit: Option<Quality>,
+    // Seconds spent under the target, and over it with headroom.
+    below: f32,
+    above: f32,
+    // Refresh rate of the monitor showing the window, read when measuring
+    // starts, since vsync holds the frame rate under it.
+    refresh_rate: Option<f32>,
+}
+
+fn govern_quality(
+    time: Res<Time<Real>>,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    q_window: Query<(Entity, &Window), With<PrimaryWindow>>,
+    winit_windows: NonSend<WinitWindows>,
+    mut governor: ResMut<QualityGovernor>,
+    mut active: ResMut<ActiveQuality>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let chosen = config.general.quality;
+    let adaptive = &config.general.adaptive_quality;
+    let dt = time.delta_seconds();
+    // The frame rate only means something while a visualizer is on screen
+    // with the focus: menus are cheap, and an unfocused window is throttled.
+    let measuring = adaptive.enabled
+        && VisualizerId::from_state(app_state.get()).is_some()
+        && q_window
+            .get_single()
+            .is_ok_and(|(_, window)| window.focused)
+        && dt > 0.0;
+
+    if !adaptive.enabled {
+        governor.limit = None;
+    }
+    if measuring {
+        let fps = 1.0 / dt;
+        if governor.fps == 0.0 {
+            governor.fps = fps;
+            governor.refresh_rate = q_window
+                .get_single()
+                .ok()
+                .and_then(|(entity, _)| winit_windows.get_window(entity))
+                .and_then(|window| window.current_monitor())
+                .and_then(|monitor| monitor.refresh_rate_millihertz())
+                .map(|millihertz| millihertz as f32 / 1000.0);
+        } else {
+            governor.fps += (fps - governor.fps) * (1.0 - (-dt / AVERAGE_SECS).exp());
+        }
+
+        // A target over the frame rate cap or the refresh rate can never be
+        // held, and would take the quality down to Low for nothing.
+        let ceiling = chosen
+            .max_fps()
+            .unwrap_or(f32::INFINITY)
+            .min(governor.refresh_rate.unwrap_or(f32::INFINITY))
+            * CEILING_MARGIN;
+        let target = adaptive.target_fps.min(ceiling);
+        let current = governor.limit.map_or(chosen, |limit| limit.min(chosen));
+        if governor.fps < target {
+            governor.below += dt;
+            governor.above = 0.0;
+            if governor.below >= LOWER_AFTER_SECS && current > Quality::Low {
+                governor.limit = Some(current.lower());
+                governor.below = 0.0;
+                toasts.info(format!(
+                    "Quality lowered to {:?} to hold {:.0} FPS",
+                    current.lower(),
+                    target
+                ));
+            }
+        } else if governor.fps > (target * RAISE_HEADROOM).min(ceiling) {
+            governor.above += dt;
+            governor.below = 0.0;
+            if governor.above >= RAISE_AFTER_SECS && current < chosen {
+                let raised = current.higher();
+                governor.limit = (raised < chosen).then_some(raised);
+                governor.above = 0.0;
+            }
+        } else {
+            governor.below = 0.0;
+            governor.above = 0.0;
+        }
+    } else {
+        governor.fps = 0.0;
+        governor.below = 0.0;
+        governor.above = 0.0;
+    }
+
+    let quality = governor.limit.map_or(chosen, |limit| limit.min(chosen));
+    if active.0 != quality {
+        active.0 = quality;
+    }
+}
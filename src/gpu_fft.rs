@@ -0,0 +1,221 @@
+// src/gpu_fft.rs
+
+use crate::audio::{apply_analysis_frames, AudioAnalysis};
+use crate::audio_texture::AUDIO_TEXTURE_WIDTH;
+use crate::config::{ShadertoySettings, VisualsConfig};
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_graph::{self, RenderGraph, RenderLabel};
+use bevy::render::render_resource::binding_types::{
+    storage_buffer_read_only, texture_storage_2d, uniform_buffer,
+};
+use bevy::render::render_resource::{
+    BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedComputePipelineId,
+    ComputePassDescriptor, ComputePipelineDescriptor, Extent3d, PipelineCache, ShaderStages,
+    ShaderType, StorageBuffer, StorageTextureAccess, TextureDimension, TextureFormat,
+    TextureUsages, UniformBuffer,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice, RenderQueue};
+use bevy::render::{Render, RenderApp, RenderSet};
+
+const SHADER_PATH: &str = "shaders/gpu_fft.wgsl";
+
+// Runs the FFT of the Shadertoy audio texture on the GPU, in a compute pass
+// writing straight into a storage texture, instead of building the texture
+// from the CPU bands. Only the raw samples of the latest analysis window are
+// uploaded.
+pub struct GpuFftPlugin;
+
+impl Plugin for GpuFftPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractResourcePlugin::<GpuFftInput>::default(),
+            ExtractResourcePlugin::<GpuAudioTexture>::default(),
+        ))
+        .init_resource::<GpuFftInput>()
+        .add_systems(Startup, setup_gpu_audio_texture)
+        .add_systems(Update, update_gpu_fft_input.after(apply_analysis_frames));
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_systems(Render, prepare_gpu_fft.in_set(RenderSet::PrepareBindGroups));
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(GpuFftLabel, GpuFftNode);
+        render_graph.add_node_edge(GpuFftLabel, bevy::render::graph::CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        app.sub_app_mut(RenderApp).init_resource::<GpuFftPipeline>();
+    }
+}
+
+// Laid out like `AudioTexture`, spectrum in row 0 and waveform in row 1, but
+// as floats the compute pass can write. The spectrum follows Shadertoy's: the
+// linear bins of a 1024-point FFT, -100 to -30 dB mapped to 0..1.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct GpuAudioTexture(pub Handle<Image>);
+
+// What the compute pass reads, extracted every frame.
+#[derive(Resource, Clone, Default, ExtractResource)]
+struct GpuFftInput {
+    enabled: bool,
+    samples: Vec<f32>,
+    gain: f32,
+}
+
+#[derive(Clone, Copy, Default, ShaderType)]
+struct GpuFftParams {
+    gain: f32,
+}
+
+fn setup_gpu_audio_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: AUDIO_TEXTURE_WIDTH,
+            height: 2,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0; 8],
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
+    commands.insert_resource(GpuAudioTexture(images.add(image)));
+}
+
+// The pass only runs while the Shadertoy visualizer shows and asks for it.
+fn update_gpu_fft_input(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    audio_analysis: Res<AudioAnalysis>,
+    mut input: ResMut<GpuFftInput>,
+) {
+    let enabled = *app_state.get() == AppState::VisualizationShadertoy
+        && config.viz::<ShadertoySettings>().gpu_fft;
+    if input.enabled != enabled {
+        input.enabled = enabled;
+    }
+    if enabled && audio_analysis.is_changed() {
+        input.samples.clone_from(&audio_analysis.waveform);
+        input.gain = config.general.bass_sensitivity;
+    }
+}
+
+#[derive(Resource)]
+struct GpuFftPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for GpuFftPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "gpu_fft_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    storage_buffer_read_only::<Vec<f32>>(false),
+                    uniform_buffer::<GpuFftParams>(false),
+                    texture_storage_2d(TextureFormat::Rgba16Float, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+        let shader = world.resource::<AssetServer>().load(SHADER_PATH);
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("gpu_fft_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: Vec::new(),
+                    shader,
+                    shader_defs: Vec::new(),
+                    entry_point: "fft".into(),
+                });
+        Self { layout, pipeline }
+    }
+}
+
+// Present only on frames the pass should run.
+#[derive(Resource)]
+struct GpuFftBindGroup(BindGroup);
+
+#[allow(clippy::too_many_arguments)]
+fn prepare_gpu_fft(
+    mut commands: Commands,
+    pipeline: Res<GpuFftPipeline>,
+    input: Option<Res<GpuFftInput>>,
+    texture: Option<Res<GpuAudioTexture>>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut samples: Local<StorageBuffer<Vec<f32>>>,
+    mut params: Local<UniformBuffer<GpuFftParams>>,
+) {
+    commands.remove_resource::<GpuFftBindGroup>();
+    let (Some(input), Some(texture)) = (input, texture) else {
+        return;
+    };
+    if !input.enabled || input.samples.is_empty() {
+        return;
+    }
+    let Some(image) = images.get(&texture.0) else {
+        return;
+    };
+
+    samples.set(input.samples.clone());
+    samples.write_buffer(&render_device, &render_queue);
+    params.set(GpuFftParams { gain: input.gain });
+    params.write_buffer(&render_device, &render_queue);
+    let (Some(samples), Some(params)) = (samples.binding(), params.binding()) else {
+        return;
+    };
+    let bind_group = render_device.create_bind_group(
+        "gpu_fft_bind_group",
+        &pipeline.layout,
+        &BindGroupEntries::sequential((samples, params, &image.texture_view)),
+    );
+    commands.insert_resource(GpuFftBindGroup(bind_group));
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuFftLabel;
+
+struct GpuFftNode;
+
+impl render_graph::Node for GpuFftNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(bind_group) = world.get_resource::<GpuFftBindGroup>() else {
+            return Ok(());
+        };
+        let pipeline = world.resource::<GpuFftPipeline>();
+        // Still compiling.
+        let Some(compute_pipeline) = world
+            .resource::<PipelineCache>()
+            .get_compute_pipeline(pipeline.pipeline)
+        else {
+            return Ok(());
+        };
+
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("gpu_fft_pass"),
+                    ..default()
+                });
+        pass.set_bind_group(0, &bind_group.0, &[]);
+        pass.set_pipeline(compute_pipeline);
+        // The whole transform fits one workgroup.
+        pass.dispatch_workgroups(1, 1, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+// src/grading.rs
+
+use crate::camera::{MainCamera2D, MainCamera3D};
+use crate::config::{ColorGradingSettings, VisualsConfig};
+use bevy::core_pipeline::core_2d::graph::{Core2d, Node2d};
+use bevy::core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_buffer};
+use bevy::render::render_resource::{
+    BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId,
+    ColorTargetState, ColorWrites, FragmentState, MultisampleState, Operations, PipelineCache,
+    PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureFormat,
+    TextureSampleType,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::texture::BevyDefault;
+use bevy::render::view::ViewTarget;
+use bevy::render::RenderApp;
+
+pub struct GradingPlugin;
+
+impl Plugin for GradingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<ColorGrade>::default(),
+            UniformComponentPlugin::<ColorGrade>::default(),
+        ))
+        .add_systems(Update, update_color_grade);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        // Right after tonemapping, so the grade works on display colors.
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<GradingNode>>(Core3d, GradingLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    GradingLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<GradingNode>>(Core2d, GradingLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    GradingLabel,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<GradingPipeline>();
+    }
+}
+
+// The grade of a camera, as the uniform of `color_grading.wgsl`. Cameras
+// without one skip the pass.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct ColorGrade {
+    // In radians.
+    hue_shift: f32,
+    saturation: f32,
+    contrast: f32,
+    gamma: f32,
+}
+
+// Grades the visualizer cameras with the global settings, turning the slow
+// hue rotation on top of the fixed shift. A neutral grade removes the pass.
+fn update_color_grade(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    mut rotation: Local<f32>,
+    mut q_cameras: Query<
+        (Entity, Option<&mut ColorGrade>),
+        Or<(With<MainCamera2D>, With<MainCamera3D>)>,
+    >,
+) {
+    let settings = &config.general.grading;
+    if settings.auto_hue {
+        *rotation = (*rotation + settings.auto_hue_speed * time.delta_seconds()) % 360.0;
+    }
+    let grade = (!is_neutral(settings) || settings.auto_hue).then(|| ColorGrade {
+        hue_shift: (settings.hue_shift + *rotation).to_radians(),
+        saturation: settings.saturation,
+        contrast: settings.contrast,
+        gamma: settings.gamma.max(0.01),
+    });
+
+    for (entity, current) in &mut q_cameras {
+        match (grade, current) {
+            (Some(grade), Some(mut current)) => *current = grade,
+            (Some(grade), None) => {
+                commands.entity(entity).insert(grade);
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<ColorGrade>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn is_neutral(settings: &ColorGradingSettings) -> bool {
+    settings.hue_shift % 360.0 == 0.0
+        && settings.saturation == 1.0
+        && settings.contrast == 1.0
+        && settings.gamma == 1.0
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GradingLabel;
+
+#[derive(Default)]
+struct GradingNode;
+
+impl ViewNode for GradingNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<ColorGrade>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, grade_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let grading_pipeline = world.resource::<GradingPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline_id = if view_target.is_hdr() {
+            grading_pipeline.hdr_pipeline
+        } else {
+            grading_pipeline.sdr_pipeline
+        };
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+            return Ok(());
+        };
+        let grades = world.resource::<ComponentUniforms<ColorGrade>>();
+        let Some(grade_binding) = grades.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "color_grading_bind_group",
+            &grading_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &grading_pipeline.sampler,
+                grade_binding.clone(),
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("color_grading_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[grade_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+// One pipeline per main texture format: the 3D cameras render in HDR.
+#[derive(Resource)]
+struct GradingPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    sdr_pipeline: CachedRenderPipelineId,
+    hdr_pipeline: CachedRenderPipelineId,
+}
+
+impl FromWorld for GradingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(
+            "color_grading_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<ColorGrade>(true),
+                ),
+            ),
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/color_grading.wgsl");
+
+        let mut queue = |format: TextureFormat| {
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("color_grading_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs: vec![],
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+        };
+        let sdr_pipeline = queue(TextureFormat::bevy_default());
+        let hdr_pipeline = queue(ViewTarget::TEXTURE_FORMAT_HDR);
+
+        Self {
+            layout,
+            sampler,
+            sdr_pipeline,
+            hdr_pipeline,
+        }
+    }
+}
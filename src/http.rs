@@ -0,0 +1,341 @@
+// src/http.rs
+
+use crate::audio::{display_name, AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource};
+use crate::config::{load_preset, VisualizerId, VisualsConfig};
+use crate::macros::set_macro;
+use crate::recording::source_for;
+use crate::toast::Toasts;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tiny_http::{Header, Method, Response, Server};
+
+// How long a request waits for the app to answer it.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct HttpPlugin;
+
+impl Plugin for HttpPlugin {
+    fn build(&self, app: &mut App) {
+        let (request_tx, request_rx) = std::sync::mpsc::channel::<HttpRequest>();
+
+        app.insert_resource(HttpServer {
+            requests: request_tx,
+            running: None,
+            requested: None,
+        })
+        .insert_non_send_resource(HttpRequestReceiver(request_rx))
+        .add_systems(
+            Update,
+            (
+                manage_http_server,
+                handle_http_requests.after(manage_http_server),
+            ),
+        );
+    }
+}
+
+// The endpoints, parsed on the server thread.
+enum HttpCommand {
+    Status,
+    Play,
+    Pause,
+    Load(PathBuf),
+    Preset(String),
+    Macro { index: usize, value: f32 },
+}
+
+// Status code and JSON body.
+type HttpReply = (u16, Value);
+
+struct HttpRequest {
+    command: HttpCommand,
+    reply: Sender<HttpReply>,
+}
+
+struct HttpRequestReceiver(Receiver<HttpRequest>);
+
+// Stops the server when dropped.
+struct ServerHandle(Arc<Server>);
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.0.unblock();
+    }
+}
+
+#[derive(Resource)]
+struct HttpServer {
+    requests: Sender<HttpRequest>,
+    running: Option<ServerHandle>,
+    // The address last asked for, so a failed bind isn't retried every frame.
+    requested: Option<(&'static str, u16)>,
+}
+
+// Starts, stops or moves the server to follow the remote settings.
+fn manage_http_server(
+    config: Res<VisualsConfig>,
+    mut server: ResMut<HttpServer>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let remote = &config.general.remote;
+    // Only this computer can reach the server unless the user opts in.
+    let host = if remote.http_allow_network {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let wanted = remote.http_enabled.then_some((host, remote.http_port));
+    if server.requested == wanted {
+        return;
+    }
+    server.requested = wanted;
+    server.running = None;
+
+    let Some((host, port)) = wanted else {
+        info!("HTTP server stopped");
+        return;
+    };
+    match Server::http((host, port)) {
+        Ok(http) => {
+            let http = Arc::new(http);
+            let requests = server.requests.clone();
+            let thread_http = http.clone();
+            let spawned = std::thread::Builder::new()
+                .name("http-control".into())
+                .spawn(move || run_server(&thread_http, &requests));
+            if let Err(e) = spawned {
+                error!("❌ Failed to spawn the HTTP server thread: {}", e);
+                return;
+            }
+            info!("HTTP server listening on {}:{}", host, port);
+            toasts.info(format!("HTTP control on port {}", port));
+            server.running = Some(ServerHandle(http));
+        }
+        Err(e) => {
+            error!("❌ Failed to start the HTTP server: {}", e);
+            toasts.error(format!("HTTP control unavailable on port {}: {}", port, e));
+        }
+    }
+}
+
+fn handle_http_requests(
+    receiver: NonSend<HttpRequestReceiver>,
+    mut config: ResMut<VisualsConfig>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+    mut playback_info: ResMut<PlaybackInfo>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+) {
+    for request in receiver.0.try_iter() {
+        let reply = match request.command {
+            HttpCommand::Status => (
+                200,
+                status(app_state.get(), &selected_source.0, &playback_info),
+            ),
+            HttpCommand::Play => set_playback(
+                &selected_source.0,
+                &mut playback_info,
+                PlaybackStatus::Playing,
+            ),
+            HttpCommand::Pause => set_playback(
+                &selected_source.0,
+                &mut playback_info,
+                PlaybackStatus::Paused,
+            ),
+            HttpCommand::Load(path) => {
+                if path.is_file() {
+                    selected_source.0 = source_for(path);
+                    // Audio only plays while a visualizer is on screen.
+                    if VisualizerId::from_state(app_state.get()).is_none() {
+                        next_app_state.set(active_viz.0.clone());
+                    }
+                    (200, json!({ "ok": true }))
+                } else {
+                    (
+                        404,
+                        json!({ "error": format!("no file at {}", path.display()) }),
+                    )
+                }
+            }
+            HttpCommand::Preset(name) => match load_preset(&name) {
+                Ok(settings) => {
+                    let id = settings.id();
+                    config.set_settings(id, settings);
+                    next_app_state.set(id.state());
+                    active_viz.0 = id.state();
+                    (200, json!({ "ok": true, "visualizer": id.key() }))
+                }
+                Err(e) => (404, json!({ "error": e })),
+            },
+            HttpCommand::Macro { index, value } => match set_macro(&mut config, index, value) {
+                Ok(()) => (200, json!({ "ok": true })),
+                Err(e) => (404, json!({ "error": e })),
+            },
+        };
+        request.reply.send(reply).ok();
+    }
+}
+
+fn set_playback(
+    source: &AudioSource,
+    playback_info: &mut PlaybackInfo,
+    status: PlaybackStatus,
+) -> HttpReply {
+    if let AudioSource::File(_) = source {
+        playback_info.status = status;
+        (200, json!({ "ok": true }))
+    } else {
+        (409, json!({ "error": "no file is loaded" }))
+    }
+}
+
+fn status(state: &AppState, source: &AudioSource, playback_info: &PlaybackInfo) -> Value {
+    let source = match source {
+        // The file name only, so the folders of the computer stay private.
+        AudioSource::File(path) => json!({ "kind": "file", "name": display_name(path) }),
+        AudioSource::Replay(path) => json!({ "kind": "replay", "name": display_name(path) }),
+        AudioSource::Microphone => json!({ "kind": "microphone" }),
+        AudioSource::None => Value::Null,
+    };
+    json!({
+        "visualizer": VisualizerId::from_state(state).map(VisualizerId::key),
+        "source": source,
+        "playing": playback_info.status == PlaybackStatus::Playing,
+        "position": playback_info.position.as_secs_f32(),
+        "duration": playback_info.duration.as_secs_f32(),
+        "speed": playback_info.speed,
+    })
+}
+
+// --- Server Thread ---
+
+// Returns once the server is unblocked.
+fn run_server(server: &Server, requests: &Sender<HttpRequest>) {
+    for request in server.incoming_requests() {
+        // Browsers send an Origin with the requests of web pages, which must
+        // not drive the app; automation tools don't.
+        let from_web_page = request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Origin"));
+        let parsed = if from_web_page {
+            Err((
+                403,
+                json!({ "error": "requests from web pages are refused" }),
+            ))
+        } else {
+            parse_command(request.method(), request.url())
+        };
+        let (status, body) = match parsed {
+            Ok(command) => {
+                let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+                requests
+                    .send(HttpRequest {
+                        command,
+                        reply: reply_tx,
+                    })
+                    .ok();
+                reply_rx
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| (503, json!({ "error": "the app did not answer" })))
+            }
+            Err(reply) => reply,
+        };
+
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("Static header is valid"),
+            );
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to answer an HTTP request: {}", e);
+        }
+    }
+}
+
+fn parse_command(method: &Method, url: &str) -> Result<HttpCommand, HttpReply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = path.trim_end_matches('/');
+    // Only reading the status is allowed with GET, which a link or an image
+    // on a web page can send.
+    let allowed = match path {
+        "/status" => matches!(method, Method::Get | Method::Post),
+        _ => matches!(method, Method::Post),
+    };
+    if !allowed {
+        return Err((405, json!({ "error": "method not allowed" })));
+    }
+    match path {
+        "/status" => Ok(HttpCommand::Status),
+        "/play" => Ok(HttpCommand::Play),
+        "/pause" => Ok(HttpCommand::Pause),
+        "/load" => Ok(HttpCommand::Load(PathBuf::from(required_param(
+            query, "path",
+        )?))),
+        "/preset" => Ok(HttpCommand::Preset(required_param(query, "name")?)),
+        "/macro" => Ok(HttpCommand::Macro {
+            index: parsed_param(query, "index")?,
+            value: parsed_param(query, "value")?,
+        }),
+        _ => Err((
+            404,
+            json!({ "error": format!("unknown endpoint {}", path) }),
+        )),
+    }
+}
+
+fn required_param(query: &str, name: &str) -> Result<String, HttpReply> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+        .ok_or_else(|| {
+            (
+                400,
+                json!({ "error": format!("missing '{}' parameter", name) }),
+            )
+        })
+}
+
+fn parsed_param<T: std::str::FromStr>(query: &str, name: &str) -> Result<T, HttpReply> {
+    required_param(query, name)?.parse().map_err(|_| {
+        (
+            400,
+            json!({ "error": format!("invalid '{}' parameter", name) }),
+        )
+    })
+}
+
+// Decodes `%XX` escapes and `+` as space.
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = input.by_ref().take(2).collect();
+                let decoded = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match decoded {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
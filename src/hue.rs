@@ -0,0 +1,632 @@
+// src/hue.rs
+
+use crate::analysis::OnsetDetector;
+use crate::audio::{AudioAnalysis, TickWatcher};
+use crate::config::{HueSettings, VisualizerId, VisualsConfig};
+use crate::i18n::Language;
+use crate::toast::Toasts;
+use crate::AppState;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use openssl::error::ErrorStack;
+use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVersion};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::UdpSocket;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+// The bridge takes Entertainment API messages at up to about 50 per second
+// and passes them on to the bulbs at 25, whatever their number.
+const STREAM_RATE_HZ: f32 = 50.0;
+// Seconds a beat flash takes to fade back to the resting brightness.
+const FLASH_DECAY: f32 = 0.3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const DISCOVERY_URL: &str = "https://discovery.meethue.com/";
+// The UDP port the bridge takes the DTLS stream on.
+const ENTERTAINMENT_PORT: u16 = 2100;
+// The only cipher suite the bridge accepts, keyed with the client key.
+const ENTERTAINMENT_CIPHER: &str = "PSK-AES128-GCM-SHA256";
+
+pub struct HuePlugin;
+
+impl Plugin for HuePlugin {
+    fn build(&self, app: &mut App) {
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<HueJob>();
+        let (event_tx, event_rx) = std::sync::mpsc::channel::<HueEvent>();
+        spawn_hue_thread(job_rx, event_tx);
+
+        app.insert_resource(HueBridge {
+            jobs: job_tx,
+            areas: Vec::new(),
+            status: None,
+            streaming: false,
+            onsets: OnsetDetector::default(),
+            ticks: TickWatcher::default(),
+            flash: 0.0,
+            since_push: 0.0,
+        })
+        .insert_non_send_resource(HueEventReceiver(event_rx))
+        .add_systems(
+            Update,
+            (
+                receive_hue_events,
+                // Also outside the visualizers, to end the stream there.
+                sync_hue_lights,
+            ),
+        );
+    }
+}
+
+// Work for the Hue thread. Network calls never block the app.
+enum HueJob {
+    Discover,
+    Pair { ip: String },
+    ListAreas { ip: String, username: String },
+    Stream(LightState),
+    StopStream,
+}
+
+// Where the light state is streamed to. A change of any of these opens a
+// new stream.
+#[derive(Clone, PartialEq)]
+struct StreamTarget {
+    ip: String,
+    username: String,
+    client_key: String,
+    area: String,
+}
+
+struct LightState {
+    target: StreamTarget,
+    xy: [f32; 2],
+    // In 0..1.
+    brightness: f32,
+}
+
+enum HueEvent {
+    Discovered(String),
+    Paired {
+        username: String,
+        client_key: String,
+    },
+    Areas(Vec<EntertainmentArea>),
+    StreamFailed(String),
+    Failed(String),
+}
+
+// A group of lights set up for streaming in the Hue app.
+struct EntertainmentArea {
+    id: String,
+    name: String,
+}
+
+struct HueEventReceiver(Receiver<HueEvent>);
+
+#[derive(Resource)]
+pub struct HueBridge {
+    jobs: Sender<HueJob>,
+    // Entertainment areas reported by the bridge, once listed.
+    areas: Vec<EntertainmentArea>,
+    // Progress of the pairing flow, shown in the settings panel.
+    status: Option<&'static str>,
+    // Whether light states are being sent, so the stream is ended once.
+    streaming: bool,
+    onsets: OnsetDetector,
+    ticks: TickWatcher,
+    flash: f32,
+    since_push: f32,
+}
+
+impl HueBridge {
+    fn request(&mut self, job: HueJob, status: &'static str) {
+        self.status = Some(status);
+        self.jobs.send(job).ok();
+    }
+}
+
+fn receive_hue_events(
+    receiver: NonSend<HueEventReceiver>,
+    mut bridge: ResMut<HueBridge>,
+    mut config: ResMut<VisualsConfig>,
+    mut toasts: ResMut<Toasts>,
+) {
+    for event in receiver.0.try_iter() {
+        match event {
+            HueEvent::Discovered(ip) => {
+                info!("Found a Hue bridge at {}", ip);
+                config.general.hue.bridge_ip = ip;
+                bridge.status = None;
+            }
+            HueEvent::Paired {
+                username,
+                client_key,
+            } => {
+                info!("Paired with the Hue bridge");
+                toasts.success("Paired with the Hue bridge");
+                let hue = &mut config.general.hue;
+                hue.username = username;
+                hue.client_key = client_key;
+                bridge.request(
+                    HueJob::ListAreas {
+                        ip: hue.bridge_ip.clone(),
+                        username: hue.username.clone(),
+                    },
+                    "Listing entertainment areas...",
+                );
+            }
+            HueEvent::Areas(areas) => {
+                bridge.areas = areas;
+                bridge.status = None;
+            }
+            // Turned off rather than retried every frame; ticking "Sync
+            // Lights" again retries. `sync_hue_lights` then ends the stream,
+            // which the thread waits for.
+            HueEvent::StreamFailed(message) => {
+                warn!("Hue streaming stopped: {}", message);
+                toasts.error(format!("Hue: {}", message));
+                config.general.hue.enabled = false;
+            }
+            HueEvent::Failed(message) => {
+                warn!("Hue: {}", message);
+                toasts.error(format!("Hue: {}", message));
+                bridge.status = None;
+            }
+        }
+    }
+}
+
+// Streams the visualizer's color to the lights of the entertainment area,
+// flashing them on beats, and ends the stream outside the visualizers.
+fn sync_hue_lights(
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    audio_analysis: Res<AudioAnalysis>,
+    mut bridge: ResMut<HueBridge>,
+) {
+    let hue = &config.general.hue;
+    let viz_id = VisualizerId::from_state(app_state.get())
+        .filter(|_| hue.enabled && !hue.client_key.is_empty() && !hue.area.is_empty());
+    let Some(viz_id) = viz_id else {
+        if bridge.streaming {
+            bridge.streaming = false;
+            bridge.jobs.send(HueJob::StopStream).ok();
+        }
+        return;
+    };
+
+    let dt = time.delta_seconds();
+    // Only new analysis frames can hold a beat.
+    let bridge = &mut *bridge;
+    let onset = bridge.ticks.ticked(&audio_analysis) && bridge.onsets.detect(audio_analysis.flux);
+    bridge.flash = if onset {
+        1.0
+    } else {
+        (bridge.flash - dt / FLASH_DECAY).max(0.0)
+    };
+
+    bridge.since_push += dt;
+    if bridge.since_push < 1.0 / STREAM_RATE_HZ {
+        return;
+    }
+    bridge.since_push = 0.0;
+
+    let level = hue.min_brightness + (hue.max_brightness - hue.min_brightness) * bridge.flash;
+    bridge.streaming = true;
+    bridge
+        .jobs
+        .send(HueJob::Stream(LightState {
+            target: StreamTarget {
+                ip: hue.bridge_ip.clone(),
+                username: hue.username.clone(),
+                client_key: hue.client_key.clone(),
+                area: hue.area.clone(),
+            },
+            xy: color_to_xy(config.settings(viz_id).dominant_color()),
+            brightness: level.clamp(0.0, 1.0),
+        }))
+        .ok();
+}
+
+// CIE xy chromaticity of a color, the way the bulbs take it.
+fn color_to_xy(color: Color) -> [f32; 2] {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+    // Wide gamut RGB to XYZ (D65).
+    let x = r * 0.664_511 + g * 0.154_324 + b * 0.162_028;
+    let y = r * 0.283_881 + g * 0.668_433 + b * 0.047_685;
+    let z = r * 0.000_088 + g * 0.072_310 + b * 0.986_039;
+    let sum = x + y + z;
+    if sum <= 0.0 {
+        // Black has no chromaticity; fall back to the white point.
+        return [0.3227, 0.329];
+    }
+    [x / sum, y / sum]
+}
+
+// The "Philips Hue" settings section, including the pairing flow.
+pub fn hue_section(
+    ui: &mut egui::Ui,
+    lang: Language,
+    hue: &mut HueSettings,
+    bridge: &mut HueBridge,
+) {
+    let t = |text: &'static str| lang.tr(text);
+
+    ui.horizontal(|ui| {
+        ui.label(t("Bridge IP"));
+        ui.text_edit_singleline(&mut hue.bridge_ip);
+        if ui.button(t("🔍 Find")).clicked() {
+            bridge.request(HueJob::Discover, "Searching for a bridge...");
+        }
+    });
+
+    // Pairings made before streaming have no client key and need redoing.
+    if hue.client_key.is_empty() {
+        ui.label(t(
+            "Press the button on the bridge, then pair within 30 seconds.",
+        ));
+        let can_pair = !hue.bridge_ip.is_empty() && bridge.status.is_none();
+        if ui
+            .add_enabled(can_pair, egui::Button::new(t("🔗 Pair")))
+            .clicked()
+        {
+            bridge.request(
+                HueJob::Pair {
+                    ip: hue.bridge_ip.clone(),
+                },
+                "Pairing...",
+            );
+        }
+    } else {
+        ui.horizontal(|ui| {
+            ui.label(t("✔ Paired"));
+            if ui.button(t("Forget")).clicked() {
+                hue.username.clear();
+                hue.client_key.clear();
+                hue.area.clear();
+                hue.enabled = false;
+                bridge.areas.clear();
+            }
+        });
+        ui.add_enabled(
+            !hue.area.is_empty(),
+            egui::Checkbox::new(&mut hue.enabled, t("Sync Lights")),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label(t("Entertainment Area"));
+            if ui.button(t("⟳ Refresh Areas")).clicked() {
+                bridge.request(
+                    HueJob::ListAreas {
+                        ip: hue.bridge_ip.clone(),
+                        username: hue.username.clone(),
+                    },
+                    "Listing entertainment areas...",
+                );
+            }
+        });
+        if bridge.areas.is_empty() && bridge.status.is_none() {
+            ui.label(t(
+                "Create an entertainment area in the Hue app, then refresh.",
+            ));
+        }
+        for area in &bridge.areas {
+            if ui.radio(hue.area == area.id, &area.name).clicked() {
+                hue.area = area.id.clone();
+            }
+        }
+
+        ui.add(egui::Slider::new(&mut hue.min_brightness, 0.0..=1.0).text(t("Resting Brightness")));
+        ui.add(egui::Slider::new(&mut hue.max_brightness, 0.0..=1.0).text(t("Beat Brightness")));
+    }
+
+    if let Some(status) = bridge.status {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(t(status));
+        });
+    }
+}
+
+// --- Hue Thread ---
+
+fn spawn_hue_thread(jobs: Receiver<HueJob>, events: Sender<HueEvent>) {
+    std::thread::Builder::new()
+        .name("hue".into())
+        .spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+            let mut stream = StreamState::Idle;
+            while let Ok(mut job) = jobs.recv() {
+                // Only the newest light state matters if they pile up.
+                if matches!(job, HueJob::Stream(_)) {
+                    while let Ok(newer) = jobs.try_recv() {
+                        let stale = std::mem::replace(&mut job, newer);
+                        if !matches!(stale, HueJob::Stream(_)) {
+                            run_job(&agent, stale, &events, &mut stream);
+                        }
+                    }
+                }
+                run_job(&agent, job, &events, &mut stream);
+            }
+            // The app is quitting: hand the lights back to the bridge.
+            if let StreamState::Open(stream) = stream {
+                stream.stop(&agent);
+            }
+        })
+        .expect("Failed to spawn the Hue thread");
+}
+
+fn run_job(agent: &ureq::Agent, job: HueJob, events: &Sender<HueEvent>, stream: &mut StreamState) {
+    let result = match job {
+        HueJob::Discover => discover(agent).map(HueEvent::Discovered),
+        HueJob::Pair { ip } => pair(agent, &ip).map(|(username, client_key)| HueEvent::Paired {
+            username,
+            client_key,
+        }),
+        HueJob::ListAreas { ip, username } => {
+            list_areas(agent, &ip, &username).map(HueEvent::Areas)
+        }
+        HueJob::Stream(state) => {
+            if let Err(e) = send_light_state(agent, stream, &state) {
+                if let StreamState::Open(open) = std::mem::replace(stream, StreamState::Failed) {
+                    open.stop(agent);
+                }
+                events.send(HueEvent::StreamFailed(e.to_string())).ok();
+            }
+            return;
+        }
+        HueJob::StopStream => {
+            if let StreamState::Open(open) = std::mem::replace(stream, StreamState::Idle) {
+                open.stop(agent);
+            }
+            return;
+        }
+    };
+    let event = result.unwrap_or_else(|e| HueEvent::Failed(e.to_string()));
+    events.send(event).ok();
+}
+
+fn discover(agent: &ureq::Agent) -> Result<String, Box<dyn std::error::Error>> {
+    let bridges: Value = agent.get(DISCOVERY_URL).call()?.into_json()?;
+    bridges
+        .get(0)
+        .and_then(|bridge| bridge["internalipaddress"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "no bridge found on this network".into())
+}
+
+// The username, and the client key the stream is encrypted with.
+fn pair(agent: &ureq::Agent, ip: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let reply: Value = agent
+        .post(&format!("http://{}/api", ip))
+        .send_json(json!({ "devicetype": "rust_visualizer#app", "generateclientkey": true }))?
+        .into_json()?;
+    let success = &reply[0]["success"];
+    match (success["username"].as_str(), success["clientkey"].as_str()) {
+        (Some(username), Some(client_key)) => Ok((username.to_string(), client_key.to_string())),
+        _ => Err(bridge_error(&reply)),
+    }
+}
+
+fn list_areas(
+    agent: &ureq::Agent,
+    ip: &str,
+    username: &str,
+) -> Result<Vec<EntertainmentArea>, Box<dyn std::error::Error>> {
+    let reply: Value = agent
+        .get(&format!("http://{}/api/{}/groups", ip, username))
+        .call()?
+        .into_json()?;
+    let Some(groups) = reply.as_object() else {
+        return Err(bridge_error(&reply));
+    };
+
+    let mut areas: Vec<EntertainmentArea> = groups
+        .iter()
+        .filter(|(_, group)| group["type"] == "Entertainment")
+        .map(|(id, group)| EntertainmentArea {
+            id: id.clone(),
+            name: group["name"].as_str().unwrap_or(id).to_string(),
+        })
+        .collect();
+    areas.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(areas)
+}
+
+// The description of the error the bridge replied with.
+fn bridge_error(reply: &Value) -> Box<dyn std::error::Error> {
+    reply[0]["error"]["description"]
+        .as_str()
+        .unwrap_or("unexpected reply from the bridge")
+        .into()
+}
+
+// Opens the stream on the first light state, or when its target changed.
+fn send_light_state(
+    agent: &ureq::Agent,
+    stream: &mut StreamState,
+    state: &LightState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut open = match std::mem::replace(stream, StreamState::Idle) {
+        StreamState::Open(open) if open.target == state.target => open,
+        // Light states sent before the app saw the failure.
+        StreamState::Failed => {
+            *stream = StreamState::Failed;
+            return Ok(());
+        }
+        stale => {
+            if let StreamState::Open(stale) = stale {
+                stale.stop(agent);
+            }
+            EntertainmentStream::start(agent, state.target.clone())?
+        }
+    };
+    let sent = open.send(state);
+    *stream = StreamState::Open(open);
+    sent
+}
+
+// --- Entertainment Stream ---
+
+enum StreamState {
+    Idle,
+    Open(EntertainmentStream),
+    // Until the app ends the stream, having been told it failed.
+    Failed,
+}
+
+// A DTLS connection to the bridge streaming the lights of an entertainment
+// area, which ignore other commands while it is active.
+struct EntertainmentStream {
+    target: StreamTarget,
+    lights: Vec<u16>,
+    connection: SslStream<UdpChannel>,
+}
+
+impl EntertainmentStream {
+    fn start(
+        agent: &ureq::Agent,
+        target: StreamTarget,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let group_url = format!(
+            "http://{}/api/{}/groups/{}",
+            target.ip, target.username, target.area
+        );
+        let group: Value = agent.get(&group_url).call()?.into_json()?;
+        let lights: Vec<u16> = group["lights"]
+            .as_array()
+            .ok_or_else(|| bridge_error(&group))?
+            .iter()
+            .filter_map(|id| id.as_str()?.parse().ok())
+            .collect();
+        if lights.is_empty() {
+            return Err("the entertainment area has no lights".into());
+        }
+
+        let reply: Value = agent
+            .put(&group_url)
+            .send_json(json!({ "stream": { "active": true } }))?
+            .into_json()?;
+        if reply[0]["success"].is_null() {
+            return Err(bridge_error(&reply));
+        }
+
+        match connect(&target) {
+            Ok(connection) => {
+                info!("Streaming to the Hue entertainment area {}", target.area);
+                Ok(Self {
+                    target,
+                    lights,
+                    connection,
+                })
+            }
+            Err(e) => {
+                agent
+                    .put(&group_url)
+                    .send_json(json!({ "stream": { "active": false } }))
+                    .ok();
+                Err(e)
+            }
+        }
+    }
+
+    // One HueStream v1 message setting every light of the area, in xy
+    // color space with the brightness.
+    fn send(&mut self, state: &LightState) -> Result<(), Box<dyn std::error::Error>> {
+        let scale = |value: f32| ((value.clamp(0.0, 1.0) * u16::MAX as f32) as u16).to_be_bytes();
+        let mut message = Vec::with_capacity(16 + self.lights.len() * 9);
+        message.extend_from_slice(b"HueStream");
+        // Version 1.0, sequence number (ignored), reserved, xy color space,
+        // reserved.
+        message.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        for &light in &self.lights {
+            message.push(0x00);
+            message.extend_from_slice(&light.to_be_bytes());
+            message.extend_from_slice(&scale(state.xy[0]));
+            message.extend_from_slice(&scale(state.xy[1]));
+            message.extend_from_slice(&scale(state.brightness));
+        }
+        self.connection.write_all(&message)?;
+        Ok(())
+    }
+
+    fn stop(mut self, agent: &ureq::Agent) {
+        self.connection.shutdown().ok();
+        let stopped = agent
+            .put(&format!(
+                "http://{}/api/{}/groups/{}",
+                self.target.ip, self.target.username, self.target.area
+            ))
+            .send_json(json!({ "stream": { "active": false } }));
+        if let Err(e) = stopped {
+            warn!("Failed to end the Hue stream: {}", e);
+        }
+        info!(
+            "Stopped streaming to the Hue entertainment area {}",
+            self.target.area
+        );
+    }
+}
+
+// The DTLS 1.2 handshake with the bridge, authenticated by the username as
+// identity and the client key as pre-shared key.
+fn connect(target: &StreamTarget) -> Result<SslStream<UdpChannel>, Box<dyn std::error::Error>> {
+    let psk = decode_hex(&target.client_key).ok_or("invalid client key, pair again")?;
+    let identity = target.username.clone().into_bytes();
+
+    let mut builder = SslConnector::builder(SslMethod::dtls())?;
+    builder.set_min_proto_version(Some(SslVersion::DTLS1_2))?;
+    builder.set_cipher_list(ENTERTAINMENT_CIPHER)?;
+    builder.set_psk_client_callback(move |_, _, identity_out, psk_out| {
+        // The identity is written NUL-terminated.
+        if identity.len() >= identity_out.len() || psk.len() > psk_out.len() {
+            return Err(ErrorStack::get());
+        }
+        identity_out[..identity.len()].copy_from_slice(&identity);
+        identity_out[identity.len()] = 0;
+        psk_out[..psk.len()].copy_from_slice(&psk);
+        Ok(psk.len())
+    });
+
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.connect((target.ip.as_str(), ENTERTAINMENT_PORT))?;
+    socket.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    // The pre-shared key authenticates the bridge; it has no certificate
+    // to check.
+    let connection = builder
+        .build()
+        .configure()?
+        .verify_hostname(false)
+        .use_server_name_indication(false)
+        .connect(&target.ip, UdpChannel(socket))?;
+    Ok(connection)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// A connected UDP socket, read and written a datagram at a time, which is
+// how OpenSSL runs DTLS over it.
+#[derive(Debug)]
+struct UdpChannel(UdpSocket);
+
+impl Read for UdpChannel {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.recv(buf)
+    }
+}
+
+impl Write for UdpChannel {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
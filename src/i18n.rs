@@ -0,0 +1,364 @@
+// src/i18n.rs
+
+use serde::{Deserialize, Serialize};
+
+// UI language. The English strings double as lookup keys, so a missing
+// translation simply falls back to English.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    // Shown in the language picker, always in the language itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+        }
+    }
+
+    pub fn tr(self, text: &'static str) -> &'static str {
+        match self {
+            Language::English => text,
+            Language::French => french(text).unwrap_or(text),
+        }
+    }
+}
+
+// --- French Bundle ---
+fn french(text: &str) -> Option<&'static str> {
+    let translated = match text {
+        // Hints
+        "Press 'H' to Show UI" => "Appuyez sur 'H' pour afficher l'interface",
+        "Press 'H' to Hide UI" => "Appuyez sur 'H' pour masquer l'interface",
+
+        // Visualizer settings panel
+        "🎨 Visualizer Settings" => "🎨 Réglages du visualiseur",
+        "🎨 Colors" => "🎨 Couleurs",
+        "📐 Geometry" => "📐 Géométrie",
+        "🌀 Motion" => "🌀 Mouvement",
+        "✨ Bloom" => "✨ Halo",
+        "Amplitude Sensitivity" => "Sensibilité d'amplitude",
+        "Inactive Color" => "Couleur inactive",
+        "Active Color" => "Couleur active",
+        "Frequency Bands (Rebuilds Grid)" => "Bandes de fréquence (reconstruit la grille)",
+        "Spread Effect" => "Effet d'étalement",
+        "Onset Flash" => "Flash sur les attaques",
+        "Flash Decay (s)" => "Déclin du flash (s)",
+        "Flash Color" => "Couleur du flash",
+        "Cube Base Color" => "Couleur de base des cubes",
+        "Column Size" => "Taille des colonnes",
+        "Treble Influence" => "Influence des aigus",
+        "Base Color" => "Couleur de base",
+        "Color From Loudest Band" => "Couleur selon la bande dominante",
+        "Bass Color" => "Couleur des basses",
+        "Mid Color" => "Couleur des médiums",
+        "Treble Color" => "Couleur des aigus",
+        "Beat Ripple Amplitude" => "Amplitude des ondes sur les temps",
+        "Beat Ripple Speed" => "Vitesse des ondes sur les temps",
+        "One Ring Per Band" => "Un anneau par bande",
+        "Glow Intensity" => "Intensité de la lueur",
+        "Ambient Color" => "Couleur ambiante",
+        "Background Color" => "Couleur de fond",
+        "Mouse Sensitivity" => "Sensibilité de la souris",
+        "Auto Orbit (Ignore Mouse)" => "Orbite automatique (ignore la souris)",
+        "Spectrum Resolution" => "Résolution du spectre",
+        "Time Scale" => "Échelle de temps",
+        "⚡ Strobe" => "⚡ Stroboscope",
+        "Flash on Beats" => "Flash sur les temps",
+        "Max Flashes per Second" => "Flashs par seconde max",
+        "Fire" => "Feu",
+        "Ocean" => "Océan",
+        "Forest" => "Forêt",
+        "Sunset" => "Coucher de soleil",
+        "Off" => "Désactivé",
+        "Every Few Bars" => "Toutes les quelques mesures",
+        "On Section Changes" => "Aux changements de section",
+        "Bars" => "Mesures",
+        "🌈 Color Grading" => "🌈 Étalonnage",
+        "Hue Shift (°)" => "Décalage de teinte (°)",
+        "Contrast" => "Contraste",
+        "Rotate Hue Slowly" => "Rotation lente de la teinte",
+        "Rotation Speed (°/s)" => "Vitesse de rotation (°/s)",
+        "Reset" => "Réinitialiser",
+        "Idle Animation in Silence" => "Animation d'attente dans le silence",
+        "Silence Before Idle (s)" => "Silence avant l'attente (s)",
+        "Noise Speed" => "Vitesse du bruit",
+        "Noise Frequency" => "Fréquence du bruit",
+        "Disc Color" => "Couleur du disque",
+        "Radius" => "Rayon",
+        "Line Thickness" => "Épaisseur du trait",
+        "Iterations (Echoes)" => "Itérations (échos)",
+        "Center Factor" => "Facteur central",
+        "Rotation Speed" => "Vitesse de rotation",
+        "Metallic Color" => "Couleur métallique",
+        "Enable Bloom" => "Activer le halo",
+        "Intensity" => "Intensité",
+        "Threshold" => "Seuil",
+        "Tint" => "Teinte",
+        "🪞 Floor Reflection" => "🪞 Reflet au sol",
+        "Enable Floor" => "Activer le sol",
+        "Roughness" => "Rugosité",
+        "🎥 Camera" => "🎥 Caméra",
+        "Field of View" => "Champ de vision",
+        "Orthographic" => "Orthographique",
+        "Bass Widens View" => "Les basses élargissent la vue",
+        "Exposure (stops)" => "Exposition (IL)",
+        "Tonemapping" => "Mappage des tons",
+        "None" => "Aucun",
+        "Depth of Field" => "Profondeur de champ",
+        "Focus Distance" => "Distance de mise au point",
+        "Aperture (f-stops)" => "Ouverture (f/)",
+        "Bookmark Transition (s)" => "Transition des favoris (s)",
+        "🌫 Fog" => "🌫 Brouillard",
+        "Enable Fog" => "Activer le brouillard",
+        "Fog Color" => "Couleur du brouillard",
+        "Fog Start" => "Début du brouillard",
+        "Fog End" => "Fin du brouillard",
+        "Bass Thickens Fog" => "Les basses épaississent le brouillard",
+
+        // Controls panel
+        "🎛 Controls" => "🎛 Contrôles",
+        "Select Visualizer:" => "Choisir le visualiseur :",
+        "2D Bars" => "Barres 2D",
+        "3D Cubes" => "Cubes 3D",
+        "3D Orb" => "Orbe 3D",
+        "Disc" => "Disque",
+        "⏹ Stop Render" => "⏹ Arrêter le rendu",
+        "▶ Start Render" => "▶ Lancer le rendu",
+        "🎵 Audio Source" => "🎵 Source audio",
+        "📂 Load File" => "📂 Charger un fichier",
+        "⏺ Record Analysis" => "⏺ Enregistrer l'analyse",
+        "⏹ Stop Recording" => "⏹ Arrêter l'enregistrement",
+        "Playback:" => "Lecture :",
+        "Speed:" => "Vitesse :",
+        "Preserve Pitch" => "Conserver la hauteur",
+        "💾 Export Analysis" => "💾 Exporter l'analyse",
+        "📊 Frequency Range" => "📊 Plage de fréquences",
+        "⏱ Sync" => "⏱ Synchronisation",
+        "Auto Latency" => "Latence automatique",
+        "Visual Offset" => "Décalage visuel",
+        "Analyse Files Ahead" => "Analyser les fichiers à l'avance",
+        "Analysing..." => "Analyse en cours...",
+        "Snap to Beats" => "Caler sur les temps",
+        "💾 Save Project" => "💾 Enregistrer le projet",
+        "📂 Open Project" => "📂 Ouvrir un projet",
+        "Switch Scenes on Sections" => "Changer de scène aux sections",
+        "Keep" => "Garder",
+        "Quiet Sections" => "Sections calmes",
+        "Loud Sections" => "Sections fortes",
+        "Preset" => "Préréglage",
+        "none" => "aucun",
+        "∿ LFOs" => "∿ LFO",
+        "Tap (T)" => "Taper (T)",
+        "Sync" => "Synchro",
+        "Sine" => "Sinus",
+        "Saw" => "Dent de scie",
+        "Square" => "Carré",
+        "Targets" => "Cibles",
+        "➕ Add Target" => "➕ Ajouter une cible",
+        "🎞 Timeline" => "🎞 Chronologie",
+        "Step" => "Par paliers",
+        "➕ Key at" => "➕ Clé à",
+        "➕ Automate..." => "➕ Automatiser...",
+        "📍 Cue Points" => "📍 Repères",
+        "➕ Cue at" => "➕ Repère à",
+        "Cue" => "Repère",
+        "Press [ and ] to jump between cues." => {
+            "Appuyez sur [ et ] pour passer d'un repère à l'autre."
+        }
+        "🎙 Source Mix" => "🎙 Mélange des sources",
+        "Mix Microphone With File" => "Mélanger le micro au fichier",
+        "File Gain" => "Gain du fichier",
+        "Mic Gain" => "Gain du micro",
+        "Mic Buffer" => "Tampon du micro",
+        "Default" => "Par défaut",
+        "Mic Latency:" => "Latence du micro :",
+        "Monitor Microphone" => "Écouter le micro",
+        "Monitor Gain" => "Gain d'écoute",
+        "Monitoring is delayed by up to" => "L'écoute est retardée jusqu'à",
+        "🎚 Equalizer" => "🎚 Égaliseur",
+        "EQ Enabled" => "Égaliseur activé",
+        "Apply to Playback" => "Appliquer à la lecture",
+        "〰 Smoothing" => "〰 Lissage",
+        "Attack" => "Attaque",
+        "Release" => "Relâchement",
+        "Bands" => "Bandes",
+        "🔇 Silence Gate" => "🔇 Porte de silence",
+        "Gate Enabled" => "Porte activée",
+        "Threshold (dB)" => "Seuil (dB)",
+        "Hold (s)" => "Maintien (s)",
+        "Pause Visuals When Silent" => "Mettre en pause dans le silence",
+        "🖌 Appearance" => "🖌 Apparence",
+        "Language" => "Langue",
+        "Dark Theme" => "Thème sombre",
+        "Accent Color" => "Couleur d'accent",
+        "Panel Opacity" => "Opacité des panneaux",
+        "Font Size" => "Taille du texte",
+        "UI Scale" => "Échelle de l'interface",
+        "Settings Panel" => "Panneau des réglages",
+        "Docked" => "Ancré",
+        "Floating" => "Flottant",
+        "Own Window" => "Fenêtre séparée",
+        "Auto-Hide in Fullscreen" => "Masquer en plein écran",
+        "Idle Delay (s)" => "Délai d'inactivité (s)",
+        "📺 Now Playing Overlay" => "📺 Incrustation « En cours »",
+        "Show When UI Is Hidden" => "Afficher quand l'interface est masquée",
+        "Corner" => "Coin",
+        "Top Left" => "En haut à gauche",
+        "Top Right" => "En haut à droite",
+        "Bottom Left" => "En bas à gauche",
+        "Bottom Right" => "En bas à droite",
+        "Opacity" => "Opacité",
+        "🔋 Power Saving" => "🔋 Économie d'énergie",
+        "Quality" => "Qualité",
+        "Low" => "Basse",
+        "Medium" => "Moyenne",
+        "High" => "Haute",
+        "Adaptive Quality" => "Qualité adaptative",
+        "Target FPS" => "IPS visées",
+        "Lowered to" => "Abaissée à",
+        "When Unfocused" => "En arrière-plan",
+        "Full Speed" => "Pleine vitesse",
+        "Low Frame Rate" => "Fréquence réduite",
+        "Pause Rendering" => "Suspendre le rendu",
+        "FPS" => "IPS",
+        "📡 Remote Control" => "📡 Contrôle à distance",
+        "WebSocket Server" => "Serveur WebSocket",
+        "Allow Network Access" => "Autoriser l'accès réseau",
+        "Anyone on the network can then control the app, without a password." => {
+            "Tout le monde sur le réseau pourra alors contrôler l'application, sans mot de passe."
+        }
+        "Allowed Web Pages" => "Pages web autorisées",
+        "Origins of the control pages allowed to connect, e.g. http://192.168.1.20:8080. Other web pages are refused." => {
+            "Origines des pages de contrôle autorisées à se connecter, p. ex. http://192.168.1.20:8080. Les autres pages web sont refusées."
+        }
+        "➕ Add Web Page" => "➕ Ajouter une page web",
+        "HTTP Server" => "Serveur HTTP",
+        "Bridge IP" => "IP du pont",
+        "🔍 Find" => "🔍 Rechercher",
+        "Press the button on the bridge, then pair within 30 seconds." => {
+            "Appuyez sur le bouton du pont, puis associez dans les 30 secondes."
+        }
+        "🔗 Pair" => "🔗 Associer",
+        "✔ Paired" => "✔ Associé",
+        "Forget" => "Oublier",
+        "Sync Lights" => "Synchroniser les lampes",
+        "Entertainment Area" => "Zone de divertissement",
+        "⟳ Refresh Areas" => "⟳ Actualiser les zones",
+        "Create an entertainment area in the Hue app, then refresh." => {
+            "Créez une zone de divertissement dans l'application Hue, puis actualisez."
+        }
+        "Resting Brightness" => "Luminosité au repos",
+        "Beat Brightness" => "Luminosité sur le temps",
+        "Searching for a bridge..." => "Recherche d'un pont...",
+        "Pairing..." => "Association...",
+        "Listing entertainment areas..." => "Liste des zones de divertissement...",
+        "this computer" => "cet ordinateur",
+        "🖥 Output Window" => "🖥 Fenêtre de sortie",
+        "Second Window (Visuals Only)" => "Seconde fenêtre (visuels seuls)",
+        "Monitor" => "Écran",
+        "Render Scale" => "Échelle de rendu",
+        "Show Analysis Data" => "Afficher les données d'analyse",
+        "Analysis Data" => "Données d'analyse",
+        "Spectrum" => "Spectre",
+        "Waveform" => "Forme d'onde",
+        "History" => "Historique",
+        "Bass" => "Basses",
+        "Mid" => "Médiums",
+        "Treble" => "Aigus",
+
+        // Menus
+        "Start Visualization" => "Lancer la visualisation",
+        "Select Microphone" => "Choisir le microphone",
+        "Select an Input Device" => "Choisir un périphérique d'entrée",
+        "⟳ Refresh" => "⟳ Actualiser",
+        "Current:" => "Actuel :",
+        "No input device found" => "Aucun périphérique d'entrée",
+        "⏮ Resume Last Session" => "⏮ Reprendre la dernière session",
+        "⚙ Settings" => "⚙ Réglages",
+        "⬅ Back" => "⬅ Retour",
+        "▶ Start-Up" => "▶ Démarrage",
+        "Start-Up Visualizer" => "Visualiseur au démarrage",
+        "Visualizers" => "Visualiseurs",
+        "Move Up" => "Monter",
+        "Move Down" => "Descendre",
+        "🔊 Audio" => "🔊 Audio",
+        "Pause Audio in Menus" => "Mettre l'audio en pause dans les menus",
+        "⌨ Keyboard Shortcuts" => "⌨ Raccourcis clavier",
+        "Show or hide the UI" => "Afficher ou masquer l'interface",
+        "Show or hide the settings panel" => "Afficher ou masquer le panneau des réglages",
+        "Show or hide the controls panel" => "Afficher ou masquer le panneau des commandes",
+        "Toggle fullscreen" => "Basculer en plein écran",
+        "Freeze the visualization" => "Figer la visualisation",
+        "Back to the main menu" => "Retour au menu principal",
+        "Switch visualizer" => "Changer de visualiseur",
+        "Shift: recall a camera view, Ctrl: save it" => {
+            "Maj : rappeler une vue caméra, Ctrl : l'enregistrer"
+        }
+        "Toggle the mini player" => "Basculer le mini-lecteur",
+        "Tap the tempo" => "Taper le tempo",
+        "Previous cue point" => "Repère précédent",
+        "Next cue point" => "Repère suivant",
+        "Show the keyboard shortcuts" => "Afficher les raccourcis clavier",
+
+        // Library
+        "📚 Library" => "📚 Bibliothèque",
+        "📁 Choose Folder" => "📁 Choisir un dossier",
+        "Scanning..." => "Analyse du dossier...",
+        "No tracks" => "Aucun morceau",
+        "🔀 Shuffle" => "🔀 Aléatoire",
+        "No Repeat" => "Sans répétition",
+        "🔁 Repeat All" => "🔁 Tout répéter",
+        "🔂 Repeat One" => "🔂 Répéter le morceau",
+
+        // Analysis
+        "Weighting" => "Pondération",
+        "A-Weighting" => "Pondération A",
+        "K-Weighting" => "Pondération K",
+        "Normalize Bands" => "Normaliser les bandes",
+        "Peak Half-Life (s)" => "Demi-vie des crêtes (s)",
+        "Zero Crossings" => "Passages par zéro",
+        "Percussive" => "Percussivité",
+        "Crossovers" => "Fréquences de coupure",
+        "Sub / Punch" => "Sub / Attaque",
+        "Bass / Mid" => "Graves / Médiums",
+        "Mid / Treble" => "Médiums / Aigus",
+        "Sub-Bass" => "Sub-basses",
+        "Punch" => "Attaque",
+        "Custom Band Edges" => "Limites des bandes personnalisées",
+        "Peak" => "Crête",
+        "Spectrum From GPU FFT" => "Spectre par FFT sur le GPU",
+        "Between Ticks" => "Entre les analyses",
+        "Step" => "Par paliers",
+        "Interpolate" => "Interpoler",
+        "Extrapolate" => "Extrapoler",
+        "🎯 Tone Detectors" => "🎯 Détecteurs de fréquence",
+        "➕ Add Detector" => "➕ Ajouter un détecteur",
+        "Drag the lines to move the edges of the bands." => {
+            "Faites glisser les lignes pour déplacer les limites des bandes."
+        }
+
+        // Tray
+        "Play / Pause" => "Lecture / Pause",
+        "Next Visualizer" => "Visualiseur suivant",
+        "Show / Hide Window" => "Afficher / Masquer la fenêtre",
+        "Quit" => "Quitter",
+
+        // Discord
+        "💬 Discord" => "💬 Discord",
+        "Show What's Playing on Discord" => "Afficher l'écoute en cours sur Discord",
+        "Application ID" => "ID de l'application",
+        "Create an application in the Discord Developer Portal and paste its ID." => {
+            "Créez une application dans le portail développeur de Discord et collez son ID."
+        }
+
+        _ => return None,
+    };
+    Some(translated)
+}
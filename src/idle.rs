@@ -0,0 +1,105 @@
+// src/idle.rs
+
+use crate::audio::{apply_analysis_frames, AudioAnalysis, AudioSource, SelectedAudioSource};
+use crate::config::{VisualizerId, VisualsConfig};
+use crate::AppState;
+use bevy::prelude::*;
+
+// RMS volume under which the input counts as silent, about -46 dBFS.
+const SILENCE_LEVEL: f32 = 0.005;
+// Peak amplitude of the made-up bands; the visuals drift rather than dance.
+const IDLE_LEVEL: f32 = 0.25;
+const IDLE_WAVEFORM_LEN: usize = 1024;
+
+pub struct IdlePlugin;
+
+impl Plugin for IdlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleMode>()
+            .add_systems(Update, drive_idle_mode.after(apply_analysis_frames));
+    }
+}
+
+// Attract mode: after a while without any sound, the visualizer is fed slow,
+// noise-like analysis values instead of sitting still.
+#[derive(Resource, Default)]
+pub struct IdleMode {
+    pub active: bool,
+    silent_for: f32,
+}
+
+// Idle values leave the volume alone, so the real volume still tells when
+// sound comes back. The bands then ease back from the idle values through
+// the usual smoothing.
+fn drive_idle_mode(
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    selected_source: Res<SelectedAudioSource>,
+    mut idle: ResMut<IdleMode>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+) {
+    let Some(viz_id) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let settings = &config.general.idle;
+    let silent =
+        matches!(selected_source.0, AudioSource::None) || audio_analysis.volume < SILENCE_LEVEL;
+    idle.silent_for = if silent {
+        idle.silent_for + time.delta_seconds()
+    } else {
+        0.0
+    };
+
+    let active = settings.enabled && idle.silent_for >= settings.delay;
+    if idle.active != active {
+        idle.active = active;
+        info!(
+            "Idle animation {}",
+            if active { "started" } else { "stopped" }
+        );
+    }
+    if !active {
+        return;
+    }
+
+    let t = time.elapsed_seconds();
+    let num_bands = config.num_bands(viz_id);
+    audio_analysis.frequency_bins.resize(num_bands, 0.0);
+    for (i, bin) in audio_analysis.frequency_bins.iter_mut().enumerate() {
+        *bin = idle_band(i as f32, t);
+    }
+    let bins = &audio_analysis.frequency_bins;
+    let bass = bins.iter().take(num_bands / 4).sum();
+    let mid = bins.iter().skip(num_bands / 4).take(num_bands / 2).sum();
+    let treble = bins.iter().skip(3 * num_bands / 4).sum();
+    let sub_bass: f32 = bins.iter().take(num_bands / 8).sum();
+    audio_analysis.bass = bass;
+    audio_analysis.sub_bass = sub_bass;
+    audio_analysis.punch = bass - sub_bass;
+    audio_analysis.mid = mid;
+    audio_analysis.treble = treble;
+    audio_analysis.flux = 0.0;
+    audio_analysis.peak = 0.0;
+    audio_analysis.volume_rise = 0.0;
+    audio_analysis.bass_rise = 0.0;
+    audio_analysis.mid_rise = 0.0;
+    audio_analysis.treble_rise = 0.0;
+
+    // A slow sine wobbling in amplitude.
+    let amplitude = 0.1 + 0.05 * (t * 0.5).sin();
+    audio_analysis.waveform = (0..IDLE_WAVEFORM_LEN)
+        .map(|i| {
+            let x = i as f32 / IDLE_WAVEFORM_LEN as f32 * std::f32::consts::TAU;
+            amplitude * (x * 2.0 + t).sin()
+        })
+        .collect();
+}
+
+// Two slow sines beating against each other across the bands look like
+// drifting noise without needing a noise function.
+fn idle_band(band: f32, t: f32) -> f32 {
+    let a = (t * 0.7 + band * 0.45).sin();
+    let b = (t * 0.31 - band * 0.17).sin();
+    IDLE_LEVEL * (0.5 + 0.5 * a * b)
+}
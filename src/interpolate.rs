@@ -0,0 +1,127 @@
+// src/interpolate.rs
+
+use crate::audio::AudioAnalysis;
+use crate::config::{AnalysisInterpolation, VisualsConfig};
+use bevy::prelude::*;
+
+// The outputs that move between analysis ticks. The rest keep the value of
+// the latest tick.
+#[derive(Clone, Default, PartialEq)]
+struct AnalysisValues {
+    levels: [f32; 7],
+    bins: Vec<f32>,
+}
+
+impl AnalysisValues {
+    fn read(analysis: &AudioAnalysis) -> Self {
+        Self {
+            levels: [
+                analysis.volume,
+                analysis.bass,
+                analysis.sub_bass,
+                analysis.punch,
+                analysis.mid,
+                analysis.treble,
+                analysis.treble_average,
+            ],
+            bins: analysis.frequency_bins.clone(),
+        }
+    }
+
+    fn write(&self, analysis: &mut AudioAnalysis) {
+        [
+            analysis.volume,
+            analysis.bass,
+            analysis.sub_bass,
+            analysis.punch,
+            analysis.mid,
+            analysis.treble,
+            analysis.treble_average,
+        ] = self.levels;
+        analysis.frequency_bins.clone_from(&self.bins);
+    }
+
+    // `self` moved toward `other` by `t`; past `other` when `t` is above 1.
+    // The levels never go below 0.
+    fn lerp(&self, other: &AnalysisValues, t: f32) -> Self {
+        let mix = |a: f32, b: f32| (a + (b - a) * t).max(0.0);
+        let bins = if self.bins.len() == other.bins.len() {
+            self.bins
+                .iter()
+                .zip(&other.bins)
+                .map(|(a, b)| mix(*a, *b))
+                .collect()
+        } else {
+            other.bins.clone()
+        };
+        Self {
+            levels: std::array::from_fn(|i| mix(self.levels[i], other.levels[i])),
+            bins,
+        }
+    }
+}
+
+// Analysis ticks run at 60 Hz, so a faster display would see the outputs
+// step. Between ticks `AudioAnalysis` holds values interpolated toward the
+// latest tick, a tick late, or extrapolated past it; the values of the
+// ticks themselves are kept here and put back before the next tick smooths
+// from them.
+#[derive(Resource, Default)]
+pub struct AnalysisInterpolator {
+    // The two latest ticks, what was shown when the latest arrived, and
+    // what is shown now.
+    before: AnalysisValues,
+    latest: AnalysisValues,
+    from: AnalysisValues,
+    shown: AnalysisValues,
+    // Seconds since the latest tick.
+    elapsed: f32,
+    // Whether `AudioAnalysis` holds shown rather than ticked values.
+    written: bool,
+}
+
+pub fn restore_analysis(
+    mut interpolator: ResMut<AnalysisInterpolator>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+) {
+    if interpolator.written {
+        interpolator.latest.write(&mut audio_analysis);
+        interpolator.written = false;
+    }
+}
+
+pub fn interpolate_analysis(
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    config: Res<VisualsConfig>,
+    mut interpolator: ResMut<AnalysisInterpolator>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+) {
+    let interpolator = &mut *interpolator;
+    let ticked = AnalysisValues::read(&audio_analysis);
+    if ticked != interpolator.latest {
+        interpolator.before = std::mem::replace(&mut interpolator.latest, ticked);
+        interpolator.from.clone_from(&interpolator.shown);
+        interpolator.elapsed = 0.0;
+    } else {
+        interpolator.elapsed += time.delta_seconds();
+    }
+
+    let t = interpolator.elapsed / fixed_time.timestep().as_secs_f32();
+    interpolator.shown = match config.general.interpolation {
+        AnalysisInterpolation::Off => {
+            interpolator.shown.clone_from(&interpolator.latest);
+            return;
+        }
+        // From what was shown to the latest tick, over one tick.
+        AnalysisInterpolation::Interpolate => {
+            interpolator.from.lerp(&interpolator.latest, t.min(1.0))
+        }
+        // Along the slope of the last two ticks, for at most one more tick.
+        AnalysisInterpolation::Extrapolate => interpolator
+            .before
+            .lerp(&interpolator.latest, 1.0 + t.min(1.0)),
+    };
+    interpolator.shown.write(&mut audio_analysis);
+    interpolator.written = true;
+}
@@ -0,0 +1,163 @@
+// src/keys.rs
+
+use crate::AppState;
+use bevy::prelude::*;
+
+const DIGIT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+// What a keyboard shortcut does. Most actions have a single key; the
+// numbered ones have a key per visualizer or camera view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    ToggleUi,
+    ToggleLeftPanel,
+    ToggleRightPanel,
+    Fullscreen,
+    Freeze,
+    MainMenu,
+    SwitchVisualizer,
+    CameraView,
+    MiniPlayer,
+    TapTempo,
+    PreviousCue,
+    NextCue,
+    CheatSheet,
+}
+
+impl KeyAction {
+    // Shown in the cheat sheet, translated.
+    pub fn description(self) -> &'static str {
+        match self {
+            KeyAction::ToggleUi => "Show or hide the UI",
+            KeyAction::ToggleLeftPanel => "Show or hide the settings panel",
+            KeyAction::ToggleRightPanel => "Show or hide the controls panel",
+            KeyAction::Fullscreen => "Toggle fullscreen",
+            KeyAction::Freeze => "Freeze the visualization",
+            KeyAction::MainMenu => "Back to the main menu",
+            KeyAction::SwitchVisualizer => "Switch visualizer",
+            KeyAction::CameraView => "Shift: recall a camera view, Ctrl: save it",
+            KeyAction::MiniPlayer => "Toggle the mini player",
+            KeyAction::TapTempo => "Tap the tempo",
+            KeyAction::PreviousCue => "Previous cue point",
+            KeyAction::NextCue => "Next cue point",
+            KeyAction::CheatSheet => "Show the keyboard shortcuts",
+        }
+    }
+
+    // Whether the action does anything in this state.
+    pub fn active_in(self, state: &AppState) -> bool {
+        match self {
+            KeyAction::CameraView => matches!(
+                state,
+                AppState::Visualization3D | AppState::VisualizationOrb
+            ),
+            _ => true,
+        }
+    }
+
+    fn numbered(self) -> bool {
+        matches!(self, KeyAction::SwitchVisualizer | KeyAction::CameraView)
+    }
+}
+
+// Every keyboard shortcut of the visualizations. The handlers look their
+// keys up here, and the cheat sheet lists them from here.
+#[derive(Resource)]
+pub struct KeyBindings(Vec<(KeyAction, KeyCode)>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = vec![
+            (KeyAction::ToggleUi, KeyCode::KeyH),
+            (KeyAction::ToggleLeftPanel, KeyCode::F2),
+            (KeyAction::ToggleRightPanel, KeyCode::F3),
+            (KeyAction::Fullscreen, KeyCode::F11),
+            (KeyAction::Freeze, KeyCode::KeyF),
+            (KeyAction::MainMenu, KeyCode::Escape),
+        ];
+        bindings.extend(
+            DIGIT_KEYS[..6]
+                .iter()
+                .map(|key| (KeyAction::SwitchVisualizer, *key)),
+        );
+        bindings.extend(DIGIT_KEYS.iter().map(|key| (KeyAction::CameraView, *key)));
+        bindings.extend([
+            (KeyAction::MiniPlayer, KeyCode::KeyM),
+            (KeyAction::TapTempo, KeyCode::KeyT),
+            (KeyAction::PreviousCue, KeyCode::BracketLeft),
+            (KeyAction::NextCue, KeyCode::BracketRight),
+            (KeyAction::CheatSheet, KeyCode::Slash),
+            (KeyAction::CheatSheet, KeyCode::F1),
+        ]);
+        Self(bindings)
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: KeyAction) -> impl Iterator<Item = KeyCode> + '_ {
+        self.0
+            .iter()
+            .filter(move |(bound, _)| *bound == action)
+            .map(|(_, key)| *key)
+    }
+
+    pub fn just_pressed(&self, action: KeyAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        self.keys(action).any(|key| keyboard.just_pressed(key))
+    }
+
+    // Which of the keys of a numbered action was just pressed, from 0.
+    pub fn just_pressed_index(
+        &self,
+        action: KeyAction,
+        keyboard: &ButtonInput<KeyCode>,
+    ) -> Option<usize> {
+        self.keys(action).position(|key| keyboard.just_pressed(key))
+    }
+
+    // Every bound action once, in the order they were bound.
+    pub fn actions(&self) -> Vec<KeyAction> {
+        let mut actions = Vec::new();
+        for (action, _) in &self.0 {
+            if !actions.contains(action) {
+                actions.push(*action);
+            }
+        }
+        actions
+    }
+
+    // "H", "? / F1", or "1 – 6" for the numbered actions.
+    pub fn label(&self, action: KeyAction) -> String {
+        let names: Vec<String> = self.keys(action).map(key_name).collect();
+        match (action.numbered(), names.first(), names.last()) {
+            (true, Some(first), Some(last)) if names.len() > 1 => format!("{} – {}", first, last),
+            _ => names.join(" / "),
+        }
+    }
+}
+
+// How a key is written for the user.
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Escape => "Esc".to_string(),
+        KeyCode::Slash => "?".to_string(),
+        KeyCode::BracketLeft => "[".to_string(),
+        KeyCode::BracketRight => "]".to_string(),
+        key => {
+            let name = format!("{:?}", key);
+            name.strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_string()
+        }
+    }
+}
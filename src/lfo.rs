@@ -0,0 +1,139 @@
+// src/lfo.rs
+
+use crate::config::{LfoRate, LfoShape, VisualsConfig, LFO_COUNT};
+use crate::keys::{KeyAction, KeyBindings};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+// Taps further apart than this start a new tempo measurement.
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_TAPS: usize = 8;
+
+pub struct LfoPlugin;
+
+impl Plugin for LfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tempo>()
+            .init_resource::<LfoBank>()
+            .add_systems(Update, (tap_tempo, update_lfos.after(tap_tempo)));
+    }
+}
+
+// The tempo beat-synced LFOs follow. Set by tapping 'T' (or the Tap button).
+#[derive(Resource)]
+pub struct Tempo {
+    pub bpm: f32,
+    taps: VecDeque<Duration>,
+    // Set when a tap lands and cleared once synced LFOs have restarted on the beat.
+    pub(crate) beat_reset: bool,
+}
+
+impl Default for Tempo {
+    fn default() -> Self {
+        Self {
+            bpm: 120.0,
+            taps: VecDeque::new(),
+            beat_reset: false,
+        }
+    }
+}
+
+impl Tempo {
+    pub fn tap(&mut self, now: Duration) {
+        if let Some(last) = self.taps.back() {
+            if now.saturating_sub(*last) > TAP_TIMEOUT {
+                self.taps.clear();
+            }
+        }
+        self.taps.push_back(now);
+        while self.taps.len() > MAX_TAPS {
+            self.taps.pop_front();
+        }
+
+        if let (Some(first), Some(last)) = (self.taps.front(), self.taps.back()) {
+            let intervals = self.taps.len() - 1;
+            if intervals > 0 {
+                let beat = (*last - *first).as_secs_f32() / intervals as f32;
+                self.bpm = (60.0 / beat).clamp(30.0, 300.0);
+            }
+        }
+        self.beat_reset = true;
+    }
+}
+
+// Current output of every LFO, in 0..1. Modulation source for visualizers
+// and shaders.
+#[derive(Resource, Default)]
+pub struct LfoBank {
+    phases: [f32; LFO_COUNT],
+    values: [f32; LFO_COUNT],
+}
+
+impl LfoBank {
+    pub fn value(&self, index: usize) -> f32 {
+        self.values.get(index).copied().unwrap_or(0.0)
+    }
+
+    // All outputs packed for a vec4 uniform.
+    pub fn as_vec4(&self) -> Vec4 {
+        Vec4::from_array(self.values)
+    }
+}
+
+fn tap_tempo(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    time: Res<Time<Real>>,
+    mut tempo: ResMut<Tempo>,
+) {
+    if bindings.just_pressed(KeyAction::TapTempo, &keyboard) {
+        tempo.tap(time.elapsed());
+    }
+}
+
+fn update_lfos(
+    time: Res<Time>,
+    mut tempo: ResMut<Tempo>,
+    config: Res<VisualsConfig>,
+    mut bank: ResMut<LfoBank>,
+) {
+    let dt = time.delta_seconds();
+    let bank = &mut *bank;
+
+    for (i, lfo) in config.general.lfos.iter().enumerate() {
+        if !lfo.enabled {
+            bank.values[i] = 0.0;
+            continue;
+        }
+
+        let frequency = match lfo.rate {
+            LfoRate::Hz(hz) => hz,
+            LfoRate::Beats(beats) => {
+                if tempo.beat_reset {
+                    bank.phases[i] = 0.0;
+                }
+                tempo.bpm / 60.0 / beats.max(0.01)
+            }
+        };
+        bank.phases[i] = (bank.phases[i] + frequency * dt).fract();
+
+        let phase = bank.phases[i];
+        bank.values[i] = match lfo.shape {
+            LfoShape::Sine => 0.5 - 0.5 * (phase * TAU).cos(),
+            LfoShape::Saw => phase,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        };
+    }
+
+    if tempo.beat_reset {
+        tempo.beat_reset = false;
+    }
+}
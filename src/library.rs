@@ -0,0 +1,344 @@
+// src/library.rs
+
+use crate::audio::{
+    display_name, manage_audio_playback, AudioSource, PlaybackInfo, PlaybackStatus,
+    SelectedAudioSource,
+};
+use crate::config::{RepeatMode, VisualsConfig};
+use crate::AppState;
+use bevy::prelude::*;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Mutex;
+use std::time::Duration;
+use symphonia::core::meta::{MetadataRevision, StandardTagKey};
+
+// Files the library picks up when scanning a folder: those the player decodes.
+const LIBRARY_EXTENSIONS: [&str; 4] = ["mp3", "wav", "flac", "ogg"];
+
+pub struct LibraryPlugin;
+
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Library>()
+            .init_resource::<Playlist>()
+            .add_systems(
+                Update,
+                (scan_library_folder, receive_scanned_tracks).chain(),
+            )
+            .add_systems(
+                Update,
+                advance_playlist.after(manage_audio_playback).run_if(
+                    in_state(AppState::Visualization2D)
+                        .or_else(in_state(AppState::Visualization3D))
+                        .or_else(in_state(AppState::VisualizationOrb))
+                        .or_else(in_state(AppState::VisualizationDisc))
+                        .or_else(in_state(AppState::VisualizationIco))
+                        .or_else(in_state(AppState::VisualizationShadertoy)),
+                ),
+            );
+    }
+}
+
+#[derive(Clone)]
+pub struct LibraryTrack {
+    pub path: PathBuf,
+    // From the tags, or the file name without them.
+    pub title: String,
+    pub artist: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl LibraryTrack {
+    // "Artist – Title", or just the title.
+    pub fn label(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{} – {}", artist, self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    // Whether a lowercase search matches the title, artist or file name.
+    pub fn matches(&self, search: &str) -> bool {
+        search.is_empty()
+            || self.label().to_lowercase().contains(search)
+            || display_name(&self.path).to_lowercase().contains(search)
+    }
+}
+
+// The audio files of the library folder (`library_folder` in the general
+// settings), filled in by a background scan.
+#[derive(Resource, Default)]
+pub struct Library {
+    // Whether the library window is shown.
+    pub open: bool,
+    pub search: String,
+    pub tracks: Vec<LibraryTrack>,
+    // The folder the tracks come from, and the scan still reading it.
+    folder: Option<PathBuf>,
+    scan: Option<Mutex<Receiver<LibraryTrack>>>,
+}
+
+impl Library {
+    pub fn is_scanning(&self) -> bool {
+        self.scan.is_some()
+    }
+}
+
+// The files played one after the other: the library as listed when a track
+// was picked from it.
+#[derive(Resource, Default)]
+pub struct Playlist {
+    pub tracks: Vec<PathBuf>,
+    pub current: Option<usize>,
+    // While shuffling, the tracks left to play this round, the next one last.
+    // `None` until shuffling starts.
+    shuffled: Option<Vec<usize>>,
+}
+
+impl Playlist {
+    pub fn current_track(&self) -> Option<&PathBuf> {
+        self.tracks.get(self.current?)
+    }
+
+    // Replaces the playlist and starts it at `index`.
+    pub fn play(&mut self, tracks: Vec<PathBuf>, index: usize, source: &mut SelectedAudioSource) {
+        self.tracks = tracks;
+        self.current = None;
+        self.shuffled = None;
+        if let Some(path) = self.tracks.get(index) {
+            source.0 = AudioSource::File(path.clone());
+            self.current = Some(index);
+        }
+    }
+
+    // The track to play after the current one, if any.
+    fn next(&mut self, repeat: RepeatMode, shuffle: bool) -> Option<usize> {
+        let current = self.current?;
+        if repeat == RepeatMode::One {
+            return Some(current);
+        }
+        if !shuffle {
+            self.shuffled = None;
+            return match current + 1 {
+                next if next < self.tracks.len() => Some(next),
+                _ if repeat == RepeatMode::All => Some(0),
+                _ => None,
+            };
+        }
+
+        // Shuffling plays every track once per round. The round that starts
+        // with shuffle leaves out the track already playing, and a new round
+        // never starts with the track that ended the last one.
+        let round = self.shuffled.get_or_insert_with(|| {
+            shuffle((0..self.tracks.len()).filter(|&i| i != current).collect())
+        });
+        if round.is_empty() && repeat == RepeatMode::All {
+            *round = shuffle((0..self.tracks.len()).collect());
+            if round.len() > 1 && round.last() == Some(&current) {
+                let last = round.len() - 1;
+                round.swap(0, last);
+            }
+        }
+        round.pop()
+    }
+}
+
+// Fisher–Yates with a xorshift generator, seeded from the random keys std
+// gives each hasher.
+fn shuffle(mut indices: Vec<usize>) -> Vec<usize> {
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    for i in (1..indices.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        indices.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+    indices
+}
+
+// Starts scanning whenever the library folder is set or changed, dropping
+// the tracks and scan of the previous one.
+fn scan_library_folder(config: Res<VisualsConfig>, mut library: ResMut<Library>) {
+    if !config.is_changed() || config.general.library_folder == library.folder {
+        return;
+    }
+    library.folder = config.general.library_folder.clone();
+    library.tracks.clear();
+    library.scan = library
+        .folder
+        .clone()
+        .map(|folder| Mutex::new(spawn_scan(folder)));
+}
+
+fn receive_scanned_tracks(mut library: ResMut<Library>) {
+    let Some(scan) = library.scan.as_ref() else {
+        return;
+    };
+    let mut received = Vec::new();
+    let mut finished = false;
+    if let Ok(receiver) = scan.lock() {
+        loop {
+            match receiver.try_recv() {
+                Ok(track) => received.push(track),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+    }
+    if received.is_empty() && !finished {
+        return;
+    }
+    library.tracks.extend(received);
+    if finished {
+        library.scan = None;
+        library
+            .tracks
+            .sort_by_cached_key(|track| track.label().to_lowercase());
+        info!("Library scan found {} tracks", library.tracks.len());
+    }
+}
+
+// Walks the folder and its subfolders in a thread, sending each audio file
+// with its tags and duration as it is read. It stops early once the
+// receiver is dropped.
+fn spawn_scan(folder: PathBuf) -> Receiver<LibraryTrack> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("library-scan".into())
+        .spawn(move || {
+            let mut pending = vec![folder];
+            while let Some(dir) = pending.pop() {
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    warn!("Could not read library folder {:?}", dir);
+                    continue;
+                };
+                for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+                    if path.is_dir() {
+                        pending.push(path);
+                    } else if is_library_file(&path) && sender.send(read_track(path)).is_err() {
+                        return;
+                    }
+                }
+            }
+        })
+        .expect("Failed to spawn the library scan thread");
+    receiver
+}
+
+fn is_library_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            LIBRARY_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+// Files that can't be probed are still listed, by file name.
+fn read_track(path: PathBuf) -> LibraryTrack {
+    let mut track = LibraryTrack {
+        title: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| display_name(&path)),
+        artist: None,
+        duration: None,
+        path,
+    };
+    if let Err(e) = probe_track(&mut track) {
+        warn!("Could not read the tags of {:?}: {}", track.path, e);
+    }
+    track
+}
+
+fn probe_track(track: &mut LibraryTrack) -> Result<(), Box<dyn std::error::Error>> {
+    let src = std::fs::File::open(&track.path)?;
+    let mss = symphonia::core::io::MediaSourceStream::new(Box::new(src), Default::default());
+    let mut hint = symphonia::core::probe::Hint::new();
+    if let Some(ext) = track.path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &Default::default(),
+        &Default::default(),
+    )?;
+
+    // Tags can come before the container, like ID3, or inside it.
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            apply_tags(track, revision);
+        }
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        apply_tags(track, revision);
+    }
+
+    track.duration = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+        .and_then(|t| {
+            let time = t
+                .codec_params
+                .time_base?
+                .calc_time(t.codec_params.n_frames?);
+            Some(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+        });
+    Ok(())
+}
+
+fn apply_tags(track: &mut LibraryTrack, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+        if value.trim().is_empty() {
+            continue;
+        }
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => track.title = value,
+            Some(StandardTagKey::Artist) => track.artist = Some(value),
+            _ => {}
+        }
+    }
+}
+
+// Plays the next file of the playlist once the current one has finished,
+// and forgets the playlist once another source is picked.
+fn advance_playlist(
+    mut playlist: ResMut<Playlist>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+    playback_info: Res<PlaybackInfo>,
+    config: Res<VisualsConfig>,
+) {
+    let Some(current) = playlist.current_track() else {
+        return;
+    };
+    if !matches!(&selected_source.0, AudioSource::File(path) if path == current) {
+        playlist.current = None;
+        return;
+    }
+    let finished = playback_info.status == PlaybackStatus::Paused
+        && playback_info.duration > Duration::ZERO
+        && playback_info.position >= playback_info.duration;
+    if !finished {
+        return;
+    }
+
+    let next = playlist.next(config.general.repeat, config.general.shuffle);
+    match next.and_then(|index| Some((index, playlist.tracks.get(index)?.clone()))) {
+        Some((index, path)) => {
+            selected_source.0 = AudioSource::File(path);
+            playlist.current = Some(index);
+        }
+        None => playlist.current = None,
+    }
+}
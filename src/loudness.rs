@@ -0,0 +1,204 @@
+// src/loudness.rs
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// Reported for silence, where the loudness is minus infinity. It is the
+// absolute gate of EBU R128.
+pub const SILENCE_LUFS: f32 = -70.0;
+// The loudness that maps to 0 in `loudness_level`; 0 LUFS maps to 1.
+const LEVEL_FLOOR_LUFS: f32 = -40.0;
+
+// Energy is summed over 100 ms blocks; the momentary window is the last 4 of
+// them (400 ms) and the short-term one the last 30 (3 s).
+const BLOCK_SECS: f64 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+
+// Loudness in LUFS as defined by ITU-R BS.1770 / EBU R128: the mean square of
+// the K-weighted signal over a window. It is measured on the mono analysis
+// stream, so a stereo track reads up to 3 LU below its stereo loudness.
+pub struct LoudnessMeter {
+    shelf: Biquad,
+    high_pass: Biquad,
+    block_len: usize,
+    block_sum: f64,
+    block_count: usize,
+    // Mean square of the latest blocks, oldest first.
+    blocks: VecDeque<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        Self {
+            shelf: Biquad::k_weighting_shelf(rate),
+            high_pass: Biquad::k_weighting_high_pass(rate),
+            block_len: (rate * BLOCK_SECS).round().max(1.0) as usize,
+            block_sum: 0.0,
+            block_count: 0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let weighted = self.high_pass.process(self.shelf.process(sample as f64));
+            self.block_sum += weighted * weighted;
+            self.block_count += 1;
+            if self.block_count == self.block_len {
+                if self.blocks.len() == SHORT_TERM_BLOCKS {
+                    self.blocks.pop_front();
+                }
+                self.blocks
+                    .push_back(self.block_sum / self.block_len as f64);
+                self.block_sum = 0.0;
+                self.block_count = 0;
+            }
+        }
+    }
+
+    pub fn momentary(&self) -> f32 {
+        self.window_lufs(MOMENTARY_BLOCKS)
+    }
+
+    pub fn short_term(&self) -> f32 {
+        self.window_lufs(SHORT_TERM_BLOCKS)
+    }
+
+    // Over the latest `blocks` blocks, or all of them until there are enough.
+    fn window_lufs(&self, blocks: usize) -> f32 {
+        let count = blocks.min(self.blocks.len());
+        if count == 0 {
+            return SILENCE_LUFS;
+        }
+        let mean_square = self.blocks.iter().rev().take(count).sum::<f64>() / count as f64;
+        if mean_square <= 0.0 {
+            return SILENCE_LUFS;
+        }
+        ((-0.691 + 10.0 * mean_square.log10()) as f32).max(SILENCE_LUFS)
+    }
+}
+
+// A loudness in LUFS mapped to 0..1 for modulation: -40 LUFS and below is 0,
+// 0 LUFS is 1. Mastered music sits around 0.6 to 0.8.
+pub fn loudness_level(lufs: f32) -> f32 {
+    ((lufs - LEVEL_FLOOR_LUFS) / -LEVEL_FLOOR_LUFS).clamp(0.0, 1.0)
+}
+
+// The A-weighting curve of IEC 61672 as a gain, 1 at 1 kHz. It follows the
+// ear at low levels, cutting the lows hardest.
+pub fn a_weighting(freq: f32) -> f32 {
+    let response = |f: f64| {
+        let f2 = f * f;
+        12194.0f64.powi(2) * f2 * f2
+            / ((f2 + 20.6f64.powi(2))
+                * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+                * (f2 + 12194.0f64.powi(2)))
+    };
+    (response(freq as f64) / response(1000.0)) as f32
+}
+
+// The gain of the K-weighting filters at each frequency, 1 at 1 kHz, for
+// weighting a spectrum the way the loudness is measured.
+pub struct KWeightingCurve {
+    shelf: Biquad,
+    high_pass: Biquad,
+    rate: f64,
+    reference: f64,
+}
+
+impl KWeightingCurve {
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        let mut curve = Self {
+            shelf: Biquad::k_weighting_shelf(rate),
+            high_pass: Biquad::k_weighting_high_pass(rate),
+            rate,
+            reference: 1.0,
+        };
+        curve.reference = curve.response(1000.0);
+        curve
+    }
+
+    pub fn gain(&self, freq: f32) -> f32 {
+        (self.response(freq as f64) / self.reference) as f32
+    }
+
+    fn response(&self, freq: f64) -> f64 {
+        self.shelf.gain(freq, self.rate) * self.high_pass.gain(freq, self.rate)
+    }
+}
+
+// Direct form I biquad.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    // The first stage of the K-weighting, a +4 dB shelf above about 1.5 kHz
+    // for the head. BS.1770 gives the coefficients at 48 kHz; these are
+    // derived from the same analog prototype for any rate.
+    fn k_weighting_shelf(rate: f64) -> Self {
+        let f0 = 1681.974_450_955_533;
+        let gain_db = 3.999_843_853_973_347;
+        let q = 0.707_175_236_955_419_6;
+        let k = (PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    // The second stage, the "RLB" high-pass around 38 Hz.
+    fn k_weighting_high_pass(rate: f64) -> Self {
+        let f0 = 38.135_470_876_024_44;
+        let q = 0.500_327_037_323_877_3;
+        let k = (PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    // Magnitude of the response at `freq`.
+    fn gain(&self, freq: f64, rate: f64) -> f64 {
+        let w = 2.0 * PI * freq / rate;
+        let (sin1, cos1) = w.sin_cos();
+        let (sin2, cos2) = (2.0 * w).sin_cos();
+        let num_re = self.b[0] + self.b[1] * cos1 + self.b[2] * cos2;
+        let num_im = -(self.b[1] * sin1 + self.b[2] * sin2);
+        let den_re = 1.0 + self.a[0] * cos1 + self.a[1] * cos2;
+        let den_im = -(self.a[0] * sin1 + self.a[1] * sin2);
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
@@ -0,0 +1,44 @@
+// src/macros.rs
+
+use crate::config::{VisualsConfig, MACRO_COUNT};
+use bevy::prelude::*;
+
+pub struct MacroPlugin;
+
+impl Plugin for MacroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_macros);
+    }
+}
+
+// Sets a macro knob, for remote control.
+pub fn set_macro(config: &mut VisualsConfig, index: usize, value: f32) -> Result<(), String> {
+    let knob = config
+        .general
+        .macros
+        .get_mut(index)
+        .ok_or_else(|| format!("no macro {} (there are {})", index, MACRO_COUNT))?;
+    knob.value = value.clamp(0.0, 1.0);
+    Ok(())
+}
+
+// Writes every target of a macro when its knob moves. Targets are left alone
+// otherwise, so they can still be tweaked by hand between two knob moves.
+fn apply_macros(mut config: ResMut<VisualsConfig>, mut applied: Local<[Option<f32>; MACRO_COUNT]>) {
+    for index in 0..MACRO_COUNT {
+        let value = config.general.macros[index].value;
+        if applied[index] == Some(value) {
+            continue;
+        }
+        applied[index] = Some(value);
+
+        let targets = config.general.macros[index].targets.clone();
+        for target in targets {
+            let (_, settings) = config.general_and_settings_mut(target.visualizer);
+            settings.set_number(
+                &target.field,
+                target.min + (target.max - target.min) * value,
+            );
+        }
+    }
+}
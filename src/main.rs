@@ -1,52 +1,147 @@
 // src/main.rs
 
 // --- Module declarations ---
+mod analysis;
 mod audio;
+mod audio_texture;
+mod audio_uniforms;
+mod backend;
+mod bench;
 mod camera;
+mod cli;
 mod config;
+mod discord;
+mod dof;
+mod eq;
+mod export;
+mod fft;
+mod floor;
+mod gamepad;
+mod governor;
+mod gpu_fft;
+mod grading;
+mod http;
+mod hue;
+mod i18n;
+mod idle;
+mod interpolate;
+mod keys;
+mod lfo;
+mod library;
+mod loudness;
+mod macros;
+mod mini_player;
+mod monitor;
+mod offline;
+mod output_window;
+mod palette;
+mod project;
+mod recording;
+mod remote;
+mod resample;
+mod scenes;
+mod session;
+mod settings_window;
+mod stretch;
+mod strobe;
+mod taskbar;
+mod throttle;
+mod thumbnails;
+mod timeline;
+mod toast;
+#[cfg(feature = "tray")]
+mod tray;
 mod ui;
 mod viz_2d;
 mod viz_3d;
 mod viz_disc;
 mod viz_ico;
 mod viz_orb;
+mod viz_shadertoy;
+mod wallpaper;
 
 // --- Plugin Imports ---
 use crate::audio::{AudioPlugin, MicStream, PlaybackInfo, SelectedAudioSource};
+use crate::audio_texture::AudioTexturePlugin;
+use crate::backend::{AudioBackend, Backend, CpalBackend};
+use crate::bench::BenchPlugin;
 use crate::camera::CameraPlugin;
-use crate::config::VisualsConfig;
+use crate::cli::LaunchOptions;
+use crate::config::ConfigPlugin;
+use crate::discord::DiscordPlugin;
+use crate::dof::DofPlugin;
+use crate::floor::FloorPlugin;
+use crate::gamepad::GamepadPlugin;
+use crate::governor::GovernorPlugin;
+use crate::grading::GradingPlugin;
+use crate::http::HttpPlugin;
+use crate::hue::HuePlugin;
+use crate::idle::IdlePlugin;
+use crate::keys::KeyBindings;
+use crate::lfo::LfoPlugin;
+use crate::library::LibraryPlugin;
+use crate::macros::MacroPlugin;
+use crate::mini_player::MiniPlayerPlugin;
+use crate::monitor::MonitorSink;
+use crate::offline::OfflinePlugin;
+use crate::output_window::OutputWindowPlugin;
+use crate::palette::PalettePlugin;
+use crate::project::ProjectPlugin;
+use crate::recording::RecordingPlugin;
+use crate::remote::RemotePlugin;
+use crate::scenes::ScenePlugin;
+use crate::session::SessionPlugin;
+use crate::settings_window::SettingsWindowPlugin;
+use crate::strobe::StrobePlugin;
+use crate::taskbar::{TaskbarPlugin, APP_ID};
+use crate::throttle::ThrottlePlugin;
+use crate::thumbnails::ThumbnailPlugin;
+use crate::timeline::TimelinePlugin;
+use crate::toast::ToastPlugin;
+#[cfg(feature = "tray")]
+use crate::tray::TrayPlugin;
 use crate::ui::{UiPlugin, UiVisibility};
 use crate::viz_2d::Viz2DPlugin;
 use crate::viz_3d::Viz3DPlugin;
 use crate::viz_disc::VizDiscPlugin;
 use crate::viz_ico::VizIcoPlugin;
 use crate::viz_orb::VizOrbPlugin;
+use crate::viz_shadertoy::VizShadertoyPlugin;
+use crate::wallpaper::{wallpaper_window, WallpaperPlugin};
 
 use bevy::prelude::*;
+use bevy::window::{ExitCondition, PresentMode};
 use bevy_egui::EguiPlugin;
-use rodio::{OutputStream, Sink};
+use rodio::Sink;
+use std::sync::Arc;
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum AppState {
     #[default]
     MainMenu,
     MicSelection,
+    Settings,
     Visualization3D,
     Visualization2D,
     VisualizationOrb,
     VisualizationDisc,
     VisualizationIco,
+    VisualizationShadertoy,
 }
 
+// Alongside `AppState`: a frozen visualization keeps its last frame on screen
+// with virtual time paused, while the audio keeps playing.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FreezeState {
+    #[default]
+    Live,
+    Frozen,
+}
+
+// The visualizer the menu starts, initially the one picked in the settings.
 #[derive(Resource, Debug, Clone)]
 pub struct ActiveVisualization(pub AppState);
 
-impl Default for ActiveVisualization {
-    fn default() -> Self {
-        Self(AppState::Visualization3D)
-    }
-}
-
 #[derive(Resource, Debug)]
 pub struct VisualizationEnabled(pub bool);
 
@@ -58,30 +153,111 @@ impl Default for VisualizationEnabled {
 
 fn main() {
     let mut app = App::new();
+    let options = LaunchOptions::from_args(std::env::args().skip(1));
 
-    let (stream, stream_handle) = OutputStream::try_default().unwrap();
+    let backend = CpalBackend;
+    let playback = backend.open_playback().unwrap();
+
+    // Benchmarks run in a hidden window without vsync so frame times aren't capped.
+    let primary_window = if options.bench.is_some() {
+        Window {
+            visible: false,
+            present_mode: PresentMode::AutoNoVsync,
+            ..default()
+        }
+    } else if options.wallpaper.is_some() {
+        wallpaper_window()
+    } else {
+        Window {
+            name: Some(APP_ID.into()),
+            ..default()
+        }
+    };
+
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(primary_window),
+        // The output and settings windows close with the main one.
+        exit_condition: ExitCondition::OnPrimaryClosed,
+        ..default()
+    }))
+    .insert_non_send_resource(Sink::try_new(&playback.handle).unwrap())
+    .insert_non_send_resource(MonitorSink(Sink::try_new(&playback.handle).unwrap()))
+    .insert_non_send_resource(playback)
+    .insert_resource(Backend(Arc::new(backend)))
+    .insert_non_send_resource(MicStream(None))
+    .init_resource::<SelectedAudioSource>()
+    .init_resource::<VisualizationEnabled>()
+    .init_resource::<PlaybackInfo>()
+    .init_resource::<UiVisibility>()
+    .init_resource::<KeyBindings>()
+    .init_state::<AppState>()
+    .init_state::<FreezeState>()
+    .add_plugins((
+        EguiPlugin,
+        ConfigPlugin,
+        ToastPlugin,
+        AudioPlugin,
+        AudioTexturePlugin,
+        UiPlugin,
+        Viz2DPlugin,
+        Viz3DPlugin,
+        VizOrbPlugin,
+        FloorPlugin,
+        CameraPlugin,
+        VizDiscPlugin,
+        VizIcoPlugin,
+        VizShadertoyPlugin,
+        GamepadPlugin,
+    ))
+    // Effects shared by every visualizer.
+    .add_plugins((
+        StrobePlugin,
+        PalettePlugin,
+        GradingPlugin,
+        DofPlugin,
+        IdlePlugin,
+    ))
+    // Modulation sources.
+    .add_plugins((LfoPlugin, MacroPlugin, TimelinePlugin))
+    // Offline analysis, session recording, remote control and integrations.
+    .add_plugins((
+        OfflinePlugin,
+        ScenePlugin,
+        RecordingPlugin,
+        RemotePlugin,
+        HttpPlugin,
+        HuePlugin,
+        DiscordPlugin,
+    ))
+    // Extra window modes.
+    .add_plugins((
+        MiniPlayerPlugin,
+        OutputWindowPlugin,
+        SettingsWindowPlugin,
+        TaskbarPlugin,
+    ))
+    // Previews of the visualizers for the selector.
+    .add_plugins(ThumbnailPlugin)
+    // Music library and playlist, and the project files bundling them with
+    // the settings.
+    .add_plugins((LibraryPlugin, ProjectPlugin))
+    // Lowers the quality of the visuals when the frame rate drops.
+    .add_plugins(GovernorPlugin);
+
+    // Bench and wallpaper windows are never focused, but must not be throttled,
+    // nor replace the session "Resume Last Session" brings back.
+    if options.bench.is_none() && options.wallpaper.is_none() {
+        app.add_plugins((ThrottlePlugin, SessionPlugin));
+    }
+    #[cfg(feature = "tray")]
+    app.add_plugins(TrayPlugin);
+
+    if let Some(bench) = options.bench {
+        app.add_plugins(BenchPlugin(bench));
+    }
+    if let Some(wallpaper) = options.wallpaper {
+        app.add_plugins(WallpaperPlugin(wallpaper));
+    }
 
-    app.add_plugins(DefaultPlugins)
-        .insert_non_send_resource(stream)
-        .insert_non_send_resource(Sink::try_new(&stream_handle).unwrap())
-        .insert_non_send_resource(MicStream(None))
-        .init_resource::<VisualsConfig>()
-        .init_resource::<SelectedAudioSource>()
-        .init_resource::<VisualizationEnabled>()
-        .init_resource::<ActiveVisualization>()
-        .init_resource::<PlaybackInfo>()
-        .init_resource::<UiVisibility>()
-        .init_state::<AppState>()
-        .add_plugins((
-            EguiPlugin,
-            AudioPlugin,
-            UiPlugin,
-            Viz2DPlugin,
-            Viz3DPlugin,
-            VizOrbPlugin,
-            CameraPlugin,
-            VizDiscPlugin,
-            VizIcoPlugin,
-        ))
-        .run();
+    app.run();
 }
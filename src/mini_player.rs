@@ -0,0 +1,138 @@
+// src/mini_player.rs
+
+use crate::keys::{KeyAction, KeyBindings};
+use crate::ui::UiVisibility;
+use crate::AppState;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowLevel, WindowMode, WindowPosition};
+
+const MINI_SIZE: Vec2 = Vec2::new(360.0, 240.0);
+// Where the mini player opens, from the top-left corner of the screen.
+const MINI_POSITION: IVec2 = IVec2::new(40, 40);
+
+pub struct MiniPlayerPlugin;
+
+impl Plugin for MiniPlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MiniPlayer>()
+            // The menus are drawn in the full window.
+            .add_systems(OnEnter(AppState::MainMenu), leave_mini_player)
+            .add_systems(
+                Update,
+                (
+                    toggle_mini_player,
+                    drag_mini_player.after(toggle_mini_player),
+                )
+                    .run_if(
+                        in_state(AppState::Visualization2D)
+                            .or_else(in_state(AppState::Visualization3D))
+                            .or_else(in_state(AppState::VisualizationOrb))
+                            .or_else(in_state(AppState::VisualizationDisc))
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
+                    ),
+            );
+    }
+}
+
+// Small, borderless, always-on-top window showing only the visualization.
+// Toggled with 'M'; the previous window layout is restored afterwards.
+#[derive(Resource, Default)]
+pub struct MiniPlayer {
+    pub active: bool,
+    restore: Option<SavedWindow>,
+}
+
+struct SavedWindow {
+    size: Vec2,
+    position: WindowPosition,
+    mode: WindowMode,
+    decorations: bool,
+    window_level: WindowLevel,
+    ui_visible: bool,
+}
+
+fn toggle_mini_player(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut mini: ResMut<MiniPlayer>,
+    mut ui_visibility: ResMut<UiVisibility>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !bindings.just_pressed(KeyAction::MiniPlayer, &keyboard) {
+        return;
+    }
+    let Ok(mut window) = q_windows.get_single_mut() else {
+        return;
+    };
+
+    if mini.active {
+        restore_window(&mut mini, &mut window, &mut ui_visibility);
+        return;
+    }
+
+    mini.restore = Some(SavedWindow {
+        size: Vec2::new(window.width(), window.height()),
+        position: window.position,
+        mode: window.mode,
+        decorations: window.decorations,
+        window_level: window.window_level,
+        ui_visible: ui_visibility.visible,
+    });
+    window.mode = WindowMode::Windowed;
+    window.decorations = false;
+    window.window_level = WindowLevel::AlwaysOnTop;
+    window.resolution.set(MINI_SIZE.x, MINI_SIZE.y);
+    window.position = WindowPosition::At(MINI_POSITION);
+    ui_visibility.visible = false;
+    // No "press H" hint either; the mini player shows only the visuals.
+    let hint_duration = ui_visibility.hint_timer.duration();
+    ui_visibility.hint_timer.set_elapsed(hint_duration);
+    mini.active = true;
+}
+
+fn leave_mini_player(
+    mut mini: ResMut<MiniPlayer>,
+    mut ui_visibility: ResMut<UiVisibility>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !mini.active {
+        return;
+    }
+    if let Ok(mut window) = q_windows.get_single_mut() {
+        restore_window(&mut mini, &mut window, &mut ui_visibility);
+    }
+}
+
+// Puts the window back the way it was before the mini player.
+fn restore_window(mini: &mut MiniPlayer, window: &mut Window, ui_visibility: &mut UiVisibility) {
+    if let Some(saved) = mini.restore.take() {
+        window.resolution.set(saved.size.x, saved.size.y);
+        window.position = saved.position;
+        window.mode = saved.mode;
+        window.decorations = saved.decorations;
+        window.window_level = saved.window_level;
+        ui_visibility.visible = saved.ui_visible;
+    }
+    mini.active = false;
+}
+
+// Without a title bar, dragging with the left button moves the window.
+fn drag_mini_player(
+    mini: Res<MiniPlayer>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut ev_motion: EventReader<MouseMotion>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let delta: Vec2 = ev_motion.read().map(|ev| ev.delta).sum();
+    if !mini.active || !mouse.pressed(MouseButton::Left) || delta == Vec2::ZERO {
+        return;
+    }
+    let Ok(mut window) = q_windows.get_single_mut() else {
+        return;
+    };
+    if let WindowPosition::At(position) = window.position {
+        window.position = WindowPosition::At(position + delta.round().as_ivec2());
+    }
+}
@@ -0,0 +1,120 @@
+// src/monitor.rs
+
+use crate::audio::MicStream;
+use crate::config::VisualsConfig;
+use crate::resample::ANALYSIS_SAMPLE_RATE;
+use bevy::prelude::*;
+use rodio::{Sink, Source};
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Most audio the monitor holds before dropping the oldest, so a hiccup
+// doesn't leave it lagging for good.
+pub const MONITOR_MAX_LATENCY_SECS: f32 = 0.1;
+
+// Sink of its own, so the microphone plays alongside the file.
+pub struct MonitorSink(pub Sink);
+
+// Where the microphone callback copies its samples while monitoring is on.
+#[derive(Resource, Clone, Default)]
+pub struct MonitorTap(Arc<Mutex<Option<Sender<Vec<f32>>>>>);
+
+impl MonitorTap {
+    // Called from the microphone callback, which must not wait on the lock.
+    pub fn send(&self, samples: &[f32]) {
+        if let Ok(tap) = self.0.try_lock() {
+            if let Some(sender) = tap.as_ref() {
+                sender.send(samples.to_vec()).ok();
+            }
+        }
+    }
+
+    fn set(&self, sender: Option<Sender<Vec<f32>>>) {
+        if let Ok(mut tap) = self.0.lock() {
+            *tap = sender;
+        }
+    }
+}
+
+// Plays the microphone through the monitor sink while it is open and
+// monitoring is on, at the monitor gain.
+pub fn manage_monitor(
+    config: Res<VisualsConfig>,
+    tap: Res<MonitorTap>,
+    mic_stream: NonSend<MicStream>,
+    sink: NonSend<MonitorSink>,
+    mut active: Local<bool>,
+) {
+    let settings = &config.general.monitor;
+    let wanted = settings.enabled && mic_stream.0.is_some();
+    if wanted != *active {
+        *active = wanted;
+        if wanted {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            tap.set(Some(sender));
+            sink.0.append(MonitorSource::new(receiver));
+            sink.0.play();
+            info!("Microphone monitoring started");
+        } else {
+            tap.set(None);
+            sink.0.stop();
+            info!("Microphone monitoring stopped");
+        }
+    }
+    if sink.0.volume() != settings.gain {
+        sink.0.set_volume(settings.gain);
+    }
+}
+
+// The microphone samples, mono at the analysis rate, with silence while
+// none are waiting. It ends once the tap is closed.
+struct MonitorSource {
+    receiver: Receiver<Vec<f32>>,
+    pending: VecDeque<f32>,
+}
+
+impl MonitorSource {
+    fn new(receiver: Receiver<Vec<f32>>) -> Self {
+        Self {
+            receiver,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Iterator for MonitorSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.pending.is_empty() {
+            loop {
+                match self.receiver.try_recv() {
+                    Ok(samples) => self.pending.extend(samples),
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => return None,
+                }
+            }
+            let max = (MONITOR_MAX_LATENCY_SECS * ANALYSIS_SAMPLE_RATE as f32) as usize;
+            let excess = self.pending.len().saturating_sub(max);
+            self.pending.drain(..excess);
+        }
+        Some(self.pending.pop_front().unwrap_or(0.0))
+    }
+}
+
+impl Source for MonitorSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+    fn channels(&self) -> u16 {
+        1
+    }
+    fn sample_rate(&self) -> u32 {
+        ANALYSIS_SAMPLE_RATE
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,654 @@
+// src/offline.rs
+
+use crate::analysis::{
+    AnalysisFrame, AnalysisRequest, AnalysisWorker, OnsetDetector, FFT_SIZE, LONG_FFT_SIZE,
+};
+use crate::audio::{
+    apply_analysis_frame, display_name, downmix, AudioAnalysis, AudioSource, PlaybackInfo,
+    PlaybackStatus, SelectedAudioSource, ANALYSIS_RATE_HZ,
+};
+use crate::backend::Backend;
+use crate::config::{SpectrumWeighting, VisualizerId, VisualsConfig};
+use crate::recording::SessionRecorder;
+use crate::resample::{Resampler, ANALYSIS_SAMPLE_RATE};
+use crate::toast::Toasts;
+use crate::{AppState, VisualizationEnabled};
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Mutex;
+
+// Tempo range the beat grid is looked for in.
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 180.0;
+// How far, in frames, a beat of the grid moves to meet an onset.
+const BEAT_SNAP_FRAMES: usize = 3;
+const BEATS_PER_BAR: usize = 4;
+// Sections are found on blocks of frames this long, comparing the blocks
+// up to `SECTION_KERNEL_BLOCKS` before and after each boundary.
+const SECTION_BLOCK_SECS: f64 = 0.5;
+const SECTION_KERNEL_BLOCKS: usize = 16;
+// Shortest section.
+const SECTION_MIN_SECS: f64 = 8.0;
+// How much louder a section has to be than the one before to be a drop.
+const DROP_RATIO: f32 = 1.5;
+// A position jumping further than this between two frames was a seek,
+// which doesn't count as passing the boundaries in between.
+const SEEK_SECS: f32 = 1.0;
+// The file is decoded this many frames at a time.
+const DECODE_CHUNK_FRAMES: usize = 4096;
+
+// With `analyse_ahead`, a playing file is analysed whole in the background,
+// and once done its frames are taken from the resulting track at the
+// playback position instead of from the live analysis. The visuals then
+// don't depend on when the sink pulled samples or how late a tick ran.
+pub struct OfflinePlugin;
+
+impl Plugin for OfflinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OfflineAnalysis>()
+            .add_event::<SectionChange>()
+            .add_systems(
+                Update,
+                (
+                    request_analysis_track,
+                    receive_analysis_track,
+                    send_section_changes,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_analysis_track
+                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0)
+                    .run_if(
+                        in_state(AppState::Visualization2D)
+                            .or_else(in_state(AppState::Visualization3D))
+                            .or_else(in_state(AppState::VisualizationOrb))
+                            .or_else(in_state(AppState::VisualizationDisc))
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
+                    ),
+            );
+    }
+}
+
+// What a track is analysed with, taken from the settings of a visualizer.
+#[derive(Clone, PartialEq)]
+pub struct TrackSettings {
+    pub num_bands: usize,
+    pub min_frequency: f32,
+    pub max_frequency: f32,
+    pub band_edges: Option<Vec<f32>>,
+    pub weighting: SpectrumWeighting,
+    pub tones: Vec<f32>,
+}
+
+impl TrackSettings {
+    pub fn new(config: &VisualsConfig, viz_id: VisualizerId) -> Self {
+        Self {
+            num_bands: config.num_bands(viz_id),
+            min_frequency: config.general.min_frequency,
+            max_frequency: config.general.max_frequency,
+            band_edges: config.band_edges(viz_id),
+            weighting: config.general.weighting,
+            tones: config
+                .general
+                .tone_detectors
+                .iter()
+                .map(|detector| detector.frequency)
+                .collect(),
+        }
+    }
+}
+
+// The raw analysis of a whole file, one frame per analysis tick. Frames
+// don't keep their spectrum or waveform, which would take tens of kilobytes
+// each; the waveform is cut from the samples again when a frame is taken.
+pub struct AnalysisTrack {
+    pub source: PathBuf,
+    frames: Vec<TrackFrame>,
+    beats: Vec<GridBeat>,
+    sections: Vec<SectionMark>,
+    // The file downmixed to mono, at the analysis rate.
+    samples: Vec<f32>,
+}
+
+pub struct TrackFrame {
+    // Seconds from the start of the file to the centre of the window.
+    pub time: f32,
+    pub beat: bool,
+    pub frame: AnalysisFrame,
+}
+
+// One beat of the grid, see `beat_grid`.
+#[derive(Clone, Copy)]
+pub struct GridBeat {
+    pub time: f32,
+    // The first beat of a bar.
+    pub downbeat: bool,
+}
+
+// A likely boundary between two sections of the file, like the end of an
+// intro or a drop, see `section_marks`.
+#[derive(Clone, Copy)]
+pub struct SectionMark {
+    pub time: f32,
+    // The section starting here is much louder than the one before.
+    pub drop: bool,
+    // The section starting here is louder than the file on average.
+    pub loud: bool,
+}
+
+// Sent when playback passes a section boundary of the file analysed ahead,
+// for anything that changes looks with the music.
+#[derive(Event, Clone, Copy)]
+pub struct SectionChange(pub SectionMark);
+
+impl AnalysisTrack {
+    pub fn frames(&self) -> &[TrackFrame] {
+        &self.frames
+    }
+
+    // Empty when the file has no steady beat.
+    pub fn beats(&self) -> &[GridBeat] {
+        &self.beats
+    }
+
+    pub fn sections(&self) -> &[SectionMark] {
+        &self.sections
+    }
+
+    // The time of the beat closest to `time`, in seconds.
+    pub fn nearest_beat(&self, time: f32) -> Option<f32> {
+        self.beats
+            .iter()
+            .map(|beat| beat.time)
+            .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+    }
+
+    // The frame whose window is centred closest to `time`, in seconds.
+    pub fn frame_at(&self, time: f32) -> AnalysisFrame {
+        let first = self.frames[0].time;
+        let index = ((time - first) as f64 * ANALYSIS_RATE_HZ).round().max(0.0) as usize;
+        let index = index.min(self.frames.len() - 1);
+        let mut frame = self.frames[index].frame.clone();
+        let start = window_start(index);
+        frame.waveform = self.samples[start..start + FFT_SIZE].to_vec();
+        frame
+    }
+}
+
+// The hop between analysis windows, in samples at the analysis rate.
+fn hop() -> usize {
+    (ANALYSIS_SAMPLE_RATE as f64 / ANALYSIS_RATE_HZ)
+        .round()
+        .max(1.0) as usize
+}
+
+fn window_start(index: usize) -> usize {
+    index * hop()
+}
+
+// Decodes the whole file and runs the live analysis over it, one window per
+// analysis tick, so the frames match what playback would have produced.
+pub fn analyse_file(
+    backend: &Backend,
+    path: &Path,
+    settings: &TrackSettings,
+) -> Result<AnalysisTrack, Box<dyn std::error::Error>> {
+    let decoded = backend.0.decode_file(path)?;
+    let input_rate = decoded.sample_rate;
+    let channels = decoded.channels;
+    let mut source = decoded.source;
+    // Downmixed and resampled a chunk at a time, so only the mono samples at
+    // the analysis rate are held for the whole file.
+    let chunk_len = DECODE_CHUNK_FRAMES * channels.max(1) as usize;
+    let mut chunk = Vec::with_capacity(chunk_len);
+    let mut resampler = Resampler::new(ANALYSIS_SAMPLE_RATE);
+    let mut samples = Vec::new();
+    loop {
+        chunk.clear();
+        chunk.extend(source.by_ref().take(chunk_len));
+        if chunk.is_empty() {
+            break;
+        }
+        for sample in downmix(&chunk, channels) {
+            resampler.push(sample, input_rate, |s| samples.push(s));
+        }
+    }
+    let samples_per_sec = ANALYSIS_SAMPLE_RATE as f64;
+    if samples.len() < FFT_SIZE {
+        return Err("the file is too short to analyse".into());
+    }
+
+    let mut worker = AnalysisWorker::new();
+    let mut onsets = OnsetDetector::default();
+    let mut frames = Vec::with_capacity(samples.len() / hop());
+    let mut consumed_until = 0;
+
+    for start in (0..=samples.len() - FFT_SIZE).step_by(hop()) {
+        worker.push_history(&samples[consumed_until..start]);
+        consumed_until = start;
+
+        let mut frame = worker.analyse(AnalysisRequest {
+            window: samples[start..start + FFT_SIZE].to_vec(),
+            consumed: Vec::new(),
+            sample_rate: ANALYSIS_SAMPLE_RATE,
+            num_bands: settings.num_bands,
+            min_frequency: settings.min_frequency,
+            max_frequency: settings.max_frequency,
+            band_edges: settings.band_edges.clone(),
+            weighting: settings.weighting,
+            tones: settings.tones.clone(),
+            // Offline, so always at full resolution.
+            long_fft_size: LONG_FFT_SIZE,
+        });
+        frame.spectrum = Vec::new();
+        frame.waveform = Vec::new();
+        frames.push(TrackFrame {
+            time: ((start + FFT_SIZE / 2) as f64 / samples_per_sec) as f32,
+            beat: onsets.detect(frame.flux),
+            frame,
+        });
+    }
+
+    let beats = beat_grid(&frames);
+    Ok(AnalysisTrack {
+        source: path.to_path_buf(),
+        sections: section_marks(&frames, &beats),
+        beats,
+        frames,
+        samples,
+    })
+}
+
+// Fits a grid of evenly spaced beats to the onsets: the period is the lag
+// at which the flux best matches itself, the phase the one putting the most
+// flux on the beats. Walking the grid, each beat moves to an onset close to
+// it and the next is counted from there, so slow tempo drift is followed.
+// Downbeats are every `BEATS_PER_BAR` beats, on the loudest of the phases.
+fn beat_grid(frames: &[TrackFrame]) -> Vec<GridBeat> {
+    let min_lag = (ANALYSIS_RATE_HZ * 60.0 / MAX_BPM).round() as usize;
+    let max_lag = (ANALYSIS_RATE_HZ * 60.0 / MIN_BPM).round() as usize;
+    if frames.len() < max_lag * 2 {
+        return Vec::new();
+    }
+    let mean = frames.iter().map(|f| f.frame.flux).sum::<f32>() / frames.len() as f32;
+    let flux: Vec<f32> = frames.iter().map(|f| f.frame.flux - mean).collect();
+
+    let scores: Vec<f32> = (min_lag..=max_lag)
+        .map(|lag| flux.iter().zip(&flux[lag..]).map(|(a, b)| a * b).sum())
+        .collect();
+    let Some(best) = (0..scores.len()).max_by(|&a, &b| scores[a].total_cmp(&scores[b])) else {
+        return Vec::new();
+    };
+    if scores[best] <= 0.0 {
+        return Vec::new();
+    }
+    // The peak of the parabola through the best lag and its neighbours.
+    let mut period = (min_lag + best) as f32;
+    if best > 0 && best + 1 < scores.len() {
+        let (before, peak, after) = (scores[best - 1], scores[best], scores[best + 1]);
+        let curvature = before - 2.0 * peak + after;
+        if curvature < 0.0 {
+            period += 0.5 * (before - after) / curvature;
+        }
+    }
+
+    let on_beats = |phase: usize| -> f32 {
+        (0..)
+            .map(|k| (phase as f32 + k as f32 * period).round() as usize)
+            .take_while(|&index| index < flux.len())
+            .map(|index| flux[index])
+            .sum()
+    };
+    let Some(phase) =
+        (0..period.round() as usize).max_by(|&a, &b| on_beats(a).total_cmp(&on_beats(b)))
+    else {
+        return Vec::new();
+    };
+
+    let mut indices = Vec::new();
+    let mut position = phase as f32;
+    while (position.round() as usize) < frames.len() {
+        let predicted = position.round() as usize;
+        let window = predicted.saturating_sub(BEAT_SNAP_FRAMES)
+            ..=(predicted + BEAT_SNAP_FRAMES).min(frames.len() - 1);
+        let index = window
+            .filter(|&i| frames[i].beat)
+            .min_by_key(|&i| i.abs_diff(predicted))
+            .unwrap_or(predicted);
+        indices.push(index);
+        position = index as f32 + period;
+    }
+
+    let bar_phase = (0..BEATS_PER_BAR)
+        .max_by(|&a, &b| {
+            let loudness = |phase: usize| -> f32 {
+                indices
+                    .iter()
+                    .skip(phase)
+                    .step_by(BEATS_PER_BAR)
+                    .map(|&i| frames[i].frame.volume)
+                    .sum()
+            };
+            loudness(a).total_cmp(&loudness(b))
+        })
+        .unwrap_or(0);
+    indices
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| GridBeat {
+            time: frames[i].time,
+            downbeat: n % BEATS_PER_BAR == bar_phase,
+        })
+        .collect()
+}
+
+// Foote's novelty on the self-similarity of the frames: each block of frames
+// is a unit vector of its mean bands and volume, and a boundary scores high
+// where the blocks before it are alike, the blocks after it are alike, and
+// the two sides aren't. The peaks of that score at least a section apart,
+// and above its average by a deviation, are the boundaries, moved to the
+// nearest beat.
+fn section_marks(frames: &[TrackFrame], beats: &[GridBeat]) -> Vec<SectionMark> {
+    let block_len = (SECTION_BLOCK_SECS * ANALYSIS_RATE_HZ).round() as usize;
+    let kernel = SECTION_KERNEL_BLOCKS;
+    let mut blocks = Vec::new();
+    let mut volumes = Vec::new();
+    for chunk in frames.chunks(block_len) {
+        let mut features = vec![0.0; chunk[0].frame.bins.len() + 1];
+        for frame in chunk {
+            let values = frame.frame.bins.iter().chain([&frame.frame.volume]);
+            for (sum, value) in features.iter_mut().zip(values) {
+                *sum += value;
+            }
+        }
+        volumes.push(features[features.len() - 1] / chunk.len() as f32);
+        let norm = features
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        if norm > 0.0 {
+            features.iter_mut().for_each(|value| *value /= norm);
+        }
+        blocks.push(features);
+    }
+    if blocks.len() < 2 * kernel + 1 {
+        return Vec::new();
+    }
+
+    let similarity =
+        |a: usize, b: usize| -> f32 { blocks[a].iter().zip(&blocks[b]).map(|(x, y)| x * y).sum() };
+    let novelty: Vec<f32> = (kernel..=blocks.len() - kernel)
+        .map(|boundary| {
+            let mut score = 0.0;
+            for a in boundary - kernel..boundary + kernel {
+                for b in boundary - kernel..boundary + kernel {
+                    let same_side = (a < boundary) == (b < boundary);
+                    score += if same_side { -1.0 } else { 1.0 } * (1.0 - similarity(a, b));
+                }
+            }
+            score
+        })
+        .collect();
+    let mean = novelty.iter().sum::<f32>() / novelty.len() as f32;
+    let deviation =
+        (novelty.iter().map(|n| (n - mean).powi(2)).sum::<f32>() / novelty.len() as f32).sqrt();
+    let spacing = (SECTION_MIN_SECS / SECTION_BLOCK_SECS).round() as usize;
+
+    let mut marks: Vec<SectionMark> = Vec::new();
+    let mut boundaries = Vec::new();
+    let mut last_boundary = None;
+    for (i, &score) in novelty.iter().enumerate() {
+        let around = &novelty[i.saturating_sub(spacing)..(i + spacing + 1).min(novelty.len())];
+        let is_peak = around.iter().all(|&other| other <= score);
+        if score <= mean + deviation || !is_peak {
+            continue;
+        }
+        let boundary = i + kernel;
+        if last_boundary.is_some_and(|last| boundary - last < spacing) {
+            continue;
+        }
+        last_boundary = Some(boundary);
+
+        let before = volumes[boundary - kernel..boundary].iter().sum::<f32>();
+        let after = volumes[boundary..boundary + kernel].iter().sum::<f32>();
+        let time = frames[boundary * block_len].time;
+        let time = beats
+            .iter()
+            .map(|beat| beat.time)
+            .filter(|beat| (beat - time).abs() <= SECTION_BLOCK_SECS as f32)
+            .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+            .unwrap_or(time);
+        boundaries.push(boundary);
+        marks.push(SectionMark {
+            time,
+            drop: after >= before * DROP_RATIO,
+            loud: false,
+        });
+    }
+
+    let average = volumes.iter().sum::<f32>() / volumes.len() as f32;
+    let ends = boundaries.iter().skip(1).copied().chain([volumes.len()]);
+    for ((mark, start), end) in marks.iter_mut().zip(&boundaries).zip(ends) {
+        let section = &volumes[*start..end];
+        mark.loud = section.iter().sum::<f32>() / section.len() as f32 >= average;
+    }
+    marks
+}
+
+// The track of the playing file, once analysed, and the analysis under way.
+#[derive(Resource, Default)]
+pub struct OfflineAnalysis {
+    track: Option<AnalysisTrack>,
+    // The file and settings of the track, or of the one being analysed.
+    wanted: Option<(PathBuf, TrackSettings)>,
+    pending: Option<Mutex<Receiver<Result<AnalysisTrack, String>>>>,
+}
+
+impl OfflineAnalysis {
+    pub fn is_analysing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // The track of `path`, if it is ready.
+    pub fn track_for(&self, path: &Path) -> Option<&AnalysisTrack> {
+        self.track.as_ref().filter(|track| track.source == path)
+    }
+
+    // The track of the selected file, if it is ready.
+    pub fn playing_track(&self, source: &AudioSource) -> Option<&AnalysisTrack> {
+        match source {
+            AudioSource::File(path) => self.track_for(path),
+            _ => None,
+        }
+    }
+}
+
+// Starts analysing the playing file when it, or what it is analysed with,
+// changes. A track for other settings is dropped right away, and the live
+// analysis takes over until the new one is ready.
+fn request_analysis_track(
+    config: Res<VisualsConfig>,
+    selected_source: Res<SelectedAudioSource>,
+    app_state: Res<State<AppState>>,
+    backend: Res<Backend>,
+    mut offline: ResMut<OfflineAnalysis>,
+) {
+    if !config.is_changed() && !selected_source.is_changed() && !app_state.is_changed() {
+        return;
+    }
+    // The band count follows the visualizer on screen; the menus keep the
+    // track of the last one.
+    let Some(viz_id) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let wanted = match &selected_source.0 {
+        AudioSource::File(path) if config.general.analyse_ahead => {
+            Some((path.clone(), TrackSettings::new(&config, viz_id)))
+        }
+        _ => None,
+    };
+    if wanted == offline.wanted {
+        return;
+    }
+
+    offline.track = None;
+    offline.pending = wanted.clone().map(|(path, settings)| {
+        info!("Analysing {:?} ahead of playback", path);
+        Mutex::new(spawn_track_analysis(backend.clone(), path, settings))
+    });
+    offline.wanted = wanted;
+}
+
+// The thread finishes even if the track is no longer wanted; its result is
+// then dropped with the receiver.
+fn spawn_track_analysis(
+    backend: Backend,
+    path: PathBuf,
+    settings: TrackSettings,
+) -> Receiver<Result<AnalysisTrack, String>> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("offline-analysis".into())
+        .spawn(move || {
+            let track = analyse_file(&backend, &path, &settings).map_err(|e| e.to_string());
+            sender.send(track).ok();
+        })
+        .expect("Failed to spawn the offline analysis thread");
+    receiver
+}
+
+fn receive_analysis_track(mut offline: ResMut<OfflineAnalysis>, mut toasts: ResMut<Toasts>) {
+    let Some(pending) = &offline.pending else {
+        return;
+    };
+    let received = pending.lock().unwrap().try_recv();
+    match received {
+        Ok(Ok(track)) => {
+            info!(
+                "Analysed {} frames and {} beats of {:?} ahead of playback",
+                track.frames.len(),
+                track.beats.len(),
+                track.source
+            );
+            toasts.info(format!("Analysed {} ahead", display_name(&track.source)));
+            offline.track = Some(track);
+            offline.pending = None;
+        }
+        Ok(Err(e)) => {
+            error!("❌ Failed to analyse the file ahead: {}", e);
+            toasts.error(format!("Could not analyse ahead: {}", e));
+            offline.pending = None;
+        }
+        Err(TryRecvError::Empty) => {}
+        Err(TryRecvError::Disconnected) => offline.pending = None,
+    }
+}
+
+// Feeds the frame at the playback position once per analysis tick, in place
+// of the live analysis (see `audio_analysis_system`).
+fn play_analysis_track(
+    offline: Res<OfflineAnalysis>,
+    selected_source: Res<SelectedAudioSource>,
+    playback_info: Res<PlaybackInfo>,
+    fixed_time: Res<Time<Fixed>>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+    mut recorder: ResMut<SessionRecorder>,
+    config: Res<VisualsConfig>,
+) {
+    let AudioSource::File(path) = &selected_source.0 else {
+        return;
+    };
+    let Some(track) = offline.track_for(path) else {
+        return;
+    };
+    if playback_info.status != PlaybackStatus::Playing {
+        return;
+    }
+    let dt = fixed_time.timestep().as_secs_f32();
+    // The frames are centred on what is heard already; only the offset of
+    // the sync settings applies.
+    let time = playback_info.position.as_secs_f32() - config.general.latency_offset_ms / 1000.0;
+
+    let frame = track.frame_at(time);
+    recorder.capture(&frame);
+    apply_analysis_frame(
+        &mut audio_analysis,
+        &config.general.smoothing,
+        &config.general.crossovers,
+        frame,
+        dt,
+    );
+}
+
+fn send_section_changes(
+    offline: Res<OfflineAnalysis>,
+    selected_source: Res<SelectedAudioSource>,
+    playback_info: Res<PlaybackInfo>,
+    mut last_position: Local<Option<f32>>,
+    mut section_changes: EventWriter<SectionChange>,
+) {
+    let Some(track) = offline.playing_track(&selected_source.0) else {
+        *last_position = None;
+        return;
+    };
+    let position = playback_info.position.as_secs_f32();
+    let Some(previous) = last_position
+        .replace(position)
+        .filter(|&previous| previous <= position && position - previous < SEEK_SECS)
+    else {
+        return;
+    };
+    for mark in track.sections() {
+        if mark.time > previous && mark.time <= position {
+            info!("Section change at {:.1}s (drop: {})", mark.time, mark.drop);
+            section_changes.send(SectionChange(*mark));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::NullBackend;
+    use std::f32::consts::TAU;
+    use std::sync::Arc;
+
+    // Two seconds of a full-scale 1 kHz sine, in stereo at 48 kHz.
+    fn sine_backend() -> Backend {
+        let samples = (0..96_000)
+            .map(|i| (TAU * 1000.0 * i as f32 / 48_000.0).sin())
+            .flat_map(|sample| [sample, sample])
+            .collect();
+        Backend(Arc::new(NullBackend::new(samples, 48_000, 2)))
+    }
+
+    #[test]
+    fn analyses_a_decoded_tone() {
+        let settings = TrackSettings {
+            num_bands: 32,
+            min_frequency: 20.0,
+            max_frequency: 20_000.0,
+            band_edges: None,
+            weighting: SpectrumWeighting::Off,
+            tones: vec![1000.0, 5000.0],
+        };
+        let track = analyse_file(&sine_backend(), Path::new("tone.wav"), &settings).unwrap();
+
+        // Downmixed and resampled to two seconds at the analysis rate.
+        let expected = 2 * ANALYSIS_SAMPLE_RATE as usize;
+        assert!(track.samples.len().abs_diff(expected) <= 1);
+        assert_eq!(
+            track.frames.len(),
+            (track.samples.len() - FFT_SIZE) / hop() + 1
+        );
+
+        let frame = &track.frames[track.frames.len() / 2].frame;
+        assert_eq!(frame.bins.len(), 32);
+        assert!((frame.tones[0] - 1.0).abs() < 0.05, "{}", frame.tones[0]);
+        assert!(frame.tones[1] < 0.01, "{}", frame.tones[1]);
+    }
+}
@@ -0,0 +1,340 @@
+// src/output_window.rs
+
+use crate::camera::{render_target_image, MainCamera2D, MainCamera3D};
+use crate::config::VisualsConfig;
+use crate::floor::FLOOR_LAYER;
+use crate::grading::ColorGrade;
+use crate::taskbar::APP_ID;
+use bevy::core_pipeline::bloom::BloomSettings;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::camera::{Exposure, RenderTarget};
+use bevy::render::render_resource::Extent3d;
+use bevy::render::view::RenderLayers;
+use bevy::window::{MonitorSelection, RawHandleWrapper, WindowMode, WindowPosition, WindowRef};
+
+// Only the camera showing the scaled frame in the output window sees this layer.
+const BLIT_LAYER: u8 = 31;
+
+pub struct OutputWindowPlugin;
+
+impl Plugin for OutputWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OutputWindow>().add_systems(
+            Update,
+            (
+                manage_output_window,
+                fullscreen_output_window,
+                fit_output_image,
+                attach_output_cameras,
+                mirror_camera_settings,
+            )
+                .chain(),
+        );
+    }
+}
+
+// The borderless window showing only the visuals on another screen, while
+// the primary window keeps the controls. egui only draws on the primary one.
+// The visuals are rendered into `image` at the window size times the render
+// scale, then stretched over the window, so a 4K projector can be fed a
+// lower (or higher) resolution than its own.
+#[derive(Resource, Default)]
+struct OutputWindow {
+    window: Option<Entity>,
+    monitor: usize,
+    image: Handle<Image>,
+}
+
+// Renders the view of its parent main camera into the output image.
+#[derive(Component)]
+struct OutputCamera;
+
+// The camera and sprite drawing the output image into the output window.
+#[derive(Component)]
+struct OutputBlit;
+
+// A main camera that already has its output camera.
+#[derive(Component)]
+struct Mirrored;
+
+// Opens, closes or moves the window to follow the output settings.
+fn manage_output_window(
+    mut commands: Commands,
+    mut config: ResMut<VisualsConfig>,
+    mut output: ResMut<OutputWindow>,
+    mut images: ResMut<Assets<Image>>,
+    q_windows: Query<(), With<Window>>,
+    q_output_cameras: Query<Entity, Or<(With<OutputCamera>, With<OutputBlit>)>>,
+    q_mirrored: Query<Entity, With<Mirrored>>,
+) {
+    let settings = &config.general.output;
+    let wanted = settings.enabled.then_some(settings.monitor);
+
+    if let Some(window) = output.window {
+        let closed_by_user = q_windows.get(window).is_err();
+        if !closed_by_user && wanted == Some(output.monitor) {
+            return;
+        }
+        if closed_by_user {
+            config.general.output.enabled = false;
+        } else {
+            commands.entity(window).despawn();
+        }
+        for camera in &q_output_cameras {
+            commands.entity(camera).despawn_recursive();
+        }
+        for camera in &q_mirrored {
+            commands.entity(camera).remove::<Mirrored>();
+        }
+        output.window = None;
+        images.remove(&output.image);
+        info!("Output window closed");
+        return;
+    }
+
+    let Some(monitor) = wanted else {
+        return;
+    };
+    // Opened windowed on the chosen screen; `fullscreen_output_window` then
+    // fills that screen, as a fullscreen window is only placed on the primary one.
+    let window = commands
+        .spawn(Window {
+            title: "Rust Visualizer Output".into(),
+            name: Some(APP_ID.into()),
+            position: WindowPosition::Centered(MonitorSelection::Index(monitor)),
+            decorations: false,
+            ..default()
+        })
+        .id();
+    // Sized by `fit_output_image` once the window exists.
+    let image = images.add(render_target_image(
+        "output_window_image",
+        Extent3d {
+            width: 1,
+            height: 1,
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Entity(window)),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(BLIT_LAYER),
+        OutputBlit,
+    ));
+    commands.spawn((
+        SpriteBundle {
+            texture: image.clone(),
+            ..default()
+        },
+        RenderLayers::layer(BLIT_LAYER),
+        OutputBlit,
+    ));
+
+    output.window = Some(window);
+    output.monitor = monitor;
+    output.image = image;
+    info!("Output window opened on monitor {}", monitor);
+}
+
+fn fullscreen_output_window(
+    output: Res<OutputWindow>,
+    mut q_created: Query<(Entity, &mut Window), Added<RawHandleWrapper>>,
+) {
+    for (entity, mut window) in &mut q_created {
+        if output.window == Some(entity) {
+            window.mode = WindowMode::BorderlessFullscreen;
+        }
+    }
+}
+
+// Keeps the output image at the window size times the render scale, and the
+// sprite covering the whole window.
+fn fit_output_image(
+    config: Res<VisualsConfig>,
+    output: Res<OutputWindow>,
+    mut images: ResMut<Assets<Image>>,
+    q_windows: Query<&Window>,
+    mut q_sprites: Query<&mut Sprite, With<OutputBlit>>,
+) {
+    let Some(window) = output.window.and_then(|window| q_windows.get(window).ok()) else {
+        return;
+    };
+    let scale = config.general.output.render_scale;
+    let size = Extent3d {
+        width: ((window.physical_width() as f32 * scale).round() as u32).max(1),
+        height: ((window.physical_height() as f32 * scale).round() as u32).max(1),
+        ..default()
+    };
+    // Only touch the asset when the size changes, as that re-uploads it.
+    if images
+        .get(&output.image)
+        .is_some_and(|image| image.texture_descriptor.size != size)
+    {
+        if let Some(image) = images.get_mut(&output.image) {
+            image.resize(size);
+        }
+    }
+
+    let window_size = Vec2::new(window.width(), window.height());
+    for mut sprite in &mut q_sprites {
+        if sprite.custom_size != Some(window_size) {
+            sprite.custom_size = Some(window_size);
+        }
+    }
+}
+
+// Gives each visualizer camera a child camera drawing into the output image,
+// so both windows always show the same view.
+fn attach_output_cameras(
+    mut commands: Commands,
+    output: Res<OutputWindow>,
+    q_cameras: Query<
+        (Entity, Has<MainCamera3D>),
+        (
+            Or<(With<MainCamera3D>, With<MainCamera2D>)>,
+            Without<Mirrored>,
+        ),
+    >,
+) {
+    if output.window.is_none() {
+        return;
+    }
+    for (entity, is_3d) in &q_cameras {
+        let camera = Camera {
+            hdr: is_3d,
+            target: RenderTarget::Image(output.image.clone()),
+            ..default()
+        };
+        let child = if is_3d {
+            commands
+                .spawn((
+                    Camera3dBundle {
+                        camera,
+                        ..default()
+                    },
+                    RenderLayers::from_layers(&[0, FLOOR_LAYER]),
+                    OutputCamera,
+                ))
+                .id()
+        } else {
+            commands
+                .spawn((
+                    Camera2dBundle {
+                        camera,
+                        // The parent camera already sits in front of the scene.
+                        transform: Transform::IDENTITY,
+                        ..default()
+                    },
+                    OutputCamera,
+                ))
+                .id()
+        };
+        commands.entity(entity).insert(Mirrored).add_child(child);
+    }
+}
+
+// Follows the 2D zoom, the 3D projection, exposure and tonemapping, the bloom,
+// the fog and the color grade of the main cameras.
+fn mirror_camera_settings(
+    mut commands: Commands,
+    config: Res<VisualsConfig>,
+    q_main: Query<
+        (
+            Option<&OrthographicProjection>,
+            Option<Ref<Projection>>,
+            Option<Ref<Exposure>>,
+            Option<Ref<Tonemapping>>,
+            Option<Ref<BloomSettings>>,
+            Option<Ref<FogSettings>>,
+            Option<Ref<ColorGrade>>,
+        ),
+        Without<OutputCamera>,
+    >,
+    mut q_output: Query<
+        (
+            Entity,
+            &Parent,
+            Ref<OutputCamera>,
+            Option<&mut OrthographicProjection>,
+            Has<Projection>,
+            Has<Exposure>,
+            Has<Tonemapping>,
+            Has<BloomSettings>,
+            Has<FogSettings>,
+            Has<ColorGrade>,
+        ),
+        With<OutputCamera>,
+    >,
+) {
+    for (
+        entity,
+        parent,
+        output,
+        projection,
+        has_projection,
+        has_exposure,
+        has_tonemapping,
+        has_bloom,
+        has_fog,
+        has_grade,
+    ) in &mut q_output
+    {
+        let Ok((
+            main_projection,
+            main_3d_projection,
+            main_exposure,
+            main_tonemapping,
+            main_bloom,
+            main_fog,
+            main_grade,
+        )) = q_main.get(parent.get())
+        else {
+            continue;
+        };
+        // A 2D camera sees one world unit per pixel of its target, so a
+        // larger image has to zoom in to frame the same area.
+        if let (Some(mut projection), Some(main_projection)) = (projection, main_projection) {
+            let scale = main_projection.scale / config.general.output.render_scale;
+            if projection.scale != scale {
+                projection.scale = scale;
+            }
+        }
+        // A new output camera starts with the defaults of its bundle, which
+        // the main camera may have changed since.
+        let fresh = output.is_added();
+        let commands = &mut commands;
+        mirror_component(commands, entity, main_3d_projection, has_projection, fresh);
+        mirror_component(commands, entity, main_exposure, has_exposure, fresh);
+        mirror_component(commands, entity, main_tonemapping, has_tonemapping, fresh);
+        mirror_component(commands, entity, main_bloom, has_bloom, fresh);
+        mirror_component(commands, entity, main_fog, has_fog, fresh);
+        mirror_component(commands, entity, main_grade, has_grade, fresh);
+    }
+}
+
+// Copies a component of the main camera when it changed, or is missing or
+// stale on the output camera, and removes it when the main camera has none.
+fn mirror_component<C: Component + Clone>(
+    commands: &mut Commands,
+    entity: Entity,
+    main: Option<Ref<C>>,
+    has: bool,
+    fresh: bool,
+) {
+    match main {
+        Some(component) if component.is_changed() || !has || fresh => {
+            commands.entity(entity).insert((*component).clone());
+        }
+        None if has => {
+            commands.entity(entity).remove::<C>();
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,163 @@
+// src/palette.rs
+
+use crate::audio::{
+    AudioAnalysis, EnergyHistory, SelectedAudioSource, TickWatcher, ANALYSIS_RATE_HZ,
+};
+use crate::config::{PaletteCycle, VisualizerId, VisualsConfig};
+use crate::lfo::Tempo;
+use crate::offline::{OfflineAnalysis, SectionChange};
+use crate::AppState;
+use bevy::prelude::*;
+
+// Sections are compared over the last few seconds of the energy history
+// against the rest of it.
+const SECTION_RECENT_SECS: f32 = 2.0;
+// How much louder or quieter the recent part has to be.
+const SECTION_RATIO: f32 = 1.5;
+// Shortest time between two section changes, so a long build-up doesn't
+// flip through the whole library.
+const SECTION_MIN_SECS: f32 = 8.0;
+const BEATS_PER_BAR: f32 = 4.0;
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, cycle_palettes);
+    }
+}
+
+// Four colors from the darkest, used for backgrounds and resting states, to
+// the brightest, used for peaks. See `VizSettings::apply_palette`.
+pub struct Palette {
+    pub name: &'static str,
+    pub colors: [Color; 4],
+}
+
+pub const PALETTES: [Palette; 6] = [
+    Palette {
+        name: "Synthwave",
+        colors: [
+            Color::rgb(0.08, 0.02, 0.15),
+            Color::rgb(0.45, 0.1, 0.75),
+            Color::rgb(1.0, 0.2, 0.6),
+            Color::rgb(0.2, 0.9, 1.0),
+        ],
+    },
+    Palette {
+        name: "Fire",
+        colors: [
+            Color::rgb(0.1, 0.02, 0.0),
+            Color::rgb(0.7, 0.1, 0.0),
+            Color::rgb(1.0, 0.45, 0.0),
+            Color::rgb(1.0, 0.9, 0.3),
+        ],
+    },
+    Palette {
+        name: "Ocean",
+        colors: [
+            Color::rgb(0.0, 0.04, 0.1),
+            Color::rgb(0.0, 0.25, 0.5),
+            Color::rgb(0.0, 0.6, 0.75),
+            Color::rgb(0.6, 1.0, 0.95),
+        ],
+    },
+    Palette {
+        name: "Forest",
+        colors: [
+            Color::rgb(0.02, 0.06, 0.03),
+            Color::rgb(0.1, 0.35, 0.15),
+            Color::rgb(0.4, 0.7, 0.2),
+            Color::rgb(0.9, 1.0, 0.6),
+        ],
+    },
+    Palette {
+        name: "Sunset",
+        colors: [
+            Color::rgb(0.1, 0.03, 0.1),
+            Color::rgb(0.6, 0.15, 0.35),
+            Color::rgb(1.0, 0.45, 0.3),
+            Color::rgb(1.0, 0.8, 0.5),
+        ],
+    },
+    Palette {
+        name: "Mono",
+        colors: [
+            Color::rgb(0.03, 0.03, 0.03),
+            Color::rgb(0.3, 0.3, 0.3),
+            Color::rgb(0.65, 0.65, 0.65),
+            Color::rgb(1.0, 1.0, 1.0),
+        ],
+    },
+];
+
+// Makes `index` the current palette and recolors the visualizer with it.
+pub fn apply_palette(config: &mut VisualsConfig, viz_id: VisualizerId, index: usize) {
+    let index = index % PALETTES.len();
+    let (general, settings) = config.general_and_settings_mut(viz_id);
+    general.palette.current = index;
+    settings.apply_palette(&PALETTES[index]);
+}
+
+// Steps to the next palette of the library every few bars, or on each
+// section change: those found ahead for a file analysed ahead, else a shift
+// in the live loudness.
+#[allow(clippy::too_many_arguments)]
+fn cycle_palettes(
+    time: Res<Time>,
+    tempo: Res<Tempo>,
+    app_state: Res<State<AppState>>,
+    audio_analysis: Res<AudioAnalysis>,
+    offline: Res<OfflineAnalysis>,
+    selected_source: Res<SelectedAudioSource>,
+    mut section_changes: EventReader<SectionChange>,
+    mut config: ResMut<VisualsConfig>,
+    mut since_change: Local<f32>,
+    mut ticks: Local<TickWatcher>,
+) {
+    let section_changed = section_changes.read().count() > 0;
+    let Some(viz_id) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    *since_change += time.delta_seconds();
+
+    let palette = &config.general.palette;
+    let due = match palette.cycle {
+        PaletteCycle::Off => false,
+        PaletteCycle::Bars => {
+            let bar = BEATS_PER_BAR * 60.0 / tempo.bpm;
+            *since_change >= palette.cycle_bars.max(1) as f32 * bar
+        }
+        PaletteCycle::Sections if offline.playing_track(&selected_source.0).is_some() => {
+            section_changed
+        }
+        PaletteCycle::Sections => {
+            ticks.ticked(&audio_analysis)
+                && *since_change >= SECTION_MIN_SECS
+                && is_section_change(&audio_analysis.history)
+        }
+    };
+    if !due {
+        return;
+    }
+    *since_change = 0.0;
+    let next = palette.current + 1;
+    apply_palette(&mut config, viz_id, next);
+}
+
+// Whether the last few seconds are much louder or quieter than the ones
+// before, like a drop after a breakdown.
+fn is_section_change(history: &EnergyHistory) -> bool {
+    if history.iter().len() < EnergyHistory::CAPACITY {
+        return false;
+    }
+    let recent = (SECTION_RECENT_SECS * ANALYSIS_RATE_HZ as f32) as usize;
+    let split = EnergyHistory::CAPACITY - recent;
+    let before = history.iter().take(split).map(|s| s.volume).sum::<f32>() / split as f32;
+    let after = history.iter().skip(split).map(|s| s.volume).sum::<f32>() / recent as f32;
+    if before <= f32::EPSILON {
+        return after > f32::EPSILON;
+    }
+    let ratio = after / before;
+    !(1.0 / SECTION_RATIO..=SECTION_RATIO).contains(&ratio)
+}
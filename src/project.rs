@@ -0,0 +1,254 @@
+// src/project.rs
+
+use crate::audio::{display_name, SelectedAudioSource};
+use crate::config::{
+    timeline_path, AnalysisInterpolation, ColorGradingSettings, CrossoverSettings, GeneralSettings,
+    LfoSettings, MacroSettings, PaletteSettings, SceneSwitchSettings, SmoothingSettings,
+    SpectrumWeighting, StrobeSettings, ToneDetector, VisualizerId, VisualsConfig, VizSettings,
+    LFO_COUNT, MACRO_COUNT,
+};
+use crate::library::Playlist;
+use crate::timeline::Timeline;
+use crate::toast::Toasts;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_EXTENSION: &str = "vizproj";
+// Bumped whenever `Project` changes shape in a way serde defaults can't cover.
+const PROJECT_VERSION: u32 = 2;
+
+pub struct ProjectPlugin;
+
+impl Plugin for ProjectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectFiles>()
+            .add_systems(Update, (save_project, open_project));
+    }
+}
+
+// Everything a show needs, in one file: reopening it brings back the look
+// of every visualizer and the playlist with its automation.
+#[derive(Serialize, Deserialize)]
+struct Project {
+    version: u32,
+    // The shared settings that make up the look of the show.
+    show: ShowSettings,
+    // The settings of every visualizer, camera bookmarks included.
+    visualizers: Vec<VizSettings>,
+    // The visualizer on screen when the project was saved.
+    visualizer: Option<VisualizerId>,
+    playlist: Vec<PathBuf>,
+    current: Option<usize>,
+    // The timeline of each playlist track that has one.
+    timelines: Vec<(PathBuf, Timeline)>,
+}
+
+// The parts of the general settings that belong to a show. The rest stays
+// with the machine: the Hue bridge and its key, the remote control servers,
+// the Discord ID, the library folder, the theme, the language and the windows,
+// so a shared project neither leaks credentials nor opens servers.
+#[derive(Serialize, Deserialize)]
+struct ShowSettings {
+    bass_sensitivity: f32,
+    min_frequency: f32,
+    max_frequency: f32,
+    weighting: SpectrumWeighting,
+    crossovers: CrossoverSettings,
+    smoothing: SmoothingSettings,
+    interpolation: AnalysisInterpolation,
+    scene_switch: SceneSwitchSettings,
+    lfos: [LfoSettings; LFO_COUNT],
+    // The macro knobs and their targets.
+    macros: [MacroSettings; MACRO_COUNT],
+    tone_detectors: Vec<ToneDetector>,
+    strobe: StrobeSettings,
+    palette: PaletteSettings,
+    grading: ColorGradingSettings,
+}
+
+impl ShowSettings {
+    fn from_general(general: &GeneralSettings) -> Self {
+        Self {
+            bass_sensitivity: general.bass_sensitivity,
+            min_frequency: general.min_frequency,
+            max_frequency: general.max_frequency,
+            weighting: general.weighting,
+            crossovers: general.crossovers,
+            smoothing: general.smoothing.clone(),
+            interpolation: general.interpolation,
+            scene_switch: general.scene_switch.clone(),
+            lfos: general.lfos.clone(),
+            macros: general.macros.clone(),
+            tone_detectors: general.tone_detectors.clone(),
+            strobe: general.strobe.clone(),
+            palette: general.palette.clone(),
+            grading: general.grading.clone(),
+        }
+    }
+
+    fn apply(self, general: &mut GeneralSettings) {
+        general.bass_sensitivity = self.bass_sensitivity;
+        general.min_frequency = self.min_frequency;
+        general.max_frequency = self.max_frequency;
+        general.weighting = self.weighting;
+        general.crossovers = self.crossovers;
+        general.smoothing = self.smoothing;
+        general.interpolation = self.interpolation;
+        general.scene_switch = self.scene_switch;
+        general.lfos = self.lfos;
+        general.macros = self.macros;
+        general.tone_detectors = self.tone_detectors;
+        general.strobe = self.strobe;
+        general.palette = self.palette;
+        general.grading = self.grading;
+    }
+}
+
+// Set by the UI; the next frame asks where to save, or what to open.
+#[derive(Resource, Default)]
+pub struct ProjectFiles {
+    pub save_requested: bool,
+    pub open_requested: bool,
+}
+
+fn save_project(
+    mut files: ResMut<ProjectFiles>,
+    config: Res<VisualsConfig>,
+    playlist: Res<Playlist>,
+    timeline: Res<Timeline>,
+    app_state: Res<State<AppState>>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !files.save_requested {
+        return;
+    }
+    files.save_requested = false;
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("project", &[PROJECT_EXTENSION])
+        .set_file_name(format!("show.{}", PROJECT_EXTENSION))
+        .save_file()
+    else {
+        return;
+    };
+
+    let timelines = playlist
+        .tracks
+        .iter()
+        .filter_map(|track| {
+            // The loaded track's timeline may not be on disk yet.
+            if timeline.track() == Some(track) {
+                return (!timeline.is_empty()).then(|| (track.clone(), timeline.clone()));
+            }
+            let contents = std::fs::read_to_string(timeline_path(track)).ok()?;
+            let saved = ron::from_str::<Timeline>(&contents).ok()?;
+            Some((track.clone(), saved))
+        })
+        .collect();
+    let project = Project {
+        version: PROJECT_VERSION,
+        show: ShowSettings::from_general(&config.general),
+        visualizers: VisualizerId::ALL
+            .into_iter()
+            .map(|id| config.settings(id).clone())
+            .collect(),
+        visualizer: VisualizerId::from_state(app_state.get()),
+        playlist: playlist.tracks.clone(),
+        current: playlist.current,
+        timelines,
+    };
+
+    match write_project(&path, &project) {
+        Ok(()) => {
+            info!("Saved project to {:?}", path);
+            toasts.success(format!("Project saved to {}", display_name(&path)));
+        }
+        Err(e) => {
+            error!("❌ Failed to save project to {:?}: {}", path, e);
+            toasts.error(format!("Could not save {}: {}", display_name(&path), e));
+        }
+    }
+}
+
+fn write_project(path: &Path, project: &Project) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = ron::ser::to_string_pretty(project, ron::ser::PrettyConfig::default())?;
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+fn read_project(path: &Path) -> Result<Project, Box<dyn std::error::Error>> {
+    let project: Project = ron::from_str(&std::fs::read_to_string(path)?)?;
+    if project.version != PROJECT_VERSION {
+        return Err(format!("unsupported project version {}", project.version).into());
+    }
+    Ok(project)
+}
+
+// Replaces the show settings, the visualizer settings, the playlist and the timelines of its tracks with
+// the project's, and starts the playlist where it was.
+#[allow(clippy::too_many_arguments)]
+fn open_project(
+    mut files: ResMut<ProjectFiles>,
+    mut config: ResMut<VisualsConfig>,
+    mut playlist: ResMut<Playlist>,
+    mut timeline: ResMut<Timeline>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !files.open_requested {
+        return;
+    }
+    files.open_requested = false;
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("project", &[PROJECT_EXTENSION])
+        .pick_file()
+    else {
+        return;
+    };
+    let project = match read_project(&path) {
+        Ok(project) => project,
+        Err(e) => {
+            error!("❌ Failed to open project {:?}: {}", path, e);
+            toasts.error(format!("Could not open {}: {}", display_name(&path), e));
+            return;
+        }
+    };
+
+    project.show.apply(&mut config.general);
+    for settings in project.visualizers {
+        config.set_settings(settings.id(), settings);
+    }
+    // Written where the timeline of each track is looked for, so they also
+    // apply when a track is loaded later on its own.
+    for (track, saved) in project.timelines {
+        if timeline.track() == Some(&track) {
+            timeline.replace_contents(saved.clone());
+        }
+        let path = timeline_path(&track);
+        let written = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(&path, contents).map_err(|e| e.to_string())
+            });
+        if let Err(e) = written {
+            error!("❌ Failed to write timeline {:?}: {}", path, e);
+        }
+    }
+    if let Some(id) = project.visualizer {
+        next_app_state.set(id.state());
+        active_viz.0 = id.state();
+    }
+    if !project.playlist.is_empty() {
+        let index = project.current.unwrap_or(0);
+        playlist.play(project.playlist, index, &mut selected_source);
+    }
+
+    info!("Opened project {:?}", path);
+    toasts.success(format!("Opened {}", display_name(&path)));
+}
@@ -0,0 +1,197 @@
+// src/recording.rs
+
+use crate::analysis::AnalysisFrame;
+use crate::audio::{
+    apply_analysis_frame, display_name, AudioAnalysis, AudioSource, SelectedAudioSource,
+    ANALYSIS_RATE_HZ,
+};
+use crate::config::VisualsConfig;
+use crate::toast::Toasts;
+use crate::{AppState, VisualizationEnabled};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+pub const RECORDING_EXTENSION: &str = "vizrec";
+// Bumped whenever `AnalysisFrame` changes shape.
+const RECORDING_VERSION: u32 = 4;
+
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionRecorder>()
+            .add_systems(Update, (save_finished_recording, load_replay))
+            .add_systems(
+                FixedUpdate,
+                replay_analysis
+                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0)
+                    .run_if(
+                        in_state(AppState::Visualization2D)
+                            .or_else(in_state(AppState::Visualization3D))
+                            .or_else(in_state(AppState::VisualizationOrb))
+                            .or_else(in_state(AppState::VisualizationDisc))
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
+                    ),
+            );
+    }
+}
+
+// On-disk format: the raw analysis frames, one per analysis tick. Smoothing
+// is applied again on replay, so the current settings still take effect.
+#[derive(Serialize, Deserialize)]
+struct Recording {
+    version: u32,
+    rate_hz: f64,
+    frames: Vec<AnalysisFrame>,
+}
+
+// Captures analysis frames while `active` is set. Turning it off asks where
+// to save what was captured.
+#[derive(Resource, Default)]
+pub struct SessionRecorder {
+    pub active: bool,
+    frames: Vec<AnalysisFrame>,
+}
+
+impl SessionRecorder {
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub(crate) fn capture(&mut self, frame: &AnalysisFrame) {
+        if self.active {
+            self.frames.push(frame.clone());
+        }
+    }
+}
+
+// A recording being played back instead of live audio. Loops at the end.
+#[derive(Resource)]
+struct ReplaySession {
+    frames: Vec<AnalysisFrame>,
+    next: usize,
+}
+
+fn save_recording(
+    path: &Path,
+    frames: Vec<AnalysisFrame>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let recording = Recording {
+        version: RECORDING_VERSION,
+        rate_hz: ANALYSIS_RATE_HZ,
+        frames,
+    };
+    bincode::serialize_into(BufWriter::new(File::create(path)?), &recording)?;
+    Ok(())
+}
+
+fn load_recording(path: &Path) -> Result<Vec<AnalysisFrame>, Box<dyn std::error::Error>> {
+    let recording: Recording = bincode::deserialize_from(BufReader::new(File::open(path)?))?;
+    if recording.version != RECORDING_VERSION {
+        return Err(format!("unsupported recording version {}", recording.version).into());
+    }
+    if recording.rate_hz != ANALYSIS_RATE_HZ {
+        warn!(
+            "Recording was made at {} Hz, replaying at {} Hz",
+            recording.rate_hz, ANALYSIS_RATE_HZ
+        );
+    }
+    if recording.frames.is_empty() {
+        return Err("the recording is empty".into());
+    }
+    Ok(recording.frames)
+}
+
+fn save_finished_recording(mut recorder: ResMut<SessionRecorder>, mut toasts: ResMut<Toasts>) {
+    if recorder.active || recorder.frames.is_empty() {
+        return;
+    }
+    let frames = std::mem::take(&mut recorder.frames);
+
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("recording", &[RECORDING_EXTENSION])
+        .set_file_name(format!("session.{}", RECORDING_EXTENSION))
+        .save_file()
+    else {
+        toasts.info("Recording discarded");
+        return;
+    };
+
+    let count = frames.len();
+    match save_recording(&path, frames) {
+        Ok(()) => {
+            info!("Saved {} analysis frames to {:?}", count, path);
+            toasts.success(format!("Recording saved to {}", display_name(&path)));
+        }
+        Err(e) => {
+            error!("❌ Failed to save recording to {:?}: {}", path, e);
+            toasts.error(format!("Could not save {}: {}", display_name(&path), e));
+        }
+    }
+}
+
+fn load_replay(
+    mut commands: Commands,
+    selected_source: Res<SelectedAudioSource>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !selected_source.is_changed() {
+        return;
+    }
+    commands.remove_resource::<ReplaySession>();
+
+    let AudioSource::Replay(path) = &selected_source.0 else {
+        return;
+    };
+    match load_recording(path) {
+        Ok(frames) => {
+            info!("Replaying {} analysis frames from {:?}", frames.len(), path);
+            toasts.success(format!("Replaying {}", display_name(path)));
+            commands.insert_resource(ReplaySession { frames, next: 0 });
+        }
+        Err(e) => {
+            error!("❌ Failed to load recording {:?}: {}", path, e);
+            toasts.error(format!("Could not load {}: {}", display_name(path), e));
+        }
+    }
+}
+
+// Feeds one recorded frame per analysis tick, like the live analysis would.
+fn replay_analysis(
+    session: Option<ResMut<ReplaySession>>,
+    fixed_time: Res<Time<Fixed>>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+    config: Res<VisualsConfig>,
+) {
+    let Some(mut session) = session else { return };
+    let dt = fixed_time.timestep().as_secs_f32();
+
+    let frame = session.frames[session.next].clone();
+    session.next = (session.next + 1) % session.frames.len();
+    apply_analysis_frame(
+        &mut audio_analysis,
+        &config.general.smoothing,
+        &config.general.crossovers,
+        frame,
+        dt,
+    );
+}
+
+// True for files that should be replayed rather than decoded as audio.
+fn is_recording(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(RECORDING_EXTENSION))
+}
+
+// The source to use for `path`: a replay for recordings, playback otherwise.
+pub fn source_for(path: PathBuf) -> AudioSource {
+    if is_recording(&path) {
+        AudioSource::Replay(path)
+    } else {
+        AudioSource::File(path)
+    }
+}
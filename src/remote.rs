@@ -0,0 +1,517 @@
+// src/remote.rs
+
+use crate::audio::AudioAnalysis;
+use crate::config::{GeneralSettings, VisualizerId, VisualsConfig, VizSettings};
+use crate::macros::set_macro;
+use crate::toast::Toasts;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+
+// Analysis updates sent to streaming clients per second.
+const STREAM_RATE_HZ: f32 = 30.0;
+// How long the server thread sleeps when there is nothing to do.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+// Sections of the general settings clients can neither read nor change: the
+// servers themselves and the credentials of the integrations.
+const PRIVATE_SETTINGS: [&str; 3] = ["remote", "hue", "discord"];
+
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        let (command_tx, command_rx) = std::sync::mpsc::channel::<(ClientId, RemoteCommand)>();
+
+        app.insert_resource(RemoteServer {
+            commands: command_tx,
+            running: None,
+            requested: None,
+            origins: Arc::default(),
+            stream_timer: Timer::from_seconds(1.0 / STREAM_RATE_HZ, TimerMode::Repeating),
+        })
+        .insert_non_send_resource(RemoteCommandReceiver(command_rx))
+        .add_systems(
+            Update,
+            (
+                manage_remote_server,
+                handle_remote_commands.after(manage_remote_server),
+                stream_analysis.run_if(
+                    in_state(AppState::Visualization2D)
+                        .or_else(in_state(AppState::Visualization3D))
+                        .or_else(in_state(AppState::VisualizationOrb))
+                        .or_else(in_state(AppState::VisualizationDisc))
+                        .or_else(in_state(AppState::VisualizationIco))
+                        .or_else(in_state(AppState::VisualizationShadertoy)),
+                ),
+            ),
+        );
+    }
+}
+
+type ClientId = u64;
+
+// JSON messages accepted from clients, tagged by their `type` field, e.g.
+// `{"type": "set_visualizer", "visualizer": "orb"}`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RemoteCommand {
+    // Switches to the visualizer with this key (see `VisualizerId::key`).
+    SetVisualizer { visualizer: String },
+    // Merges `values` into a config section: "general" or a visualizer key.
+    SetConfig { section: String, values: Value },
+    // Replies with the current visualizer and every config section.
+    GetConfig,
+    // Turns macro knob `index` (from 0) to `value`, in 0..1.
+    SetMacro { index: usize, value: f32 },
+    // Starts or stops the analysis stream for this client. Handled on the
+    // server thread.
+    Subscribe { analysis: bool },
+}
+
+#[derive(Serialize)]
+struct AnalysisMessage<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    volume: f32,
+    bass: f32,
+    sub_bass: f32,
+    punch: f32,
+    mid: f32,
+    treble: f32,
+    flux: f32,
+    volume_rise: f32,
+    bass_rise: f32,
+    mid_rise: f32,
+    treble_rise: f32,
+    momentary_lufs: f32,
+    short_term_lufs: f32,
+    zero_crossing_rate: f32,
+    percussiveness: f32,
+    bins: &'a [f32],
+}
+
+// From the app to the server thread.
+enum Outgoing {
+    Analysis(String),
+    Reply(ClientId, String),
+}
+
+struct RemoteCommandReceiver(Receiver<(ClientId, RemoteCommand)>);
+
+struct ServerHandle {
+    stop: Arc<AtomicBool>,
+    outgoing: Sender<Outgoing>,
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Resource)]
+struct RemoteServer {
+    commands: Sender<(ClientId, RemoteCommand)>,
+    running: Option<ServerHandle>,
+    // The address last asked for, so a failed bind isn't retried every frame.
+    requested: Option<(&'static str, u16)>,
+    // Web pages allowed to connect besides this computer's, read by the
+    // server thread at each handshake so edits apply without a restart.
+    origins: Arc<RwLock<Vec<String>>>,
+    stream_timer: Timer,
+}
+
+impl RemoteServer {
+    fn send(&self, message: Outgoing) {
+        if let Some(server) = &self.running {
+            server.outgoing.send(message).ok();
+        }
+    }
+}
+
+// Starts, stops or moves the server to follow the remote settings.
+fn manage_remote_server(
+    config: Res<VisualsConfig>,
+    mut server: ResMut<RemoteServer>,
+    mut toasts: ResMut<Toasts>,
+) {
+    let remote = &config.general.remote;
+    // Only this computer can connect unless the user opts in, e.g. for a phone.
+    let host = if remote.allow_network {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let origins = if remote.allow_network {
+        remote.allowed_origins.as_slice()
+    } else {
+        &[]
+    };
+    if let Ok(mut allowed) = server.origins.write() {
+        if *allowed != origins {
+            *allowed = origins.to_vec();
+        }
+    }
+
+    let wanted = remote.enabled.then_some((host, remote.port));
+    if server.requested == wanted {
+        return;
+    }
+    server.requested = wanted;
+    server.running = None;
+
+    let Some((host, port)) = wanted else {
+        info!("Remote control server stopped");
+        return;
+    };
+    match start_server(host, port, server.commands.clone(), server.origins.clone()) {
+        Ok(handle) => {
+            info!("Remote control server listening on {}:{}", host, port);
+            toasts.info(format!("Remote control on port {}", port));
+            server.running = Some(handle);
+        }
+        Err(e) => {
+            error!("❌ Failed to start the remote control server: {}", e);
+            toasts.error(format!(
+                "Remote control unavailable on port {}: {}",
+                port, e
+            ));
+        }
+    }
+}
+
+fn handle_remote_commands(
+    receiver: NonSend<RemoteCommandReceiver>,
+    server: Res<RemoteServer>,
+    mut config: ResMut<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+) {
+    for (client, command) in receiver.0.try_iter() {
+        let result = match command {
+            RemoteCommand::SetVisualizer { visualizer } => {
+                match VisualizerId::from_key(&visualizer) {
+                    Some(id) => {
+                        next_app_state.set(id.state());
+                        active_viz.0 = id.state();
+                        Ok(())
+                    }
+                    None => Err(format!("unknown visualizer '{}'", visualizer)),
+                }
+            }
+            RemoteCommand::SetConfig { section, values } => {
+                set_config_section(&mut config, &section, values)
+            }
+            RemoteCommand::GetConfig => {
+                let reply = config_message(&config, app_state.get());
+                server.send(Outgoing::Reply(client, reply.to_string()));
+                Ok(())
+            }
+            RemoteCommand::SetMacro { index, value } => set_macro(&mut config, index, value),
+            RemoteCommand::Subscribe { .. } => Ok(()),
+        };
+
+        if let Err(message) = result {
+            warn!("Rejected remote command: {}", message);
+            let reply = json!({ "type": "error", "message": message });
+            server.send(Outgoing::Reply(client, reply.to_string()));
+        }
+    }
+}
+
+fn stream_analysis(
+    time: Res<Time>,
+    mut server: ResMut<RemoteServer>,
+    analysis: Res<AudioAnalysis>,
+) {
+    if server.running.is_none() || !server.stream_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let message = AnalysisMessage {
+        kind: "analysis",
+        volume: analysis.volume,
+        bass: analysis.bass,
+        sub_bass: analysis.sub_bass,
+        punch: analysis.punch,
+        mid: analysis.mid,
+        treble: analysis.treble,
+        flux: analysis.flux,
+        volume_rise: analysis.volume_rise,
+        bass_rise: analysis.bass_rise,
+        mid_rise: analysis.mid_rise,
+        treble_rise: analysis.treble_rise,
+        momentary_lufs: analysis.momentary_lufs,
+        short_term_lufs: analysis.short_term_lufs,
+        zero_crossing_rate: analysis.zero_crossing_rate,
+        percussiveness: analysis.percussiveness,
+        bins: &analysis.frequency_bins,
+    };
+    if let Ok(text) = serde_json::to_string(&message) {
+        server.send(Outgoing::Analysis(text));
+    }
+}
+
+fn config_message(config: &VisualsConfig, state: &AppState) -> Value {
+    let mut sections = serde_json::Map::new();
+    let mut general = serde_json::to_value(&config.general).unwrap_or_default();
+    if let Some(general) = general.as_object_mut() {
+        for key in PRIVATE_SETTINGS {
+            general.remove(key);
+        }
+    }
+    sections.insert("general".to_string(), general);
+    for id in VisualizerId::ALL {
+        // Drop the enum tag so sections read the same way `set_config` takes them.
+        let settings = serde_json::to_value(config.settings(id)).unwrap_or_default();
+        let untagged = match settings {
+            Value::Object(tagged) => tagged.into_iter().next().map(|(_, inner)| inner),
+            _ => None,
+        };
+        sections.insert(id.key().to_string(), untagged.unwrap_or_default());
+    }
+
+    json!({
+        "type": "config",
+        "visualizer": VisualizerId::from_state(state).map(VisualizerId::key),
+        "sections": sections,
+    })
+}
+
+// Applies a partial update to one section by round-tripping it through JSON,
+// so any field can be set without a command per setting.
+fn set_config_section(
+    config: &mut VisualsConfig,
+    section: &str,
+    values: Value,
+) -> Result<(), String> {
+    if section == "general" {
+        if let Some(private) = PRIVATE_SETTINGS
+            .into_iter()
+            .find(|key| values.get(key).is_some())
+        {
+            return Err(format!("'{}' can't be changed remotely", private));
+        }
+        let mut current = serde_json::to_value(&config.general).map_err(|e| e.to_string())?;
+        merge(&mut current, values);
+        config.general =
+            serde_json::from_value::<GeneralSettings>(current).map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let id =
+        VisualizerId::from_key(section).ok_or_else(|| format!("unknown section '{}'", section))?;
+    let mut current = serde_json::to_value(config.settings(id)).map_err(|e| e.to_string())?;
+    // Settings are stored as `{"Variant": {..}}`; merge into the inner object.
+    if let Some(inner) = current
+        .as_object_mut()
+        .and_then(|tagged| tagged.values_mut().next())
+    {
+        merge(inner, values);
+    }
+    let settings = serde_json::from_value::<VizSettings>(current).map_err(|e| e.to_string())?;
+    config.set_settings(id, settings);
+    Ok(())
+}
+
+// Recursively overwrites the fields of `target` present in `patch`.
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+// --- Server Thread ---
+
+struct Client {
+    id: ClientId,
+    socket: WebSocket<TcpStream>,
+    streaming: bool,
+}
+
+impl Client {
+    // False once the connection is gone.
+    fn send(&mut self, text: String) -> bool {
+        match self.socket.send(Message::Text(text)) {
+            Ok(()) => true,
+            // Queued; flushed by a later write.
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => true,
+            Err(_) => false,
+        }
+    }
+
+    // Reads every pending message. False once the connection is gone.
+    fn receive(&mut self, commands: &Sender<(ClientId, RemoteCommand)>) -> bool {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                    Ok(RemoteCommand::Subscribe { analysis }) => self.streaming = analysis,
+                    Ok(command) => {
+                        commands.send((self.id, command)).ok();
+                    }
+                    Err(e) => {
+                        let reply = json!({ "type": "error", "message": e.to_string() });
+                        if !self.send(reply.to_string()) {
+                            return false;
+                        }
+                    }
+                },
+                Ok(Message::Close(_)) => return false,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+fn start_server(
+    host: &str,
+    port: u16,
+    commands: Sender<(ClientId, RemoteCommand)>,
+    origins: Arc<RwLock<Vec<String>>>,
+) -> std::io::Result<ServerHandle> {
+    let listener = TcpListener::bind((host, port))?;
+    listener.set_nonblocking(true)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let (outgoing_tx, outgoing_rx) = std::sync::mpsc::channel();
+
+    let thread_stop = stop.clone();
+    std::thread::Builder::new()
+        .name("remote-control".into())
+        .spawn(move || run_server(listener, thread_stop, commands, outgoing_rx, origins))?;
+
+    Ok(ServerHandle {
+        stop,
+        outgoing: outgoing_tx,
+    })
+}
+
+fn run_server(
+    listener: TcpListener,
+    stop: Arc<AtomicBool>,
+    commands: Sender<(ClientId, RemoteCommand)>,
+    outgoing: Receiver<Outgoing>,
+    origins: Arc<RwLock<Vec<String>>>,
+) {
+    let mut clients: Vec<Client> = Vec::new();
+    let mut next_id: ClientId = 0;
+
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, address)) => match accept_client(stream, &origins) {
+                Ok(socket) => {
+                    info!("Remote client {} connected", address);
+                    clients.push(Client {
+                        id: next_id,
+                        socket,
+                        streaming: false,
+                    });
+                    next_id += 1;
+                }
+                Err(e) => warn!("Remote client {} failed to connect: {}", address, e),
+            },
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => warn!("Remote control server failed to accept: {}", e),
+        }
+
+        clients.retain_mut(|client| client.receive(&commands));
+
+        loop {
+            match outgoing.try_recv() {
+                Ok(Outgoing::Analysis(text)) => {
+                    clients.retain_mut(|client| !client.streaming || client.send(text.clone()))
+                }
+                Ok(Outgoing::Reply(id, text)) => {
+                    clients.retain_mut(|client| client.id != id || client.send(text.clone()))
+                }
+                Err(TryRecvError::Empty) => break,
+                // The app dropped the server.
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn accept_client(
+    stream: TcpStream,
+    origins: &RwLock<Vec<String>>,
+) -> Result<WebSocket<TcpStream>, Box<dyn std::error::Error>> {
+    // The handshake is done blocking, with a timeout so a silent client can't stall the server.
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let allowed = origins
+        .read()
+        .map(|origins| origins.clone())
+        .unwrap_or_default();
+    let socket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        check_origin(request, response, &allowed)
+    })
+    .map_err(|e| e.to_string())?;
+    socket.get_ref().set_read_timeout(None)?;
+    socket.get_ref().set_nonblocking(true)?;
+    Ok(socket)
+}
+
+// Browsers let any web page open a WebSocket to this computer, but tell the
+// page they come from in `Origin`. Native clients send none. Pages are
+// refused unless served from this computer or allowed in the settings.
+fn check_origin(
+    request: &Request,
+    response: Response,
+    allowed: &[String],
+) -> Result<Response, ErrorResponse> {
+    let Some(origin) = request.headers().get("Origin") else {
+        return Ok(response);
+    };
+    let accepted = origin.to_str().is_ok_and(|origin| {
+        is_local_origin(origin)
+            || allowed.iter().any(|allowed| {
+                allowed
+                    .trim()
+                    .trim_end_matches('/')
+                    .eq_ignore_ascii_case(origin)
+            })
+    });
+    if accepted {
+        return Ok(response);
+    }
+    let mut refusal = ErrorResponse::new(Some("connections from web pages are refused".into()));
+    *refusal.status_mut() = StatusCode::FORBIDDEN;
+    Err(refusal)
+}
+
+// Pages served from this computer, e.g. a dashboard of the user's own.
+fn is_local_origin(origin: &str) -> bool {
+    let Some((_, host)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !port.ends_with(']') => name,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
@@ -0,0 +1,56 @@
+// src/resample.rs
+
+// Rate the analysis always runs at, whatever the rate of the source, so the
+// FFT resolution and the band limits are the same for every file and mic.
+pub const ANALYSIS_SAMPLE_RATE: u32 = 44_100;
+
+// Streaming linear-interpolation resampler for mono samples. It is cheap
+// enough for the audio callbacks; the little aliasing it lets through when
+// downsampling stays above the range the bands cover.
+pub struct Resampler {
+    output_rate: u32,
+    // The last input sample, and where the next output falls after it, in
+    // input samples.
+    previous: f32,
+    position: f64,
+}
+
+impl Resampler {
+    pub fn new(output_rate: u32) -> Self {
+        Self {
+            output_rate,
+            previous: 0.0,
+            position: 0.0,
+        }
+    }
+
+    // Takes the next input sample at `input_rate` and hands the output
+    // samples falling before it to `emit`: none, one or several.
+    pub fn push(&mut self, sample: f32, input_rate: u32, mut emit: impl FnMut(f32)) {
+        if input_rate == self.output_rate {
+            self.previous = sample;
+            self.position = 0.0;
+            emit(sample);
+            return;
+        }
+        let step = input_rate as f64 / self.output_rate.max(1) as f64;
+        while self.position < 1.0 {
+            let t = self.position as f32;
+            emit(self.previous + (sample - self.previous) * t);
+            self.position += step;
+        }
+        self.position -= 1.0;
+        self.previous = sample;
+    }
+
+    // Resamples a whole buffer.
+    pub fn process(&mut self, samples: &[f32], input_rate: u32) -> Vec<f32> {
+        let mut output = Vec::with_capacity(
+            samples.len() * self.output_rate as usize / input_rate.max(1) as usize + 1,
+        );
+        for &sample in samples {
+            self.push(sample, input_rate, |s| output.push(s));
+        }
+        output
+    }
+}
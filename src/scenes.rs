@@ -0,0 +1,76 @@
+// src/scenes.rs
+
+use crate::config::{load_preset, SceneTarget, VisualizerId, VisualsConfig};
+use crate::offline::SectionChange;
+use crate::toast::Toasts;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+
+// With `scene_switch` on, switches the visualizer, or loads a preset, as
+// playback enters a section of a file analysed ahead, by whether the
+// section is quiet or loud.
+pub struct ScenePlugin;
+
+impl Plugin for ScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, switch_scenes);
+    }
+}
+
+fn switch_scenes(
+    mut section_changes: EventReader<SectionChange>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    mut config: ResMut<VisualsConfig>,
+    mut toasts: ResMut<Toasts>,
+) {
+    // Only the last boundary passed this frame matters.
+    let Some(SectionChange(mark)) = section_changes.read().last().copied() else {
+        return;
+    };
+    let scenes = &config.general.scene_switch;
+    if !scenes.enabled {
+        return;
+    }
+    let Some(current) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let target: SceneTarget = if mark.loud {
+        scenes.loud.clone()
+    } else {
+        scenes.quiet.clone()
+    };
+
+    let (id, preset) = if !target.preset.is_empty() {
+        match load_preset(&target.preset) {
+            Ok(settings) => (settings.id(), Some(settings)),
+            Err(e) => {
+                error!("❌ Failed to load the section preset: {}", e);
+                toasts.error(format!("Could not load {}", e));
+                return;
+            }
+        }
+    } else if let Some(id) = target.visualizer {
+        (id, None)
+    } else {
+        return;
+    };
+    // Checked first, so a hidden visualizer's settings aren't changed either.
+    if !config.general.visualizers.is_shown(id) {
+        info!(
+            "Section change: {:?} is hidden, staying on {:?}",
+            id, current
+        );
+        return;
+    }
+    if let Some(settings) = preset {
+        config.set_settings(id, settings);
+        info!("Section change: loaded preset '{}'", target.preset);
+    }
+    if id != current {
+        info!("Section change: switching to {:?}", id);
+        next_app_state.set(id.state());
+        active_viz.0 = id.state();
+    }
+}
@@ -0,0 +1,215 @@
+// src/session.rs
+
+use crate::audio::{display_name, AudioSource, SelectedAudioSource, SelectedMic};
+use crate::camera::{apply_bookmark, capture_bookmark, MainCamera3D, PanOrbitController};
+use crate::config::{session_path, CameraBookmark, VisualizerId};
+use crate::mini_player::MiniPlayer;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Changes are written to disk at most this often.
+const SAVE_INTERVAL_SECS: f32 = 1.0;
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_session())
+            .init_resource::<ResumeSession>()
+            .add_systems(
+                Update,
+                (
+                    resume_session,
+                    restore_camera,
+                    track_session.after(restore_camera),
+                    save_session.after(track_session),
+                ),
+            );
+    }
+}
+
+// The audio source, visualizer, 3D camera and window mode of the last
+// visualization, kept up to date while one runs so the main menu can bring
+// all of it back after a restart.
+#[derive(Resource, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastSession {
+    pub source: Option<SessionSource>,
+    pub visualizer: Option<VisualizerId>,
+    // The orbit camera, from the last 3D scene.
+    pub camera: Option<CameraBookmark>,
+    pub fullscreen: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum SessionSource {
+    File(PathBuf),
+    Replay(PathBuf),
+    // The device by backend and name, as picked in the selection screen.
+    Microphone {
+        host: Option<String>,
+        device: Option<String>,
+    },
+}
+
+impl LastSession {
+    // The file or microphone, as shown under the resume entry of the menu.
+    pub fn source_name(&self) -> Option<String> {
+        match self.source.as_ref()? {
+            SessionSource::File(path) | SessionSource::Replay(path) => Some(display_name(path)),
+            SessionSource::Microphone { device, .. } => {
+                Some(format!("🎤 {}", device.as_deref().unwrap_or_default()))
+            }
+        }
+    }
+}
+
+// Set by the main menu to resume the last session. The camera waits for the
+// scene to spawn its own.
+#[derive(Resource, Default)]
+pub struct ResumeSession {
+    pub requested: bool,
+    camera: Option<CameraBookmark>,
+}
+
+fn load_session() -> LastSession {
+    let path = session_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return LastSession::default();
+    };
+    ron::from_str(&contents).unwrap_or_else(|e| {
+        warn!("Ignoring invalid session {:?}: {}", path, e);
+        LastSession::default()
+    })
+}
+
+fn resume_session(
+    mut resume: ResMut<ResumeSession>,
+    session: Res<LastSession>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+    mut selected_mic: ResMut<SelectedMic>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !resume.requested {
+        return;
+    }
+    resume.requested = false;
+    let Some(visualizer) = session.visualizer else {
+        return;
+    };
+    info!("Resuming the last session with {:?}", visualizer);
+
+    match &session.source {
+        Some(SessionSource::File(path)) => selected_source.0 = AudioSource::File(path.clone()),
+        Some(SessionSource::Replay(path)) => {
+            selected_source.0 = AudioSource::Replay(path.clone());
+        }
+        Some(SessionSource::Microphone { host, device }) => {
+            selected_mic.host = host.clone();
+            selected_mic.device = device.clone();
+            selected_source.0 = AudioSource::Microphone;
+        }
+        None => {}
+    }
+
+    if let Ok(mut window) = q_windows.get_single_mut() {
+        window.mode = if session.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+    }
+
+    if matches!(visualizer, VisualizerId::Cubes3D | VisualizerId::Orb) {
+        resume.camera = session.camera;
+    }
+    active_viz.0 = visualizer.state();
+    next_app_state.set(visualizer.state());
+}
+
+fn restore_camera(
+    mut resume: ResMut<ResumeSession>,
+    mut q_camera: Query<(&mut PanOrbitController, &mut Transform), With<MainCamera3D>>,
+) {
+    if resume.camera.is_none() {
+        return;
+    }
+    let Ok((mut pan_orbit, mut transform)) = q_camera.get_single_mut() else {
+        return;
+    };
+    if let Some(pose) = resume.camera.take() {
+        apply_bookmark(&pose, &mut pan_orbit, &mut transform);
+    }
+}
+
+// Only visualizations are recorded; the menus leave the session as it was.
+#[allow(clippy::too_many_arguments)]
+fn track_session(
+    app_state: Res<State<AppState>>,
+    selected_source: Res<SelectedAudioSource>,
+    selected_mic: Res<SelectedMic>,
+    mini: Res<MiniPlayer>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&PanOrbitController, &Transform), With<MainCamera3D>>,
+    mut session: ResMut<LastSession>,
+) {
+    let Some(visualizer) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+
+    let mut current = session.clone();
+    current.visualizer = Some(visualizer);
+    current.source = match &selected_source.0 {
+        AudioSource::File(path) => Some(SessionSource::File(path.clone())),
+        AudioSource::Replay(path) => Some(SessionSource::Replay(path.clone())),
+        AudioSource::Microphone => Some(SessionSource::Microphone {
+            host: selected_mic.host.clone(),
+            device: selected_mic.device.clone(),
+        }),
+        AudioSource::None => None,
+    };
+    if let Ok((pan_orbit, transform)) = q_camera.get_single() {
+        current.camera = Some(capture_bookmark(pan_orbit, transform));
+    }
+    // The mini player is a temporary layout, not the one to come back to.
+    if let (false, Ok(window)) = (mini.active, q_windows.get_single()) {
+        current.fullscreen = window.mode != WindowMode::Windowed;
+    }
+    session.set_if_neq(current);
+}
+
+fn save_session(
+    time: Res<Time<Real>>,
+    session: Res<LastSession>,
+    mut timer: Local<Option<Timer>>,
+    mut pending: Local<bool>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(SAVE_INTERVAL_SECS, TimerMode::Repeating));
+    // Loading marks it changed too, which only rewrites the same file.
+    if session.is_changed() {
+        *pending = true;
+    }
+    if !timer.tick(time.delta()).just_finished() || !*pending {
+        return;
+    }
+    *pending = false;
+
+    let path = session_path();
+    let result = ron::ser::to_string_pretty(&*session, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, contents).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        error!("❌ Failed to save the session {:?}: {}", path, e);
+    }
+}
@@ -0,0 +1,86 @@
+// src/settings_window.rs
+
+use crate::config::{PanelPlacement, VisualsConfig};
+use crate::taskbar::APP_ID;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::view::RenderLayers;
+use bevy::window::WindowRef;
+
+// Nothing is drawn on this layer: the camera only clears the window for egui.
+const SETTINGS_LAYER: u8 = 29;
+
+pub struct SettingsWindowPlugin;
+
+impl Plugin for SettingsWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsWindow>()
+            .add_systems(Update, manage_settings_window);
+    }
+}
+
+// The window the visualizer settings move to with `PanelPlacement::Window`,
+// e.g. to keep them on a second screen while the visuals fill the first.
+// bevy_egui gives it a context of its own, which `main_ui_layout` draws in.
+#[derive(Resource, Default)]
+pub struct SettingsWindow {
+    pub window: Option<Entity>,
+    camera: Option<Entity>,
+}
+
+// Opens or closes the window to follow the placement of the panel. Closing
+// it docks the panel again.
+fn manage_settings_window(
+    mut commands: Commands,
+    mut config: ResMut<VisualsConfig>,
+    mut settings_window: ResMut<SettingsWindow>,
+    q_windows: Query<(), With<Window>>,
+) {
+    let wanted = config.general.settings_panel == PanelPlacement::Window;
+
+    if let Some(window) = settings_window.window {
+        let closed_by_user = q_windows.get(window).is_err();
+        if !closed_by_user && wanted {
+            return;
+        }
+        if closed_by_user {
+            config.general.settings_panel = PanelPlacement::Docked;
+        } else {
+            commands.entity(window).despawn();
+        }
+        if let Some(camera) = settings_window.camera.take() {
+            commands.entity(camera).despawn();
+        }
+        settings_window.window = None;
+        info!("Settings window closed");
+        return;
+    }
+
+    if !wanted {
+        return;
+    }
+    let window = commands
+        .spawn(Window {
+            title: "Rust Visualizer Settings".into(),
+            name: Some(APP_ID.into()),
+            resolution: (320.0, 720.0).into(),
+            ..default()
+        })
+        .id();
+    let camera = commands
+        .spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    target: RenderTarget::Window(WindowRef::Entity(window)),
+                    ..default()
+                },
+                ..default()
+            },
+            RenderLayers::layer(SETTINGS_LAYER),
+        ))
+        .id();
+
+    settings_window.window = Some(window);
+    settings_window.camera = Some(camera);
+    info!("Settings window opened");
+}
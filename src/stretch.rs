@@ -0,0 +1,240 @@
+// src/stretch.rs
+
+use bevy::prelude::Resource;
+use rodio::Source;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Length of the grains the input is cut into.
+const GRAIN_SECS: f32 = 0.04;
+// Input is dropped in chunks of this many grains, rather than every step.
+const TRIM_GRAINS: usize = 4;
+// The similarity search only looks at every n-th frame of the overlap.
+const SEARCH_STRIDE: usize = 4;
+
+// Tempo of the time-stretched file, shared between the app and the audio
+// thread. Stored as the bits of an f32.
+#[derive(Resource, Clone)]
+pub struct StretchControl(Arc<AtomicU32>);
+
+impl Default for StretchControl {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU32::new(1.0f32.to_bits())))
+    }
+}
+
+impl StretchControl {
+    pub fn speed(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        self.0.store(speed.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// Changes the tempo of a source without changing its pitch, with WSOLA
+// (waveform similarity overlap-add): Hann-windowed grains are taken from the
+// input every `hop * speed` frames and overlapped every `hop` frames, each
+// one shifted by up to half a hop to where it best continues the previous
+// one. At 1x the input is passed through unchanged.
+pub struct TimeStretch<S> {
+    source: S,
+    control: StretchControl,
+    channels: usize,
+    sample_rate: u32,
+    // Grain length and output hop, in frames; the hop is half a grain.
+    grain: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    // Interleaved input not yet done with, and positions in it, in frames:
+    // where the next grain should nominally start, and where the previous
+    // grain would have naturally continued.
+    input: Vec<f32>,
+    position: f64,
+    natural: usize,
+    // Input frames still to come once the source has ended.
+    input_end: Option<usize>,
+    // Second half of the previous windowed grain, waiting for the next one.
+    overlap: Vec<f32>,
+    output: VecDeque<f32>,
+}
+
+impl<S> TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    pub fn new(source: S, control: StretchControl) -> Self {
+        let channels = source.channels().max(1) as usize;
+        let sample_rate = source.sample_rate();
+        let grain = ((sample_rate as f32 * GRAIN_SECS) as usize / 2 * 2).max(64);
+        let hop = grain / 2;
+        // Periodic Hann: two of them half a grain apart sum to one.
+        let window = (0..grain)
+            .map(|i| 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / grain as f32).cos()))
+            .collect();
+
+        Self {
+            source,
+            control,
+            channels,
+            sample_rate,
+            grain,
+            hop,
+            tolerance: hop / 2,
+            window,
+            input: Vec::new(),
+            position: 0.0,
+            natural: 0,
+            input_end: None,
+            overlap: vec![0.0; hop * channels],
+            output: VecDeque::new(),
+        }
+    }
+
+    fn frames(&self) -> usize {
+        self.input.len() / self.channels
+    }
+
+    // Reads the source until `frames` input frames are buffered, padding
+    // with silence past its end.
+    fn fill(&mut self, frames: usize) {
+        while self.frames() < frames {
+            match self.input_end {
+                Some(_) => self.input.push(0.0),
+                None => match self.source.next() {
+                    Some(sample) => self.input.push(sample),
+                    None => {
+                        self.input_end = Some(self.frames());
+                        // Complete a partial last frame.
+                        while self.input.len() % self.channels != 0 {
+                            self.input.push(0.0);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    // Sum of the channels of an input frame, for the similarity search.
+    fn mono(&self, frame: usize) -> f32 {
+        let start = frame * self.channels;
+        self.input[start..start + self.channels].iter().sum()
+    }
+
+    // The start within `tolerance` of the nominal one whose overlap best
+    // matches the natural continuation of the previous grain, by
+    // normalized cross-correlation.
+    fn best_start(&self, nominal: usize) -> usize {
+        let first = nominal.saturating_sub(self.tolerance);
+        let last = nominal + self.tolerance;
+        let mut best = (nominal, f32::MIN);
+        for start in first..=last {
+            let mut dot = 0.0;
+            let mut energy = 0.0;
+            for i in (0..self.hop).step_by(SEARCH_STRIDE) {
+                let candidate = self.mono(start + i);
+                dot += candidate * self.mono(self.natural + i);
+                energy += candidate * candidate;
+            }
+            let score = dot / (energy + 1e-9).sqrt();
+            if score > best.1 {
+                best = (start, score);
+            }
+        }
+        best.0
+    }
+
+    // Overlap-adds the next grain and queues one hop of output. Returns
+    // false once the input has run out.
+    fn step(&mut self) -> bool {
+        let speed = self.control.speed().clamp(0.1, 4.0);
+        if let Some(end) = self.input_end {
+            if self.position as usize >= end && self.natural >= end {
+                return false;
+            }
+        }
+
+        let start = if (speed - 1.0).abs() < f32::EPSILON {
+            // Straight continuation: the grains add back up to the input.
+            self.natural
+        } else {
+            let nominal = self.position as usize;
+            self.fill(nominal + self.tolerance + self.grain);
+            self.fill(self.natural + self.grain);
+            self.best_start(nominal)
+        };
+        self.fill(start + self.grain);
+
+        let channels = self.channels;
+        for i in 0..self.hop {
+            for c in 0..channels {
+                let sample = self.input[(start + i) * channels + c] * self.window[i];
+                self.output
+                    .push_back(self.overlap[i * channels + c] + sample);
+                self.overlap[i * channels + c] =
+                    self.input[(start + self.hop + i) * channels + c] * self.window[self.hop + i];
+            }
+        }
+
+        self.natural = start + self.hop;
+        self.position = if (speed - 1.0).abs() < f32::EPSILON {
+            self.natural as f64
+        } else {
+            self.position + self.hop as f64 * speed as f64
+        };
+        self.trim();
+        true
+    }
+
+    // Drops the input no later grain can start in.
+    fn trim(&mut self) {
+        let keep_from = (self.position as usize)
+            .saturating_sub(self.tolerance)
+            .min(self.natural);
+        if keep_from < TRIM_GRAINS * self.grain {
+            return;
+        }
+        self.input.drain(..keep_from * self.channels);
+        self.position -= keep_from as f64;
+        self.natural -= keep_from;
+        if let Some(end) = self.input_end.as_mut() {
+            *end = end.saturating_sub(keep_from);
+        }
+    }
+}
+
+impl<S> Iterator for TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.output.is_empty() && !self.step() {
+            return None;
+        }
+        self.output.pop_front()
+    }
+}
+
+impl<S> Source for TimeStretch<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
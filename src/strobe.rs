@@ -0,0 +1,93 @@
+// src/strobe.rs
+
+use crate::analysis::OnsetDetector;
+use crate::audio::{AudioAnalysis, TickWatcher};
+use crate::config::{StrobeSettings, VisualizerId, VisualsConfig};
+use crate::AppState;
+use bevy::prelude::*;
+
+pub struct StrobePlugin;
+
+impl Plugin for StrobePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Strobe>()
+            .add_systems(Update, (manage_strobe_overlay, update_strobe).chain());
+    }
+}
+
+#[derive(Resource, Default)]
+struct Strobe {
+    onsets: OnsetDetector,
+    ticks: TickWatcher,
+    // Current opacity of the flash, relative to the configured intensity.
+    flash: f32,
+    // Seconds since the last flash started.
+    since_flash: f32,
+}
+
+// Full-screen node drawn over the visualizer, below the egui panels.
+#[derive(Component)]
+struct StrobeOverlay;
+
+// Spawns the overlay while the strobe is enabled in a visualizer.
+fn manage_strobe_overlay(
+    mut commands: Commands,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    q_overlay: Query<Entity, With<StrobeOverlay>>,
+) {
+    let wanted =
+        config.general.strobe.enabled && VisualizerId::from_state(app_state.get()).is_some();
+    match (wanted, q_overlay.get_single()) {
+        (true, Err(_)) => {
+            commands.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::NONE.into(),
+                    z_index: ZIndex::Global(i32::MAX),
+                    ..default()
+                },
+                StrobeOverlay,
+            ));
+        }
+        (false, Ok(entity)) => {
+            commands.entity(entity).despawn_recursive();
+        }
+        _ => {}
+    }
+}
+
+// Starts a flash on each beat, unless one started less than `1 / max_rate`
+// seconds ago, and fades it out over the decay time.
+fn update_strobe(
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    audio_analysis: Res<AudioAnalysis>,
+    mut strobe: ResMut<Strobe>,
+    mut q_overlay: Query<&mut BackgroundColor, With<StrobeOverlay>>,
+) {
+    let Ok(mut background) = q_overlay.get_single_mut() else {
+        strobe.flash = 0.0;
+        return;
+    };
+    let settings = &config.general.strobe;
+    let dt = time.delta_seconds();
+    strobe.since_flash += dt;
+
+    let onset = strobe.ticks.ticked(&audio_analysis) && strobe.onsets.detect(audio_analysis.flux);
+    let min_interval = 1.0 / settings.max_rate.clamp(0.1, StrobeSettings::MAX_SAFE_RATE);
+    if onset && strobe.since_flash >= min_interval {
+        strobe.flash = 1.0;
+        strobe.since_flash = 0.0;
+    } else {
+        strobe.flash = (strobe.flash - dt / settings.decay.max(0.01)).max(0.0);
+    }
+
+    let alpha = settings.intensity.clamp(0.0, 1.0) * strobe.flash;
+    background.0 = settings.color.with_a(alpha);
+}
@@ -0,0 +1,263 @@
+// src/taskbar.rs
+
+use crate::audio::{display_name, AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource};
+use crate::wallpaper::WallpaperMode;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, RawHandleWrapper};
+use raw_window_handle::RawWindowHandle;
+use std::sync::mpsc::{Receiver, Sender};
+
+const APP_NAME: &str = "Rust Visualizer";
+// The app id (Wayland) and WM_CLASS (X11) of the windows, which desktops match
+// against `rust_visualizer.desktop` to find the launcher of the app.
+pub const APP_ID: &str = "rust_visualizer";
+// Progress is sent in steps of a thousandth, not every frame.
+const PROGRESS_STEPS: u64 = 1000;
+
+// Shows the state of playback while the window isn't focused: the track in
+// the window title, and its progress on the taskbar button (Windows) or the
+// dock icon (Linux docks following the Unity launcher API, such as KDE
+// Plasma's, Dash to Dock or Plank).
+pub struct TaskbarPlugin;
+
+impl Plugin for TaskbarPlugin {
+    fn build(&self, app: &mut App) {
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<TaskbarJob>();
+        spawn_taskbar_thread(job_rx);
+
+        app.insert_resource(Taskbar { jobs: job_tx })
+            .add_systems(Update, (update_window_title, update_taskbar_progress));
+    }
+}
+
+#[derive(Resource)]
+pub struct Taskbar {
+    jobs: Sender<TaskbarJob>,
+}
+
+// In thousandths of the track.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Progress {
+    Hidden,
+    Playing(u64),
+    Paused(u64),
+}
+
+struct TaskbarJob {
+    // The native handle of the primary window, where the platform needs it.
+    window: Option<isize>,
+    progress: Progress,
+}
+
+// "▶ track.mp3 – Rust Visualizer" while a file plays.
+fn update_window_title(
+    selected_source: Res<SelectedAudioSource>,
+    playback_info: Res<PlaybackInfo>,
+    wallpaper: Option<Res<WallpaperMode>>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if wallpaper.is_some() {
+        return;
+    }
+    let title = match &selected_source.0 {
+        AudioSource::File(path) | AudioSource::Replay(path) => {
+            let status = match playback_info.status {
+                PlaybackStatus::Playing => "▶",
+                PlaybackStatus::Paused => "⏸",
+            };
+            format!("{} {} – {}", status, display_name(path), APP_NAME)
+        }
+        AudioSource::Microphone | AudioSource::None => APP_NAME.to_string(),
+    };
+    let Ok(mut window) = q_window.get_single_mut() else {
+        return;
+    };
+    // Only on changes, so the window isn't marked changed every frame.
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+fn update_taskbar_progress(
+    taskbar: Res<Taskbar>,
+    selected_source: Res<SelectedAudioSource>,
+    playback_info: Res<PlaybackInfo>,
+    q_window: Query<&RawHandleWrapper, With<PrimaryWindow>>,
+    mut sent: Local<Option<Progress>>,
+) {
+    let Ok(handle) = q_window.get_single() else {
+        return;
+    };
+    let has_track = matches!(
+        selected_source.0,
+        AudioSource::File(_) | AudioSource::Replay(_)
+    );
+    let progress = if has_track && !playback_info.duration.is_zero() {
+        let fraction = playback_info.position.as_secs_f64() / playback_info.duration.as_secs_f64();
+        let steps = (fraction.clamp(0.0, 1.0) * PROGRESS_STEPS as f64).round() as u64;
+        match playback_info.status {
+            PlaybackStatus::Playing => Progress::Playing(steps),
+            PlaybackStatus::Paused => Progress::Paused(steps),
+        }
+    } else {
+        Progress::Hidden
+    };
+    if *sent == Some(progress) {
+        return;
+    }
+
+    taskbar
+        .jobs
+        .send(TaskbarJob {
+            window: native_window(handle.window_handle),
+            progress,
+        })
+        .ok();
+    *sent = Some(progress);
+}
+
+fn native_window(handle: RawWindowHandle) -> Option<isize> {
+    match handle {
+        RawWindowHandle::Win32(handle) => Some(handle.hwnd.get()),
+        _ => None,
+    }
+}
+
+// --- Taskbar Thread ---
+
+// Talks to the shell away from the frame loop. Gives up for the run at the
+// first error, e.g. without a taskbar or session bus.
+fn spawn_taskbar_thread(jobs: Receiver<TaskbarJob>) {
+    std::thread::Builder::new()
+        .name("taskbar".into())
+        .spawn(move || {
+            let mut indicator = match ProgressIndicator::new() {
+                Ok(indicator) => indicator,
+                Err(e) => {
+                    warn!("Taskbar progress unavailable: {}", e);
+                    return;
+                }
+            };
+            while let Ok(job) = jobs.recv() {
+                // Only the newest progress matters if updates pile up.
+                let job = jobs.try_iter().last().unwrap_or(job);
+                if let Err(e) = indicator.set(job.window, job.progress) {
+                    warn!("Taskbar progress unavailable: {}", e);
+                    return;
+                }
+            }
+        })
+        .expect("Failed to spawn the taskbar thread");
+}
+
+// Windows: the progress bar of the taskbar button, green while playing and
+// yellow while paused.
+#[cfg(windows)]
+struct ProgressIndicator(windows::Win32::UI::Shell::ITaskbarList3);
+
+#[cfg(windows)]
+impl ProgressIndicator {
+    fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+        };
+        use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList};
+
+        // SAFETY: COM is initialised on this thread, which the taskbar object
+        // is then created and only used on.
+        unsafe {
+            CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok()?;
+            let list: ITaskbarList3 = CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)?;
+            list.HrInit()?;
+            Ok(Self(list))
+        }
+    }
+
+    fn set(
+        &mut self,
+        window: Option<isize>,
+        progress: Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::UI::Shell::{TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED};
+
+        let Some(window) = window else {
+            return Ok(());
+        };
+        let hwnd = HWND(window);
+        // SAFETY: plain calls on a live window handle of this process.
+        unsafe {
+            match progress {
+                Progress::Hidden => self.0.SetProgressState(hwnd, TBPF_NOPROGRESS)?,
+                Progress::Playing(steps) | Progress::Paused(steps) => {
+                    self.0.SetProgressValue(hwnd, steps, PROGRESS_STEPS)?;
+                    let state = if matches!(progress, Progress::Playing(_)) {
+                        TBPF_NORMAL
+                    } else {
+                        TBPF_PAUSED
+                    };
+                    self.0.SetProgressState(hwnd, state)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Linux: the Unity launcher entry signal, shown by docks over the icon of the
+// application whose desktop file is named here.
+#[cfg(target_os = "linux")]
+struct ProgressIndicator(zbus::blocking::Connection);
+
+#[cfg(target_os = "linux")]
+impl ProgressIndicator {
+    // Must match `APP_ID` and the installed desktop file.
+    const DESKTOP_ENTRY: &'static str = "application://rust_visualizer.desktop";
+
+    fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self(zbus::blocking::Connection::session()?))
+    }
+
+    fn set(
+        &mut self,
+        _window: Option<isize>,
+        progress: Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use std::collections::HashMap;
+        use zbus::zvariant::Value;
+
+        let (steps, visible) = match progress {
+            Progress::Hidden => (0, false),
+            Progress::Playing(steps) | Progress::Paused(steps) => (steps, true),
+        };
+        let mut properties: HashMap<&str, Value> = HashMap::new();
+        properties.insert("progress", Value::F64(steps as f64 / PROGRESS_STEPS as f64));
+        properties.insert("progress-visible", Value::Bool(visible));
+        self.0.emit_signal(
+            None::<&str>,
+            "/com/github/rust_visualizer/launcher",
+            "com.canonical.Unity.LauncherEntry",
+            "Update",
+            &(Self::DESKTOP_ENTRY, properties),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+struct ProgressIndicator;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl ProgressIndicator {
+    fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Err("not supported on this platform".into())
+    }
+
+    fn set(
+        &mut self,
+        _window: Option<isize>,
+        _progress: Progress,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+// src/throttle.rs
+
+use crate::config::{BackgroundMode, VisualsConfig};
+use crate::mini_player::MiniPlayer;
+use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+use std::time::Duration;
+
+// How long a suspended app waits for a window event before updating anyway.
+const SUSPENDED_WAIT: Duration = Duration::from_secs(60);
+
+// Lowers the frame rate while the window is unfocused or minimized, following
+// `GeneralSettings::background`, and caps it while focused at lower quality.
+// The output window and the mini player are watched without the focus, so
+// while either is open the unfocused app runs as if focused.
+// Not added for bench or wallpaper runs, whose window never has the focus.
+pub struct ThrottlePlugin;
+
+impl Plugin for ThrottlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_background_mode);
+    }
+}
+
+fn apply_background_mode(
+    config: Res<VisualsConfig>,
+    mini_player: Res<MiniPlayer>,
+    mut winit_settings: ResMut<WinitSettings>,
+) {
+    if !config.is_changed() && !mini_player.is_changed() {
+        return;
+    }
+    let focused_mode = match config.general.quality.max_fps() {
+        Some(fps) => UpdateMode::ReactiveLowPower {
+            wait: Duration::from_secs_f32(1.0 / fps),
+        },
+        None => UpdateMode::Continuous,
+    };
+    let background = &config.general.background;
+    let watched = config.general.output.enabled || mini_player.active;
+    let unfocused_mode = match background.mode {
+        _ if watched => focused_mode,
+        BackgroundMode::FullSpeed => UpdateMode::Continuous,
+        BackgroundMode::Throttled => UpdateMode::ReactiveLowPower {
+            wait: Duration::from_secs_f32(1.0 / background.fps.max(1.0)),
+        },
+        BackgroundMode::Suspended => UpdateMode::ReactiveLowPower {
+            wait: SUSPENDED_WAIT,
+        },
+    };
+    winit_settings.focused_mode = focused_mode;
+    winit_settings.unfocused_mode = unfocused_mode;
+}
@@ -0,0 +1,172 @@
+// src/thumbnails.rs
+
+use crate::config::{thumbnail_path, VisualizerId};
+use crate::ui::UiVisibility;
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+
+// Size of the previews, in pixels.
+pub const THUMBNAIL_WIDTH: u32 = 96;
+pub const THUMBNAIL_HEIGHT: u32 = 54;
+// How long a visualizer has to be on screen, with the panels hidden, before
+// its preview is taken, so it has settled and isn't covered by the UI.
+const CAPTURE_AFTER_SECS: f32 = 3.0;
+
+// Keeps a small preview of each visualizer for the selector: the one cached
+// from an earlier run, replaced once per run by a screenshot taken while the
+// visualizer is on screen without the UI.
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        app.insert_resource(Thumbnails {
+            textures: HashMap::new(),
+            images: HashMap::new(),
+            captured: HashSet::new(),
+            sender,
+            receiver: Mutex::new(receiver),
+        })
+        .add_systems(Startup, load_cached_thumbnails)
+        .add_systems(Update, (capture_thumbnail, receive_thumbnails));
+    }
+}
+
+#[derive(Resource)]
+pub struct Thumbnails {
+    textures: HashMap<VisualizerId, egui::TextureId>,
+    // Kept alive for egui, which only holds weak handles.
+    images: HashMap<VisualizerId, Handle<Image>>,
+    // Taken this run, or being taken.
+    captured: HashSet<VisualizerId>,
+    sender: Sender<(VisualizerId, Image)>,
+    receiver: Mutex<Receiver<(VisualizerId, Image)>>,
+}
+
+impl Thumbnails {
+    pub fn texture(&self, id: VisualizerId) -> Option<egui::TextureId> {
+        self.textures.get(&id).copied()
+    }
+
+    fn insert(
+        &mut self,
+        id: VisualizerId,
+        image: Image,
+        images: &mut Assets<Image>,
+        contexts: &mut EguiContexts,
+    ) {
+        let handle = images.add(image);
+        if let Some(old) = self.images.insert(id, handle.clone()) {
+            contexts.remove_image(&old);
+        }
+        self.textures.insert(id, contexts.add_image(handle));
+    }
+}
+
+fn load_cached_thumbnails(
+    mut thumbnails: ResMut<Thumbnails>,
+    mut images: ResMut<Assets<Image>>,
+    mut contexts: EguiContexts,
+) {
+    for id in VisualizerId::ALL {
+        let Ok(bytes) = std::fs::read(thumbnail_path(id)) else {
+            continue;
+        };
+        match Image::from_buffer(
+            &bytes,
+            ImageType::Extension("png"),
+            CompressedImageFormats::NONE,
+            true,
+            ImageSampler::Default,
+            RenderAssetUsages::default(),
+        ) {
+            Ok(image) => thumbnails.insert(id, image, &mut images, &mut contexts),
+            Err(e) => warn!("Ignoring invalid thumbnail of {:?}: {}", id, e),
+        }
+    }
+}
+
+fn capture_thumbnail(
+    time: Res<Time<Real>>,
+    app_state: Res<State<AppState>>,
+    ui_visibility: Res<UiVisibility>,
+    q_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    mut thumbnails: ResMut<Thumbnails>,
+    mut shown_for: Local<f32>,
+) {
+    let Some(id) = VisualizerId::from_state(app_state.get()) else {
+        *shown_for = 0.0;
+        return;
+    };
+    let ui_hidden = !ui_visibility.visible || ui_visibility.idle_hidden;
+    if app_state.is_changed() || !ui_hidden {
+        *shown_for = 0.0;
+        return;
+    }
+    *shown_for += time.delta_seconds();
+    if *shown_for < CAPTURE_AFTER_SECS || thumbnails.captured.contains(&id) {
+        return;
+    }
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    let sender = thumbnails.sender.clone();
+    let requested =
+        screenshots.take_screenshot(window, move |screenshot| match shrink(screenshot, id) {
+            Ok(thumbnail) => {
+                sender.send((id, thumbnail)).ok();
+            }
+            Err(e) => error!("❌ Failed to save the thumbnail of {:?}: {}", id, e),
+        });
+    if requested.is_ok() {
+        thumbnails.captured.insert(id);
+    }
+}
+
+// Scales the screenshot down to a thumbnail and caches it on disk. Runs on
+// the render thread that took it.
+fn shrink(screenshot: Image, id: VisualizerId) -> Result<Image, Box<dyn std::error::Error>> {
+    let thumbnail = screenshot
+        .try_into_dynamic()?
+        .thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
+        .to_rgba8();
+    let path = thumbnail_path(id);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    thumbnail.save(&path)?;
+    Ok(Image::new(
+        Extent3d {
+            width: thumbnail.width(),
+            height: thumbnail.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        thumbnail.into_raw(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+fn receive_thumbnails(
+    mut thumbnails: ResMut<Thumbnails>,
+    mut images: ResMut<Assets<Image>>,
+    mut contexts: EguiContexts,
+) {
+    let received: Vec<_> = thumbnails.receiver.lock().unwrap().try_iter().collect();
+    for (id, image) in received {
+        info!("Updated the thumbnail of {:?}", id);
+        thumbnails.insert(id, image, &mut images, &mut contexts);
+    }
+}
@@ -0,0 +1,250 @@
+// src/timeline.rs
+
+use crate::audio::{display_name, AudioSource, PlaybackInfo, SelectedAudioSource};
+use crate::config::{timeline_path, VisualizerId, VisualsConfig};
+use crate::keys::{KeyAction, KeyBindings};
+use crate::toast::Toasts;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Edits are written to disk at most this often.
+const SAVE_INTERVAL_SECS: f32 = 1.0;
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>().add_systems(
+            Update,
+            (
+                load_timeline,
+                save_timeline.after(load_timeline),
+                apply_timeline.after(load_timeline),
+                jump_between_cues.after(load_timeline),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Keyframe {
+    // Track position, in seconds.
+    pub time: f32,
+    pub value: f32,
+}
+
+// Keyframes of one numeric visualizer parameter (a field named in its
+// `UI_FIELDS`), kept sorted by time.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AutomationLane {
+    pub visualizer: VisualizerId,
+    pub field: String,
+    // Jump from one keyframe value to the next instead of ramping.
+    pub step: bool,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl AutomationLane {
+    // Holds the first and last values before and after the keyframes.
+    pub fn value_at(&self, time: f32) -> Option<f32> {
+        let next = self.keyframes.iter().position(|key| key.time > time);
+        match next {
+            None => self.keyframes.last().map(|key| key.value),
+            Some(0) => Some(self.keyframes[0].value),
+            Some(i) => {
+                let (from, to) = (self.keyframes[i - 1], self.keyframes[i]);
+                if self.step {
+                    return Some(from.value);
+                }
+                let t = (time - from.time) / (to.time - from.time).max(f32::EPSILON);
+                Some(from.value + (to.value - from.value) * t)
+            }
+        }
+    }
+
+    pub fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+// A named position in the track, e.g. "drop" or "second verse".
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CuePoint {
+    pub name: String,
+    pub time: f32,
+}
+
+// The parameter automation and cue points of the loaded file, saved per file
+// (see `timeline_path`). Automation is replayed against its playback
+// position.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeline {
+    pub lanes: Vec<AutomationLane>,
+    // Kept sorted by time.
+    pub cues: Vec<CuePoint>,
+    #[serde(skip)]
+    track: Option<PathBuf>,
+}
+
+impl Timeline {
+    pub fn track(&self) -> Option<&PathBuf> {
+        self.track.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.is_empty() && self.cues.is_empty()
+    }
+
+    pub fn sort_cues(&mut self) {
+        self.cues.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    // Takes the lanes and cues of `other`, keeping the track.
+    pub fn replace_contents(&mut self, other: Timeline) {
+        self.lanes = other.lanes;
+        self.cues = other.cues;
+    }
+}
+
+fn load_timeline(
+    selected_source: Res<SelectedAudioSource>,
+    mut timeline: ResMut<Timeline>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !selected_source.is_changed() {
+        return;
+    }
+    let track = match &selected_source.0 {
+        AudioSource::File(path) => Some(path.clone()),
+        _ => None,
+    };
+    if timeline.track == track {
+        return;
+    }
+
+    let mut loaded = track
+        .as_ref()
+        .and_then(|track| {
+            let contents = std::fs::read_to_string(timeline_path(track)).ok()?;
+            match ron::from_str::<Timeline>(&contents) {
+                Ok(timeline) => Some(timeline),
+                Err(e) => {
+                    warn!("Ignoring invalid timeline of {:?}: {}", track, e);
+                    None
+                }
+            }
+        })
+        .unwrap_or_default();
+    if let (Some(track), false) = (&track, loaded.is_empty()) {
+        toasts.info(format!("Loaded the timeline of {}", display_name(track)));
+    }
+    loaded.track = track;
+    *timeline = loaded;
+}
+
+fn save_timeline(
+    time: Res<Time<Real>>,
+    timeline: Res<Timeline>,
+    mut timer: Local<Option<Timer>>,
+    mut pending: Local<bool>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(SAVE_INTERVAL_SECS, TimerMode::Repeating));
+    // Loading a timeline marks it changed too, which only rewrites the same file.
+    if timeline.is_changed() {
+        *pending = true;
+    }
+    if !timer.tick(time.delta()).just_finished() || !*pending {
+        return;
+    }
+    *pending = false;
+
+    let Some(track) = timeline.track() else {
+        return;
+    };
+    let path = timeline_path(track);
+    let result = if timeline.is_empty() {
+        // Don't leave empty timelines behind.
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    } else {
+        ron::ser::to_string_pretty(&*timeline, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(&path, contents).map_err(|e| e.to_string())
+            })
+    };
+    if let Err(e) = result {
+        error!("❌ Failed to save timeline {:?}: {}", path, e);
+    }
+}
+
+// Sets every automated parameter from the playback position. Parameters are
+// only written when their automated value moves, so they can still be
+// tweaked by hand while the track is paused.
+fn apply_timeline(
+    timeline: Res<Timeline>,
+    playback_info: Res<PlaybackInfo>,
+    mut config: ResMut<VisualsConfig>,
+    mut applied: Local<Vec<Option<f32>>>,
+) {
+    if timeline.track().is_none() {
+        return;
+    }
+    if timeline.is_changed() {
+        applied.clear();
+    }
+    applied.resize(timeline.lanes.len(), None);
+
+    let position = playback_info.position.as_secs_f32();
+    for (lane, applied) in timeline.lanes.iter().zip(applied.iter_mut()) {
+        let Some(value) = lane.value_at(position) else {
+            continue;
+        };
+        if *applied == Some(value) {
+            continue;
+        }
+        *applied = Some(value);
+        let (_, settings) = config.general_and_settings_mut(lane.visualizer);
+        settings.set_number(&lane.field, value);
+    }
+}
+
+// '[' and ']' jump to the previous and next cue point. Right after a cue,
+// '[' goes to the one before it, like the transport of most DAWs.
+fn jump_between_cues(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    timeline: Res<Timeline>,
+    mut playback_info: ResMut<PlaybackInfo>,
+) {
+    let backward = bindings.just_pressed(KeyAction::PreviousCue, &keyboard);
+    let forward = bindings.just_pressed(KeyAction::NextCue, &keyboard);
+    if !(backward || forward) || timeline.track().is_none() {
+        return;
+    }
+    let position = playback_info.position.as_secs_f32();
+    let cue = if forward {
+        timeline.cues.iter().find(|cue| cue.time > position + 0.5)
+    } else {
+        timeline
+            .cues
+            .iter()
+            .rev()
+            .find(|cue| cue.time < position - 1.0)
+    };
+    let target = match (cue, backward) {
+        (Some(cue), _) => cue.time,
+        // Before the first cue, go back to the start.
+        (None, true) => 0.0,
+        (None, false) => return,
+    };
+    playback_info.seek_to = Some(target);
+}
@@ -0,0 +1,145 @@
+// src/toast.rs
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiSet};
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+
+// How long a toast stays on screen.
+const TOAST_DURATION_SECS: f32 = 4.0;
+// Oldest toasts are dropped when more than this many are queued.
+const MAX_TOASTS: usize = 5;
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        let (toast_tx, toast_rx) = std::sync::mpsc::channel::<Toast>();
+
+        app.init_resource::<Toasts>()
+            .insert_resource(ToastSender(toast_tx))
+            .insert_non_send_resource(ToastReceiver(toast_rx))
+            .add_systems(
+                Update,
+                (receive_toasts, show_toasts.after(receive_toasts)).after(EguiSet::InitContexts),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+}
+
+impl Toast {
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            level: ToastLevel::Info,
+            message: message.into(),
+        }
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self {
+            level: ToastLevel::Success,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: ToastLevel::Error,
+            message: message.into(),
+        }
+    }
+}
+
+// The queue of notifications currently on screen.
+#[derive(Resource, Default)]
+pub struct Toasts {
+    queue: VecDeque<(Toast, Timer)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, toast: Toast) {
+        self.queue.push_back((
+            toast,
+            Timer::from_seconds(TOAST_DURATION_SECS, TimerMode::Once),
+        ));
+        while self.queue.len() > MAX_TOASTS {
+            self.queue.pop_front();
+        }
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Toast::info(message));
+    }
+
+    pub fn success(&mut self, message: impl Into<String>) {
+        self.push(Toast::success(message));
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Toast::error(message));
+    }
+}
+
+// Lets other threads (e.g. audio stream callbacks) raise toasts.
+#[derive(Resource, Clone)]
+pub struct ToastSender(pub Sender<Toast>);
+pub struct ToastReceiver(pub Receiver<Toast>);
+
+fn receive_toasts(receiver: Option<NonSend<ToastReceiver>>, mut toasts: ResMut<Toasts>) {
+    if let Some(receiver) = receiver {
+        for toast in receiver.0.try_iter() {
+            toasts.push(toast);
+        }
+    }
+}
+
+fn show_toasts(mut contexts: EguiContexts, time: Res<Time>, mut toasts: ResMut<Toasts>) {
+    if toasts.queue.is_empty() {
+        return;
+    }
+
+    for (_, timer) in toasts.queue.iter_mut() {
+        timer.tick(time.delta());
+    }
+    toasts.queue.retain(|(_, timer)| !timer.finished());
+
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Area::new("toasts".into())
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+        .order(egui::Order::Foreground)
+        .interactable(false)
+        .show(ctx, |ui| {
+            for (toast, _) in &toasts.queue {
+                let (icon, fill) = match toast.level {
+                    ToastLevel::Info => ("ℹ", egui::Color32::from_rgb(40, 40, 48)),
+                    ToastLevel::Success => ("✔", egui::Color32::from_rgb(30, 80, 40)),
+                    ToastLevel::Error => ("⚠", egui::Color32::from_rgb(110, 30, 30)),
+                };
+                egui::Frame::popup(ui.style())
+                    .fill(fill)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            egui::Color32::WHITE,
+                            format!("{} {}", icon, toast.message),
+                        );
+                    });
+                ui.add_space(4.0);
+            }
+        });
+}
@@ -0,0 +1,147 @@
+// src/tray.rs
+
+use crate::audio::{AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource};
+use crate::config::{VisualizerId, VisualsConfig};
+use crate::i18n::Language;
+use crate::{ActiveVisualization, AppState};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+// Ids of the menu entries, as sent back in `MenuEvent`s.
+const PLAY_PAUSE: &str = "play-pause";
+const NEXT_VISUALIZER: &str = "next-visualizer";
+const SHOW_HIDE: &str = "show-hide";
+const QUIT: &str = "quit";
+
+const ICON_SIZE: u32 = 32;
+
+pub struct TrayPlugin;
+
+impl Plugin for TrayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_tray)
+            .add_systems(Update, handle_tray_menu);
+    }
+}
+
+// Keeps the tray icon alive; it disappears when dropped.
+#[cfg(not(target_os = "linux"))]
+struct Tray(#[allow(dead_code)] TrayIcon);
+
+// Windows and macOS need the icon made on the main thread, whose event loop
+// winit already runs.
+#[cfg(not(target_os = "linux"))]
+fn create_tray(world: &mut World) {
+    let lang = world.resource::<VisualsConfig>().general.language;
+    match build_tray(lang) {
+        Ok(tray) => world.insert_non_send_resource(Tray(tray)),
+        Err(e) => warn!("Could not create the tray icon: {}", e),
+    }
+}
+
+// On Linux the icon lives on a thread of its own running the GTK main loop.
+// Its menu events still arrive through the global `MenuEvent` channel.
+#[cfg(target_os = "linux")]
+fn create_tray(config: Res<VisualsConfig>) {
+    let lang = config.general.language;
+    std::thread::Builder::new()
+        .name("tray".into())
+        .spawn(move || {
+            if let Err(e) = gtk::init() {
+                warn!("Could not create the tray icon: {}", e);
+                return;
+            }
+            match build_tray(lang) {
+                Ok(_tray) => gtk::main(),
+                Err(e) => warn!("Could not create the tray icon: {}", e),
+            }
+        })
+        .expect("Failed to spawn the tray thread");
+}
+
+fn build_tray(lang: Language) -> Result<TrayIcon, Box<dyn std::error::Error>> {
+    let t = |text: &'static str| lang.tr(text);
+    let menu = Menu::new();
+    menu.append_items(&[
+        &MenuItem::with_id(PLAY_PAUSE, t("Play / Pause"), true, None),
+        &MenuItem::with_id(NEXT_VISUALIZER, t("Next Visualizer"), true, None),
+        &MenuItem::with_id(SHOW_HIDE, t("Show / Hide Window"), true, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id(QUIT, t("Quit"), true, None),
+    ])?;
+    let tray = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip("Rust Visualizer")
+        .with_icon(Icon::from_rgba(icon_pixels(), ICON_SIZE, ICON_SIZE)?)
+        .build()?;
+    Ok(tray)
+}
+
+// Five spectrum bars, drawn here rather than shipping an image.
+fn icon_pixels() -> Vec<u8> {
+    const HEIGHTS: [f32; 5] = [0.45, 0.8, 1.0, 0.65, 0.35];
+    let size = ICON_SIZE as usize;
+    let bar_width = size / HEIGHTS.len();
+    let mut pixels = vec![0u8; size * size * 4];
+    for y in 0..size {
+        for x in 0..size {
+            let bar = x / bar_width;
+            let Some(height) = HEIGHTS.get(bar) else {
+                continue;
+            };
+            // One pixel of space between bars.
+            let filled = x % bar_width < bar_width - 1 && (size - y) as f32 <= height * size as f32;
+            if filled {
+                let i = (y * size + x) * 4;
+                pixels[i..i + 4].copy_from_slice(&[80, 200, 255, 255]);
+            }
+        }
+    }
+    pixels
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_tray_menu(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    selected_source: Res<SelectedAudioSource>,
+    mut playback_info: ResMut<PlaybackInfo>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in MenuEvent::receiver().try_iter() {
+        match event.id.0.as_str() {
+            PLAY_PAUSE => {
+                if let AudioSource::File(_) = selected_source.0 {
+                    playback_info.status = match playback_info.status {
+                        PlaybackStatus::Playing => PlaybackStatus::Paused,
+                        PlaybackStatus::Paused => PlaybackStatus::Playing,
+                    };
+                }
+            }
+            // From the menus, this starts the visualizer after the one they
+            // would start.
+            NEXT_VISUALIZER => {
+                let current = VisualizerId::from_state(app_state.get())
+                    .or_else(|| VisualizerId::from_state(&active_viz.0));
+                let state = config.general.visualizers.cycle(current, 1).state();
+                next_app_state.set(state.clone());
+                active_viz.0 = state;
+            }
+            SHOW_HIDE => {
+                if let Ok(mut window) = q_windows.get_single_mut() {
+                    window.visible = !window.visible;
+                }
+            }
+            QUIT => {
+                exit.send(AppExit);
+            }
+            _ => {}
+        }
+    }
+}
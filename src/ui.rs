@@ -1,15 +1,46 @@
 // src/ui.rs
 
+use crate::analysis::log_band_edges;
 use crate::audio::{
-    AudioAnalysis, AudioSource, PlaybackInfo, PlaybackStatus, SelectedAudioSource, SelectedMic,
+    display_name, AudioAnalysis, AudioSource, EnergyHistory, EnergySample, MicLatency,
+    PlaybackInfo, PlaybackStatus, SelectedAudioSource, SelectedMic,
 };
-use crate::config::VisualsConfig;
-use crate::{ActiveVisualization, AppState, VisualizationEnabled};
+use crate::backend::Backend;
+use crate::config::{
+    AnalysisInterpolation, BackgroundMode, BloomConfig, CameraConfig, FloorConfig, FogConfig,
+    GeneralSettings, LfoRate, LfoSettings, LfoShape, MacroSettings, MacroTarget, OverlayCorner,
+    PaletteCycle, PanelPlacement, Quality, RepeatMode, SceneSwitchSettings, SettingsGroup,
+    SpectrumWeighting, StrobeSettings, ThemeSettings, ToneDetector, TonemappingMode, VisualizerId,
+    VisualizerList, VisualsConfig, VizSettings, MAX_TONE_DETECTORS,
+};
+use crate::discord::discord_section;
+use crate::eq::EQ_FREQUENCIES;
+use crate::export::{spawn_export, ExportSettings};
+use crate::governor::{ActiveQuality, QualityGovernor};
+use crate::hue::{hue_section, HueBridge};
+use crate::i18n::Language;
+use crate::keys::{KeyAction, KeyBindings};
+use crate::lfo::{LfoBank, Tempo};
+use crate::library::{Library, LibraryTrack, Playlist};
+use crate::monitor::MONITOR_MAX_LATENCY_SECS;
+use crate::offline::{GridBeat, OfflineAnalysis, SectionMark, TrackSettings};
+use crate::palette::{apply_palette, PALETTES};
+use crate::project::ProjectFiles;
+use crate::recording::{source_for, SessionRecorder, RECORDING_EXTENSION};
+use crate::session::{LastSession, ResumeSession};
+use crate::settings_window::SettingsWindow;
+use crate::thumbnails::{Thumbnails, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+use crate::timeline::{AutomationLane, CuePoint, Keyframe, Timeline};
+use crate::toast::ToastSender;
+use crate::wallpaper::WallpaperMode;
+use crate::{ActiveVisualization, AppState, FreezeState, VisualizationEnabled};
+use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use bevy::window::{PrimaryWindow, WindowMode};
 use bevy_egui::egui::color_picker;
-use bevy_egui::{egui, EguiContexts, EguiSet};
-use cpal::traits::{DeviceTrait, HostTrait};
+use bevy_egui::{egui, EguiContext, EguiContexts, EguiSet, EguiSettings};
+use egui_plot::{Line, Plot, PlotPoints, VLine};
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 // A resource to know if the UI is shown or hidden
@@ -17,6 +48,13 @@ use std::time::Duration;
 pub struct UiVisibility {
     pub visible: bool,
     pub hint_timer: Timer,
+    // Set while the panels are auto-hidden in idle fullscreen, independently of 'H'.
+    pub idle_hidden: bool,
+    // The keyboard shortcut list, toggled with '?'.
+    pub cheat_sheet: bool,
+    // Each side panel on its own, toggled with F2 and F3 under 'H'.
+    pub left_panel: bool,
+    pub right_panel: bool,
 }
 
 impl Default for UiVisibility {
@@ -25,6 +63,10 @@ impl Default for UiVisibility {
             visible: true,
             // Timer set to 5 seconds, runs once
             hint_timer: Timer::from_seconds(5.0, TimerMode::Once),
+            idle_hidden: false,
+            cheat_sheet: false,
+            left_panel: true,
+            right_panel: true,
         }
     }
 }
@@ -33,51 +75,86 @@ pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
+        app.add_systems(OnEnter(AppState::MainMenu), spawn_menu_camera)
+            .add_systems(OnExit(AppState::MainMenu), cleanup_menu)
+            .add_systems(
+                OnEnter(AppState::MicSelection),
+                (spawn_menu_camera, list_mic_devices),
+            )
+            .add_systems(
+                OnExit(AppState::MicSelection),
+                (cleanup_menu, forget_mic_devices),
+            )
+            .add_systems(OnEnter(AppState::Settings), spawn_menu_camera)
+            .add_systems(OnExit(AppState::Settings), cleanup_menu)
             .add_systems(
                 Update,
-                menu_button_interaction.run_if(in_state(AppState::MainMenu)),
+                (
+                    main_menu_ui.run_if(in_state(AppState::MainMenu)),
+                    mic_selection_ui.run_if(in_state(AppState::MicSelection)),
+                    settings_ui.run_if(in_state(AppState::Settings)),
+                )
+                    .after(EguiSet::InitContexts),
             )
-            .add_systems(OnExit(AppState::MainMenu), cleanup_menu)
-            .add_systems(OnEnter(AppState::MicSelection), setup_mic_selection_menu)
+            .add_systems(Update, apply_ui_theme.after(EguiSet::InitContexts))
+            .add_systems(OnEnter(FreezeState::Frozen), pause_virtual_time)
+            .add_systems(OnExit(FreezeState::Frozen), unpause_virtual_time)
+            // Leaving the visualizers always unfreezes.
+            .add_systems(OnEnter(AppState::MainMenu), unfreeze)
             .add_systems(
                 Update,
-                mic_selection_interaction.run_if(in_state(AppState::MicSelection)),
+                auto_hide_when_idle.run_if(not(resource_exists::<WallpaperMode>)),
             )
-            .add_systems(OnExit(AppState::MicSelection), cleanup_menu)
             .add_systems(
                 Update,
                 (
-                    toggle_ui_visibility, // System for 'H' key
-                    main_ui_layout,       // The main system handling panels
+                    toggle_ui_visibility,                      // System for 'H' key
+                    toggle_fullscreen,                         // System for 'F11' key
+                    toggle_freeze,                             // System for 'F' key
+                    return_to_menu,                            // System for 'Escape' key
+                    visualizer_hotkeys,                        // System for '1' to '6' keys
+                    toggle_cheat_sheet,                        // System for '?' key
+                    main_ui_layout.after(auto_hide_when_idle), // The main system handling panels
+                    now_playing_overlay.after(main_ui_layout),
+                    visualizer_name_flash.after(now_playing_overlay),
+                    cheat_sheet_overlay.after(toggle_cheat_sheet),
+                    library_window.after(main_ui_layout),
                 )
                     .after(EguiSet::InitContexts)
+                    // A wallpaper can't be interacted with, so it has no UI.
+                    .run_if(not(resource_exists::<WallpaperMode>))
                     .run_if(
                         in_state(AppState::Visualization2D)
                             .or_else(in_state(AppState::Visualization3D))
                             .or_else(in_state(AppState::VisualizationOrb))
                             .or_else(in_state(AppState::VisualizationDisc))
-                            .or_else(in_state(AppState::VisualizationIco)),
+                            .or_else(in_state(AppState::VisualizationIco))
+                            .or_else(in_state(AppState::VisualizationShadertoy)),
                     ),
             );
     }
 }
 
-// --- Main Menu Components (Unchanged) ---
+// --- Menu Screens ---
 #[derive(Component)]
-enum MenuButtonAction {
-    Start,
-    ToMicSelection,
-}
+struct MenuCamera;
 
-#[derive(Component)]
-struct MainMenuUI;
-#[derive(Component)]
-struct MicDeviceButton(String);
+const MENU_BUTTON_SIZE: egui::Vec2 = egui::Vec2::new(250.0, 48.0);
+const MENU_SPACING: f32 = 10.0;
+
+// How often the selection screen lists the input devices again.
+const DEVICE_REFRESH_SECS: f32 = 3.0;
+// Range of the UI scale, also enforced on hand-edited configs: outside it the
+// panels no longer fit, or get too small to read or grab.
+const UI_SCALE_RANGE: RangeInclusive<f32> = 0.75..=2.0;
 
 // --- UI Toggle System ---
-fn toggle_ui_visibility(keyboard: Res<ButtonInput<KeyCode>>, mut ui_viz: ResMut<UiVisibility>) {
-    if keyboard.just_pressed(KeyCode::KeyH) {
+fn toggle_ui_visibility(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut ui_viz: ResMut<UiVisibility>,
+) {
+    if bindings.just_pressed(KeyAction::ToggleUi, &keyboard) {
         ui_viz.visible = !ui_viz.visible;
 
         // If we just hid the UI, reset the timer to show the hint for 10s
@@ -85,6 +162,329 @@ fn toggle_ui_visibility(keyboard: Res<ButtonInput<KeyCode>>, mut ui_viz: ResMut<
             ui_viz.hint_timer.reset();
         }
     }
+    if bindings.just_pressed(KeyAction::ToggleLeftPanel, &keyboard) {
+        ui_viz.left_panel = !ui_viz.left_panel;
+    }
+    if bindings.just_pressed(KeyAction::ToggleRightPanel, &keyboard) {
+        ui_viz.right_panel = !ui_viz.right_panel;
+    }
+}
+
+fn toggle_fullscreen(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if bindings.just_pressed(KeyAction::Fullscreen, &keyboard) {
+        if let Ok(mut window) = q_windows.get_single_mut() {
+            window.mode = match window.mode {
+                WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+                _ => WindowMode::Windowed,
+            };
+        }
+    }
+}
+
+// Escape goes back to the main menu, which starts the same visualizer again.
+fn return_to_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    app_state: Res<State<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if bindings.just_pressed(KeyAction::MainMenu, &keyboard) {
+        active_viz.0 = app_state.get().clone();
+        next_app_state.set(AppState::MainMenu);
+    }
+}
+
+// How long the name of a visualizer shows after switching to it, the last
+// part of which it fades out over.
+const NAME_FLASH_SECS: f32 = 1.5;
+const NAME_FADE_SECS: f32 = 0.5;
+
+// Whether a text field has the keyboard, in the primary window or the
+// settings window: the keys then go to it rather than to the shortcuts.
+fn typing_in_any_window(q_contexts: &mut Query<&mut EguiContext>) -> bool {
+    q_contexts
+        .iter_mut()
+        .any(|mut ctx| ctx.get_mut().wants_keyboard_input())
+}
+
+// The number keys open the visualizers in the order of the panel, e.g. with
+// the UI hidden during a set. With Ctrl or Shift they are camera bookmarks.
+fn visualizer_hotkeys(
+    mut q_contexts: Query<&mut EguiContext>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut active_viz: ResMut<ActiveVisualization>,
+) {
+    let modifiers = [
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::ShiftLeft,
+        KeyCode::ShiftRight,
+    ];
+    if keyboard.any_pressed(modifiers) {
+        return;
+    }
+    let shown = config.general.visualizers.shown();
+    let Some(id) = bindings
+        .just_pressed_index(KeyAction::SwitchVisualizer, &keyboard)
+        .and_then(|index| shown.get(index))
+    else {
+        return;
+    };
+    // Digits typed into a text field, e.g. the settings search, stay there.
+    if typing_in_any_window(&mut q_contexts) {
+        return;
+    }
+
+    let state = id.state();
+    if *app_state.get() != state {
+        next_app_state.set(state.clone());
+        active_viz.0 = state;
+    }
+}
+
+// Shows the name of the visualizer in the middle of the screen for a moment
+// after it opens, even with the UI hidden.
+fn visualizer_name_flash(
+    mut contexts: EguiContexts,
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    time: Res<Time<Real>>,
+    mut shown_for: Local<f32>,
+) {
+    if app_state.is_changed() {
+        *shown_for = 0.0;
+    } else {
+        *shown_for += time.delta_seconds();
+    }
+    if *shown_for >= NAME_FLASH_SECS {
+        return;
+    }
+    let Some(id) = VisualizerId::from_state(app_state.get()) else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let alpha = ((NAME_FLASH_SECS - *shown_for) / NAME_FADE_SECS).min(1.0);
+    egui::Area::new("visualizer_name_flash".into())
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::none()
+                .fill(egui::Color32::from_black_alpha((alpha * 150.0) as u8))
+                .rounding(8.0)
+                .inner_margin(16.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new(config.general.language.tr(viz_label(id)))
+                            .size(40.0)
+                            .color(egui::Color32::from_white_alpha((alpha * 255.0) as u8)),
+                    );
+                });
+        });
+}
+
+// --- Keyboard Shortcuts ---
+fn toggle_cheat_sheet(
+    mut q_contexts: Query<&mut EguiContext>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut ui_visibility: ResMut<UiVisibility>,
+) {
+    if !bindings.just_pressed(KeyAction::CheatSheet, &keyboard) {
+        return;
+    }
+    if typing_in_any_window(&mut q_contexts) {
+        return;
+    }
+    ui_visibility.cheat_sheet = !ui_visibility.cheat_sheet;
+}
+
+// Lists the shortcuts that work in the current visualization, straight from
+// the bindings, over the visuals and whether or not the UI is hidden.
+fn cheat_sheet_overlay(
+    mut contexts: EguiContexts,
+    config: Res<VisualsConfig>,
+    bindings: Res<KeyBindings>,
+    app_state: Res<State<AppState>>,
+    mut ui_visibility: ResMut<UiVisibility>,
+) {
+    if !ui_visibility.cheat_sheet {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let lang = config.general.language;
+
+    let mut open = true;
+    egui::Window::new(lang.tr("⌨ Keyboard Shortcuts"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("cheat_sheet")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for action in bindings.actions() {
+                        if !action.active_in(app_state.get()) {
+                            continue;
+                        }
+                        ui.strong(bindings.label(action));
+                        ui.label(lang.tr(action.description()));
+                        ui.end_row();
+                    }
+                });
+        });
+    if !open {
+        ui_visibility.cheat_sheet = false;
+    }
+}
+
+// --- Freeze ---
+// Stops everything driven by virtual time: the fixed-rate analysis, LFOs and
+// animations. The camera can still be moved, e.g. to frame a screenshot.
+fn toggle_freeze(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    freeze_state: Res<State<FreezeState>>,
+    mut next_freeze_state: ResMut<NextState<FreezeState>>,
+) {
+    if bindings.just_pressed(KeyAction::Freeze, &keyboard) {
+        next_freeze_state.set(match freeze_state.get() {
+            FreezeState::Live => FreezeState::Frozen,
+            FreezeState::Frozen => FreezeState::Live,
+        });
+    }
+}
+
+fn pause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_virtual_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn unfreeze(mut next_freeze_state: ResMut<NextState<FreezeState>>) {
+    next_freeze_state.set(FreezeState::Live);
+}
+
+// --- Idle Auto-Hide ---
+// In fullscreen visualizations, hides the cursor and panels after a few idle
+// seconds; any mouse or keyboard input brings them back.
+#[allow(clippy::too_many_arguments)]
+fn auto_hide_when_idle(
+    config: Res<VisualsConfig>,
+    app_state: Res<State<AppState>>,
+    time: Res<Time>,
+    mut ev_motion: EventReader<MouseMotion>,
+    mut ev_scroll: EventReader<MouseWheel>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut ui_visibility: ResMut<UiVisibility>,
+    mut idle_secs: Local<f32>,
+) {
+    let Ok(mut window) = q_windows.get_single_mut() else {
+        return;
+    };
+
+    let active = ev_motion.read().count() > 0
+        || ev_scroll.read().count() > 0
+        || mouse.get_pressed().next().is_some()
+        || keyboard.get_pressed().next().is_some();
+    let eligible = config.general.auto_hide_enabled
+        && window.mode != WindowMode::Windowed
+        && VisualizerId::from_state(app_state.get()).is_some();
+
+    if active || !eligible {
+        *idle_secs = 0.0;
+    } else {
+        *idle_secs += time.delta_seconds();
+    }
+
+    let hide = *idle_secs >= config.general.auto_hide_delay;
+    if ui_visibility.idle_hidden != hide {
+        ui_visibility.idle_hidden = hide;
+    }
+    if window.cursor.visible == hide {
+        window.cursor.visible = !hide;
+    }
+}
+
+// --- Theme System ---
+// Rebuilds the egui style, and scales egui and the Bevy UI, whenever the
+// theme settings change.
+fn apply_ui_theme(
+    mut contexts: EguiContexts,
+    config: Res<VisualsConfig>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut ui_scale: ResMut<UiScale>,
+    mut applied: Local<Option<ThemeSettings>>,
+) {
+    let theme = &config.general.theme;
+    if applied.as_ref() == Some(theme) {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    // Rescaling under the dragged slider would move it away from the
+    // pointer, so a new scale waits for the drag to end.
+    let rescaled = applied.as_ref().map(|old| old.ui_scale) != Some(theme.ui_scale);
+    if rescaled && ctx.input(|input| input.pointer.any_down()) {
+        return;
+    }
+    let scale = theme
+        .ui_scale
+        .clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+    egui_settings.scale_factor = scale;
+    ui_scale.0 = scale;
+
+    let mut visuals = if theme.dark_mode {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    };
+
+    let accent = to_egui_color(theme.accent_color);
+    visuals.selection.bg_fill = accent;
+    visuals.hyperlink_color = accent;
+    visuals.widgets.active.bg_fill = accent;
+    visuals.widgets.hovered.bg_stroke.color = accent;
+
+    let alpha = (theme.panel_opacity.clamp(0.0, 1.0) * 255.0) as u8;
+    let with_alpha =
+        |c: egui::Color32| egui::Color32::from_rgba_unmultiplied(c.r(), c.g(), c.b(), alpha);
+    visuals.panel_fill = with_alpha(visuals.panel_fill);
+    visuals.window_fill = with_alpha(visuals.window_fill);
+
+    let mut style = (*ctx.style()).clone();
+    style.visuals = visuals;
+    style.text_styles = egui::Style::default()
+        .text_styles
+        .into_iter()
+        .map(|(text_style, mut font)| {
+            font.size *= theme.font_scale;
+            (text_style, font)
+        })
+        .collect();
+    ctx.set_style(style);
+
+    *applied = Some(theme.clone());
 }
 
 // --- Main UI System (Layout & Content) ---
@@ -104,12 +504,59 @@ fn main_ui_layout(
     mut next_app_state: ResMut<NextState<AppState>>,
     mut active_viz: ResMut<ActiveVisualization>,
     q_windows: Query<Entity, With<PrimaryWindow>>,
+    mut settings_search: Local<String>,
+    (mut tempo, lfo_bank, mut timeline, governor, active_quality): (
+        ResMut<Tempo>,
+        Res<LfoBank>,
+        ResMut<Timeline>,
+        Res<QualityGovernor>,
+        Res<ActiveQuality>,
+    ),
+    real_time: Res<Time<Real>>,
+    (
+        mut recorder,
+        toast_sender,
+        mut hue_bridge,
+        mic_latency,
+        mut library,
+        backend,
+        offline,
+        mut project_files,
+        thumbnails,
+        settings_window,
+    ): (
+        ResMut<SessionRecorder>,
+        Res<ToastSender>,
+        ResMut<HueBridge>,
+        Res<MicLatency>,
+        ResMut<Library>,
+        Res<Backend>,
+        Res<OfflineAnalysis>,
+        ResMut<ProjectFiles>,
+        Res<Thumbnails>,
+        Res<SettingsWindow>,
+    ),
 ) {
     if q_windows.get_single().is_err() {
         return;
     }
 
+    let detached_ctx = settings_window
+        .window
+        .and_then(|window| contexts.try_ctx_for_window_mut(window))
+        .cloned();
     let ctx = contexts.ctx_mut();
+    let lang = config.general.language;
+    let t = |text: &'static str| lang.tr(text);
+    let current_state = app_state.get();
+
+    // In a window of its own, the panel stays usable with the UI hidden.
+    if let Some(detached) = &detached_ctx {
+        detached.set_style(ctx.style());
+        egui::CentralPanel::default().show(detached, |ui| {
+            visualizer_settings_panel(ui, lang, &mut settings_search, &mut config, current_state);
+        });
+    }
 
     // 1. LOGIC WHEN UI IS HIDDEN
     if !ui_visibility.visible {
@@ -126,163 +573,92 @@ fn main_ui_layout(
                     ui.visuals_mut().widgets.noninteractive.fg_stroke =
                         egui::Stroke::new(1.0, egui::Color32::WHITE);
                     egui::Frame::default().inner_margin(8.0).show(ui, |ui| {
-                        ui.label(egui::RichText::new("Press 'H' to Show UI").size(16.0));
+                        ui.label(egui::RichText::new(t("Press 'H' to Show UI")).size(16.0));
                     });
                 });
         }
         // Stop here, don't render panels
         return;
     }
+    if ui_visibility.idle_hidden {
+        return;
+    }
 
     // 2. LOGIC WHEN UI IS VISIBLE (Panels)
-    let current_state = app_state.get();
+    // The panels are drawn over the visuals, which keep rendering at the full
+    // window size; "Panel Opacity" makes them see-through.
 
     // --- LEFT PANEL: Active Visualizer Settings ---
-    egui::SidePanel::left("viz_settings_panel")
-        .resizable(true)
-        .default_width(250.0)
-        .show(ctx, |ui| {
-            ui.add_space(10.0);
-            ui.heading("🎨 Visualizer Settings");
-            ui.separator();
-
-            // Global Parameter
-            ui.label("Amplitude Sensitivity");
-            ui.add(egui::Slider::new(&mut config.bass_sensitivity, 0.1..=10.0));
-
-            ui.separator();
-
-            // Contextual Parameters
-            egui::ScrollArea::vertical().show(ui, |ui| match current_state {
-                AppState::Visualization2D => {
-                    ui.label("Inactive Color");
-                    color_picker_widget(ui, &mut config.viz2d_inactive_color);
-                    ui.label("Active Color");
-                    color_picker_widget(ui, &mut config.viz2d_active_color);
-
-                    ui.separator();
-                    ui.label("Frequency Bands (Rebuilds Grid)");
-                    ui.add(egui::Slider::new(&mut config.num_bands, 4..=64));
-                }
-                AppState::Visualization3D => {
-                    ui.checkbox(&mut config.spread_enabled, "Spread Effect");
-                    ui.label("Column Size");
-                    ui.add(egui::Slider::new(&mut config.viz3d_column_size, 1..=16));
-                    ui.label("Cube Base Color");
-                    color_picker_widget(ui, &mut config.viz3d_base_color);
-
-                    ui.separator();
-                    ui.label("Frequency Bands (Rebuilds Grid)");
-                    ui.add(egui::Slider::new(&mut config.num_bands, 4..=32));
-
-                    ui.separator();
-                    render_bloom_ui(ui, &mut config);
-                }
-                AppState::VisualizationOrb => {
-                    ui.label("Base Color");
-                    color_picker_widget(ui, &mut config.orb_base_color);
-                    ui.label("Peak Color");
-                    color_picker_widget(ui, &mut config.orb_peak_color);
-
-                    ui.separator();
-                    ui.label("Noise Speed");
-                    ui.add(egui::Slider::new(&mut config.orb_noise_speed, 0.1..=5.0));
-                    ui.label("Noise Frequency");
-                    ui.add(egui::Slider::new(
-                        &mut config.orb_noise_frequency,
-                        0.5..=10.0,
-                    ));
-                    ui.label("Treble Influence");
-                    ui.add(egui::Slider::new(
-                        &mut config.orb_treble_influence,
-                        0.0..=1.0,
-                    ));
-
-                    ui.separator();
-                    render_bloom_ui(ui, &mut config);
-                }
-                AppState::VisualizationDisc => {
-                    ui.label("Disc Color");
-                    color_picker_widget(ui, &mut config.disc_color);
-
-                    ui.label("Radius");
-                    ui.add(egui::Slider::new(&mut config.disc_radius, 0.1..=2.0));
-
-                    ui.label("Line Thickness");
-                    ui.add(egui::Slider::new(
-                        &mut config.disc_line_thickness,
-                        0.01..=0.5,
-                    ));
-
-                    ui.label("Iterations (Echoes)");
-                    ui.add(egui::Slider::new(&mut config.disc_iterations, 1..=50));
-
-                    ui.label("Rotation Speed");
-                    ui.add(egui::Slider::new(&mut config.disc_speed, -5.0..=5.0));
-
-                    ui.label("Center Factor");
-                    ui.add(egui::Slider::new(
-                        &mut config.disc_center_radius_factor,
-                        -1.0..=2.0,
-                    ));
-                }
-                AppState::VisualizationIco => {
-                    ui.label("Metallic Color");
-                    color_picker_widget(ui, &mut config.ico_color);
-
-                    ui.label("Rotation Speed");
-                    ui.add(egui::Slider::new(&mut config.ico_speed, -3.0..=3.0));
-                }
-                _ => {}
-            });
-        });
+    match config.general.settings_panel {
+        PanelPlacement::Docked => {
+            egui::SidePanel::left("viz_settings_panel")
+                .resizable(true)
+                .default_width(250.0)
+                .show_animated(ctx, ui_visibility.left_panel, |ui| {
+                    visualizer_settings_panel(
+                        ui,
+                        lang,
+                        &mut settings_search,
+                        &mut config,
+                        current_state,
+                    );
+                });
+        }
+        // Movable anywhere over the visuals.
+        PanelPlacement::Floating if ui_visibility.left_panel => {
+            egui::Window::new(t("🎨 Visualizer Settings"))
+                .id(egui::Id::new("viz_settings_window"))
+                .title_bar(false)
+                .default_pos([10.0, 10.0])
+                .default_width(250.0)
+                .default_height(500.0)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    visualizer_settings_panel(
+                        ui,
+                        lang,
+                        &mut settings_search,
+                        &mut config,
+                        current_state,
+                    );
+                });
+        }
+        PanelPlacement::Floating | PanelPlacement::Window => {}
+    }
 
     // --- RIGHT PANEL: Global Controls ---
     egui::SidePanel::right("global_controls_panel")
         .resizable(true)
         .default_width(250.0)
-        .show(ctx, |ui| {
+        .show_animated(ctx, ui_visibility.right_panel, |ui| {
             ui.add_space(10.0);
-            ui.heading("🎛 Controls");
+            ui.heading(t("🎛 Controls"));
             ui.separator();
 
             // Visualizer Choice
-            ui.label("Select Visualizer:");
+            ui.label(t("Select Visualizer:"));
+            // A preview of each visualizer once one was taken, see `thumbnails.rs`.
             ui.horizontal_wrapped(|ui| {
-                if ui
-                    .selectable_label(*current_state == AppState::Visualization2D, "2D Bars")
-                    .clicked()
-                {
-                    next_app_state.set(AppState::Visualization2D);
-                    active_viz.0 = AppState::Visualization2D;
-                }
-                if ui
-                    .selectable_label(*current_state == AppState::Visualization3D, "3D Cubes")
-                    .clicked()
-                {
-                    next_app_state.set(AppState::Visualization3D);
-                    active_viz.0 = AppState::Visualization3D;
-                }
-                if ui
-                    .selectable_label(*current_state == AppState::VisualizationOrb, "3D Orb")
-                    .clicked()
-                {
-                    next_app_state.set(AppState::VisualizationOrb);
-                    active_viz.0 = AppState::VisualizationOrb;
-                }
-                if ui
-                    .selectable_label(*current_state == AppState::VisualizationDisc, "Disc")
-                    .clicked()
-                {
-                    next_app_state.set(AppState::VisualizationDisc);
-                    active_viz.0 = AppState::VisualizationDisc;
-                }
-                if ui
-                    .selectable_label(*current_state == AppState::VisualizationIco, "Ico")
-                    .clicked()
-                {
-                    next_app_state.set(AppState::VisualizationIco);
-                    active_viz.0 = AppState::VisualizationIco;
+                for id in config.general.visualizers.shown() {
+                    let selected = *current_state == id.state();
+                    let clicked = match thumbnails.texture(id) {
+                        Some(texture) => {
+                            let size = egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32);
+                            ui.vertical(|ui| {
+                                let image = egui::load::SizedTexture::new(texture, size);
+                                let button =
+                                    ui.add(egui::ImageButton::new(image).selected(selected));
+                                ui.small(t(viz_label(id)));
+                                button.clicked()
+                            })
+                            .inner
+                        }
+                        None => ui.selectable_label(selected, t(viz_label(id))).clicked(),
+                    };
+                    if clicked {
+                        next_app_state.set(id.state());
+                        active_viz.0 = id.state();
+                    }
                 }
             });
 
@@ -290,34 +666,62 @@ fn main_ui_layout(
 
             // Global Toggle On/Off
             let btn_text = if viz_enabled.0 {
-                "⏹ Stop Render"
+                t("⏹ Stop Render")
             } else {
-                "▶ Start Render"
+                t("▶ Start Render")
             };
             if ui.button(btn_text).clicked() {
                 viz_enabled.0 = !viz_enabled.0;
             }
 
             ui.separator();
-            ui.heading("🎵 Audio Source");
+            ui.heading(t("🎵 Audio Source"));
 
-            if ui.button("🎤 Microphone").clicked() {
+            if ui.button(t("🎤 Microphone")).clicked() {
                 selected_source.0 = AudioSource::Microphone;
             }
 
-            if ui.button("📂 Load File").clicked() {
+            if ui.button(t("📂 Load File")).clicked() {
                 if let Some(path) = rfd::FileDialog::new()
                     .add_filter("audio", &["mp3", "wav"])
+                    .add_filter("recording", &[RECORDING_EXTENSION])
                     .pick_file()
                 {
-                    selected_source.0 = AudioSource::File(path);
+                    selected_source.0 = source_for(path);
                 }
             }
+            if ui.selectable_label(library.open, t("📚 Library")).clicked() {
+                library.open = !library.open;
+            }
+            // A project bundles the settings, the playlist and its timelines.
+            ui.horizontal(|ui| {
+                if ui.button(t("💾 Save Project")).clicked() {
+                    project_files.save_requested = true;
+                }
+                if ui.button(t("📂 Open Project")).clicked() {
+                    project_files.open_requested = true;
+                }
+            });
+
+            // Recording captures the analysis frames, to replay them later without audio.
+            ui.horizontal(|ui| {
+                let label = if recorder.active {
+                    t("⏹ Stop Recording")
+                } else {
+                    t("⏺ Record Analysis")
+                };
+                if ui.button(label).clicked() {
+                    recorder.active = !recorder.active;
+                }
+                if recorder.active {
+                    ui.label(format!("{}", recorder.frame_count()));
+                }
+            });
 
             // Playback Controls (If file)
-            if let AudioSource::File(_) = selected_source.0 {
+            if let AudioSource::File(path) = &selected_source.0 {
                 ui.separator();
-                ui.label("Playback:");
+                ui.label(t("Playback:"));
                 ui.horizontal(|ui| {
                     let icon = if playback_info.status == PlaybackStatus::Playing {
                         "⏸"
@@ -332,12 +736,22 @@ fn main_ui_layout(
                     }
 
                     // Speed
-                    ui.label("Speed:");
+                    ui.label(t("Speed:"));
                     ui.add(egui::Slider::new(&mut playback_info.speed, 0.25..=2.0).text("x"));
                 });
+                ui.checkbox(&mut config.general.preserve_pitch, t("Preserve Pitch"));
 
                 // Progress Bar
                 if playback_info.duration > Duration::ZERO {
+                    let track = offline.track_for(path);
+                    let beats = track.map_or(&[][..], |track| track.beats());
+                    let sections = track.map_or(&[][..], |track| track.sections());
+                    let snap = |time: f32| match track {
+                        Some(track) if config.general.snap_to_beats => {
+                            track.nearest_beat(time).unwrap_or(time)
+                        }
+                        _ => time,
+                    };
                     let total = playback_info.duration.as_secs_f32();
                     let mut pos = playback_info.position.as_secs_f32();
                     let label = format!("{:.0}s / {:.0}s", pos, total);
@@ -349,232 +763,2116 @@ fn main_ui_layout(
                         )
                         .changed()
                     {
-                        playback_info.seek_to = Some(pos);
+                        playback_info.seek_to = Some(snap(pos));
+                    }
+                    if let Some(time) = beat_ticks(ui, beats, sections, total) {
+                        playback_info.seek_to = Some(time);
+                    }
+                    if let Some(time) = cue_ticks(ui, &timeline.cues, total) {
+                        playback_info.seek_to = Some(time);
+                    }
+                    if !beats.is_empty() {
+                        ui.checkbox(&mut config.general.snap_to_beats, t("Snap to Beats"));
+                    }
+                }
+
+                // Analyses the whole file in the background with the current settings.
+                if ui.button(t("💾 Export Analysis")).clicked() {
+                    if let Some(destination) = rfd::FileDialog::new()
+                        .add_filter("CSV", &["csv"])
+                        .add_filter("JSON", &["json"])
+                        .set_file_name("analysis.csv")
+                        .save_file()
+                    {
+                        let viz_id = VisualizerId::from_state(app_state.get())
+                            .unwrap_or(VisualizerId::Bars2D);
+                        let settings = ExportSettings {
+                            track: TrackSettings::new(&config, viz_id),
+                            crossovers: config.general.crossovers,
+                            smoothing: config.general.smoothing.clone(),
+                        };
+                        spawn_export(
+                            path.clone(),
+                            destination,
+                            settings,
+                            backend.clone(),
+                            toast_sender.0.clone(),
+                        );
                     }
                 }
-            }
 
-            ui.separator();
-            ui.checkbox(&mut config.details_panel_enabled, "Show Analysis Data");
+                egui::CollapsingHeader::new(t("🎞 Timeline")).show(ui, |ui| {
+                    let position = playback_info.position.as_secs_f32();
+                    let current = VisualizerId::from_state(app_state.get());
+                    // Only real edits count as changes, so automation isn't
+                    // reapplied on every frame the panel is drawn.
+                    let edited = timeline_editor(
+                        ui,
+                        lang,
+                        timeline.bypass_change_detection(),
+                        &config,
+                        position,
+                        current,
+                    );
+                    if edited {
+                        timeline.set_changed();
+                    }
+                });
 
-            // Integrated details panel
-            if config.details_panel_enabled {
-                ui.separator();
-                ui.label(egui::RichText::new("Analysis Data").strong());
-                ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
-                ui.label(format!("Volume: {:.3}", audio_analysis.volume));
-                ui.label(format!("Bass:   {:.2}", audio_analysis.bass));
-                ui.label(format!("Mid:    {:.2}", audio_analysis.mid));
-                ui.label(format!("Treble: {:.2}", audio_analysis.treble));
-                ui.label(format!("Flux:   {:.2}", audio_analysis.flux));
+                egui::CollapsingHeader::new(t("📍 Cue Points")).show(ui, |ui| {
+                    let edited = cue_editor(
+                        ui,
+                        lang,
+                        timeline.bypass_change_detection(),
+                        &mut playback_info,
+                    );
+                    if edited {
+                        timeline.set_changed();
+                    }
+                });
             }
 
-            // --- BOTTOM SECTION: Hide UI Hint ---
-            ui.add_space(20.0);
             ui.separator();
-            ui.vertical_centered(|ui| {
-                ui.label(egui::RichText::new("Press 'H' to Hide UI").weak().italics());
+            egui::CollapsingHeader::new(t("📊 Frequency Range")).show(ui, |ui| {
+                let general = &mut config.general;
+                ui.add(
+                    egui::Slider::new(&mut general.min_frequency, 20.0..=10000.0)
+                        .logarithmic(true)
+                        .suffix(" Hz")
+                        .text(t("Min")),
+                );
+                ui.add(
+                    egui::Slider::new(&mut general.max_frequency, 40.0..=20000.0)
+                        .logarithmic(true)
+                        .suffix(" Hz")
+                        .text(t("Max")),
+                );
+                // Keep at least an octave between the two ends.
+                general.max_frequency = general.max_frequency.max(general.min_frequency * 2.0);
+                SettingsSection::new(ui, "").choice(
+                    t("Weighting"),
+                    &mut general.weighting,
+                    &[
+                        (SpectrumWeighting::Off, t("Off")),
+                        (SpectrumWeighting::A, t("A-Weighting")),
+                        (SpectrumWeighting::K, t("K-Weighting")),
+                    ],
+                );
+                ui.label(t("Crossovers"));
+                let crossovers = &mut general.crossovers;
+                for (value, range, label) in [
+                    (&mut crossovers.sub_punch, 30.0..=120.0, t("Sub / Punch")),
+                    (&mut crossovers.bass_mid, 80.0..=500.0, t("Bass / Mid")),
+                    (
+                        &mut crossovers.mid_treble,
+                        1000.0..=12000.0,
+                        t("Mid / Treble"),
+                    ),
+                ] {
+                    ui.add(
+                        egui::Slider::new(value, range)
+                            .logarithmic(true)
+                            .suffix(" Hz")
+                            .text(label),
+                    );
+                }
+                // Keep the ranges in order.
+                crossovers.bass_mid = crossovers.bass_mid.max(crossovers.sub_punch);
+                crossovers.mid_treble = crossovers.mid_treble.max(crossovers.bass_mid);
+
+                // The edges belong to the current visualizer, and its presets.
+                if let Some(id) = VisualizerId::from_state(current_state) {
+                    let (general, settings) = config.general_and_settings_mut(id);
+                    let mut custom = settings.band_edges().is_some();
+                    if ui.checkbox(&mut custom, t("Custom Band Edges")).changed() {
+                        *settings.band_edges_mut() = if custom {
+                            log_band_edges(
+                                settings.num_bands(),
+                                general.min_frequency,
+                                general.max_frequency,
+                            )
+                        } else {
+                            Vec::new()
+                        };
+                    }
+                    if custom {
+                        band_edges_plot(ui, &audio_analysis, settings.band_edges_mut());
+                        ui.weak(t("Drag the lines to move the edges of the bands."));
+                    }
+                }
             });
-        });
-}
 
-fn render_bloom_ui(ui: &mut egui::Ui, config: &mut VisualsConfig) {
-    ui.heading("✨ Bloom");
-    ui.checkbox(&mut config.bloom_enabled, "Enable");
-    if config.bloom_enabled {
-        ui.label("Intensity");
-        ui.add(egui::Slider::new(&mut config.bloom_intensity, 0.0..=1.0));
-        ui.label("Threshold");
-        ui.add(egui::Slider::new(&mut config.bloom_threshold, 0.0..=2.0));
-        ui.label("Tint");
-        color_picker_widget(ui, &mut config.bloom_color);
-    }
-}
+            egui::CollapsingHeader::new(t("🎙 Source Mix")).show(ui, |ui| {
+                let mix = &mut config.general.mix;
+                let mut section = SettingsSection::new(ui, "");
+                section.checkbox(t("Mix Microphone With File"), &mut mix.mic_with_file);
+                section.slider(t("File Gain"), &mut mix.file_gain, 0.0..=2.0);
+                section.slider(t("Mic Gain"), &mut mix.mic_gain, 0.0..=2.0);
+                mic_buffer_setting(&mut section, lang, &mut config.general.mic_buffer_frames);
+                if let Some(latency) = mic_latency.get() {
+                    ui.label(format!(
+                        "{} {:.1} ms",
+                        t("Mic Latency:"),
+                        latency.as_secs_f32() * 1000.0
+                    ));
+                }
 
-// Adaptation for egui 0.27+ and Bevy Color
-fn color_picker_widget(ui: &mut egui::Ui, color: &mut Color) {
-    // 1. Convert Bevy Color -> [f32; 4]
-    let rgba_array = [color.r(), color.g(), color.b(), color.a()];
+                let monitor = &mut config.general.monitor;
+                ui.checkbox(&mut monitor.enabled, t("Monitor Microphone"));
+                if monitor.enabled {
+                    ui.add(egui::Slider::new(&mut monitor.gain, 0.0..=2.0).text(t("Monitor Gain")));
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ {} {:.0} ms",
+                            t("Monitoring is delayed by up to"),
+                            MONITOR_MAX_LATENCY_SECS * 1000.0
+                        ),
+                    );
+                }
+            });
 
-    // 2. Create Egui color (Rgba is premultiplied by default)
-    let mut egui_color = egui::Rgba::from_rgba_unmultiplied(
-        rgba_array[0],
-        rgba_array[1],
-        rgba_array[2],
-        rgba_array[3],
-    );
+            egui::CollapsingHeader::new(t("🎚 Equalizer")).show(ui, |ui| {
+                let eq = &mut config.general.eq;
+                ui.checkbox(&mut eq.enabled, t("EQ Enabled"));
+                ui.checkbox(&mut eq.playback, t("Apply to Playback"));
+                ui.add_enabled_ui(eq.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        for (gain, frequency) in eq.gains.iter_mut().zip(EQ_FREQUENCIES) {
+                            ui.vertical(|ui| {
+                                ui.add(
+                                    egui::Slider::new(gain, -12.0..=12.0)
+                                        .vertical()
+                                        .show_value(false),
+                                );
+                                ui.small(eq_band_label(frequency));
+                            });
+                        }
+                    });
+                    if ui.button(t("Reset")).clicked() {
+                        eq.gains = Default::default();
+                    }
+                });
+            });
 
-    // 3. Display Widget
-    if color_picker::color_edit_button_rgba(ui, &mut egui_color, color_picker::Alpha::Opaque)
-        .changed()
-    {
-        // 4. Convert back to Bevy Color
-        let result = egui_color.to_rgba_unmultiplied();
-        *color = Color::rgba(result[0], result[1], result[2], result[3]);
-    }
-}
+            egui::CollapsingHeader::new(t("⏱ Sync")).show(ui, |ui| {
+                let general = &mut config.general;
+                ui.checkbox(&mut general.auto_latency, t("Auto Latency"));
+                ui.add(
+                    egui::Slider::new(&mut general.latency_offset_ms, -200.0..=500.0)
+                        .suffix(" ms")
+                        .text(t("Visual Offset")),
+                );
+                ui.checkbox(&mut general.analyse_ahead, t("Analyse Files Ahead"));
+                if offline.is_analysing() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(t("Analysing..."));
+                    });
+                }
+                if general.analyse_ahead {
+                    let shown = general.visualizers.shown();
+                    scene_switch_settings(ui, lang, &mut general.scene_switch, shown);
+                }
+                ui.separator();
 
-// --- Setup Main Menu (Unchanged) ---
-fn setup_main_menu(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), MainMenuUI));
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    flex_direction: FlexDirection::Column,
-                    row_gap: Val::Px(15.0),
-                    ..default()
-                },
-                ..default()
-            },
-            MainMenuUI,
-        ))
-        .with_children(|parent| {
-            create_menu_button(parent, "Start Visualization", MenuButtonAction::Start);
-            create_menu_button(
-                parent,
-                "Select Microphone",
-                MenuButtonAction::ToMicSelection,
-            );
-        });
-}
+                ui.heading(t("💬 Discord"));
+                discord_section(ui, lang, &mut general.discord);
+            });
 
-#[allow(clippy::type_complexity)]
-fn menu_button_interaction(
-    mut button_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut next_app_state: ResMut<NextState<AppState>>,
-    active_viz: Res<ActiveVisualization>,
+            egui::CollapsingHeader::new(t("∿ LFOs")).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut tempo.bpm)
+                            .clamp_range(30.0..=300.0)
+                            .suffix(" BPM"),
+                    );
+                    if ui.button(t("Tap (T)")).clicked() {
+                        tempo.tap(real_time.elapsed());
+                    }
+                });
+                for (i, lfo) in config.general.lfos.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| lfo_row(ui, lang, i, lfo, lfo_bank.value(i)));
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🎯 Tone Detectors")).show(ui, |ui| {
+                let detectors = &mut config.general.tone_detectors;
+                let mut removed = None;
+                for (i, detector) in detectors.iter_mut().enumerate() {
+                    ui.push_id(("tone", i), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut detector.name).desired_width(80.0),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut detector.frequency)
+                                    .clamp_range(20.0..=20000.0)
+                                    .suffix(" Hz"),
+                            );
+                            let level = audio_analysis.tones.get(i).copied().unwrap_or(0.0);
+                            ui.add(egui::ProgressBar::new(level).desired_width(40.0));
+                            if ui.small_button("✖").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    });
+                }
+                if let Some(i) = removed {
+                    detectors.remove(i);
+                }
+                if detectors.len() < MAX_TONE_DETECTORS && ui.button(t("➕ Add Detector")).clicked()
+                {
+                    detectors.push(ToneDetector {
+                        name: format!("Tone {}", detectors.len() + 1),
+                        frequency: 440.0,
+                    });
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🎛 Macros")).show(ui, |ui| {
+                let current = VisualizerId::from_state(current_state);
+                for (i, knob) in config.general.macros.iter_mut().enumerate() {
+                    ui.push_id(("macro", i), |ui| macro_row(ui, lang, i, knob, current));
+                }
+            });
+
+            egui::CollapsingHeader::new(t("〰 Smoothing")).show(ui, |ui| {
+                let general = &mut config.general;
+                let smoothing = &mut general.smoothing;
+                egui::Grid::new("smoothing_grid")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.label(t("Attack"));
+                        ui.label(t("Release"));
+                        ui.end_row();
+                        for (label, envelope) in [
+                            (t("Bands"), &mut smoothing.bins),
+                            (t("Bass"), &mut smoothing.bass),
+                            (t("Mid"), &mut smoothing.mid),
+                            (t("Treble"), &mut smoothing.treble),
+                            (t("Volume"), &mut smoothing.volume),
+                            (t("Peak"), &mut smoothing.peak),
+                        ] {
+                            ui.label(label);
+                            ui.add(envelope_drag(&mut envelope.attack));
+                            ui.add(envelope_drag(&mut envelope.release));
+                            ui.end_row();
+                        }
+                    });
+                let mut section = SettingsSection::new(ui, "");
+                section.choice(
+                    t("Between Ticks"),
+                    &mut general.interpolation,
+                    &[
+                        (AnalysisInterpolation::Off, t("Step")),
+                        (AnalysisInterpolation::Interpolate, t("Interpolate")),
+                        (AnalysisInterpolation::Extrapolate, t("Extrapolate")),
+                    ],
+                );
+                section.checkbox(t("Normalize Bands"), &mut smoothing.normalize_bands);
+                if smoothing.normalize_bands {
+                    section.slider(
+                        t("Peak Half-Life (s)"),
+                        &mut smoothing.normalize_release,
+                        0.5..=30.0,
+                    );
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🔇 Silence Gate")).show(ui, |ui| {
+                let gate = &mut config.general.silence_gate;
+                let mut section = SettingsSection::new(ui, "");
+                section.checkbox(t("Gate Enabled"), &mut gate.enabled);
+                if gate.enabled {
+                    section.slider(t("Threshold (dB)"), &mut gate.threshold_db, -90.0..=-20.0);
+                    section.slider(t("Hold (s)"), &mut gate.hold, 0.0..=5.0);
+                    section.checkbox(t("Pause Visuals When Silent"), &mut gate.pause_visuals);
+                }
+            });
+
+            ui.separator();
+            egui::CollapsingHeader::new(t("🖌 Appearance")).show(ui, |ui| {
+                let general = &mut config.general;
+                appearance_settings(ui, lang, general);
+                let mut section = SettingsSection::new(ui, "");
+                section.checkbox(t("Auto-Hide in Fullscreen"), &mut general.auto_hide_enabled);
+                if general.auto_hide_enabled {
+                    section.slider(
+                        t("Idle Delay (s)"),
+                        &mut general.auto_hide_delay,
+                        1.0..=30.0,
+                    );
+                }
+                section.checkbox(t("Idle Animation in Silence"), &mut general.idle.enabled);
+                if general.idle.enabled {
+                    section.slider(
+                        t("Silence Before Idle (s)"),
+                        &mut general.idle.delay,
+                        1.0..=60.0,
+                    );
+                }
+            });
+
+            egui::CollapsingHeader::new(t("📺 Now Playing Overlay")).show(ui, |ui| {
+                let overlay = &mut config.general.now_playing;
+                ui.checkbox(&mut overlay.enabled, t("Show When UI Is Hidden"));
+                egui::ComboBox::from_label(t("Corner"))
+                    .selected_text(t(corner_label(overlay.corner)))
+                    .show_ui(ui, |ui| {
+                        for corner in OverlayCorner::ALL {
+                            ui.selectable_value(
+                                &mut overlay.corner,
+                                corner,
+                                t(corner_label(corner)),
+                            );
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut overlay.opacity, 0.1..=1.0).text(t("Opacity")));
+            });
+
+            egui::CollapsingHeader::new(t("⚡ Strobe")).show(ui, |ui| {
+                let strobe = &mut config.general.strobe;
+                let mut section = SettingsSection::new(ui, "");
+                section.checkbox(t("Flash on Beats"), &mut strobe.enabled);
+                section.color(t("Flash Color"), &mut strobe.color);
+                section.slider(t("Intensity"), &mut strobe.intensity, 0.0..=1.0);
+                section.slider(t("Flash Decay (s)"), &mut strobe.decay, 0.05..=1.0);
+                section.slider(
+                    t("Max Flashes per Second"),
+                    &mut strobe.max_rate,
+                    0.5..=StrobeSettings::MAX_SAFE_RATE,
+                );
+            });
+
+            egui::CollapsingHeader::new(t("🎨 Palettes")).show(ui, |ui| {
+                let current = VisualizerId::from_state(current_state);
+                for (i, palette) in PALETTES.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        for color in palette.colors {
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, to_egui_color(color));
+                        }
+                        let selected = config.general.palette.current == i;
+                        if ui.selectable_label(selected, t(palette.name)).clicked() {
+                            if let Some(id) = current {
+                                apply_palette(&mut config, id, i);
+                            }
+                        }
+                    });
+                }
+                let palette = &mut config.general.palette;
+                let mut section = SettingsSection::new(ui, "");
+                section.choice(
+                    t("Cycle"),
+                    &mut palette.cycle,
+                    &[
+                        (PaletteCycle::Off, t("Off")),
+                        (PaletteCycle::Bars, t("Every Few Bars")),
+                        (PaletteCycle::Sections, t("On Section Changes")),
+                    ],
+                );
+                if palette.cycle == PaletteCycle::Bars {
+                    section.slider(t("Bars"), &mut palette.cycle_bars, 1..=32);
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🌈 Color Grading")).show(ui, |ui| {
+                let grading = &mut config.general.grading;
+                let mut section = SettingsSection::new(ui, "");
+                section.slider(t("Hue Shift (°)"), &mut grading.hue_shift, -180.0..=180.0);
+                section.slider(t("Saturation"), &mut grading.saturation, 0.0..=2.0);
+                section.slider(t("Contrast"), &mut grading.contrast, 0.5..=2.0);
+                section.slider(t("Gamma"), &mut grading.gamma, 0.5..=2.5);
+                section.checkbox(t("Rotate Hue Slowly"), &mut grading.auto_hue);
+                if grading.auto_hue {
+                    section.slider(
+                        t("Rotation Speed (°/s)"),
+                        &mut grading.auto_hue_speed,
+                        1.0..=90.0,
+                    );
+                }
+                if ui.button(t("Reset")).clicked() {
+                    *grading = default();
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🔋 Power Saving")).show(ui, |ui| {
+                // Orb detail, cube count, bloom, long FFT and frame rate.
+                let mut section = SettingsSection::new(ui, "");
+                section.choice(
+                    t("Quality"),
+                    &mut config.general.quality,
+                    &[
+                        (Quality::Low, t(quality_label(Quality::Low))),
+                        (Quality::Medium, t(quality_label(Quality::Medium))),
+                        (Quality::High, t(quality_label(Quality::High))),
+                    ],
+                );
+                let adaptive = &mut config.general.adaptive_quality;
+                section.checkbox(t("Adaptive Quality"), &mut adaptive.enabled);
+                if adaptive.enabled {
+                    section.slider(t("Target FPS"), &mut adaptive.target_fps, 20.0..=144.0);
+                    if governor.limit.is_some() {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!(
+                                "⚠ {} {} ({:.0} FPS)",
+                                t("Lowered to"),
+                                t(quality_label(active_quality.0)),
+                                governor.fps
+                            ),
+                        );
+                    }
+                }
+                let background = &mut config.general.background;
+                egui::ComboBox::from_label(t("When Unfocused"))
+                    .selected_text(t(background_label(background.mode)))
+                    .show_ui(ui, |ui| {
+                        for mode in BackgroundMode::ALL {
+                            ui.selectable_value(
+                                &mut background.mode,
+                                mode,
+                                t(background_label(mode)),
+                            );
+                        }
+                    });
+                if background.mode == BackgroundMode::Throttled {
+                    ui.add(egui::Slider::new(&mut background.fps, 1.0..=30.0).text(t("FPS")));
+                }
+            });
+
+            egui::CollapsingHeader::new(t("📡 Remote Control")).show(ui, |ui| {
+                let remote = &mut config.general.remote;
+                ui.checkbox(&mut remote.enabled, t("WebSocket Server"));
+                ui.horizontal(|ui| {
+                    ui.label(t("Port"));
+                    ui.add(egui::DragValue::new(&mut remote.port).clamp_range(1024..=65535));
+                });
+                ui.checkbox(&mut remote.allow_network, t("Allow Network Access"))
+                    .on_hover_text(t(
+                        "Anyone on the network can then control the app, without a password.",
+                    ));
+                if remote.allow_network {
+                    ui.label(t("Allowed Web Pages"))
+                        .on_hover_text(t(
+                            "Origins of the control pages allowed to connect, e.g. http://192.168.1.20:8080. Other web pages are refused.",
+                        ));
+                    let mut removed = None;
+                    for (i, origin) in remote.allowed_origins.iter_mut().enumerate() {
+                        ui.push_id(("origin", i), |ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(origin)
+                                        .hint_text("http://192.168.1.20:8080")
+                                        .desired_width(180.0),
+                                );
+                                if ui.small_button("✖").clicked() {
+                                    removed = Some(i);
+                                }
+                            });
+                        });
+                    }
+                    if let Some(i) = removed {
+                        remote.allowed_origins.remove(i);
+                    }
+                    if ui.button(t("➕ Add Web Page")).clicked() {
+                        remote.allowed_origins.push(String::new());
+                    }
+                }
+                if remote.enabled {
+                    let host = if remote.allow_network {
+                        format!("<{}>", t("this computer"))
+                    } else {
+                        "127.0.0.1".to_string()
+                    };
+                    ui.label(format!("ws://{}:{}", host, remote.port));
+                }
+                ui.checkbox(&mut remote.http_enabled, t("HTTP Server"));
+                ui.horizontal(|ui| {
+                    ui.label(t("Port"));
+                    ui.add(egui::DragValue::new(&mut remote.http_port).clamp_range(1024..=65535));
+                });
+                ui.checkbox(&mut remote.http_allow_network, t("Allow Network Access"))
+                    .on_hover_text(t(
+                        "Anyone on the network can then control the app, without a password.",
+                    ));
+                if remote.http_enabled {
+                    let host = if remote.http_allow_network {
+                        format!("<{}>", t("this computer"))
+                    } else {
+                        "127.0.0.1".to_string()
+                    };
+                    ui.label(format!("http://{}:{}/status", host, remote.http_port));
+                }
+            });
+
+            egui::CollapsingHeader::new(t("🖥 Output Window")).show(ui, |ui| {
+                let output = &mut config.general.output;
+                ui.checkbox(&mut output.enabled, t("Second Window (Visuals Only)"));
+                ui.horizontal(|ui| {
+                    ui.label(t("Monitor"));
+                    ui.add(egui::DragValue::new(&mut output.monitor).clamp_range(0..=7));
+                });
+                ui.add(
+                    egui::Slider::new(&mut output.render_scale, 0.25..=2.0).text(t("Render Scale")),
+                );
+            });
+
+            egui::CollapsingHeader::new(t("💡 Philips Hue")).show(ui, |ui| {
+                hue_section(ui, lang, &mut config.general.hue, &mut hue_bridge);
+            });
+
+            ui.separator();
+            ui.checkbox(
+                &mut config.general.details_panel_enabled,
+                t("Show Analysis Data"),
+            );
+
+            // Integrated details panel
+            if config.general.details_panel_enabled {
+                ui.separator();
+                ui.label(egui::RichText::new(t("Analysis Data")).strong());
+                spectrum_plot(ui, lang, &audio_analysis);
+                waveform_plot(ui, lang, &audio_analysis);
+                history_plot(ui, lang, &audio_analysis);
+                ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+                ui.label(readout(
+                    t("Volume"),
+                    format!("{:.3}", audio_analysis.volume),
+                ));
+                ui.label(readout(t("Bass"), format!("{:.2}", audio_analysis.bass)));
+                ui.label(readout(
+                    t("Sub-Bass"),
+                    format!("{:.2}", audio_analysis.sub_bass),
+                ));
+                ui.label(readout(t("Punch"), format!("{:.2}", audio_analysis.punch)));
+                ui.label(readout(t("Mid"), format!("{:.2}", audio_analysis.mid)));
+                ui.label(readout(
+                    t("Treble"),
+                    format!("{:.2}", audio_analysis.treble),
+                ));
+                ui.label(readout(t("Flux"), format!("{:.2}", audio_analysis.flux)));
+                // EBU R128 momentary and short-term loudness.
+                ui.label(readout(
+                    t("LUFS M"),
+                    format!("{:.1}", audio_analysis.momentary_lufs),
+                ));
+                ui.label(readout(
+                    t("LUFS S"),
+                    format!("{:.1}", audio_analysis.short_term_lufs),
+                ));
+                ui.label(readout(
+                    t("Zero Crossings"),
+                    format!("{:.3}", audio_analysis.zero_crossing_rate),
+                ));
+                ui.label(readout(
+                    t("Percussive"),
+                    format!("{:.2}", audio_analysis.percussiveness),
+                ));
+            }
+
+            // --- BOTTOM SECTION: Hide UI Hint ---
+            ui.add_space(20.0);
+            ui.separator();
+            ui.vertical_centered(|ui| {
+                ui.label(
+                    egui::RichText::new(t("Press 'H' to Hide UI"))
+                        .weak()
+                        .italics(),
+                );
+            });
+        });
+}
+
+// --- "Now Playing" Overlay ---
+// Compact track info and spectrum, shown while the panels are hidden.
+fn now_playing_overlay(
+    mut contexts: EguiContexts,
+    config: Res<VisualsConfig>,
+    ui_visibility: Res<UiVisibility>,
+    selected_source: Res<SelectedAudioSource>,
+    selected_mic: Res<SelectedMic>,
+    playback_info: Res<PlaybackInfo>,
+    audio_analysis: Res<AudioAnalysis>,
 ) {
-    for (interaction, action) in &mut button_query {
-        if *interaction == Interaction::Pressed {
-            match action {
-                MenuButtonAction::Start => {
-                    next_app_state.set(active_viz.0.clone());
+    let overlay = &config.general.now_playing;
+    if !overlay.enabled || (ui_visibility.visible && !ui_visibility.idle_hidden) {
+        return;
+    }
+
+    let title = match &selected_source.0 {
+        AudioSource::File(path) | AudioSource::Replay(path) => display_name(path),
+        AudioSource::Microphone => selected_mic
+            .device
+            .clone()
+            .unwrap_or_else(|| config.general.language.tr("🎤 Microphone").to_string()),
+        AudioSource::None => return,
+    };
+
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    let (anchor, offset) = match overlay.corner {
+        OverlayCorner::TopLeft => (egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0)),
+        OverlayCorner::TopRight => (egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0)),
+        OverlayCorner::BottomLeft => (egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -10.0)),
+        OverlayCorner::BottomRight => (egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0)),
+    };
+    let alpha = (overlay.opacity.clamp(0.0, 1.0) * 255.0) as u8;
+
+    egui::Area::new("now_playing_overlay".into())
+        .anchor(anchor, offset)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style())
+                .fill(egui::Color32::from_black_alpha(alpha))
+                .inner_margin(8.0)
+                .show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new(format!("🎵 {}", title))
+                            .color(egui::Color32::WHITE)
+                            .strong(),
+                    );
+                    if playback_info.duration > Duration::ZERO {
+                        ui.colored_label(
+                            egui::Color32::LIGHT_GRAY,
+                            format!(
+                                "{} / {}",
+                                format_time(playback_info.position),
+                                format_time(playback_info.duration)
+                            ),
+                        );
+                    }
+                    mini_spectrum(ui, &audio_analysis.frequency_bins);
+                });
+        });
+}
+
+// One bar per band, scaled to the loudest band of the frame.
+fn mini_spectrum(ui: &mut egui::Ui, bins: &[f32]) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(160.0, 30.0), egui::Sense::hover());
+    if bins.is_empty() {
+        return;
+    }
+
+    let peak = bins.iter().copied().fold(f32::EPSILON, f32::max);
+    let width = rect.width() / bins.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (i, amplitude) in bins.iter().enumerate() {
+        let height = rect.height() * (amplitude / peak).clamp(0.0, 1.0);
+        let x = rect.left() + i as f32 * width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(x + 1.0, rect.bottom() - height),
+            egui::pos2(x + width - 1.0, rect.bottom()),
+        );
+        ui.painter().rect_filled(bar, 0.0, color);
+    }
+}
+
+fn format_time(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// --- Library ---
+// The tracks of the library folder, searchable. Double-clicking one plays it
+// and queues the tracks listed, in the shuffle and repeat modes above them.
+fn library_window(
+    mut contexts: EguiContexts,
+    mut config: ResMut<VisualsConfig>,
+    ui_visibility: Res<UiVisibility>,
+    mut library: ResMut<Library>,
+    mut playlist: ResMut<Playlist>,
+    mut selected_source: ResMut<SelectedAudioSource>,
+) {
+    if !library.open || !ui_visibility.visible || ui_visibility.idle_hidden {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let lang = config.general.language;
+    let t = |text: &'static str| lang.tr(text);
+
+    let mut open = true;
+    egui::Window::new(t("📚 Library"))
+        .open(&mut open)
+        .default_size([420.0, 480.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(t("📁 Choose Folder")).clicked() {
+                    if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                        config.general.library_folder = Some(folder);
+                    }
+                }
+                if let Some(folder) = &config.general.library_folder {
+                    ui.label(display_name(folder));
+                }
+            });
+            ui.horizontal(|ui| {
+                let general = &mut config.general;
+                ui.toggle_value(&mut general.shuffle, t("🔀 Shuffle"));
+                ui.separator();
+                for (mode, label) in [
+                    (RepeatMode::Off, t("No Repeat")),
+                    (RepeatMode::All, t("🔁 Repeat All")),
+                    (RepeatMode::One, t("🔂 Repeat One")),
+                ] {
+                    ui.selectable_value(&mut general.repeat, mode, label);
                 }
-                MenuButtonAction::ToMicSelection => {
-                    next_app_state.set(AppState::MicSelection);
+            });
+            ui.horizontal(|ui| {
+                ui.label("🔍");
+                ui.text_edit_singleline(&mut library.search);
+            });
+            if library.is_scanning() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!("{} {}", t("Scanning..."), library.tracks.len()));
+                });
+            }
+            ui.separator();
+
+            let search = library.search.to_lowercase();
+            let listed: Vec<&LibraryTrack> = library
+                .tracks
+                .iter()
+                .filter(|track| track.matches(&search))
+                .collect();
+            if listed.is_empty() {
+                ui.weak(t("No tracks"));
+                return;
+            }
+            let playing = playlist.current_track().cloned();
+            let mut picked = None;
+            let row_height = ui.spacing().interact_size.y;
+            egui::ScrollArea::vertical().show_rows(ui, row_height, listed.len(), |ui, rows| {
+                for index in rows {
+                    let track = listed[index];
+                    ui.horizontal(|ui| {
+                        let label = ui
+                            .selectable_label(playing.as_ref() == Some(&track.path), track.label());
+                        if label.double_clicked() {
+                            picked = Some(index);
+                        }
+                        if let Some(duration) = track.duration {
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    ui.weak(format_time(duration));
+                                },
+                            );
+                        }
+                    });
                 }
+            });
+            if let Some(index) = picked {
+                let tracks = listed.iter().map(|track| track.path.clone()).collect();
+                playlist.play(tracks, index, &mut selected_source);
             }
+        });
+    if !open {
+        library.open = false;
+    }
+}
+
+// The settings of the current visualizer, wherever the panel is placed.
+fn visualizer_settings_panel(
+    ui: &mut egui::Ui,
+    lang: Language,
+    settings_search: &mut String,
+    config: &mut VisualsConfig,
+    current_state: &AppState,
+) {
+    let t = |text: &'static str| lang.tr(text);
+    ui.add_space(10.0);
+    ui.heading(t("🎨 Visualizer Settings"));
+    // Detaching the panel gives the visuals the whole window.
+    ui.horizontal(|ui| {
+        for placement in PanelPlacement::ALL {
+            ui.selectable_value(
+                &mut config.general.settings_panel,
+                placement,
+                t(placement_label(placement)),
+            );
+        }
+    });
+    ui.separator();
+
+    // Search box filtering the parameters below by name
+    ui.horizontal(|ui| {
+        ui.label("🔍");
+        ui.text_edit_singleline(settings_search);
+        if !settings_search.is_empty() && ui.small_button("✖").clicked() {
+            settings_search.clear();
+        }
+    });
+    let filter = settings_search.trim().to_lowercase();
+
+    ui.separator();
+
+    // Contextual Parameters
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if let Some(id) = VisualizerId::from_state(current_state) {
+            let (general, settings) = config.general_and_settings_mut(id);
+            viz_settings(ui, lang, &filter, general, settings);
         }
+    });
+}
+
+fn placement_label(placement: PanelPlacement) -> &'static str {
+    match placement {
+        PanelPlacement::Docked => "Docked",
+        PanelPlacement::Floating => "Floating",
+        PanelPlacement::Window => "Own Window",
     }
 }
 
-fn setup_mic_selection_menu(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), MainMenuUI));
-    let mut root = commands.spawn((
-        NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
-                flex_direction: FlexDirection::Column,
-                row_gap: Val::Px(10.0),
-                ..default()
-            },
-            ..default()
-        },
-        MainMenuUI,
-    ));
+// Ticks and arrows to hide visualizers and reorder them. The last shown one
+// can't be hidden.
+fn visualizer_list_settings(ui: &mut egui::Ui, lang: Language, list: &mut VisualizerList) {
+    let t = |text: &'static str| lang.tr(text);
+    let ordered = list.ordered();
+    let shown = list.shown();
+    for (index, id) in ordered.iter().copied().enumerate() {
+        ui.horizontal(|ui| {
+            let mut visible = !list.hidden.contains(&id);
+            let last_shown = visible && shown.len() == 1;
+            if ui
+                .add_enabled(
+                    !last_shown,
+                    egui::Checkbox::new(&mut visible, t(viz_label(id))),
+                )
+                .changed()
+            {
+                list.set_hidden(id, !visible);
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .add_enabled(index + 1 < ordered.len(), egui::Button::new("⬇").small())
+                    .on_hover_text(t("Move Down"))
+                    .clicked()
+                {
+                    list.move_by(id, 1);
+                }
+                if ui
+                    .add_enabled(index > 0, egui::Button::new("⬆").small())
+                    .on_hover_text(t("Move Up"))
+                    .clicked()
+                {
+                    list.move_by(id, -1);
+                }
+            });
+        });
+    }
+}
+
+// What sections of a file analysed ahead switch to, by loudness.
+fn scene_switch_settings(
+    ui: &mut egui::Ui,
+    lang: Language,
+    scenes: &mut SceneSwitchSettings,
+    shown: Vec<VisualizerId>,
+) {
+    let t = |text: &'static str| lang.tr(text);
+    ui.checkbox(&mut scenes.enabled, t("Switch Scenes on Sections"));
+    if !scenes.enabled {
+        return;
+    }
+    let mut visualizers = vec![(None, t("Keep"))];
+    visualizers.extend(shown.into_iter().map(|id| (Some(id), t(viz_label(id)))));
+    for (label, target) in [
+        (t("Quiet Sections"), &mut scenes.quiet),
+        (t("Loud Sections"), &mut scenes.loud),
+    ] {
+        let mut section = SettingsSection::new(ui, "");
+        section.choice(label, &mut target.visualizer, &visualizers);
+        ui.horizontal(|ui| {
+            ui.label(t("Preset"));
+            ui.add(
+                egui::TextEdit::singleline(&mut target.preset)
+                    .hint_text(t("none"))
+                    .desired_width(120.0),
+            );
+        });
+    }
+}
+
+// The beat grid of a file analysed ahead, drawn as ticks under the seek
+// bar, downbeats taller, and its section boundaries over them, drops
+// thicker. Beats too close together to tell apart are left out. Clicking
+// jumps to the nearest beat.
+fn beat_ticks(
+    ui: &mut egui::Ui,
+    beats: &[GridBeat],
+    sections: &[SectionMark],
+    total: f32,
+) -> Option<f32> {
+    if beats.is_empty() || total <= 0.0 {
+        return None;
+    }
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.spacing().slider_width, 8.0),
+        egui::Sense::click(),
+    );
+    let x_of = |time: f32| rect.left() + rect.width() * (time / total).clamp(0.0, 1.0);
+    let painter = ui.painter_at(rect);
+    let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+    let all_beats = rect.width() / beats.len() as f32 >= 3.0;
+    for beat in beats.iter().filter(|beat| beat.downbeat || all_beats) {
+        let x = x_of(beat.time);
+        let top = if beat.downbeat {
+            rect.top()
+        } else {
+            rect.center().y
+        };
+        painter.line_segment([egui::pos2(x, top), egui::pos2(x, rect.bottom())], stroke);
+    }
+    let accent = ui.visuals().selection.bg_fill;
+    for section in sections {
+        let x = x_of(section.time);
+        let width = if section.drop { 3.0 } else { 1.5 };
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            egui::Stroke::new(width, accent),
+        );
+    }
+
+    if !response.clicked() {
+        return None;
+    }
+    let pointer = response.interact_pointer_pos()?;
+    let time = (pointer.x - rect.left()) / rect.width() * total;
+    beats
+        .iter()
+        .map(|beat| beat.time)
+        .min_by(|a, b| (a - time).abs().total_cmp(&(b - time).abs()))
+}
+
+// Cue points drawn as ticks under the seek bar. Hovering shows the nearest
+// cue's name and clicking jumps to it.
+fn cue_ticks(ui: &mut egui::Ui, cues: &[CuePoint], total: f32) -> Option<f32> {
+    if cues.is_empty() || total <= 0.0 {
+        return None;
+    }
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.spacing().slider_width, 8.0),
+        egui::Sense::click(),
+    );
+    let x_of = |time: f32| rect.left() + rect.width() * (time / total).clamp(0.0, 1.0);
+    let painter = ui.painter_at(rect);
+    let stroke = egui::Stroke::new(2.0, ui.visuals().selection.bg_fill);
+    for cue in cues {
+        let x = x_of(cue.time);
+        painter.line_segment(
+            [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+            stroke,
+        );
+    }
+
+    let pointer = response.hover_pos()?;
+    let distance = |cue: &CuePoint| (x_of(cue.time) - pointer.x).abs();
+    let nearest = cues
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))?;
+    let clicked = response.clicked();
+    response.on_hover_text(nearest.name.as_str());
+    clicked.then_some(nearest.time)
+}
+
+// Named positions in the track. Returns whether anything was edited.
+fn cue_editor(
+    ui: &mut egui::Ui,
+    lang: Language,
+    timeline: &mut Timeline,
+    playback_info: &mut PlaybackInfo,
+) -> bool {
+    let t = |text: &'static str| lang.tr(text);
+    let mut edited = false;
+    let mut removed = None;
+
+    for (i, cue) in timeline.cues.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.small_button("▶").clicked() {
+                playback_info.seek_to = Some(cue.time);
+            }
+            ui.label(format_time(Duration::from_secs_f32(cue.time.max(0.0))));
+            edited |= ui
+                .add(egui::TextEdit::singleline(&mut cue.name).desired_width(100.0))
+                .changed();
+            if ui.small_button("✖").clicked() {
+                removed = Some(i);
+            }
+        });
+    }
+    if let Some(i) = removed {
+        timeline.cues.remove(i);
+        edited = true;
+    }
+
+    let position = playback_info.position.as_secs_f32();
+    let add_label = format!(
+        "{} {}",
+        t("➕ Cue at"),
+        format_time(Duration::from_secs_f32(position))
+    );
+    if ui.button(add_label).clicked() {
+        let name = format!("{} {}", t("Cue"), timeline.cues.len() + 1);
+        timeline.cues.push(CuePoint {
+            name,
+            time: position,
+        });
+        timeline.sort_cues();
+        edited = true;
+    }
+    ui.label(t("Press [ and ] to jump between cues."));
+    edited
+}
+
+// Reads "1:32" as well as plain seconds.
+fn parse_time(text: &str) -> Option<f64> {
+    match text.split_once(':') {
+        Some((minutes, seconds)) => {
+            Some(minutes.trim().parse::<f64>().ok()? * 60.0 + seconds.trim().parse::<f64>().ok()?)
+        }
+        None => text.trim().parse().ok(),
+    }
+}
+
+fn corner_label(corner: OverlayCorner) -> &'static str {
+    match corner {
+        OverlayCorner::TopLeft => "Top Left",
+        OverlayCorner::TopRight => "Top Right",
+        OverlayCorner::BottomLeft => "Bottom Left",
+        OverlayCorner::BottomRight => "Bottom Right",
+    }
+}
+
+// "31", "1k", "16k"...
+fn eq_band_label(frequency: f32) -> String {
+    if frequency >= 1000.0 {
+        format!("{}k", frequency / 1000.0)
+    } else {
+        format!("{}", frequency)
+    }
+}
+
+fn quality_label(quality: Quality) -> &'static str {
+    match quality {
+        Quality::Low => "Low",
+        Quality::Medium => "Medium",
+        Quality::High => "High",
+    }
+}
+
+fn background_label(mode: BackgroundMode) -> &'static str {
+    match mode {
+        BackgroundMode::FullSpeed => "Full Speed",
+        BackgroundMode::Throttled => "Low Frame Rate",
+        BackgroundMode::Suspended => "Pause Rendering",
+    }
+}
+
+// Live spectrum on a logarithmic frequency axis, with the band edges used
+// to build `frequency_bins` drawn as vertical lines.
+fn spectrum_plot(ui: &mut egui::Ui, lang: Language, audio_analysis: &AudioAnalysis) {
+    let points: PlotPoints = audio_analysis
+        .previous_spectrum
+        .iter()
+        .filter(|(freq, _)| *freq > 0.0)
+        .map(|(freq, magnitude)| [(*freq as f64).log10(), *magnitude as f64])
+        .collect();
+
+    ui.label(lang.tr("Spectrum"));
+    Plot::new("spectrum_plot")
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .show_axes([true, false])
+        .x_axis_formatter(|mark, _max_chars, _range| format_frequency(10f64.powf(mark.value)))
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(points));
+            // The last limit is the top of the analysed range, not a boundary between bands.
+            let limits = &audio_analysis.band_limits;
+            for limit in limits.iter().take(limits.len().saturating_sub(1)) {
+                plot_ui.vline(
+                    VLine::new((*limit as f64).log10()).color(egui::Color32::from_white_alpha(40)),
+                );
+            }
+        });
+}
+
+// The spectrum under the band edges of a visualizer. Dragging moves the edge
+// nearest the pointer, kept between its neighbours.
+fn band_edges_plot(ui: &mut egui::Ui, audio_analysis: &AudioAnalysis, edges: &mut [f32]) {
+    // Neighbouring edges stay at least this ratio apart.
+    const MIN_BAND_RATIO: f32 = 1.02;
+
+    let points: PlotPoints = audio_analysis
+        .previous_spectrum
+        .iter()
+        .filter(|(freq, _)| *freq > 0.0)
+        .map(|(freq, magnitude)| [(*freq as f64).log10(), *magnitude as f64])
+        .collect();
+
+    Plot::new("band_edges_plot")
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_double_click_reset(false)
+        .include_x(20f64.log10())
+        .include_x(20000f64.log10())
+        .include_y(0.0)
+        .show_axes([true, false])
+        .x_axis_formatter(|mark, _max_chars, _range| format_frequency(10f64.powf(mark.value)))
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(points));
+            for edge in edges.iter() {
+                plot_ui.vline(VLine::new((*edge as f64).log10()).color(egui::Color32::YELLOW));
+            }
+
+            if !plot_ui.response().dragged() {
+                return;
+            }
+            let Some(pointer) = plot_ui.pointer_coordinate() else {
+                return;
+            };
+            let distance = |edge: &f32| ((*edge as f64).log10() - pointer.x).abs();
+            let Some(nearest) = (0..edges.len())
+                .min_by(|&a, &b| distance(&edges[a]).total_cmp(&distance(&edges[b])))
+            else {
+                return;
+            };
+            let lower = match nearest {
+                0 => 20.0,
+                _ => edges[nearest - 1] * MIN_BAND_RATIO,
+            };
+            let upper = edges
+                .get(nearest + 1)
+                .map_or(20000.0, |next| next / MIN_BAND_RATIO);
+            edges[nearest] = (10f64.powf(pointer.x) as f32).max(lower).min(upper);
+        });
+}
+
+// The samples of the last analysis window.
+fn waveform_plot(ui: &mut egui::Ui, lang: Language, audio_analysis: &AudioAnalysis) {
+    let points: PlotPoints = audio_analysis
+        .waveform
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| [i as f64, *sample as f64])
+        .collect();
+
+    ui.label(lang.tr("Waveform"));
+    Plot::new("waveform_plot")
+        .height(80.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_y(-1.0)
+        .include_y(1.0)
+        .show_axes([false, false])
+        .show(ui, |plot_ui| plot_ui.line(Line::new(points)));
+}
+
+fn lfo_row(ui: &mut egui::Ui, lang: Language, index: usize, lfo: &mut LfoSettings, value: f32) {
+    let t = |text: &'static str| lang.tr(text);
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut lfo.enabled, format!("LFO {}", index + 1));
+        egui::ComboBox::from_id_source("shape")
+            .width(70.0)
+            .selected_text(t(shape_label(lfo.shape)))
+            .show_ui(ui, |ui| {
+                for shape in LfoShape::ALL {
+                    ui.selectable_value(&mut lfo.shape, shape, t(shape_label(shape)));
+                }
+            });
+        ui.add(egui::ProgressBar::new(value).desired_width(40.0));
+    });
+    ui.horizontal(|ui| {
+        let mut synced = matches!(lfo.rate, LfoRate::Beats(_));
+        if ui.checkbox(&mut synced, t("Sync")).changed() {
+            lfo.rate = if synced {
+                LfoRate::Beats(1.0)
+            } else {
+                LfoRate::Hz(1.0)
+            };
+        }
+        match &mut lfo.rate {
+            LfoRate::Hz(hz) => {
+                ui.add(
+                    egui::Slider::new(hz, 0.01..=10.0)
+                        .logarithmic(true)
+                        .suffix(" Hz"),
+                );
+            }
+            LfoRate::Beats(beats) => {
+                egui::ComboBox::from_id_source("division")
+                    .selected_text(beat_division_label(*beats))
+                    .show_ui(ui, |ui| {
+                        for division in BEAT_DIVISIONS {
+                            ui.selectable_value(beats, division, beat_division_label(division));
+                        }
+                    });
+            }
+        }
+    });
+}
 
-    root.with_children(|parent| {
-        parent.spawn(TextBundle::from_section(
-            "Select an Input Device",
-            TextStyle {
-                font_size: 32.0,
-                color: Color::WHITE,
-                ..default()
-            },
-        ));
+// Beats per LFO cycle offered in the picker.
+const BEAT_DIVISIONS: [f32; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+// A macro knob and the parameters it drives. New targets are picked among
+// the parameters of the visualizer on screen.
+fn macro_row(
+    ui: &mut egui::Ui,
+    lang: Language,
+    index: usize,
+    knob: &mut MacroSettings,
+    current: Option<VisualizerId>,
+) {
+    let t = |text: &'static str| lang.tr(text);
+    ui.horizontal(|ui| {
+        ui.label(format!("{} {}", t("Macro"), index + 1));
+        ui.add(egui::Slider::new(&mut knob.value, 0.0..=1.0));
     });
 
-    let host = cpal::default_host();
-    if let Ok(devices) = host.input_devices() {
-        root.with_children(|parent| {
-            for device in devices {
-                if let Ok(name) = device.name() {
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: Style {
-                                    width: Val::Px(500.0),
-                                    height: Val::Px(50.0),
-                                    justify_content: JustifyContent::Center,
-                                    align_items: AlignItems::Center,
-                                    margin: UiRect::top(Val::Px(5.0)),
-                                    ..default()
-                                },
-                                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                                ..default()
-                            },
-                            MicDeviceButton(name.clone()),
-                        ))
-                        .with_children(|btn| {
-                            btn.spawn(TextBundle::from_section(
-                                name,
-                                TextStyle {
-                                    font_size: 18.0,
-                                    color: Color::WHITE,
-                                    ..default()
-                                },
-                            ));
+    egui::CollapsingHeader::new(format!("{} ({})", t("Targets"), knob.targets.len()))
+        .id_source("targets")
+        .show(ui, |ui| {
+            let mut removed = None;
+            for (i, target) in knob.targets.iter_mut().enumerate() {
+                let label = target
+                    .visualizer
+                    .ui_fields()
+                    .iter()
+                    .find(|field| field.field == target.field)
+                    .map_or(target.field.as_str(), |field| t(field.label));
+                ui.label(format!("{}: {}", t(viz_label(target.visualizer)), label));
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut target.min).speed(0.01));
+                    ui.label("→");
+                    ui.add(egui::DragValue::new(&mut target.max).speed(0.01));
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                knob.targets.remove(i);
+            }
+
+            let Some(id) = current else {
+                return;
+            };
+            egui::ComboBox::from_id_source("add_target")
+                .selected_text(t("➕ Add Target"))
+                .show_ui(ui, |ui| {
+                    // Only numeric parameters have a range to sweep.
+                    for field in id.ui_fields() {
+                        let Some((min, max)) = field.range else {
+                            continue;
+                        };
+                        if ui.selectable_label(false, t(field.label)).clicked() {
+                            knob.targets.push(MacroTarget {
+                                visualizer: id,
+                                field: field.field.to_string(),
+                                min: min as f32,
+                                max: max as f32,
+                            });
+                        }
+                    }
+                });
+        });
+}
+
+// Lanes of keyframes against the track position. Keys are added at the
+// playback position with the parameter's current value, then fine-tuned.
+// Returns whether anything was edited.
+fn timeline_editor(
+    ui: &mut egui::Ui,
+    lang: Language,
+    timeline: &mut Timeline,
+    config: &VisualsConfig,
+    position: f32,
+    current: Option<VisualizerId>,
+) -> bool {
+    let t = |text: &'static str| lang.tr(text);
+    let mut edited = false;
+    let mut removed_lane = None;
+
+    for (i, lane) in timeline.lanes.iter_mut().enumerate() {
+        ui.push_id(("lane", i), |ui| {
+            let label = lane
+                .visualizer
+                .ui_fields()
+                .iter()
+                .find(|field| field.field == lane.field)
+                .map_or(lane.field.as_str(), |field| t(field.label));
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new(format!("{}: {}", t(viz_label(lane.visualizer)), label))
+                        .strong(),
+                );
+                if ui.small_button("✖").clicked() {
+                    removed_lane = Some(i);
+                }
+            });
+            edited |= ui.checkbox(&mut lane.step, t("Step")).changed();
+
+            let mut removed_key = None;
+            for (k, key) in lane.keyframes.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    edited |= ui
+                        .add(
+                            egui::DragValue::new(&mut key.time)
+                                .clamp_range(0.0..=f32::MAX)
+                                .speed(0.1)
+                                .custom_formatter(|secs, _| {
+                                    format_time(Duration::from_secs_f64(secs.max(0.0)))
+                                })
+                                .custom_parser(parse_time),
+                        )
+                        .changed();
+                    edited |= ui
+                        .add(egui::DragValue::new(&mut key.value).speed(0.01))
+                        .changed();
+                    if ui.small_button("✖").clicked() {
+                        removed_key = Some(k);
+                    }
+                });
+            }
+            if let Some(k) = removed_key {
+                lane.keyframes.remove(k);
+                edited = true;
+            }
+
+            let add_label = format!(
+                "{} {}",
+                t("➕ Key at"),
+                format_time(Duration::from_secs_f32(position))
+            );
+            if ui.button(add_label).clicked() {
+                let value = config
+                    .settings(lane.visualizer)
+                    .number(&lane.field)
+                    .unwrap_or(0.0);
+                lane.keyframes.push(Keyframe {
+                    time: position,
+                    value,
+                });
+                edited = true;
+            }
+            if edited {
+                lane.sort();
+            }
+            ui.separator();
+        });
+    }
+    if let Some(i) = removed_lane {
+        timeline.lanes.remove(i);
+        edited = true;
+    }
+
+    // New lanes automate parameters of the visualizer on screen.
+    if let Some(id) = current {
+        egui::ComboBox::from_id_source("add_lane")
+            .selected_text(t("➕ Automate..."))
+            .show_ui(ui, |ui| {
+                for field in id.ui_fields().iter().filter(|field| field.range.is_some()) {
+                    if ui.selectable_label(false, t(field.label)).clicked() {
+                        timeline.lanes.push(AutomationLane {
+                            visualizer: id,
+                            field: field.field.to_string(),
+                            step: false,
+                            keyframes: Vec::new(),
                         });
+                        edited = true;
+                    }
                 }
+            });
+    }
+    edited
+}
+
+// The language and theme, shared by the appearance section of the panel and
+// the settings screen.
+fn appearance_settings(ui: &mut egui::Ui, lang: Language, general: &mut GeneralSettings) {
+    let t = |text: &'static str| lang.tr(text);
+    egui::ComboBox::from_label(t("Language"))
+        .selected_text(general.language.name())
+        .show_ui(ui, |ui| {
+            for language in Language::ALL {
+                ui.selectable_value(&mut general.language, language, language.name());
             }
         });
+
+    let theme = &mut general.theme;
+    let mut section = SettingsSection::new(ui, "");
+    section.checkbox(t("Dark Theme"), &mut theme.dark_mode);
+    section.color(t("Accent Color"), &mut theme.accent_color);
+    section.slider(t("Panel Opacity"), &mut theme.panel_opacity, 0.2..=1.0);
+    section.slider(t("Font Size"), &mut theme.font_scale, 0.75..=2.0);
+    section.slider(t("UI Scale"), &mut theme.ui_scale, UI_SCALE_RANGE);
+    let placements: Vec<(PanelPlacement, &str)> = PanelPlacement::ALL
+        .into_iter()
+        .map(|placement| (placement, t(placement_label(placement))))
+        .collect();
+    section.choice(
+        t("Settings Panel"),
+        &mut general.settings_panel,
+        &placements,
+    );
+}
+
+fn mic_buffer_setting(section: &mut SettingsSection<'_>, lang: Language, frames: &mut u32) {
+    section.choice(
+        lang.tr("Mic Buffer"),
+        frames,
+        &[
+            (0, lang.tr("Default")),
+            (64, "64"),
+            (128, "128"),
+            (256, "256"),
+            (512, "512"),
+            (1024, "1024"),
+            (2048, "2048"),
+            (4096, "4096"),
+        ],
+    );
+}
+
+pub fn viz_label(id: VisualizerId) -> &'static str {
+    match id {
+        VisualizerId::Bars2D => "2D Bars",
+        VisualizerId::Cubes3D => "3D Cubes",
+        VisualizerId::Orb => "3D Orb",
+        VisualizerId::Disc => "Disc",
+        VisualizerId::Ico => "Ico",
+        VisualizerId::Shadertoy => "Shadertoy",
     }
 }
 
-fn mic_selection_interaction(
-    mut button_query: Query<(&Interaction, &MicDeviceButton)>,
-    mut selected_mic: ResMut<SelectedMic>,
-    mut next_app_state: ResMut<NextState<AppState>>,
+fn beat_division_label(beats: f32) -> String {
+    if beats < 1.0 {
+        format!("1/{} beat", (1.0 / beats).round())
+    } else {
+        format!("{} beat", beats)
+    }
+}
+
+fn shape_label(shape: LfoShape) -> &'static str {
+    match shape {
+        LfoShape::Sine => "Sine",
+        LfoShape::Saw => "Saw",
+        LfoShape::Square => "Square",
+    }
+}
+
+// Attack/release time in seconds.
+fn envelope_drag(seconds: &mut f32) -> egui::DragValue<'_> {
+    egui::DragValue::new(seconds)
+        .speed(0.005)
+        .clamp_range(0.0..=2.0)
+        .suffix(" s")
+}
+
+// "Label: value" with the values aligned, whatever the label's translation.
+fn readout(label: &str, value: String) -> String {
+    format!("{:<8}{}", format!("{}:", label), value)
+}
+
+// Volume, bass and flux over the last few seconds, newest on the right.
+fn history_plot(ui: &mut egui::Ui, lang: Language, audio_analysis: &AudioAnalysis) {
+    let history = &audio_analysis.history;
+    let offset = (EnergyHistory::CAPACITY - history.iter().len()) as f64;
+    let line = |name: &str, value: fn(&EnergySample) -> f32| {
+        let points: PlotPoints = history
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| [offset + i as f64, value(sample) as f64])
+            .collect();
+        Line::new(points).name(name)
+    };
+
+    ui.label(lang.tr("History"));
+    Plot::new("history_plot")
+        .height(80.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_x(0.0)
+        .include_x(EnergyHistory::CAPACITY as f64)
+        .include_y(0.0)
+        .show_axes([false, false])
+        .legend(egui_plot::Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(line(lang.tr("Volume"), |s| s.volume));
+            plot_ui.line(line(lang.tr("Bass"), |s| s.bass));
+            plot_ui.line(line(lang.tr("Flux"), |s| s.flux));
+        });
+}
+
+fn format_frequency(hz: f64) -> String {
+    if hz >= 1000.0 {
+        format!("{:.1}k", hz / 1000.0)
+    } else {
+        format!("{:.0}", hz)
+    }
+}
+
+// Draws the current visualizer's parameters from its `UI_FIELDS`, grouped
+// into sections, followed by its camera, bloom, floor and fog settings if it
+// has any.
+fn viz_settings(
+    ui: &mut egui::Ui,
+    lang: Language,
+    filter: &str,
+    general: &mut GeneralSettings,
+    settings: &mut VizSettings,
 ) {
-    for (interaction, button) in &mut button_query {
-        if *interaction == Interaction::Pressed {
-            selected_mic.0 = Some(button.0.clone());
-            next_app_state.set(AppState::MainMenu);
+    let t = |text: &'static str| lang.tr(text);
+    let fields = settings.id().ui_fields();
+    for group in SettingsGroup::ALL {
+        // Every visualizer reacts to the shared sensitivity.
+        let has_fields = fields.iter().any(|field| field.group == group);
+        if group != SettingsGroup::Modulation && !has_fields {
+            continue;
+        }
+        settings_section(ui, t(group_title(group)), filter, |section| {
+            if group == SettingsGroup::Modulation {
+                section.slider(
+                    t("Amplitude Sensitivity"),
+                    &mut general.bass_sensitivity,
+                    0.1..=10.0,
+                );
+            }
+            let values = settings.as_struct_mut();
+            for field in fields.iter().filter(|field| field.group == group) {
+                match values.field_mut(field.field) {
+                    Some(value) => section.reflected(t(field.label), value, field.range),
+                    None => warn_once!("No settings field named '{}'", field.field),
+                }
+            }
+        });
+    }
+    if let Some(camera) = settings.camera_mut() {
+        camera_section(ui, lang, filter, camera);
+    }
+    if let Some(bloom) = settings.bloom_mut() {
+        bloom_section(ui, lang, filter, bloom);
+    }
+    if let Some(floor) = settings.floor_mut() {
+        floor_section(ui, lang, filter, floor);
+    }
+    if let Some(fog) = settings.fog_mut() {
+        fog_section(ui, lang, filter, fog);
+    }
+}
+
+fn group_title(group: SettingsGroup) -> &'static str {
+    match group {
+        SettingsGroup::Modulation => "🎚 Modulation",
+        SettingsGroup::Colors => "🎨 Colors",
+        SettingsGroup::Geometry => "📐 Geometry",
+        SettingsGroup::Motion => "🌀 Motion",
+    }
+}
+
+fn camera_section(ui: &mut egui::Ui, lang: Language, filter: &str, camera: &mut CameraConfig) {
+    let t = |text: &'static str| lang.tr(text);
+    settings_section(ui, t("🎥 Camera"), filter, |section| {
+        section.slider(t("Field of View"), &mut camera.fov, 20.0..=120.0);
+        section.checkbox(t("Orthographic"), &mut camera.orthographic);
+        section.slider(t("Bass Widens View"), &mut camera.bass_fov, 0.0..=30.0);
+        section.slider(t("Exposure (stops)"), &mut camera.exposure, -4.0..=4.0);
+        let tonemappers = TonemappingMode::ALL.map(|mode| (mode, t(tonemapping_label(mode))));
+        section.choice(t("Tonemapping"), &mut camera.tonemapping, &tonemappers);
+        section.checkbox(t("Depth of Field"), &mut camera.depth_of_field);
+        if camera.depth_of_field {
+            section.slider(t("Focus Distance"), &mut camera.focus_distance, 1.0..=100.0);
+            section.slider(
+                t("Aperture (f-stops)"),
+                &mut camera.aperture_f_stops,
+                1.4..=22.0,
+            );
         }
+        section.slider(
+            t("Bookmark Transition (s)"),
+            &mut camera.bookmark_tween,
+            0.0..=3.0,
+        );
+    });
+}
+
+fn tonemapping_label(mode: TonemappingMode) -> &'static str {
+    match mode {
+        TonemappingMode::None => "None",
+        TonemappingMode::Reinhard => "Reinhard",
+        TonemappingMode::AcesFitted => "ACES",
+        TonemappingMode::AgX => "AgX",
+        TonemappingMode::TonyMcMapface => "Tony McMapface",
+        TonemappingMode::BlenderFilmic => "Blender Filmic",
     }
 }
 
-fn create_menu_button(parent: &mut ChildBuilder, text: &str, action: MenuButtonAction) {
-    parent
-        .spawn((
-            ButtonBundle {
-                style: Style {
-                    width: Val::Px(250.0),
-                    height: Val::Px(65.0),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
-                ..default()
-            },
-            action,
-        ))
-        .with_children(|p| {
-            p.spawn(TextBundle::from_section(
-                text,
-                TextStyle {
-                    font_size: 24.0,
-                    color: Color::WHITE,
-                    ..default()
-                },
-            ));
+fn bloom_section(ui: &mut egui::Ui, lang: Language, filter: &str, bloom: &mut BloomConfig) {
+    let t = |text: &'static str| lang.tr(text);
+    settings_section(ui, t("✨ Bloom"), filter, |section| {
+        section.checkbox(t("Enable Bloom"), &mut bloom.enabled);
+        if bloom.enabled {
+            section.slider(t("Intensity"), &mut bloom.intensity, 0.0..=1.0);
+            section.slider(t("Threshold"), &mut bloom.threshold, 0.0..=2.0);
+            section.color(t("Tint"), &mut bloom.color);
+        }
+    });
+}
+
+fn floor_section(ui: &mut egui::Ui, lang: Language, filter: &str, floor: &mut FloorConfig) {
+    let t = |text: &'static str| lang.tr(text);
+    settings_section(ui, t("🪞 Floor Reflection"), filter, |section| {
+        section.checkbox(t("Enable Floor"), &mut floor.enabled);
+        if floor.enabled {
+            section.slider(t("Roughness"), &mut floor.roughness, 0.0..=1.0);
+            section.color(t("Tint"), &mut floor.tint);
+        }
+    });
+}
+
+fn fog_section(ui: &mut egui::Ui, lang: Language, filter: &str, fog: &mut FogConfig) {
+    let t = |text: &'static str| lang.tr(text);
+    settings_section(ui, t("🌫 Fog"), filter, |section| {
+        section.checkbox(t("Enable Fog"), &mut fog.enabled);
+        if fog.enabled {
+            section.color(t("Fog Color"), &mut fog.color);
+            section.slider(t("Fog Start"), &mut fog.start, 0.0..=100.0);
+            section.slider(t("Fog End"), &mut fog.end, 1.0..=200.0);
+            section.slider(t("Bass Thickens Fog"), &mut fog.audio_density, 0.0..=2.0);
+        }
+    });
+}
+
+// --- Collapsible, searchable settings sections ---
+
+// Draws one group of parameters inside a collapsing header.
+// While a search is active, only matching parameters are drawn and
+// sections without any match are hidden entirely.
+fn settings_section(
+    ui: &mut egui::Ui,
+    title: &str,
+    filter: &str,
+    mut add_contents: impl FnMut(&mut SettingsSection),
+) {
+    if filter.is_empty() {
+        egui::CollapsingHeader::new(title)
+            .default_open(true)
+            .show(ui, |ui| add_contents(&mut SettingsSection::new(ui, filter)));
+        return;
+    }
+
+    // First pass only counts the parameters matching the search.
+    let mut counter = SettingsSection::new(ui, filter);
+    counter.counting = true;
+    add_contents(&mut counter);
+    if counter.matches == 0 {
+        return;
+    }
+
+    egui::CollapsingHeader::new(title)
+        .open(Some(true))
+        .show(ui, |ui| add_contents(&mut SettingsSection::new(ui, filter)));
+}
+
+struct SettingsSection<'a> {
+    ui: &'a mut egui::Ui,
+    filter: &'a str,
+    counting: bool,
+    matches: usize,
+}
+
+impl<'a> SettingsSection<'a> {
+    fn new(ui: &'a mut egui::Ui, filter: &'a str) -> Self {
+        Self {
+            ui,
+            filter,
+            counting: false,
+            matches: 0,
+        }
+    }
+
+    // Whether a parameter should be drawn, given the current search.
+    fn visible(&mut self, label: &str) -> bool {
+        let matches = self.filter.is_empty() || label.to_lowercase().contains(self.filter);
+        if self.counting {
+            if matches {
+                self.matches += 1;
+            }
+            return false;
+        }
+        matches
+    }
+
+    fn slider<Num: egui::emath::Numeric>(
+        &mut self,
+        label: &str,
+        value: &mut Num,
+        range: RangeInclusive<Num>,
+    ) {
+        if self.visible(label) {
+            self.ui.label(label);
+            self.ui.add(egui::Slider::new(value, range));
+        }
+    }
+
+    fn color(&mut self, label: &str, color: &mut Color) {
+        if self.visible(label) {
+            self.ui.label(label);
+            color_picker_widget(self.ui, color);
+        }
+    }
+
+    fn checkbox(&mut self, label: &str, value: &mut bool) {
+        if self.visible(label) {
+            self.ui.checkbox(value, label);
+        }
+    }
+
+    // A drop-down over `options`, each shown with its name.
+    fn choice<T: Copy + PartialEq>(&mut self, label: &str, value: &mut T, options: &[(T, &str)]) {
+        if self.visible(label) {
+            let selected = options
+                .iter()
+                .find(|(option, _)| option == value)
+                .map_or("", |(_, name)| *name);
+            self.ui.label(label);
+            egui::ComboBox::from_id_source(label)
+                .selected_text(selected)
+                .show_ui(self.ui, |ui| {
+                    for (option, name) in options {
+                        ui.selectable_value(value, *option, *name);
+                    }
+                });
+        }
+    }
+
+    // Picks the widget from the reflected type of the field.
+    fn reflected(&mut self, label: &str, value: &mut dyn Reflect, range: Option<(f64, f64)>) {
+        let (min, max) = range.unwrap_or((0.0, 1.0));
+        if let Some(value) = value.downcast_mut::<f32>() {
+            self.slider(label, value, min as f32..=max as f32);
+        } else if let Some(value) = value.downcast_mut::<usize>() {
+            self.slider(label, value, min as usize..=max as usize);
+        } else if let Some(value) = value.downcast_mut::<i32>() {
+            self.slider(label, value, min as i32..=max as i32);
+        } else if let Some(value) = value.downcast_mut::<bool>() {
+            self.checkbox(label, value);
+        } else if let Some(value) = value.downcast_mut::<Color>() {
+            self.color(label, value);
+        } else {
+            warn_once!("No settings widget for the type of '{}'", label);
+        }
+    }
+}
+
+// Bevy Color -> egui color, interpreted the same way as in `color_picker_widget`
+// so the accent matches the swatch the user picked.
+fn to_egui_color(color: Color) -> egui::Color32 {
+    egui::Rgba::from_rgba_unmultiplied(color.r(), color.g(), color.b(), color.a()).into()
+}
+
+// Adaptation for egui 0.27+ and Bevy Color
+fn color_picker_widget(ui: &mut egui::Ui, color: &mut Color) {
+    // 1. Convert Bevy Color -> [f32; 4]
+    let rgba_array = [color.r(), color.g(), color.b(), color.a()];
+
+    // 2. Create Egui color (Rgba is premultiplied by default)
+    let mut egui_color = egui::Rgba::from_rgba_unmultiplied(
+        rgba_array[0],
+        rgba_array[1],
+        rgba_array[2],
+        rgba_array[3],
+    );
+
+    // 3. Display Widget
+    if color_picker::color_edit_button_rgba(ui, &mut egui_color, color_picker::Alpha::Opaque)
+        .changed()
+    {
+        // 4. Convert back to Bevy Color
+        let result = egui_color.to_rgba_unmultiplied();
+        *color = Color::rgba(result[0], result[1], result[2], result[3]);
+    }
+}
+
+// --- Menu Screens ---
+// The main menu, microphone selection and settings are egui screens sharing
+// the theme of the panels: a centered column under a title, over the whole
+// window.
+fn menu_screen(ctx: &egui::Context, title: &str, add_contents: impl FnOnce(&mut egui::Ui)) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() * 0.1);
+            ui.heading(egui::RichText::new(title).size(32.0));
+            ui.add_space(20.0);
+            add_contents(ui);
         });
+    });
+}
+
+fn menu_button(ui: &mut egui::Ui, text: &str) -> bool {
+    ui.add_sized(
+        MENU_BUTTON_SIZE,
+        egui::Button::new(egui::RichText::new(text).size(20.0)),
+    )
+    .clicked()
+}
+
+// egui draws on top of a camera, which the menus otherwise don't need.
+fn spawn_menu_camera(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), MenuCamera));
 }
 
-fn cleanup_menu(mut commands: Commands, ui_query: Query<Entity, With<MainMenuUI>>) {
-    for entity in ui_query.iter() {
+fn cleanup_menu(mut commands: Commands, q_camera: Query<Entity, With<MenuCamera>>) {
+    for entity in q_camera.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
+
+// Bench and wallpaper runs have no session to resume.
+fn main_menu_ui(
+    mut contexts: EguiContexts,
+    config: Res<VisualsConfig>,
+    active_viz: Res<ActiveVisualization>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    last_session: Option<Res<LastSession>>,
+    resume: Option<ResMut<ResumeSession>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let t = |text: &'static str| config.general.language.tr(text);
+    menu_screen(ctx, "Rust Audio Visualizer", |ui| {
+        if let (Some(session), Some(mut resume)) = (last_session, resume) {
+            if let Some(visualizer) = session.visualizer {
+                if menu_button(ui, t("⏮ Resume Last Session")) {
+                    resume.requested = true;
+                }
+                let mut summary = t(viz_label(visualizer)).to_string();
+                if let Some(source) = session.source_name() {
+                    summary = format!("{} · {}", summary, source);
+                }
+                ui.weak(summary);
+                ui.add_space(MENU_SPACING);
+            }
+        }
+        if menu_button(ui, t("Start Visualization")) {
+            next_app_state.set(active_viz.0.clone());
+        }
+        ui.add_space(MENU_SPACING);
+        if menu_button(ui, t("Select Microphone")) {
+            next_app_state.set(AppState::MicSelection);
+        }
+        ui.add_space(MENU_SPACING);
+        if menu_button(ui, t("⚙ Settings")) {
+            next_app_state.set(AppState::Settings);
+        }
+    });
+}
+
+// The input devices of the selected backend as last listed, and the label of
+// the one capture would open, while the selection screen is open. Listing
+// them is too slow to do every frame.
+#[derive(Resource)]
+struct MicDevices {
+    names: Vec<String>,
+    status: String,
+    since_refresh: f32,
+}
+
+impl MicDevices {
+    fn list(backend: &Backend, selected_mic: &SelectedMic, lang: Language) -> Self {
+        let host = selected_mic.host(backend);
+        Self {
+            names: backend.0.input_devices(&host),
+            status: selected_device_label(backend, &host, selected_mic, lang),
+            since_refresh: 0.0,
+        }
+    }
+}
+
+fn list_mic_devices(
+    mut commands: Commands,
+    config: Res<VisualsConfig>,
+    selected_mic: Res<SelectedMic>,
+    backend: Res<Backend>,
+) {
+    commands.insert_resource(MicDevices::list(
+        &backend,
+        &selected_mic,
+        config.general.language,
+    ));
+}
+
+fn forget_mic_devices(mut commands: Commands) {
+    commands.remove_resource::<MicDevices>();
+}
+
+// "Current: name (48000 Hz)", for the device capture would open.
+fn selected_device_label(
+    backend: &Backend,
+    host: &str,
+    selected_mic: &SelectedMic,
+    lang: Language,
+) -> String {
+    let Some(device) = backend
+        .0
+        .resolve_input(host, selected_mic.device.as_deref())
+    else {
+        return lang.tr("No input device found").to_string();
+    };
+    match device.sample_rate {
+        Some(rate) => format!("{} {} ({} Hz)", lang.tr("Current:"), device.name, rate),
+        None => format!("{} {}", lang.tr("Current:"), device.name),
+    }
+}
+
+// Picking a backend lists its devices; picking a device returns to the menu.
+// The list is refreshed when asked to, when the backend changes, and every
+// few seconds in case a device was plugged in.
+fn mic_selection_ui(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    mut selected_mic: ResMut<SelectedMic>,
+    mut devices: ResMut<MicDevices>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    backend: Res<Backend>,
+) {
+    let lang = config.general.language;
+    devices.since_refresh += time.delta_seconds();
+    if selected_mic.is_changed() || devices.since_refresh >= DEVICE_REFRESH_SECS {
+        *devices = MicDevices::list(&backend, &selected_mic, lang);
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let t = |text: &'static str| lang.tr(text);
+
+    let mut refresh = false;
+    menu_screen(ctx, t("Select an Input Device"), |ui| {
+        ui.weak(&devices.status);
+        ui.add_space(MENU_SPACING);
+
+        // One entry per backend compiled in and available, e.g. ASIO or
+        // JACK next to the platform default.
+        let current_host = selected_mic.host(&backend);
+        ui.horizontal_wrapped(|ui| {
+            for host in backend.0.hosts() {
+                if ui.selectable_label(host == current_host, &host).clicked()
+                    && host != current_host
+                {
+                    selected_mic.host = Some(host);
+                    selected_mic.device = None;
+                }
+            }
+        });
+        ui.add_space(MENU_SPACING);
+
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() * 0.6)
+            .show(ui, |ui| {
+                for name in &devices.names {
+                    if ui
+                        .add_sized(egui::vec2(500.0, 36.0), egui::Button::new(name))
+                        .clicked()
+                    {
+                        selected_mic.device = Some(name.clone());
+                        next_app_state.set(AppState::MainMenu);
+                    }
+                }
+            });
+        ui.add_space(MENU_SPACING);
+
+        refresh = menu_button(ui, t("⟳ Refresh"));
+        ui.add_space(MENU_SPACING);
+        if menu_button(ui, t("⬅ Back")) {
+            next_app_state.set(AppState::MainMenu);
+        }
+    });
+    if refresh {
+        *devices = MicDevices::list(&backend, &selected_mic, lang);
+    }
+}
+
+// What applies before any visualizer is open: the look of the interface,
+// the visualizer "Start Visualization" opens, and the audio defaults.
+fn settings_ui(
+    mut contexts: EguiContexts,
+    mut config: ResMut<VisualsConfig>,
+    mut active_viz: ResMut<ActiveVisualization>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let lang = config.general.language;
+    let t = |text: &'static str| lang.tr(text);
+
+    menu_screen(ctx, t("⚙ Settings"), |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 2.0 * MENU_BUTTON_SIZE.y)
+            .show(ui, |ui| {
+                ui.set_max_width(400.0);
+                let general = &mut config.general;
+
+                ui.heading(t("🖌 Appearance"));
+                appearance_settings(ui, lang, general);
+                ui.separator();
+
+                ui.heading(t("▶ Start-Up"));
+                let visualizers: Vec<(VisualizerId, &str)> = general
+                    .visualizers
+                    .shown()
+                    .into_iter()
+                    .map(|id| (id, t(viz_label(id))))
+                    .collect();
+                let mut section = SettingsSection::new(ui, "");
+                let before = general.start_visualizer;
+                section.choice(
+                    t("Start-Up Visualizer"),
+                    &mut general.start_visualizer,
+                    &visualizers,
+                );
+                if general.start_visualizer != before {
+                    active_viz.0 = general.start_visualizer.state();
+                }
+                ui.add_space(4.0);
+                ui.label(t("Visualizers"));
+                visualizer_list_settings(ui, lang, &mut general.visualizers);
+                ui.separator();
+
+                ui.heading(t("🔊 Audio"));
+                let mut section = SettingsSection::new(ui, "");
+                mic_buffer_setting(&mut section, lang, &mut general.mic_buffer_frames);
+                section.checkbox(t("Preserve Pitch"), &mut general.preserve_pitch);
+                section.checkbox(t("Pause Audio in Menus"), &mut general.pause_in_menu);
+                section.checkbox(t("Auto Latency"), &mut general.auto_latency);
+                ui.add(
+                    egui::Slider::new(&mut general.latency_offset_ms, -200.0..=500.0)
+                        .suffix(" ms")
+                        .text(t("Visual Offset")),
+                );
+                ui.checkbox(&mut general.analyse_ahead, t("Analyse Files Ahead"));
+            });
+        ui.add_space(MENU_SPACING);
+        if menu_button(ui, t("⬅ Back")) {
+            next_app_state.set(AppState::MainMenu);
+        }
+    });
+}
@@ -1,6 +1,10 @@
 // src/viz_2d.rs
 
-use crate::{audio::AudioAnalysis, config::VisualsConfig, AppState, VisualizationEnabled};
+use crate::{
+    audio::{visuals_awake, AudioAnalysis},
+    config::{Bars2DSettings, VisualsConfig},
+    AppState, VisualizationEnabled,
+};
 use bevy::prelude::*;
 
 pub struct Viz2DPlugin;
@@ -22,7 +26,12 @@ impl Plugin for Viz2DPlugin {
             .add_systems(OnEnter(AppState::Visualization2D), setup_2d_scene)
             .add_systems(
                 Update,
-                (manage_bar_chart, update_2d_visuals.after(manage_bar_chart))
+                (
+                    manage_bar_chart,
+                    update_2d_visuals
+                        .after(manage_bar_chart)
+                        .run_if(visuals_awake),
+                )
                     .run_if(in_state(AppState::Visualization2D))
                     .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0),
             )
@@ -51,7 +60,7 @@ fn despawn_scene(mut commands: Commands, scene_query: Query<Entity, With<Viz2DSc
     commands.insert_resource(BarChartState::default());
 }
 
-// Manages the bar chart by checking if the configuration has changed.
+// Manages the bar chart by checking if the 2D settings have changed.
 // If the number of bands changes, it despawns the old bars and spawns new ones.
 #[allow(clippy::collapsible_if)]
 fn manage_bar_chart(
@@ -61,22 +70,24 @@ fn manage_bar_chart(
     bar_query: Query<Entity, With<VizBar>>,
     scene_query: Query<Entity, With<Viz2DScene>>,
 ) {
-    // Rebuild the bar chart if the config has changed.
-    if config.is_changed() {
+    let settings = config.viz::<Bars2DSettings>();
+
+    // Rebuild the bar chart only if the number of bands has changed.
+    if settings.num_bands != chart_state.num_bands {
         if let Ok(scene_entity) = scene_query.get_single() {
             // Despawn existing bars before creating new ones.
             for entity in &bar_query {
                 commands.entity(entity).despawn_recursive();
             }
-            spawn_visuals(commands, &config, scene_entity);
-            chart_state.num_bands = config.num_bands;
+            spawn_visuals(commands, settings, scene_entity);
+            chart_state.num_bands = settings.num_bands;
         }
     }
 }
 
 // Spawns the individual bars for the 2D visualizer.
-fn spawn_visuals(mut commands: Commands, config: &VisualsConfig, parent_entity: Entity) {
-    let num_bars = config.num_bands;
+fn spawn_visuals(mut commands: Commands, settings: &Bars2DSettings, parent_entity: Entity) {
+    let num_bars = settings.num_bands;
     let bar_width = 40.0;
     let spacing = 10.0;
     let total_width = (num_bars as f32 * bar_width) + ((num_bars - 1) as f32 * spacing);
@@ -88,7 +99,7 @@ fn spawn_visuals(mut commands: Commands, config: &VisualsConfig, parent_entity:
             parent.spawn((
                 SpriteBundle {
                     sprite: Sprite {
-                        color: config.viz2d_inactive_color,
+                        color: settings.inactive_color,
                         custom_size: Some(Vec2::new(bar_width, 50.0)),
                         ..default()
                     },
@@ -107,7 +118,8 @@ fn update_2d_visuals(
     config: Res<VisualsConfig>,
     mut query: Query<(&mut Sprite, &mut Transform, &VizBar)>,
 ) {
-    if audio_analysis.frequency_bins.len() != config.num_bands {
+    let settings = config.viz::<Bars2DSettings>();
+    if audio_analysis.frequency_bins.len() != settings.num_bands {
         return;
     }
 
@@ -115,7 +127,7 @@ fn update_2d_visuals(
 
     for (mut sprite, mut transform, bar) in &mut query {
         if let Some(amplitude) = audio_analysis.frequency_bins.get(bar.index) {
-            let target_height = 50.0 + amplitude * config.bass_sensitivity * 100.0;
+            let target_height = 50.0 + amplitude * config.general.bass_sensitivity * 100.0;
 
             // Apply smoothing to the height change for a smoother animation.
             let current_size = sprite.custom_size.unwrap_or(Vec2::ZERO);
@@ -127,8 +139,8 @@ fn update_2d_visuals(
 
             // Interpolate the bar's color based on its height.
             let color_intensity = (new_height / 800.0).clamp(0.0, 1.0);
-            let inactive = config.viz2d_inactive_color;
-            let active = config.viz2d_active_color;
+            let inactive = settings.inactive_color;
+            let active = settings.active_color;
 
             let r = inactive.r() + (active.r() - inactive.r()) * color_intensity;
             let g = inactive.g() + (active.g() - inactive.g()) * color_intensity;
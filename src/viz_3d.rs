@@ -1,6 +1,12 @@
 // src/viz_3d.rs
 
-use crate::{audio::AudioAnalysis, config::VisualsConfig, AppState, VisualizationEnabled};
+use crate::{
+    analysis::OnsetDetector,
+    audio::{visuals_awake, AudioAnalysis, TickWatcher},
+    config::{Cubes3DSettings, VisualsConfig},
+    governor::ActiveQuality,
+    AppState, VisualizationEnabled,
+};
 use bevy::prelude::*;
 
 pub struct Viz3DPlugin;
@@ -19,17 +25,31 @@ struct VoxelGridState {
 #[derive(Resource, Default)]
 struct ColumnMaterials(Vec<Handle<StandardMaterial>>);
 
+// Per-column onset detection on the rise of each band, and the current
+// strength of each column's flash (1 on an onset, decaying to 0).
+#[derive(Resource, Default)]
+struct ColumnFlashes {
+    detectors: Vec<OnsetDetector>,
+    previous: Vec<f32>,
+    flash: Vec<f32>,
+}
+
 impl Plugin for Viz3DPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<VoxelGridState>()
             .init_resource::<ColumnMaterials>()
+            .init_resource::<ColumnFlashes>()
             .add_systems(
                 Update,
                 (
                     manage_voxel_grid,
                     // The visual update is split into two systems for clarity and order.
-                    update_column_materials.after(manage_voxel_grid),
-                    update_cube_transforms.after(update_column_materials),
+                    update_column_materials
+                        .after(manage_voxel_grid)
+                        .run_if(visuals_awake),
+                    update_cube_transforms
+                        .after(update_column_materials)
+                        .run_if(visuals_awake),
                 )
                     .run_if(in_state(AppState::Visualization3D))
                     .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0),
@@ -39,7 +59,10 @@ impl Plugin for Viz3DPlugin {
                 (
                     despawn_visuals,
                     // Reset the grid state when exiting.
-                    |mut state: ResMut<VoxelGridState>| *state = VoxelGridState::default(),
+                    |mut state: ResMut<VoxelGridState>, mut flashes: ResMut<ColumnFlashes>| {
+                        *state = VoxelGridState::default();
+                        *flashes = ColumnFlashes::default();
+                    },
                 ),
             );
     }
@@ -57,23 +80,34 @@ struct VisualizerCube {
 fn manage_voxel_grid(
     mut commands: Commands,
     config: Res<VisualsConfig>,
+    quality: Res<ActiveQuality>,
     mut grid_state: ResMut<VoxelGridState>,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<StandardMaterial>>,
     cube_query: Query<Entity, With<VisualizerCube>>,
 ) {
+    let settings = config.viz::<Cubes3DSettings>();
+    // Fewer cubes at lower quality.
+    let column_size = quality.0.cube_column_size(settings.column_size);
+
     // Check if the number of bands, color, or column size has changed.
-    if config.num_bands != grid_state.num_bands
-        || config.viz3d_base_color != grid_state.base_color
-        || config.viz3d_column_size != grid_state.column_size
+    if settings.num_bands != grid_state.num_bands
+        || settings.base_color != grid_state.base_color
+        || column_size != grid_state.column_size
     {
         info!("3D visual config changed. Rebuilding voxel grid...");
         despawn_visuals(commands.reborrow(), cube_query);
-        spawn_visuals(commands.reborrow(), meshes, materials, &config);
+        spawn_visuals(
+            commands.reborrow(),
+            meshes,
+            materials,
+            settings,
+            column_size,
+        );
         // Update the state to reflect the new configuration.
-        grid_state.num_bands = config.num_bands;
-        grid_state.base_color = config.viz3d_base_color;
-        grid_state.column_size = config.viz3d_column_size;
+        grid_state.num_bands = settings.num_bands;
+        grid_state.base_color = settings.base_color;
+        grid_state.column_size = column_size;
     }
 }
 
@@ -92,12 +126,12 @@ fn spawn_visuals(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    config: &VisualsConfig,
+    settings: &Cubes3DSettings,
+    column_size: usize,
 ) {
     let cube_mesh = meshes.add(Cuboid::new(1.0, 1.0, 1.0));
     let cube_spacing = 1.5;
-    let num_bands = config.num_bands;
-    let column_size = config.viz3d_column_size;
+    let num_bands = settings.num_bands;
 
     let mut column_materials_vec = Vec::with_capacity(num_bands);
 
@@ -106,7 +140,7 @@ fn spawn_visuals(
 
         // Create a single material for the entire column.
         let material = materials.add(StandardMaterial {
-            base_color: config.viz3d_base_color,
+            base_color: settings.base_color,
             emissive: Color::BLACK, // Emissive color will be updated based on audio.
             metallic: 1.0,
             perceptual_roughness: 0.1,
@@ -138,46 +172,107 @@ fn spawn_visuals(
     commands.insert_resource(ColumnMaterials(column_materials_vec));
 }
 
-// Updates the emissive property of each column's material based on audio amplitude.
+// Updates the emissive property of each column's material based on audio amplitude,
+// and flashes columns toward the accent color on their band's onsets.
 // This is a very fast operation as it only modifies a few material assets.
 #[allow(clippy::collapsible_if)]
 fn update_column_materials(
     audio_analysis: Res<AudioAnalysis>,
     config: Res<VisualsConfig>,
+    time: Res<Time>,
     column_materials: Res<ColumnMaterials>,
+    mut flashes: ResMut<ColumnFlashes>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ticks: Local<TickWatcher>,
 ) {
-    if audio_analysis.frequency_bins.len() != config.num_bands {
+    let settings = config.viz::<Cubes3DSettings>();
+    if audio_analysis.frequency_bins.len() != settings.num_bands {
         return;
     }
 
     let afr = &audio_analysis.frequency_bins;
+    update_flashes(
+        &mut flashes,
+        afr,
+        ticks.ticked(&audio_analysis),
+        time.delta_seconds(),
+        settings.flash_decay,
+    );
 
-    for band_index in 0..config.num_bands {
+    for band_index in 0..settings.num_bands {
         if let (Some(material_handle), Some(amplitude)) =
             (column_materials.0.get(band_index), afr.get(band_index))
         {
             if let Some(material) = materials.get_mut(material_handle) {
-                let scale_y = 1.0 + amplitude * config.bass_sensitivity;
+                let scale_y = 1.0 + amplitude * config.general.bass_sensitivity;
                 // If bloom is enabled, make the cubes glow based on their scale.
-                material.emissive = if config.bloom_enabled {
+                let glow = if settings.bloom.enabled {
                     let glow_intensity = (scale_y - 1.0).max(0.0);
-                    config.bloom_color * glow_intensity * 2.0
+                    settings.bloom.color * glow_intensity * 2.0
                 } else {
                     Color::BLACK
                 };
+
+                let flash = if settings.onset_flash {
+                    flashes.flash[band_index]
+                } else {
+                    0.0
+                };
+                material.base_color = mix(settings.base_color, settings.flash_color, flash);
+                material.emissive = glow + settings.flash_color * flash * 2.0;
+            }
+        }
+    }
+}
+
+// Runs the detectors when a new analysis frame came in and decays the flashes.
+fn update_flashes(
+    flashes: &mut ColumnFlashes,
+    bands: &[f32],
+    new_frame: bool,
+    dt: f32,
+    decay: f32,
+) {
+    if flashes.flash.len() != bands.len() {
+        *flashes = ColumnFlashes {
+            detectors: (0..bands.len()).map(|_| OnsetDetector::default()).collect(),
+            previous: bands.to_vec(),
+            flash: vec![0.0; bands.len()],
+        };
+    }
+
+    let fade = (-dt / decay.max(0.01)).exp();
+    let ColumnFlashes {
+        detectors,
+        previous,
+        flash,
+    } = flashes;
+    for (band, &amplitude) in bands.iter().enumerate() {
+        flash[band] *= fade;
+        if new_frame {
+            let rise = (amplitude - previous[band]).max(0.0);
+            previous[band] = amplitude;
+            if detectors[band].detect(rise) {
+                flash[band] = 1.0;
             }
         }
     }
 }
 
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    Color::rgba_from_array(from.lerp(to, amount))
+}
+
 // Updates the transform (position and scale) of each individual cube.
 fn update_cube_transforms(
     audio_analysis: Res<AudioAnalysis>,
     config: Res<VisualsConfig>,
     mut query: Query<(&mut Transform, &VisualizerCube)>,
 ) {
-    if audio_analysis.frequency_bins.len() != config.num_bands {
+    let settings = config.viz::<Cubes3DSettings>();
+    if audio_analysis.frequency_bins.len() != settings.num_bands {
         return;
     }
 
@@ -186,13 +281,13 @@ fn update_cube_transforms(
     for (mut transform, cube) in &mut query {
         if let Some(band_amplitude) = audio_analysis.frequency_bins.get(cube.frequency_band) {
             // Scale the cube's height based on the amplitude of its frequency band.
-            let target_scale = 1.0 + band_amplitude * config.bass_sensitivity;
+            let target_scale = 1.0 + band_amplitude * config.general.bass_sensitivity;
             // Apply smoothing for a more fluid motion.
             transform.scale.y =
                 transform.scale.y + (target_scale - transform.scale.y) * smoothing_factor;
 
             // If the spread effect is enabled, move the cubes outwards based on treble.
-            if config.spread_enabled {
+            if settings.spread_enabled {
                 let spread_factor = 1.0 + (audio_analysis.treble_average * 0.1).min(1.5);
                 transform.translation.x = cube.initial_position.x * spread_factor;
                 transform.translation.z = cube.initial_position.z * spread_factor;
@@ -1,10 +1,15 @@
-use crate::{audio::AudioAnalysis, camera::MainCamera2D, config::VisualsConfig, AppState};
+use crate::{
+    audio::{visuals_awake, AudioAnalysis},
+    audio_texture::{storage_array, AudioTexture},
+    audio_uniforms::AudioUniformsBuffer,
+    config::{DiscSettings, VisualsConfig},
+    AppState,
+};
 use bevy::{
     prelude::*,
     reflect::TypePath,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    render::render_resource::{AsBindGroup, Buffer, ShaderRef},
     sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
-    window::PrimaryWindow,
 };
 
 pub struct VizDiscPlugin;
@@ -15,7 +20,9 @@ impl Plugin for VizDiscPlugin {
             .add_systems(OnEnter(AppState::VisualizationDisc), setup_disc_scene)
             .add_systems(
                 Update,
-                update_disc_material.run_if(in_state(AppState::VisualizationDisc)),
+                update_disc_material
+                    .run_if(in_state(AppState::VisualizationDisc))
+                    .run_if(visuals_awake),
             )
             .add_systems(OnExit(AppState::VisualizationDisc), despawn_scene);
     }
@@ -24,34 +31,34 @@ impl Plugin for VizDiscPlugin {
 #[derive(Component)]
 struct DiscScene;
 
-// Added #[repr(C)] to guarantee memory alignment with the shader
+// Added #[repr(C)] to guarantee memory alignment with the shader. Time,
+// resolution, zoom and the audio levels come from the shared `AudioUniforms`.
 #[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
 #[repr(C)]
 pub struct DiscMaterial {
     #[uniform(0)]
     color: Vec4, // 16 bytes (offset 0)
     #[uniform(0)]
-    time: f32, // 4 bytes  (offset 16)
-    #[uniform(0)]
-    radius: f32, // 4 bytes  (offset 20)
-    #[uniform(0)]
-    line_thickness: f32, // 4 bytes  (offset 24)
-    #[uniform(0)]
-    iterations: f32, // 4 bytes  (offset 28)
+    radius: f32, // 4 bytes  (offset 16)
     #[uniform(0)]
-    speed: f32, // 4 bytes  (offset 32)
+    line_thickness: f32, // 4 bytes  (offset 20)
     #[uniform(0)]
-    center_radius_factor: f32, // 4 bytes  (offset 36)
+    iterations: f32, // 4 bytes  (offset 24)
     #[uniform(0)]
-    resolution: Vec2, // 8 bytes  (offset 40)
+    speed: f32, // 4 bytes  (offset 28)
     #[uniform(0)]
-    bass: f32, // 4 bytes  (offset 48)
+    center_radius_factor: f32, // 4 bytes  (offset 32)
     #[uniform(0)]
-    flux: f32, // 4 bytes  (offset 52)
-    #[uniform(0)]
-    zoom: f32, // 4 bytes  (offset 56)
-    #[uniform(0)]
-    _padding: f32, // 4 bytes  (offset 60 -> 64 total)
+    band_count: f32, // 4 bytes  (offset 36 -> 48 padded), 0 unless each ring follows its own band
+    #[texture(1)]
+    #[sampler(2)]
+    audio_texture: Handle<Image>, // spectrum and waveform, see `AudioTexture`
+    #[storage(3, read_only)]
+    bins: Vec<f32>, // every band of the latest analysis frame
+    #[storage(4, read_only)]
+    waveform: Vec<f32>, // samples of the latest analysis window, -1..1
+    #[storage(5, read_only, buffer)]
+    audio: Buffer, // see `AudioUniforms`
 }
 
 impl Material2d for DiscMaterial {
@@ -69,23 +76,25 @@ fn setup_disc_scene(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<DiscMaterial>>,
     config: Res<VisualsConfig>,
+    audio_texture: Res<AudioTexture>,
+    audio_uniforms: Res<AudioUniformsBuffer>,
 ) {
+    let settings = config.viz::<DiscSettings>();
     let quad_handle = meshes.add(Rectangle::new(1.0, 1.0));
 
     // Initialize with default values
     let material_handle = materials.add(DiscMaterial {
-        color: color_to_vec4(config.disc_color),
-        time: 0.0,
-        radius: config.disc_radius,
-        line_thickness: config.disc_line_thickness,
-        iterations: config.disc_iterations as f32,
-        speed: config.disc_speed,
-        center_radius_factor: config.disc_center_radius_factor,
-        resolution: Vec2::new(800.0, 600.0), // Temporary value, updated in the loop
-        bass: 0.0,
-        flux: 0.0,
-        zoom: 1.0,
-        _padding: 0.0,
+        color: color_to_vec4(settings.color),
+        radius: settings.radius,
+        line_thickness: settings.line_thickness,
+        iterations: settings.iterations as f32,
+        speed: settings.speed,
+        center_radius_factor: settings.center_radius_factor,
+        band_count: 0.0,
+        audio_texture: audio_texture.0.clone(),
+        bins: storage_array(&[]),
+        waveform: storage_array(&[]),
+        audio: audio_uniforms.0.clone(),
     });
 
     commands.spawn((
@@ -101,44 +110,35 @@ fn setup_disc_scene(
 }
 
 fn update_disc_material(
-    time: Res<Time>,
     config: Res<VisualsConfig>,
     audio_analysis: Res<AudioAnalysis>,
     mut materials: ResMut<Assets<DiscMaterial>>,
-    q_window: Query<&Window, With<PrimaryWindow>>,
-    q_camera: Query<&OrthographicProjection, With<MainCamera2D>>,
+    q_material: Query<&Handle<DiscMaterial>, With<DiscScene>>,
 ) {
-    let Ok(window) = q_window.get_single() else {
+    let Some(material) = q_material
+        .get_single()
+        .ok()
+        .and_then(|handle| materials.get_mut(handle))
+    else {
         return;
     };
+    let settings = config.viz::<DiscSettings>();
 
-    // Use PHYSICAL resolution to match frag_coord
-    let window_resolution = Vec2::new(
-        window.resolution.physical_width() as f32,
-        window.resolution.physical_height() as f32,
-    );
-
-    // Retrieve camera zoom (mouse wheel)
-    let zoom_level = if let Ok(projection) = q_camera.get_single() {
-        projection.scale
+    let band_count = if settings.band_rings {
+        audio_analysis.frequency_bins.len()
     } else {
-        1.0
+        0
     };
 
-    for (_, material) in materials.iter_mut() {
-        material.time = time.elapsed_seconds();
-        material.color = color_to_vec4(config.disc_color);
-        material.radius = config.disc_radius;
-        material.line_thickness = config.disc_line_thickness;
-        material.iterations = config.disc_iterations as f32;
-        material.speed = config.disc_speed;
-        material.center_radius_factor = config.disc_center_radius_factor;
-        material.resolution = window_resolution;
-        material.bass = audio_analysis.bass;
-        material.flux = audio_analysis.flux;
-        material.zoom = zoom_level;
-        // _padding doesn't need to be updated
-    }
+    material.color = color_to_vec4(settings.color);
+    material.radius = settings.radius;
+    material.line_thickness = settings.line_thickness;
+    material.iterations = settings.iterations as f32;
+    material.speed = settings.speed;
+    material.center_radius_factor = settings.center_radius_factor;
+    material.band_count = band_count as f32;
+    material.bins = storage_array(&audio_analysis.frequency_bins);
+    material.waveform = storage_array(&audio_analysis.waveform);
 }
 
 fn despawn_scene(mut commands: Commands, scene_query: Query<Entity, With<DiscScene>>) {
@@ -1,8 +1,14 @@
-use crate::{audio::AudioAnalysis, camera::MainCamera2D, config::VisualsConfig, AppState};
+use crate::{
+    audio::{visuals_awake, AudioAnalysis},
+    audio_texture::{storage_array, AudioTexture},
+    audio_uniforms::AudioUniformsBuffer,
+    config::{IcoSettings, VisualsConfig},
+    AppState,
+};
 use bevy::{
     prelude::*,
     reflect::TypePath,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    render::render_resource::{AsBindGroup, Buffer, ShaderRef},
     sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
     window::PrimaryWindow,
 };
@@ -15,7 +21,9 @@ impl Plugin for VizIcoPlugin {
             .add_systems(OnEnter(AppState::VisualizationIco), setup_ico_scene)
             .add_systems(
                 Update,
-                update_ico_material.run_if(in_state(AppState::VisualizationIco)),
+                update_ico_material
+                    .run_if(in_state(AppState::VisualizationIco))
+                    .run_if(visuals_awake),
             )
             .add_systems(OnExit(AppState::VisualizationIco), despawn_scene);
     }
@@ -24,17 +32,31 @@ impl Plugin for VizIcoPlugin {
 #[derive(Component)]
 struct IcoScene;
 
+// Time, resolution and zoom come from the shared `AudioUniforms`.
 #[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
 #[repr(C)]
 pub struct IcoMaterial {
     #[uniform(0)]
     pub color: Vec4, // r, g, b, a
     #[uniform(0)]
-    pub resolution_mouse: Vec4, // x=width, y=height, z=mouseX, w=mouseY
+    pub view_params: Vec4, // x,y=view orbit offset, -1..1, z=speed, w=unused
+    #[uniform(0)]
+    pub audio_params: Vec4, // x=bass, y=mid, z=treble, w=flux, scaled by the bass sensitivity
     #[uniform(0)]
-    pub time_params: Vec4, // x=time, y=speed, z=ZOOM (camera scale), w=unused
+    pub secondary_color: Vec4, // ambient light tint
     #[uniform(0)]
-    pub audio_params: Vec4, // x=bass, y=mid, z=treble, w=flux
+    pub background_color: Vec4,
+    #[uniform(0)]
+    pub glow_params: Vec4, // x=rim glow intensity, y,z,w=unused
+    #[texture(1)]
+    #[sampler(2)]
+    pub audio_texture: Handle<Image>, // spectrum and waveform, see `AudioTexture`
+    #[storage(3, read_only)]
+    pub bins: Vec<f32>, // every band of the latest analysis frame
+    #[storage(4, read_only)]
+    pub waveform: Vec<f32>, // samples of the latest analysis window, -1..1
+    #[storage(5, read_only, buffer)]
+    pub audio: Buffer, // see `AudioUniforms`
 }
 
 impl Material2d for IcoMaterial {
@@ -48,15 +70,24 @@ fn setup_ico_scene(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<IcoMaterial>>,
     config: Res<VisualsConfig>,
+    audio_texture: Res<AudioTexture>,
+    audio_uniforms: Res<AudioUniformsBuffer>,
 ) {
+    let settings = config.viz::<IcoSettings>();
     let quad_handle = meshes.add(Rectangle::new(1.0, 1.0));
 
     // Initialize with default values
     let material_handle = materials.add(IcoMaterial {
-        color: Vec4::from(config.ico_color.as_linear_rgba_f32()),
-        resolution_mouse: Vec4::new(800.0, 600.0, 0.0, 0.0),
-        time_params: Vec4::new(0.0, config.ico_speed, 1.0, 0.0),
+        color: Vec4::from(settings.color.as_linear_rgba_f32()),
+        view_params: Vec4::new(0.0, 0.0, settings.speed, 0.0),
         audio_params: Vec4::ZERO,
+        secondary_color: Vec4::from(settings.secondary_color.as_linear_rgba_f32()),
+        background_color: Vec4::from(settings.background_color.as_linear_rgba_f32()),
+        glow_params: Vec4::new(settings.glow_intensity, 0.0, 0.0, 0.0),
+        audio_texture: audio_texture.0.clone(),
+        bins: storage_array(&[]),
+        waveform: storage_array(&[]),
+        audio: audio_uniforms.0.clone(),
     });
 
     commands.spawn((
@@ -76,46 +107,60 @@ fn update_ico_material(
     config: Res<VisualsConfig>,
     audio_analysis: Res<AudioAnalysis>,
     mut materials: ResMut<Assets<IcoMaterial>>,
+    q_material: Query<&Handle<IcoMaterial>, With<IcoScene>>,
     q_window: Query<&Window, With<PrimaryWindow>>,
-    q_camera: Query<&OrthographicProjection, With<MainCamera2D>>,
 ) {
     let Ok(window) = q_window.get_single() else {
         return;
     };
-
-    let width = window.resolution.physical_width() as f32;
-    let height = window.resolution.physical_height() as f32;
-    let mouse = window.cursor_position().unwrap_or(Vec2::ZERO);
-
-    let zoom_level = if let Ok(projection) = q_camera.get_single() {
-        projection.scale
-    } else {
-        1.0
+    let Some(material) = q_material
+        .get_single()
+        .ok()
+        .and_then(|handle| materials.get_mut(handle))
+    else {
+        return;
     };
 
+    let settings = config.viz::<IcoSettings>();
+    let orbit = view_orbit(window, settings, time.elapsed_seconds());
+
     // --- SENSITIVITY LOGIC ---
     // Retrieve sensitivity from UI (default 4.0)
     // Multiply by 0.05 (equivalent to dividing by 20) to drastically reduce the base effect.
     // Thus, at 4.0, we have a factor of 0.2, which is much smoother.
-    let sensitivity = config.bass_sensitivity * 0.03;
-
-    for (_, material) in materials.iter_mut() {
-        material.color = Vec4::from(config.ico_color.as_linear_rgba_f32());
-
-        material.resolution_mouse = Vec4::new(width, height, mouse.x, height - mouse.y);
-
-        material.time_params.x = time.elapsed_seconds();
-        material.time_params.y = config.ico_speed;
-        material.time_params.z = zoom_level;
+    let sensitivity = config.general.bass_sensitivity * 0.03;
+
+    material.color = Vec4::from(settings.color.as_linear_rgba_f32());
+    material.secondary_color = Vec4::from(settings.secondary_color.as_linear_rgba_f32());
+    material.background_color = Vec4::from(settings.background_color.as_linear_rgba_f32());
+    material.glow_params.x = settings.glow_intensity;
+
+    material.view_params = Vec4::new(orbit.x, orbit.y, settings.speed, 0.0);
+
+    // Apply 'sensitivity' factor to all bands
+    material.audio_params = Vec4::new(
+        audio_analysis.bass * sensitivity,
+        audio_analysis.mid * sensitivity,
+        audio_analysis.treble * sensitivity,
+        audio_analysis.flux * sensitivity,
+    );
+    material.bins = storage_array(&audio_analysis.frequency_bins);
+    material.waveform = storage_array(&audio_analysis.waveform);
+}
 
-        // Apply 'sensitivity' factor to all bands
-        material.audio_params = Vec4::new(
-            audio_analysis.bass * sensitivity,
-            audio_analysis.mid * sensitivity,
-            audio_analysis.treble * sensitivity,
-            audio_analysis.flux * sensitivity,
-        );
-    }
+// Offset of the view around the ico, from the cursor's distance to the window
+// center, or a slow figure-eight with auto orbit. A cursor outside the window
+// leaves the view centered.
+fn view_orbit(window: &Window, settings: &IcoSettings, time: f32) -> Vec2 {
+    let offset = if settings.auto_orbit {
+        Vec2::new((time * 0.2).sin(), (time * 0.4).sin() * 0.5)
+    } else {
+        let center = Vec2::new(window.width(), window.height()) / 2.0;
+        window.cursor_position().map_or(Vec2::ZERO, |cursor| {
+            (cursor - center) / center.y * Vec2::new(1.0, -1.0)
+        })
+    };
+    (offset * settings.mouse_sensitivity).clamp(Vec2::splat(-1.0), Vec2::splat(1.0))
 }
 
 fn despawn_scene(mut commands: Commands, scene_query: Query<Entity, With<IcoScene>>) {
@@ -1,6 +1,12 @@
 // src/viz_orb.rs
 
-use crate::{audio::AudioAnalysis, config::VisualsConfig, AppState, VisualizationEnabled};
+use crate::{
+    analysis::OnsetDetector,
+    audio::{visuals_awake, AudioAnalysis, TickWatcher},
+    config::{OrbSettings, VisualsConfig},
+    governor::ActiveQuality,
+    AppState, VisualizationEnabled,
+};
 use bevy::{
     prelude::*,
     render::mesh::{Mesh, VertexAttributeValues},
@@ -9,6 +15,11 @@ use noise::{NoiseFn, Perlin};
 
 pub struct VizOrbPlugin;
 
+// Angular width of a ripple ring on the orb, in radians.
+const RIPPLE_WIDTH: f32 = 0.25;
+// How fast a ripple fades as it travels, per second.
+const RIPPLE_FADE: f32 = 1.0;
+
 // A marker component for all visual elements of the orb scene.
 #[derive(Component)]
 struct OrbVisual;
@@ -17,8 +28,15 @@ struct OrbVisual;
 // including its original vertex positions and a noise generator.
 #[derive(Component)]
 struct DeformableOrb {
+    // The icosphere subdivisions the mesh was built with, see `Quality`.
+    subdivisions: usize,
     original_vertices: Vec<[f32; 3]>,
     noise: Perlin,
+    // Beats start rings at the top of the orb that travel down its surface;
+    // these are the ages of the rings still moving, in seconds.
+    onsets: OnsetDetector,
+    ticks: TickWatcher,
+    ripples: Vec<f32>,
 }
 
 impl Plugin for VizOrbPlugin {
@@ -26,9 +44,10 @@ impl Plugin for VizOrbPlugin {
         app.add_systems(OnEnter(AppState::VisualizationOrb), setup_orb)
             .add_systems(
                 Update,
-                deform_orb
+                (apply_orb_quality, deform_orb.after(apply_orb_quality))
                     .run_if(in_state(AppState::VisualizationOrb))
-                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0),
+                    .run_if(|viz_enabled: Res<VisualizationEnabled>| viz_enabled.0)
+                    .run_if(visuals_awake),
             )
             .add_systems(OnExit(AppState::VisualizationOrb), despawn_orb_visuals);
     }
@@ -40,44 +59,77 @@ fn setup_orb(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     config: Res<VisualsConfig>,
+    quality: Res<ActiveQuality>,
 ) {
-    // Create a base IcoSphere mesh with a given subdivision level.
-    let mut sphere_mesh = Sphere::new(3.0).mesh().ico(5).unwrap();
-
-    // The mesh must be "un-indexed" or "flattened" so that each triangle
-    // has its own unique vertices. This is required for `compute_flat_normals`
-    // to work correctly and give the orb its low-poly, faceted look.
-    sphere_mesh.duplicate_vertices();
-    sphere_mesh.compute_flat_normals();
-
-    // Store the original positions of the vertices from the flattened mesh.
-    // These will be used as a base for the deformation calculations.
-    let original_vertices = match sphere_mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
-        Some(VertexAttributeValues::Float32x3(vertices)) => vertices.clone(),
-        _ => Vec::new(),
-    };
+    let settings = config.viz::<OrbSettings>();
+    let subdivisions = quality.0.orb_subdivisions();
+    let (sphere_mesh, original_vertices) = orb_mesh(subdivisions);
 
     // Spawn the orb entity.
     commands.spawn((
         PbrBundle {
             mesh: meshes.add(sphere_mesh), // Add the prepared mesh to assets.
             material: materials.add(StandardMaterial {
-                base_color: config.orb_base_color,
+                base_color: settings.base_color,
                 perceptual_roughness: 0.8,
                 metallic: 0.2,
-                emissive: config.orb_base_color,
+                emissive: settings.base_color,
                 ..default()
             }),
             ..default()
         },
         DeformableOrb {
+            subdivisions,
             original_vertices,
             noise: Perlin::new(1), // Initialize the Perlin noise generator.
+            onsets: OnsetDetector::default(),
+            ticks: TickWatcher::default(),
+            ripples: Vec::new(),
         },
         OrbVisual,
     ));
 }
 
+// Creates a base IcoSphere mesh with the given subdivision level, with the
+// original positions of its vertices.
+fn orb_mesh(subdivisions: usize) -> (Mesh, Vec<[f32; 3]>) {
+    let mut sphere_mesh = Sphere::new(3.0).mesh().ico(subdivisions).unwrap();
+
+    // The mesh must be "un-indexed" or "flattened" so that each triangle
+    // has its own unique vertices. This is required for `compute_flat_normals`
+    // to work correctly and give the orb its low-poly, faceted look.
+    sphere_mesh.duplicate_vertices();
+    sphere_mesh.compute_flat_normals();
+
+    // Store the original positions of the vertices from the flattened mesh.
+    // These will be used as a base for the deformation calculations.
+    let original_vertices = match sphere_mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(vertices)) => vertices.clone(),
+        _ => Vec::new(),
+    };
+    (sphere_mesh, original_vertices)
+}
+
+// Rebuilds the orb's mesh when the quality changes its subdivisions.
+fn apply_orb_quality(
+    quality: Res<ActiveQuality>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&Handle<Mesh>, &mut DeformableOrb)>,
+) {
+    if !quality.is_changed() {
+        return;
+    }
+    let subdivisions = quality.0.orb_subdivisions();
+    for (mesh_handle, mut orb) in &mut query {
+        if orb.subdivisions != subdivisions {
+            let (sphere_mesh, original_vertices) = orb_mesh(subdivisions);
+            meshes.insert(mesh_handle, sphere_mesh);
+            orb.subdivisions = subdivisions;
+            orb.original_vertices = original_vertices;
+        }
+    }
+}
+
 // This system deforms the orb's mesh and updates its material properties each frame.
 fn deform_orb(
     time: Res<Time>,
@@ -85,19 +137,42 @@ fn deform_orb(
     audio_analysis: Res<AudioAnalysis>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut query: Query<(&Handle<Mesh>, &Handle<StandardMaterial>, &DeformableOrb)>,
+    mut query: Query<(&Handle<Mesh>, &Handle<StandardMaterial>, &mut DeformableOrb)>,
+    // Smoothed position along the palette, 0 (bass) to 1 (treble).
+    mut palette_position: Local<f32>,
 ) {
-    if audio_analysis.frequency_bins.is_empty() {
+    let settings = config.viz::<OrbSettings>();
+    let bass_bands = (settings.num_bands / 4).max(1);
+    if audio_analysis.frequency_bins.len() < bass_bands {
         return;
     }
 
     // Calculate the total amplitude of the bass frequencies.
-    let total_bass_amplitude = audio_analysis.frequency_bins[0..config.num_bands / 4]
+    let total_bass_amplitude = audio_analysis.frequency_bins[0..bass_bands]
         .iter()
         .sum::<f32>()
-        / (config.num_bands / 4) as f32;
+        / bass_bands as f32;
+
+    let target = if settings.dominant_band_hue {
+        dominant_band(&audio_analysis.frequency_bins)
+    } else {
+        spectral_centroid(&audio_analysis.frequency_bins)
+    };
+    // Eased so the color drifts with the music instead of flickering.
+    let ease = 1.0 - (-time.delta_seconds() * 3.0).exp();
+    *palette_position += (target.unwrap_or(*palette_position) - *palette_position) * ease;
+    let glow_color = sample_palette(
+        &[
+            settings.palette_low,
+            settings.palette_mid,
+            settings.palette_high,
+        ],
+        *palette_position,
+    );
+
+    for (mesh_handle, material_handle, mut orb) in &mut query {
+        update_ripples(&mut orb, &audio_analysis, time.delta_seconds(), settings);
 
-    for (mesh_handle, material_handle, orb) in &mut query {
         if let Some(mesh) = meshes.get_mut(mesh_handle) {
             let vertices = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION).unwrap();
 
@@ -112,10 +187,10 @@ fn deform_orb(
                     let normalized_pos = original_pos.normalize();
 
                     // Influence the noise with time and treble from the audio.
-                    let time_val = time.elapsed_seconds() * config.orb_noise_speed;
+                    let time_val = time.elapsed_seconds() * settings.noise_speed;
                     let treble_factor =
-                        1.0 + audio_analysis.treble_average * config.orb_treble_influence;
-                    let noise_frequency = config.orb_noise_frequency * treble_factor;
+                        1.0 + audio_analysis.treble_average * settings.treble_influence;
+                    let noise_frequency = settings.noise_frequency * treble_factor;
 
                     // Sample the 3D Perlin noise function.
                     let noise_input = (normalized_pos * noise_frequency) + time_val;
@@ -126,7 +201,9 @@ fn deform_orb(
                     ]) as f32;
 
                     // Displace the vertex along its normal based on the noise value and bass amplitude.
-                    let displacement = noise_value * total_bass_amplitude * config.bass_sensitivity;
+                    let displacement =
+                        noise_value * total_bass_amplitude * config.general.bass_sensitivity
+                            + ripple_displacement(&orb.ripples, normalized_pos, settings);
                     let new_pos = original_pos + normalized_pos * displacement;
 
                     // Assign the new position
@@ -139,14 +216,86 @@ fn deform_orb(
             mesh.compute_flat_normals();
         }
 
-        // Update the material's emissive color based on the bass amplitude.
+        // The palette picks the glow color, the bass amplitude its strength.
         if let Some(material) = materials.get_mut(material_handle) {
             let emissive_intensity = (total_bass_amplitude * 2.0).clamp(0.0, 5.0);
-            material.emissive = config.orb_peak_color * emissive_intensity;
+            material.emissive = glow_color * emissive_intensity;
         }
     }
 }
 
+// Starts a ripple on each beat and drops the ones that went past the bottom.
+fn update_ripples(
+    orb: &mut DeformableOrb,
+    audio_analysis: &Res<AudioAnalysis>,
+    dt: f32,
+    settings: &OrbSettings,
+) {
+    for age in &mut orb.ripples {
+        *age += dt;
+    }
+    let end = std::f32::consts::PI + RIPPLE_WIDTH * 2.0;
+    let speed = settings.ripple_speed.max(0.1);
+    orb.ripples.retain(|age| age * speed < end);
+
+    // Only new analysis frames can hold a beat.
+    if orb.ticks.ticked(audio_analysis)
+        && orb.onsets.detect(audio_analysis.flux)
+        && settings.ripple_amplitude > 0.0
+    {
+        orb.ripples.push(0.0);
+    }
+}
+
+// The outward push of every ring at a point of the unit sphere: a bump
+// around the ring's angle from the top, fading as the ring ages.
+fn ripple_displacement(ripples: &[f32], normal: Vec3, settings: &OrbSettings) -> f32 {
+    if ripples.is_empty() {
+        return 0.0;
+    }
+    let angle = normal.y.clamp(-1.0, 1.0).acos();
+    ripples
+        .iter()
+        .map(|age| {
+            let offset = (angle - age * settings.ripple_speed) / RIPPLE_WIDTH;
+            settings.ripple_amplitude * (-offset * offset).exp() * (-age * RIPPLE_FADE).exp()
+        })
+        .sum()
+}
+
+// Where the spectrum's center of mass sits among the bands, from 0 (all bass)
+// to 1 (all treble). None in silence.
+fn spectral_centroid(bands: &[f32]) -> Option<f32> {
+    let total: f32 = bands.iter().sum();
+    if bands.len() < 2 || total <= f32::EPSILON {
+        return None;
+    }
+    let weighted: f32 = bands
+        .iter()
+        .enumerate()
+        .map(|(i, amplitude)| i as f32 * amplitude)
+        .sum();
+    Some(weighted / total / (bands.len() - 1) as f32)
+}
+
+// The position of the loudest band, from 0 to 1. None in silence.
+fn dominant_band(bands: &[f32]) -> Option<f32> {
+    let (index, loudest) = bands.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    if bands.len() < 2 || *loudest <= f32::EPSILON {
+        return None;
+    }
+    Some(index as f32 / (bands.len() - 1) as f32)
+}
+
+// Linear blend between evenly spaced palette stops.
+fn sample_palette(stops: &[Color], position: f32) -> Color {
+    let scaled = position.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let index = (scaled.floor() as usize).min(stops.len() - 2);
+    let from = Vec4::from(stops[index].as_rgba_f32());
+    let to = Vec4::from(stops[index + 1].as_rgba_f32());
+    Color::rgba_from_array(from.lerp(to, scaled - index as f32))
+}
+
 // Despawns the orb visuals when exiting the `VisualizationOrb` state.
 fn despawn_orb_visuals(mut commands: Commands, query: Query<Entity, With<OrbVisual>>) {
     for entity in &query {
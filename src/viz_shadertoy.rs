@@ -0,0 +1,188 @@
+use crate::{
+    audio::{visuals_awake, AudioAnalysis},
+    audio_texture::{storage_array, AudioTexture},
+    audio_uniforms::AudioUniformsBuffer,
+    config::{ShadertoySettings, VisualsConfig},
+    gpu_fft::GpuAudioTexture,
+    loudness::loudness_level,
+    AppState,
+};
+use bevy::{
+    prelude::*,
+    reflect::TypePath,
+    render::render_resource::{AsBindGroup, Buffer, ShaderRef},
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+    window::PrimaryWindow,
+};
+
+pub struct VizShadertoyPlugin;
+
+impl Plugin for VizShadertoyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<ShadertoyMaterial>::default())
+            .add_systems(
+                OnEnter(AppState::VisualizationShadertoy),
+                setup_shadertoy_scene,
+            )
+            .add_systems(
+                Update,
+                update_shadertoy_material
+                    .run_if(in_state(AppState::VisualizationShadertoy))
+                    .run_if(visuals_awake),
+            )
+            .add_systems(OnExit(AppState::VisualizationShadertoy), despawn_scene);
+    }
+}
+
+#[derive(Component)]
+struct ShadertoyScene;
+
+// The uniforms of a Shadertoy shader, with the `AudioTexture` as iChannel0, so
+// ports only need their GLSL translated to WGSL (see the header of
+// `shadertoy.wgsl`).
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+pub struct ShadertoyMaterial {
+    #[uniform(0)]
+    pub resolution: Vec4, // x=width, y=height, z=pixel aspect ratio, w=unused
+    #[uniform(0)]
+    pub mouse: Vec4, // as Shadertoy's iMouse, in pixels from the bottom-left corner
+    #[uniform(0)]
+    pub time_params: Vec4, // x=time, y=time delta, z=frame, w=unused
+    #[uniform(0)]
+    pub tint: Vec4,
+    #[uniform(0)]
+    pub loudness: Vec4, // x=momentary, y=short-term loudness in 0..1; z, w the same in LUFS
+    #[texture(1)]
+    #[sampler(2)]
+    pub channel0: Handle<Image>,
+    #[storage(3, read_only)]
+    pub bins: Vec<f32>, // every band of the latest analysis frame
+    #[storage(4, read_only)]
+    pub waveform: Vec<f32>, // samples of the latest analysis window, -1..1
+    #[storage(5, read_only, buffer)]
+    pub audio: Buffer, // see `AudioUniforms`
+}
+
+impl Material2d for ShadertoyMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/shadertoy.wgsl".into()
+    }
+}
+
+fn setup_shadertoy_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ShadertoyMaterial>>,
+    config: Res<VisualsConfig>,
+    audio_texture: Res<AudioTexture>,
+    audio_uniforms: Res<AudioUniformsBuffer>,
+) {
+    let settings = config.viz::<ShadertoySettings>();
+    let quad_handle = meshes.add(Rectangle::new(1.0, 1.0));
+
+    let material_handle = materials.add(ShadertoyMaterial {
+        resolution: Vec4::new(800.0, 600.0, 1.0, 0.0),
+        mouse: Vec4::ZERO,
+        time_params: Vec4::ZERO,
+        tint: Vec4::from(settings.tint.as_linear_rgba_f32()),
+        loudness: Vec4::ZERO,
+        channel0: audio_texture.0.clone(),
+        bins: storage_array(&[]),
+        waveform: storage_array(&[]),
+        audio: audio_uniforms.0.clone(),
+    });
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: quad_handle.into(),
+            material: material_handle,
+            // Very large quad to cover the screen
+            transform: Transform::from_scale(Vec3::splat(10_000.0)),
+            ..default()
+        },
+        ShadertoyScene,
+    ));
+}
+
+fn update_shadertoy_material(
+    time: Res<Time>,
+    config: Res<VisualsConfig>,
+    audio_analysis: Res<AudioAnalysis>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut materials: ResMut<Assets<ShadertoyMaterial>>,
+    q_material: Query<&Handle<ShadertoyMaterial>, With<ShadertoyScene>>,
+    audio_texture: Res<AudioTexture>,
+    gpu_audio_texture: Res<GpuAudioTexture>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut shader_time: Local<f32>,
+    mut frame: Local<u32>,
+    mut mouse: Local<Vec4>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    let Some(material) = q_material
+        .get_single()
+        .ok()
+        .and_then(|handle| materials.get_mut(handle))
+    else {
+        return;
+    };
+    let settings = config.viz::<ShadertoySettings>();
+    let width = window.resolution.physical_width() as f32;
+    let height = window.resolution.physical_height() as f32;
+
+    let delta = time.delta_seconds() * settings.time_scale;
+    *shader_time += delta;
+    *frame += 1;
+    *mouse = shadertoy_mouse(*mouse, window, &mouse_buttons);
+    let channel0 = if settings.gpu_fft {
+        &gpu_audio_texture.0
+    } else {
+        &audio_texture.0
+    };
+
+    material.resolution = Vec4::new(width, height, 1.0, 0.0);
+    material.mouse = *mouse;
+    material.time_params = Vec4::new(*shader_time, delta, *frame as f32, 0.0);
+    material.tint = Vec4::from(settings.tint.as_linear_rgba_f32());
+    material.loudness = Vec4::new(
+        audio_analysis.loudness,
+        loudness_level(audio_analysis.short_term_lufs),
+        audio_analysis.momentary_lufs,
+        audio_analysis.short_term_lufs,
+    );
+    if material.channel0 != *channel0 {
+        material.channel0 = channel0.clone();
+    }
+    material.bins = storage_array(&audio_analysis.frequency_bins);
+    material.waveform = storage_array(&audio_analysis.waveform);
+}
+
+// Shadertoy's iMouse: xy follows the cursor while the left button is held, zw
+// is where it was pressed. z is negative once released, and w only stays
+// positive on the frame of the click.
+fn shadertoy_mouse(previous: Vec4, window: &Window, buttons: &ButtonInput<MouseButton>) -> Vec4 {
+    let mut mouse = previous;
+    mouse.z = -mouse.z.abs();
+    mouse.w = -mouse.w.abs();
+    let Some(cursor) = window.physical_cursor_position() else {
+        return mouse;
+    };
+    // Shadertoy's origin is the bottom-left corner.
+    let position = Vec2::new(cursor.x, window.physical_height() as f32 - cursor.y);
+    if buttons.just_pressed(MouseButton::Left) {
+        mouse = Vec4::new(position.x, position.y, position.x, position.y);
+    } else if buttons.pressed(MouseButton::Left) {
+        mouse.x = position.x;
+        mouse.y = position.y;
+        mouse.z = mouse.z.abs();
+    }
+    mouse
+}
+
+fn despawn_scene(mut commands: Commands, scene_query: Query<Entity, With<ShadertoyScene>>) {
+    if let Ok(entity) = scene_query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
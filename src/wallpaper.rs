@@ -0,0 +1,188 @@
+// src/wallpaper.rs
+
+use crate::audio::{AudioSource, SelectedAudioSource};
+use crate::config::VisualizerId;
+use crate::recording::source_for;
+use crate::{ActiveVisualization, AppState};
+use bevy::prelude::*;
+use bevy::window::{Cursor, PrimaryWindow, RawHandleWrapper, WindowLevel, WindowMode};
+use raw_window_handle::RawWindowHandle;
+use std::path::PathBuf;
+
+// Options of the `--wallpaper` mode, see `cli.rs`. Without a file the
+// default microphone is used.
+pub struct WallpaperOptions {
+    pub viz: Option<VisualizerId>,
+    pub file: Option<PathBuf>,
+}
+
+// Present while running as a wallpaper; the UI stays off.
+#[derive(Resource)]
+pub struct WallpaperMode;
+
+pub struct WallpaperPlugin(pub WallpaperOptions);
+
+impl Plugin for WallpaperPlugin {
+    fn build(&self, app: &mut App) {
+        let options = &self.0;
+        let source = options
+            .file
+            .clone()
+            .map_or(AudioSource::Microphone, source_for);
+
+        app.insert_resource(WallpaperMode)
+            .insert_resource(SelectedAudioSource(source))
+            .add_systems(Startup, start_wallpaper)
+            .add_systems(Update, attach_behind_icons);
+        if let Some(viz) = options.viz {
+            app.insert_resource(ActiveVisualization(viz.state()));
+        }
+    }
+}
+
+// A borderless fullscreen window kept below every other window and ignoring
+// the mouse, which is all most platforms allow. `attach_behind_icons` goes
+// further where it can.
+pub fn wallpaper_window() -> Window {
+    Window {
+        title: "Rust Visualizer Wallpaper".into(),
+        mode: WindowMode::BorderlessFullscreen,
+        decorations: false,
+        window_level: WindowLevel::AlwaysOnBottom,
+        focused: false,
+        cursor: Cursor {
+            hit_test: false,
+            visible: false,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn start_wallpaper(
+    active_viz: Res<ActiveVisualization>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    next_app_state.set(active_viz.0.clone());
+}
+
+// Moves the window into the desktop layer once the OS window exists.
+fn attach_behind_icons(
+    q_windows: Query<&RawHandleWrapper, With<PrimaryWindow>>,
+    mut attached: Local<bool>,
+) {
+    if *attached {
+        return;
+    }
+    let Ok(handle) = q_windows.get_single() else {
+        return;
+    };
+    *attached = true;
+
+    match attach_to_desktop(handle.window_handle) {
+        Ok(()) => info!("Attached the visualizer behind the desktop icons"),
+        Err(e) => warn!(
+            "Could not attach behind the desktop icons, keeping the window at the bottom: {}",
+            e
+        ),
+    }
+}
+
+// Windows: parent the window to the WorkerW that Explorer draws the wallpaper in.
+#[cfg(windows)]
+fn attach_to_desktop(handle: RawWindowHandle) -> Result<(), Box<dyn std::error::Error>> {
+    use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, FindWindowExW, FindWindowW, SendMessageTimeoutW, SetParent, SMTO_NORMAL,
+    };
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    // The WorkerW following the window that hosts the icons is the wallpaper layer.
+    unsafe extern "system" fn find_worker(top: HWND, found: LPARAM) -> BOOL {
+        let shell = FindWindowExW(top, 0, wide("SHELLDLL_DefView").as_ptr(), std::ptr::null());
+        if shell != 0 {
+            *(found as *mut HWND) =
+                FindWindowExW(0, top, wide("WorkerW").as_ptr(), std::ptr::null());
+        }
+        1
+    }
+
+    let RawWindowHandle::Win32(handle) = handle else {
+        return Err("not a Win32 window".into());
+    };
+    let hwnd = handle.hwnd.get() as HWND;
+
+    // SAFETY: plain Win32 calls on window handles; `find_worker` only writes
+    // to the `worker` local it is given.
+    unsafe {
+        let progman = FindWindowW(wide("Progman").as_ptr(), std::ptr::null());
+        if progman == 0 {
+            return Err("Explorer's Progman window was not found".into());
+        }
+        // Undocumented message asking Explorer to split out the WorkerW window.
+        SendMessageTimeoutW(
+            progman,
+            0x052C,
+            0,
+            0,
+            SMTO_NORMAL,
+            1000,
+            std::ptr::null_mut(),
+        );
+
+        let mut worker: HWND = 0;
+        EnumWindows(Some(find_worker), &mut worker as *mut HWND as LPARAM);
+        if worker == 0 {
+            return Err("the desktop WorkerW window was not found".into());
+        }
+        if SetParent(hwnd, worker) == 0 {
+            return Err("SetParent failed".into());
+        }
+    }
+    Ok(())
+}
+
+// X11: mark the window as the desktop, which window managers keep under the icons.
+#[cfg(target_os = "linux")]
+fn attach_to_desktop(handle: RawWindowHandle) -> Result<(), Box<dyn std::error::Error>> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, PropMode};
+    use x11rb::wrapper::ConnectionExt as _;
+
+    let window = match handle {
+        RawWindowHandle::Xlib(handle) => handle.window as u32,
+        RawWindowHandle::Xcb(handle) => handle.window.get(),
+        _ => return Err("only X11 desktops are supported".into()),
+    };
+
+    let (connection, _) = x11rb::connect(None)?;
+    let window_type = connection
+        .intern_atom(false, b"_NET_WM_WINDOW_TYPE")?
+        .reply()?
+        .atom;
+    let desktop = connection
+        .intern_atom(false, b"_NET_WM_WINDOW_TYPE_DESKTOP")?
+        .reply()?
+        .atom;
+
+    // Window managers only read the type when a window is mapped.
+    connection.unmap_window(window)?;
+    connection.change_property32(
+        PropMode::REPLACE,
+        window,
+        window_type,
+        AtomEnum::ATOM,
+        &[desktop],
+    )?;
+    connection.map_window(window)?;
+    connection.flush()?;
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn attach_to_desktop(_handle: RawWindowHandle) -> Result<(), Box<dyn std::error::Error>> {
+    Err("not supported on this platform".into())
+}